@@ -1,7 +1,11 @@
-use miden_hir::{StructType, Type};
+use miden_hir::{Felt, FieldElement, Overflow, StructType, Type};
+use smallvec::SmallVec;
 
 use super::OpEmitter;
-use crate::masm::{NativePtr, Op};
+use crate::{
+    codegen::TypedValue,
+    masm::{LocalId, NativePtr, Op},
+};
 
 /// Allocation
 impl<'a> OpEmitter<'a> {
@@ -20,6 +24,49 @@ impl<'a> OpEmitter<'a> {
             ty => panic!("expected a pointer type, got {ty}"),
         }
     }
+
+    /// Allocate the procedure-local memory slots used to hold an operand of `size` field
+    /// elements when it is spilled from the operand stack.
+    ///
+    /// A slot is allocated for each element of the operand.
+    pub fn alloc_spill_slots(&mut self, size: usize) -> SmallVec<[LocalId; 4]> {
+        (0..size).map(|_| self.function.alloc_local(Type::Felt)).collect()
+    }
+
+    /// Spill the `n`th operand on the stack to the given procedure-local memory slots,
+    /// removing it from the operand stack.
+    ///
+    /// The slots are expected to have been allocated with [Self::alloc_spill_slots].
+    pub fn spill(&mut self, n: u8, slots: &[LocalId]) {
+        if n > 0 {
+            self.movup(n);
+        }
+        let operand = self.stack.pop().expect("operand stack is empty");
+        assert_eq!(
+            operand.size(),
+            slots.len(),
+            "invalid spill: expected the operand and its slots to be the same size"
+        );
+        // The first element of the operand is on top of the stack, and is written to the
+        // first slot.
+        for slot in slots.iter().copied() {
+            self.emit(Op::LocStore(slot));
+        }
+    }
+
+    /// Reload `value` from the procedure-local memory slots it was spilled to with
+    /// [Self::spill], placing it on top of the operand stack.
+    pub fn reload(&mut self, value: TypedValue, slots: &[LocalId]) {
+        for slot in slots.iter().rev().copied() {
+            self.emit(Op::LocLoad(slot));
+        }
+        self.stack.push(value);
+        assert_eq!(
+            self.stack.peek().unwrap().size(),
+            slots.len(),
+            "invalid reload: expected the operand and its slots to be the same size"
+        );
+    }
 }
 
 /// Loads
@@ -31,47 +78,50 @@ impl<'a> OpEmitter<'a> {
     /// address space.
     pub fn load(&mut self, ty: Type) {
         let ptr = self.stack.pop().expect("operand stack is empty");
+        assert!(!ty.is_zst(), "cannot load a zero-sized type from memory");
         match ptr.ty() {
             Type::Ptr(_) => {
                 // Converet the pointer to a native pointer representation
                 self.emit_native_ptr();
-                match &ty {
-                    Type::I128 => self.load_quad_word(None),
-                    Type::I64 | Type::U64 => self.load_double_word(None),
-                    Type::Felt => self.load_felt(None),
-                    Type::I32 | Type::U32 => self.load_word(None),
-                    ty @ (Type::I16 | Type::U16 | Type::U8 | Type::I8 | Type::I1) => {
-                        self.load_word(None);
-                        self.trunc_int32(ty.size_in_bits() as u32);
-                    }
-                    ty => todo!("support for loading {ty} is not yet implemented"),
-                }
-                self.stack.push(ty);
-            }
-            ty if !ty.is_pointer() => {
-                panic!("invalid operand to load: expected pointer, got {ty}")
             }
-            ty => unimplemented!("load support for pointers of type {ty} is not implemented"),
+            // Native pointers are already represented on the stack as a pointer triplet
+            Type::NativePtr(..) => (),
+            ty => panic!("invalid operand to load: expected pointer, got {ty}"),
         }
+        self.load_value(&ty, None);
+        self.stack.push(ty);
     }
 
     /// Load a value of type `ty` from `addr`.
     ///
     /// NOTE: The address represented by `addr` is in the IR's byte-addressable address space.
     pub fn load_imm(&mut self, addr: u32, ty: Type) {
+        assert!(!ty.is_zst(), "cannot load a zero-sized type from memory");
         let ptr = NativePtr::from_ptr(addr);
-        match &ty {
-            Type::I128 => self.load_quad_word(Some(ptr)),
-            Type::I64 | Type::U64 => self.load_double_word(Some(ptr)),
-            Type::Felt => self.load_felt(Some(ptr)),
-            Type::I32 | Type::U32 => self.load_word(Some(ptr)),
-            Type::I16 | Type::U16 | Type::U8 | Type::I8 | Type::I1 => {
-                self.load_word(Some(ptr));
-                self.trunc_int32(ty.size_in_bits() as u32);
+        self.load_value(&ty, Some(ptr));
+        self.stack.push(ty);
+    }
+
+    /// Load a value of type `ty`, either from an immediate address, or from the native pointer
+    /// triplet on top of the stack.
+    ///
+    /// Values are laid out in memory as described by [Type::size_in_bytes], one 32-bit chunk per
+    /// field element. Values larger than a field element are placed on the operand stack as
+    /// their raw in-memory elements, with the first element on top of the stack.
+    fn load_value(&mut self, ty: &Type, ptr: Option<NativePtr>) {
+        match ty {
+            Type::I128 | Type::U128 => self.load_quad_word(ptr),
+            Type::I64 | Type::U64 | Type::F64 => self.load_double_word(ptr),
+            Type::Felt => self.load_felt(ptr),
+            Type::I32 | Type::U32 | Type::Ptr(_) => self.load_word(ptr),
+            Type::I1 | Type::I8 | Type::U8 | Type::I16 | Type::U16 => self.load_small(ty, ptr),
+            Type::U256 | Type::NativePtr(..) | Type::Array(..) | Type::Struct(_) => {
+                self.load_elements(ty.size_in_bytes(), ptr)
+            }
+            Type::Unknown | Type::Unit | Type::Never | Type::List(_) => {
+                unreachable!("invalid load: {ty} has no representation in memory")
             }
-            ty => todo!("support for loading {ty} is not yet implemented"),
         }
-        self.stack.push(ty);
     }
 
     /// Emit a sequence of instructions to translate a raw pointer value to
@@ -112,6 +162,23 @@ impl<'a> OpEmitter<'a> {
         ]);
     }
 
+    /// Emit a sequence of instructions to translate a native pointer triplet of
+    /// `(waddr, index, offset)` on top of the stack, to a raw pointer value in
+    /// the IR's byte-addressable address space.
+    ///
+    /// This is the inverse of [OpEmitter::emit_native_ptr], and traps if the
+    /// resulting address does not fit in a u32.
+    fn emit_byte_ptr(&mut self) {
+        // [waddr * 16, index, offset]
+        self.mul_imm_u32(16, Overflow::Checked);
+        // [index * 4, waddr * 16, offset]
+        self.emit(Op::Swap(1));
+        self.mul_imm_u32(4, Overflow::Checked);
+        // [addr]
+        self.add_u32(Overflow::Checked);
+        self.add_u32(Overflow::Checked);
+    }
+
     /// Load a field element from a naturally aligned address, either immediate or dynamic
     ///
     /// A native pointer triplet is expected on the stack if an immediate is not given.
@@ -131,9 +198,8 @@ impl<'a> OpEmitter<'a> {
                 self.emit_all(&[
                     Op::Padw,
                     Op::MemLoadwImm(ptr.waddr),
-                    Op::Movup(4),
-                    Op::Movup(4),
                     Op::Drop,
+                    Op::Movdn(2),
                     Op::Drop,
                     Op::Drop,
                 ]);
@@ -175,66 +241,24 @@ impl<'a> OpEmitter<'a> {
 
     /// Loads a single 32-bit machine word from the given immediate address.
     fn load_word_imm(&mut self, ptr: NativePtr) {
-        let is_aligned = ptr.is_element_aligned();
-        let rshift = 32 - ptr.offset as u32;
+        if !ptr.is_element_aligned() {
+            return self.load_unaligned_element_imm(ptr, 4);
+        }
         match ptr.index {
-            0 if is_aligned => self.emit(Op::MemLoadImm(ptr.waddr)),
-            0 => {
-                self.emit_all(&[
-                    // Load a quad-word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
-                    // Move the two elements across which the desired machine word spans
-                    // to the bottom of the stack temporarily
-                    Op::Movdn(4),
-                    Op::Movdn(4),
-                    // Drop the unused elements
-                    Op::Drop,
-                    Op::Drop,
-                    // Shift the high bits left by the offset
-                    Op::U32ShlImm(ptr.offset as u32),
-                    // Move the low bits to the top and shift them right
-                    Op::Swap(1),
-                    Op::U32ShrImm(rshift),
-                    // OR the high and low bits together
-                    Op::U32Or,
-                ]);
-            }
-            1 if is_aligned => self.emit_all(&[
+            0 => self.emit(Op::MemLoadImm(ptr.waddr)),
+            1 => self.emit_all(&[
                 // Load a quad-word
                 Op::Padw,
                 Op::MemLoadwImm(ptr.waddr),
                 // Drop the first unused element
                 Op::Drop,
                 // Move the desired element past the last two unused
-                Op::Movdn(3),
+                Op::Movdn(2),
                 // Drop the remaining unused elements
                 Op::Drop,
                 Op::Drop,
             ]),
-            1 => {
-                self.emit_all(&[
-                    // Load a quad-word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
-                    // Drop the first unused element
-                    Op::Drop,
-                    // Move the two elements across which the desired machine word spans
-                    // to the bottom of the stack temporarily
-                    Op::Movdn(3),
-                    Op::Movdn(3),
-                    // Drop the remaining unused element
-                    Op::Drop,
-                    // Shift the high bits left by the offset
-                    Op::U32ShlImm(ptr.offset as u32),
-                    // Move the low bits to the top and shift them right
-                    Op::Swap(1),
-                    Op::U32ShrImm(rshift),
-                    // OR the high and low bits together
-                    Op::U32Or,
-                ]);
-            }
-            2 if is_aligned => self.emit_all(&[
+            2 => self.emit_all(&[
                 // Load a quad-word
                 Op::Padw,
                 Op::MemLoadwImm(ptr.waddr),
@@ -245,24 +269,7 @@ impl<'a> OpEmitter<'a> {
                 Op::Swap(1),
                 Op::Drop,
             ]),
-            2 => {
-                self.emit_all(&[
-                    // Load a quad-word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
-                    // Drop the first two unused elements
-                    Op::Drop,
-                    Op::Drop,
-                    // Shift the high bits left by the offset
-                    Op::U32ShlImm(ptr.offset as u32),
-                    // Move the low bits to the top and shift them right
-                    Op::Swap(1),
-                    Op::U32ShrImm(rshift),
-                    // OR the high and low bits together
-                    Op::U32Or,
-                ]);
-            }
-            3 if is_aligned => self.emit_all(&[
+            3 => self.emit_all(&[
                 // Load a quad-word
                 Op::Padw,
                 Op::MemLoadwImm(ptr.waddr),
@@ -271,32 +278,6 @@ impl<'a> OpEmitter<'a> {
                 Op::Drop,
                 Op::Drop,
             ]),
-            3 => {
-                self.emit_all(&[
-                    // Load the quad-word containing the low bits
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr + 1),
-                    // Move the element we need to the bottom temporarily
-                    Op::Movdn(4),
-                    // Drop the unused elements
-                    Op::Drop,
-                    Op::Drop,
-                    Op::Drop,
-                    // Shift the low bits right by the offset
-                    Op::U32ShrImm(rshift),
-                    // Load the quad-word containing the high bits
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
-                    // Drop the unused elements
-                    Op::Drop,
-                    Op::Drop,
-                    Op::Drop,
-                    // Shift the high bits left by the offset
-                    Op::U32ShlImm(ptr.offset as u32),
-                    // OR the high and low bits together
-                    Op::U32Or,
-                ]);
-            }
             _ => unreachable!(),
         }
     }
@@ -311,34 +292,26 @@ impl<'a> OpEmitter<'a> {
     }
 
     fn load_double_word_imm(&mut self, ptr: NativePtr) {
-        let aligned = ptr.is_element_aligned();
+        if !ptr.is_element_aligned() {
+            // An unaligned double-word load spans three elements, so we load each machine word
+            // individually, starting with the second, so that the first ends up on top
+            self.load_unaligned_element_imm(element_ptr(ptr, 1), 4);
+            return self.load_unaligned_element_imm(ptr, 4);
+        }
         match ptr.index {
-            0 if aligned => {
+            0 => {
                 self.emit_all(&[
                     // Load quad-word
                     Op::Padw,
                     Op::MemLoadwImm(ptr.waddr),
-                    // Move the two elements we need to the bottom temporarily
-                    Op::Movdn(4),
-                    Op::Movdn(4),
                     // Drop the unused elements
+                    Op::Movup(2),
                     Op::Drop,
+                    Op::Movup(2),
                     Op::Drop,
                 ]);
             }
-            0 => {
-                // An unaligned double-word load spans three elements
-                self.emit_all(&[
-                    // Load quad-word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
-                    // Move the unused element to the top and drop it
-                    Op::Movup(4),
-                    Op::Drop,
-                ]);
-                self.realign_double_word(ptr);
-            }
-            1 if aligned => {
+            1 => {
                 self.emit_all(&[
                     // Load quad-word
                     Op::Padw,
@@ -346,22 +319,11 @@ impl<'a> OpEmitter<'a> {
                     // Drop the first word, its unused
                     Op::Drop,
                     // Move the last word up and drop it, also unused
-                    Op::Movup(3),
-                    Op::Drop,
-                ]);
-            }
-            1 => {
-                // An unaligned double-word load spans three elements
-                self.emit_all(&[
-                    // Load a quad-word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
-                    // Drop the unused element
+                    Op::Movup(2),
                     Op::Drop,
                 ]);
-                self.realign_double_word(ptr);
             }
-            2 if aligned => {
+            2 => {
                 self.emit_all(&[
                     // Load quad-word
                     Op::Padw,
@@ -371,53 +333,15 @@ impl<'a> OpEmitter<'a> {
                     Op::Drop,
                 ]);
             }
-            2 => {
-                // An unaligned double-word load spans three elements,
-                // and in this case, two quad-words, because the last
-                // element is across a quad-word boundary
-                self.emit_all(&[
-                    // Load the second quad-word first
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr + 1),
-                    // Move the element we need to the bottom temporarily
-                    Op::Movdn(4),
-                    // Drop the three unused elements of this word
-                    Op::Drop,
-                    Op::Drop,
-                    Op::Drop,
-                    // Load the first quad-word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
-                    // Drop the two unused elements
-                    Op::Drop,
-                    Op::Drop,
-                ]);
-                self.realign_double_word(ptr);
-            }
-            3 if aligned => {
+            3 => {
                 self.emit_all(&[
                     // Load second word, drop unused elements
                     Op::Padw,
                     Op::MemLoadwImm(ptr.waddr + 1),
-                    Op::Movup(4),
-                    Op::Drop,
-                    Op::Movup(3),
-                    Op::Drop,
-                    // Load first word, drop unused elements
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
+                    Op::Movdn(3),
                     Op::Drop,
                     Op::Drop,
                     Op::Drop,
-                ]);
-            }
-            3 => {
-                self.emit_all(&[
-                    // Load second word, drop unused element
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr + 1),
-                    Op::Movup(4),
-                    Op::Drop,
                     // Load first word, drop unused elements
                     Op::Padw,
                     Op::MemLoadwImm(ptr.waddr),
@@ -425,9 +349,8 @@ impl<'a> OpEmitter<'a> {
                     Op::Drop,
                     Op::Drop,
                 ]);
-                self.realign_double_word(ptr);
             }
-            _ => unimplemented!("unaligned loads are not yet implemented: {ptr:#?}"),
+            _ => unreachable!(),
         }
     }
 
@@ -436,39 +359,27 @@ impl<'a> OpEmitter<'a> {
         if let Some(imm) = ptr {
             return self.load_quad_word_imm(imm);
         }
-        self.emit(Op::Exec("intrinsics::mem::load_qw".parse().unwrap()));
+        self.load_elements(16, None);
     }
 
     fn load_quad_word_imm(&mut self, ptr: NativePtr) {
-        // For all other cases, more complicated loads are required
-        let aligned = ptr.is_element_aligned();
+        if !ptr.is_element_aligned() {
+            // An unaligned quad-word load spans five elements, so we load each machine word
+            // individually
+            return self.load_elements(16, Some(ptr));
+        }
         match ptr.index {
             // Naturally-aligned
-            0 if aligned => self.emit_all(&[Op::Padw, Op::MemLoadwImm(ptr.waddr)]),
-            0 => {
-                // An unaligned quad-word load spans five elements
+            0 => self.emit_all(&[Op::Padw, Op::MemLoadwImm(ptr.waddr)]),
+            1 => {
                 self.emit_all(&[
                     // Load second quad-word
                     Op::Padw,
                     Op::MemLoadwImm(ptr.waddr + 1),
                     // Drop all but the first element
-                    Op::Movdn(4),
-                    Op::Drop,
+                    Op::Movdn(3),
                     Op::Drop,
                     Op::Drop,
-                    // Load first quad-word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
-                ]);
-                self.realign_quad_word(ptr);
-            }
-            1 if aligned => {
-                self.emit_all(&[
-                    // Load second quad-word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr + 1),
-                    // Drop last element
-                    Op::Movup(4),
                     Op::Drop,
                     // Load first quad-word
                     Op::Padw,
@@ -477,34 +388,15 @@ impl<'a> OpEmitter<'a> {
                     Op::Drop,
                 ]);
             }
-            1 => {
-                // An unaligned double-word load spans five elements
+            2 => {
                 self.emit_all(&[
                     // Load second quad-word
                     Op::Padw,
                     Op::MemLoadwImm(ptr.waddr + 1),
-                    // Drop all but the first two elements
-                    Op::Movdn(4),
-                    Op::Movdn(4),
-                    Op::Drop,
-                    Op::Drop,
-                    // Load first quad-word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
-                    // Drop the first word
-                    Op::Drop,
-                ]);
-                self.realign_quad_word(ptr);
-            }
-            2 if aligned => {
-                self.emit_all(&[
-                    // Load second quad-word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
                     // Drop last two elements
-                    Op::Movup(4),
-                    Op::Movup(4),
+                    Op::Movup(2),
                     Op::Drop,
+                    Op::Movup(2),
                     Op::Drop,
                     // Load first quad-word
                     Op::Padw,
@@ -514,30 +406,12 @@ impl<'a> OpEmitter<'a> {
                     Op::Drop,
                 ]);
             }
-            2 => {
-                // An unaligned double-word load spans five elements
-                self.emit_all(&[
-                    // Load the second quad-word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr + 1),
-                    // Drop the last element
-                    Op::Movup(4),
-                    Op::Drop,
-                    // Load the first quad-word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
-                    // Drop the two unused elements
-                    Op::Drop,
-                    Op::Drop,
-                ]);
-                self.realign_quad_word(ptr);
-            }
-            3 if aligned => {
+            3 => {
                 self.emit_all(&[
                     // Load second word, drop last element
                     Op::Padw,
                     Op::MemLoadwImm(ptr.waddr + 1),
-                    Op::Movup(4),
+                    Op::Movup(3),
                     Op::Drop,
                     // Load first word
                     Op::Padw,
@@ -548,269 +422,121 @@ impl<'a> OpEmitter<'a> {
                     Op::Drop,
                 ]);
             }
-            3 => {
-                // An unaligned quad-word load spans five elements,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Load a value smaller than a single 32-bit machine word, i.e. one which fits in a single
+    /// field element, such as `u8`, `i16`, or a small aggregate.
+    ///
+    /// Such values occupy the bytes starting at the byte offset of the pointer, with the first
+    /// byte in the least significant position, and may span two elements if unaligned. The
+    /// loaded value is zero-extended.
+    ///
+    /// Expects a native pointer triplet on the stack if an immediate address is not given.
+    fn load_small(&mut self, ty: &Type, ptr: Option<NativePtr>) {
+        let size = ty.size_in_bytes();
+        match ptr {
+            Some(imm) => self.load_unaligned_element_imm(imm, size),
+            None => {
                 self.emit_all(&[
-                    // Load second word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr + 1),
-                    // Load first word
-                    Op::Padw,
-                    Op::MemLoadwImm(ptr.waddr),
-                    // Drop unused elements
-                    Op::Drop,
-                    Op::Drop,
-                    Op::Drop,
+                    // Compute the element address
+                    //
+                    // [addr, offset]
+                    Op::MulImm(Felt::new(4)),
+                    Op::Add,
+                    // Load the machine word starting at the byte offset
+                    Op::Exec("intrinsics::mem::load_unaligned_element".parse().unwrap()),
                 ]);
-                self.realign_quad_word(ptr);
             }
-            _ => unimplemented!("unaligned loads are not yet implemented: {ptr:#?}"),
-        }
-    }
-
-    /// This handles emitting code that handles aligning an unaligned double machine-word value
-    /// which is split across three machine words (field elements).
-    ///
-    /// To recap:
-    ///
-    /// * A machine word is a 32-bit chunk stored in a single field element
-    /// * A double word is a pair of 32-bit chunks
-    /// * A quad word is a quartet of 32-bit chunks (i.e. a Miden "word")
-    /// * An unaligned double-word requires three 32-bit chunks to represent,
-    /// since the first chunk does not contain a full 32-bits, so an extra is
-    /// needed to hold those bits.
-    ///
-    /// As an example, assume the pointer we are dereferencing is a u64 value,
-    /// which has 8-byte alignment, and the value is stored 40 bytes from the
-    /// nearest quad-word-aligned boundary. To load the value, we must fetch
-    /// the full quad-word from the aligned address, drop the first word, as
-    /// it is unused, and then recombine the 64 bits we need spread across
-    /// the remaining three words to obtain the double-word value we actually want.
-    ///
-    /// The data, on the stack, is shown below:
-    ///
-    /// ```text,ignore
-    /// # If we visualize which bytes are contained in each 32-bit chunk on the stack, we get:
-    /// [0..=4, 5..=8, 9..=12]
-    ///
-    /// # These byte indices are relative to the nearest word-aligned address, in the same order
-    /// # as they would occur in a byte-addressable address space. The significance of each byte
-    /// # depends on the value being dereferenced, but Miden is a little-endian machine, so typically
-    /// # the most significant bytes come first (i.e. also commonly referred to as "high" vs "low" bits).
-    /// #
-    /// # If we visualize the layout of the bits of our u64 value spread across the three chunks, we get:
-    /// [00000000111111111111111111111111, 111111111111111111111111111111, 11111111111111111111111100000000]
-    /// ```
-    ///
-    /// As illustrated above, what should be a double-word value is occupying three words. To
-    /// "realign" the value, i.e. ensure that it is naturally aligned and fits in two words, we
-    /// have to perform a sequence of shifts and masks to get the bits where they belong. This
-    /// function performs those steps, with the assumption that the caller has three values on
-    /// the operand stack representing any unaligned double-word value
-    fn realign_double_word(&mut self, ptr: NativePtr) {
-        // The stack starts as: [chunk_hi, chunk_mid, chunk_lo]
-        //
-        // We will refer to the parts of our desired double-word value
-        // as two parts, `x_hi` and `x_lo`.
-        self.emit_all(&[
-            // Re-align the high bits by shifting out the offset
-            //
-            // This gives us the first half of the first word.
-            //
-            // [x_hi_hi, chunk_mid, chunk__lo]
-            Op::U32ShlImm(ptr.offset as u32),
-            // Move the value below the other chunks temporarily
-            //
-            // [chunk_mid, chunk_lo, x_hi_hi]
-            Op::Movdn(3),
-            // We must split the middle chunk into two parts,
-            // one containing the bits to be combined with the
-            // first machine word; the other to be combined with
-            // the second machine word.
-            //
-            // First, we duplicate the chunk, since we need two
-            // copies of it:
-            //
-            // [chunk_mid, chunk_mid, chunk_lo, x_hi_hi]
-            Op::Dup(0),
-            // Then, we shift the chunk right by 32 - offset bits,
-            // re-aligning the low bits of the first word, and
-            // isolating them.
-            //
-            // [x_hi_lo, chunk_mid, chunk_lo, x_hi_hi]
-            Op::U32ShrImm(32 - ptr.offset as u32),
-            // Move the high bits back to the top
-            //
-            // [x_hi_hi, x_hi_lo, chunk_mid, chunk_lo]
-            Op::Movup(3),
-            // OR the two parts of the `x_hi` chunk together
-            //
-            // [x_hi, chunk_mid, chunk_lo]
-            Op::U32Or,
-            // Move `x_hi` to the bottom for later
-            Op::Movdn(2),
-            // Now, we need to re-align the high bits of the second word
-            // by shifting the remaining copy of the middle chunk, similar
-            // to what we did at the very beginning.
-            //
-            // This gives us the first half of the second word.
-            //
-            // [x_lo_hi, chunk_lo, x_hi]
-            Op::U32ShlImm(ptr.offset as u32),
-            // Next, swap the low bit chunk to the top temporarily
-            Op::Swap(1),
-            // Shift the value right, as done previously for the middle chunk
-            Op::U32ShrImm(32 - ptr.offset as u32),
-            // OR the two halves together, giving us our second word, `x_lo`
-            //
-            // [x_lo, x_hi]
-            Op::U32Or,
-            // Swap the words so they are in the correct order
-            //
-            // [x_hi, x_lo]
-            Op::Swap(1),
-        ]);
+        }
+        // Clear the bits which are not part of the value
+        self.emit_all(&[Op::PushU32(element_mask(size)), Op::U32And]);
     }
 
-    /// This handles emitting code that handles aligning an unaligned quad machine-word value
-    /// which is split across five machine words (field elements).
+    /// Load a value of `size` bytes which spans one or more field elements, e.g. an array or
+    /// struct.
     ///
-    /// To recap:
+    /// The elements are placed on the operand stack in the order in which they appear in memory,
+    /// with the first element on top of the stack. If `size` is not a multiple of the element
+    /// size, the unused bytes of the last element are cleared. If the pointer is not
+    /// element-aligned, each element is assembled from the two elements it spans, see
+    /// [OpEmitter::load_unaligned_element_imm].
     ///
-    /// * A machine word is a 32-bit chunk stored in a single field element
-    /// * A double word is a pair of 32-bit chunks
-    /// * A quad word is a quartet of 32-bit chunks (i.e. a Miden "word")
-    /// * An unaligned quad-word requires five 32-bit chunks to represent,
-    /// since the first chunk does not contain a full 32-bits, so an extra is
-    /// needed to hold those bits.
+    /// Expects a native pointer triplet on the stack if an immediate address is not given.
+    fn load_elements(&mut self, size: usize, ptr: Option<NativePtr>) {
+        let num_elements = (size / 4) + (size % 4 > 0) as usize;
+        let trailing = size % 4;
+        match ptr {
+            Some(imm) => {
+                // Load the elements in reverse, so that the first element ends up on top
+                for i in (0..num_elements).rev() {
+                    if i == num_elements - 1 && trailing > 0 {
+                        self.load_unaligned_element_imm(element_ptr(imm, i), trailing);
+                        self.emit_all(&[Op::PushU32(element_mask(trailing)), Op::U32And]);
+                    } else {
+                        self.load_unaligned_element_imm(element_ptr(imm, i), 4);
+                    }
+                }
+            }
+            None => {
+                self.emit_all(&[
+                    // Compute the element address of the first element
+                    //
+                    // [element_addr, offset]
+                    Op::MulImm(Felt::new(4)),
+                    Op::Add,
+                ]);
+                // Load the elements in reverse, starting from the last element, so that the
+                // first element ends up on top
+                if num_elements > 1 {
+                    self.emit(Op::AddImm(Felt::new(num_elements as u64 - 1)));
+                }
+                for i in (0..num_elements).rev() {
+                    self.emit_all(&[
+                        // [element, element_addr, offset]
+                        Op::Dup(1),
+                        Op::Dup(1),
+                        Op::Exec("intrinsics::mem::load_unaligned_element".parse().unwrap()),
+                    ]);
+                    if i == num_elements - 1 && trailing > 0 {
+                        self.emit_all(&[Op::PushU32(element_mask(trailing)), Op::U32And]);
+                    }
+                    // [element_addr, offset, element]
+                    self.emit(Op::Movdn(2));
+                    if i > 0 {
+                        self.emit(Op::SubImm(Felt::ONE));
+                    }
+                }
+                // Drop the element address and byte offset
+                self.emit_all(&[Op::Drop, Op::Drop]);
+            }
+        }
+    }
+
+    /// Load the `size` bytes, at most four, starting at the immediate address `ptr`, as a single
+    /// 32-bit machine word, with the first byte in the least significant position.
     ///
-    /// See the example in [OpEmitter::realign_quad_word] for more details on how bits are
-    /// laid out in each word, and what is required to realign unaligned words.
-    fn realign_quad_word(&mut self, ptr: NativePtr) {
-        // The stack starts as: [chunk_hi, chunk_mid_hi, chunk_mid_mid, chunk_mid_lo, chunk_lo]
-        //
-        // We will refer to the parts of our desired quad-word value
-        // as four parts, `x_hi2`, `x_hi1`, `x_lo2`, and `x_lo1`, where
-        // the integer suffix should appear in decreasing order on the
-        // stack when we're done.
-        self.emit_all(&[
-            // Re-align the high bits by shifting out the offset
-            //
-            // This gives us the first half of `x_hi2`.
-            //
-            // [x_hi2_hi, chunk_mid_hi, chunk_mid_mid, chunk_mid_lo, chunk__lo]
-            Op::U32ShlImm(ptr.offset as u32),
-            // Move the value below the other chunks temporarily
-            //
-            // [chunk_mid_hi, chunk_mid_mid, chunk_mid_lo, chunk__lo, x_hi2_hi]
-            Op::Movdn(5),
-            // We must split the `chunk_mid_hi` chunk into two parts,
-            // one containing the bits to be combined with `x_hi2_hi`;
-            // the other to be combined with `x_hi1_hi`.
-            //
-            // First, we duplicate the chunk, since we need two
-            // copies of it:
-            //
-            // [chunk_mid_hi, chunk_mid_hi, chunk_mid_mid, chunk_mid_lo, chunk_lo, x_hi2_hi]
-            Op::Dup(0),
-            // Then, we shift the chunk right by 32 - offset bits,
-            // re-aligning the low bits of `x_hi2`, and isolating them.
-            //
-            // [x_hi2_lo, chunk_mid_hi, chunk_mid_mid, chunk_mid_lo, chunk_lo, x_hi2_hi]
-            Op::U32ShrImm(32 - ptr.offset as u32),
-            // Move the high bits of `x_hi2` back to the top
-            //
-            // [x_hi2_hi, x_hi2_lo, chunk_mid_hi, chunk_mid_mid, chunk_mid_lo, chunk_lo]
-            Op::Movup(3),
-            // OR the two parts of the `x_hi2` chunk together
-            //
-            // [x_hi2, chunk_mid_hi, chunk_mid_mid, chunk_mid_lo, chunk_lo]
-            Op::U32Or,
-            // Move `x_hi2` to the bottom for later
-            //
-            // [chunk_mid_hi, chunk_mid_mid, chunk_mid_lo, chunk_lo, x_hi2]
-            Op::Movdn(5),
-            // Now, we need to re-align the high bits of `x_hi1` by shifting
-            // the remaining copy of `chunk_mid_hi`, similar to what we did for `x_hi2`
-            //
-            // This gives us the first half of `x_hi1`
-            //
-            // [x_hi1_hi, chunk_mid_mid, chunk_mid_lo, chunk_lo, x_hi2]
-            Op::U32ShlImm(ptr.offset as u32),
-            // Next, move the chunk containing the low bits of `x_hi1` to the top temporarily
-            //
-            // [chunk_mid_mid, chunk_mid_lo, chunk_lo, x_hi2, x_hi1_hi]
-            Op::Movdn(5),
-            // Duplicate it, as we need two copies
-            //
-            // [chunk_mid_mid, chunk_mid_mid, chunk_mid_lo, chunk_lo, x_hi2, x_hi1_hi]
-            Op::Dup(0),
-            // Shift the value right, as done previously for the low bits of `x_hi2`
-            //
-            // [x_hi1_lo, chunk_mid_mid, chunk_mid_lo, chunk_lo, x_hi2, x_hi1_hi]
-            Op::U32ShrImm(32 - ptr.offset as u32),
-            // Move the high bits of `x_hi1` to the top
-            Op::Movup(5),
-            // OR the two halves together, giving us our second word, `x_hi1`
-            //
-            // [x_hi1, chunk_mid_mid, chunk_mid_lo, chunk_lo, x_hi2]
-            Op::U32Or,
-            // Move the word to the bottom of the stack
-            //
-            // [chunk_mid_mid, chunk_mid_lo, chunk_lo, x_hi2, x_hi1]
-            Op::Movdn(5),
-            // Now, we need to re-align the high bits of `x_lo2` by shifting
-            // the remaining copy of `chunk_mid_mid`, as done previously.
-            //
-            // [x_lo2_hi, chunk_mid_lo, chunk_lo, x_hi2, x_hi1]
-            Op::U32ShlImm(ptr.offset as u32),
-            // Next, move the chunk containing the low bits of `x_lo2` to the top temporarily
-            //
-            // [chunk_mid_lo, chunk_lo, x_hi2, x_hi1, x_lo2_hi]
-            Op::Movdn(5),
-            // Duplicate it, as done previously
-            //
-            // [chunk_mid_lo, chunk_mid_lo, chunk_lo, x_hi2, x_hi1, x_lo2_hi]
-            Op::Dup(0),
-            // Shift the value right to get the low bits of `x_lo2`
-            //
-            // [x_lo2_lo, chunk_mid_lo, chunk_lo, x_hi2, x_hi1, x_lo2_hi]
-            Op::U32ShrImm(32 - ptr.offset as u32),
-            // Move the high bits of `x_lo2` to the top
-            //
-            // [x_lo2_hi, x_lo2_lo, chunk_mid_lo, chunk_lo, x_hi2, x_hi1]
-            Op::Movup(6),
-            // OR the two halves together, giving us our third word, `x_lo2`
-            //
-            // [x_lo2, chunk_mid_lo, chunk_lo, x_hi2, x_hi1]
-            Op::U32Or,
-            // Move to the bottom of the stack
-            //
-            // [chunk_mid_lo, chunk_lo, x_hi2, x_hi1, x_lo2]
-            Op::Movdn(5),
-            // Re-align the high bits of `x_lo1`
-            //
-            // [x_lo1_hi, chunk_lo, x_hi2, x_hi1, x_lo2]
-            Op::U32ShlImm(ptr.offset as u32),
-            // Move the chunk containing the low bits to the top
-            //
-            // [chunk_lo, x_hi2, x_hi1, x_lo2, x_lo1_hi]
-            Op::Movdn(5),
-            // Shift the value right to get the low bits of `x_lo1`
-            Op::U32ShrImm(32 - ptr.offset as u32),
-            // Move the high bits of `x_lo1` to the top
-            //
-            // [x_lo1_hi, x_lo1_lo, x_hi2, x_hi1, x_lo2]
-            Op::Movup(5),
-            // OR the two halves together, giving us our fourth word, `x_lo1`
-            //
-            // [x_lo1, x_hi2, x_hi1, x_lo2]
-            Op::U32Or,
-            // Move to the bottom
-            //
-            // [x_hi2, x_hi1, x_lo2, x_lo1]
-            Op::Movdn(5),
-        ]);
+    /// If the pointer is not element-aligned, and the value does not fit in the remainder of the
+    /// element containing its first byte, the value spans two elements: the low bits are the
+    /// high bytes of the first element, and the high bits are the low bytes of the second.
+    ///
+    /// NOTE: The bits of the result beyond `size` bytes are not cleared.
+    fn load_unaligned_element_imm(&mut self, ptr: NativePtr, size: usize) {
+        let elem_ptr = NativePtr { offset: 0, ..ptr };
+        self.load_felt_imm(elem_ptr);
+        if ptr.is_element_aligned() {
+            return;
+        }
+
+        // Shift the low bits into place
+        let shift = ptr.offset as u32 * 8;
+        self.emit(Op::U32ShrImm(shift));
+        if ptr.offset as usize + size > 4 {
+            // Shift the high bits into place, and combine them with the low bits
+            self.load_felt_imm(element_ptr(elem_ptr, 1));
+            self.emit_all(&[Op::U32ShlImm(32 - shift), Op::U32Or]);
+        }
     }
 }
 
@@ -832,24 +558,12 @@ impl<'a> OpEmitter<'a> {
             Type::Ptr(_) => {
                 // Converet the pointer to a native pointer representation
                 self.emit_native_ptr();
-                match value_ty {
-                    Type::I128 => self.store_quad_word(None),
-                    Type::I64 | Type::U64 => self.store_double_word(None),
-                    Type::Felt => self.store_felt(None),
-                    Type::I32 | Type::U32 => self.store_word(None),
-                    ref ty if ty.size_in_bytes() <= 4 => self.store_small(ty, None),
-                    Type::Array(ref elem_ty, _) => self.store_array(elem_ty, None),
-                    Type::Struct(ref struct_ty) => self.store_struct(struct_ty, None),
-                    ty => unimplemented!(
-                        "invalid store: support for storing {ty} has not been implemented"
-                    ),
-                }
-            }
-            ty if !ty.is_pointer() => {
-                panic!("invalid operand to store: expected pointer, got {ty}")
             }
-            ty => unimplemented!("store support for pointers of type {ty} is not implemented"),
+            // Native pointers are already represented on the stack as a pointer triplet
+            Type::NativePtr(..) => (),
+            ty => panic!("invalid operand to store: expected pointer, got {ty}"),
         }
+        self.store_value(&value_ty, None);
     }
 
     /// Store a value of type `ty` to `addr`.
@@ -860,16 +574,25 @@ impl<'a> OpEmitter<'a> {
         let value_ty = value.ty();
         assert!(!value_ty.is_zst(), "cannot store a zero-sized type in memory");
         let ptr = NativePtr::from_ptr(addr);
-        match value_ty {
-            Type::I128 => self.store_quad_word(Some(ptr)),
-            Type::I64 | Type::U64 => self.store_double_word(Some(ptr)),
-            Type::Felt => self.store_felt(Some(ptr)),
-            Type::I32 | Type::U32 => self.store_word(Some(ptr)),
-            ref ty if ty.size_in_bytes() <= 4 => self.store_small(ty, Some(ptr)),
-            Type::Array(ref elem_ty, _) => self.store_array(elem_ty, Some(ptr)),
-            Type::Struct(ref struct_ty) => self.store_struct(struct_ty, Some(ptr)),
-            ty => {
-                unimplemented!("invalid store: support for storing {ty} has not been implemented")
+        self.store_value(&value_ty, Some(ptr));
+    }
+
+    /// Store a value of type `ty`, either to an immediate address, or to the native pointer
+    /// triplet on top of the stack.
+    ///
+    /// See [OpEmitter::load_value] for details on how values are laid out in memory.
+    fn store_value(&mut self, ty: &Type, ptr: Option<NativePtr>) {
+        match ty {
+            Type::I128 | Type::U128 => self.store_quad_word(ptr),
+            Type::I64 | Type::U64 | Type::F64 => self.store_double_word(ptr),
+            Type::Felt => self.store_felt(ptr),
+            Type::I32 | Type::U32 | Type::Ptr(_) => self.store_word(ptr),
+            Type::I1 | Type::I8 | Type::U8 | Type::I16 | Type::U16 => self.store_small(ty, ptr),
+            Type::Array(elem_ty, len) => self.store_array(elem_ty, *len, ptr),
+            Type::Struct(struct_ty) => self.store_struct(struct_ty, ptr),
+            Type::U256 | Type::NativePtr(..) => self.store_elements(ty.size_in_bytes(), ptr),
+            Type::Unknown | Type::Unit | Type::Never | Type::List(_) => {
+                unreachable!("invalid store: {ty} has no representation in memory")
             }
         }
     }
@@ -879,12 +602,20 @@ impl<'a> OpEmitter<'a> {
     /// The order of operands on the stack is `src`, `dst`, then `count`.
     ///
     /// The addresses on the stack are interpreted based on the pointer type: native pointers are
-    /// in the Miden address space; non-native pointers are assumed to be in the IR's byte
-    /// addressable address space, and require translation.
+    /// in the Miden address space, and are converted to raw pointers before copying; non-native
+    /// pointers are assumed to be in the IR's byte addressable address space, and require
+    /// translation.
     ///
     /// The semantics of this instruction are as follows:
     ///
-    /// * The ``
+    /// * The size of the region to copy is `count` multiplied by the size of the pointee type,
+    /// padded to its alignment, i.e. the stride of an array of the pointee type.
+    /// * If `src` and `dst` have the same alignment relative to a word boundary, the bulk of
+    /// the region is copied a word at a time, and only the unaligned leading and trailing bytes
+    /// are copied individually. Otherwise, the region is copied a byte at a time.
+    /// * The source and destination regions may overlap, i.e. this has the semantics of
+    /// `memmove`. When the destination starts within the source region, the region is copied a
+    /// byte at a time, starting from the end.
     pub fn memcpy(&mut self) {
         let src = self.stack.pop().expect("operand stack is empty");
         let dst = self.stack.pop().expect("operand stack is empty");
@@ -892,42 +623,396 @@ impl<'a> OpEmitter<'a> {
         assert_eq!(count.ty(), Type::U32, "expected count operand to be a u32");
         let ty = src.ty();
         assert_eq!(ty, dst.ty(), "expected src and dst operands to have the same type");
-        match ty {
-            Type::Ptr(ref _pointee) => {
-                todo!()
+        let pointee = match ty {
+            Type::Ptr(ref pointee) => pointee,
+            Type::NativePtr(ref pointee, _) => {
+                // Convert both pointers to raw pointers
+                //
+                // [src, dst_waddr, dst_index, dst_offset, count]
+                self.emit_byte_ptr();
+                // [dst, src, count]
+                self.emit(Op::Movdn(3));
+                self.emit_byte_ptr();
+                // [src, dst, count]
+                self.emit(Op::Swap(1));
+                pointee
             }
-            ty if !ty.is_pointer() => {
-                panic!("invalid operand to memcpy: expected pointer, got {ty}")
+            ty => panic!("invalid operand to memcpy: expected pointer, got {ty}"),
+        };
+        let stride = pointee.aligned_size_in_bytes() as u32;
+        if stride == 0 {
+            // There is nothing to copy
+            return self.emit_all(&[Op::Drop, Op::Drop, Op::Drop]);
+        }
+        // Compute the size of the region to copy in bytes
+        //
+        // [src, dst, num_bytes]
+        self.emit(Op::Movup(2));
+        self.mul_imm_u32(stride, Overflow::Checked);
+        self.emit_all(&[Op::Movdn(2), Op::Exec("intrinsics::mem::memcpy".parse().unwrap())]);
+    }
+
+    /// Write `count` copies of `value` to consecutive elements of an array starting at the
+    /// destination address, i.e. `count * sizeof(*ty)` bytes are written.
+    ///
+    /// The order of operands on the stack is `dst`, `count`, then `value`, where `value` must be
+    /// of the pointee type of `dst`. If `dst` is a native pointer, it is converted to a raw
+    /// pointer first.
+    ///
+    /// Byte-sized values are written a word at a time where possible, with only the unaligned
+    /// leading and trailing bytes written individually. Larger values are written one at a time.
+    pub fn memset(&mut self) {
+        let dst = self.stack.pop().expect("operand stack is empty");
+        let count = self.stack.pop().expect("operand stack is empty");
+        let value = self.stack.pop().expect("operand stack is empty");
+        assert_eq!(count.ty(), Type::U32, "expected count operand to be a u32");
+        let ty = dst.ty();
+        let pointee = match ty {
+            Type::Ptr(ref pointee) => pointee,
+            Type::NativePtr(ref pointee, _) => {
+                // Convert the destination to a raw pointer
+                //
+                // [dst, count, value]
+                self.emit_byte_ptr();
+                pointee
             }
-            ty => unimplemented!("memcpy support for pointers of type {ty} is not implemented"),
+            ty => panic!("invalid operand to memset: expected pointer, got {ty}"),
+        };
+        assert_eq!(
+            pointee.as_ref(),
+            &value.ty(),
+            "expected value operand to match the pointee type of dst"
+        );
+        let stride = pointee.aligned_size_in_bytes() as u32;
+        if stride == 0 {
+            // There is nothing to write
+            return self.emit_all(&[Op::Drop, Op::Drop]);
+        }
+        if stride == 1 {
+            return self.emit(Op::Exec("intrinsics::mem::memset".parse().unwrap()));
         }
+        self.memset_elements(pointee, stride, value.size());
     }
 
-    fn store_quad_word(&mut self, _ptr: Option<NativePtr>) {
-        todo!()
+    /// Emit a loop which stores `count` copies of a value of type `ty`, of `size` field elements,
+    /// to consecutive addresses `stride` bytes apart, starting at `dst`.
+    ///
+    /// Expects `dst`, `count`, and the value on the operand stack, in that order.
+    ///
+    /// Values which are too large to be copied with `dup` on each iteration, i.e. wider than 14
+    /// elements, are spilled to procedure locals before the loop, and reloaded from them on each
+    /// iteration instead.
+    fn memset_elements(&mut self, ty: &Type, stride: u32, size: usize) {
+        let spilled = if size > 14 {
+            // Move each element of the value from beneath `dst` and `count` to its slot
+            //
+            // [dst, count]
+            let slots = self.alloc_spill_slots(size);
+            for slot in slots.iter().copied() {
+                self.emit_all(&[Op::Movup(2), Op::LocStore(slot)]);
+            }
+            // The destination address is spilled on each iteration, as it may not be reachable
+            // with `dup` once the value has been reloaded
+            let dst_slot = self.function.alloc_local(Type::U32);
+            Some((slots, dst_slot))
+        } else {
+            None
+        };
+
+        let body_blk = self.function.create_block();
+        self.emit_all(&[
+            // Skip the loop entirely if there is nothing to write
+            //
+            // [count != 0, dst, count, value]
+            Op::Dup(1),
+            Op::NeqImm(Felt::ZERO),
+            Op::While(body_blk),
+        ]);
+
+        let prev = self.switch_to_block(body_blk);
+        match spilled {
+            None => {
+                // Copy the value to the top of the stack
+                for _ in 0..size {
+                    self.emit(Op::Dup(size as u8 + 1));
+                }
+                // Copy the destination address
+                //
+                // [dst, value, dst, count, value]
+                self.emit(Op::Dup(size as u8));
+            }
+            Some((ref slots, dst_slot)) => {
+                // Save a copy of the destination address, and reload the value
+                //
+                // [value, dst, count]
+                self.emit_all(&[Op::Dup(0), Op::LocStore(dst_slot)]);
+                for slot in slots.iter().rev().copied() {
+                    self.emit(Op::LocLoad(slot));
+                }
+                // [dst, value, dst, count]
+                self.emit(Op::LocLoad(dst_slot));
+            }
+        }
+        // Convert the destination address to a native pointer, and store the value
+        //
+        // [dst, count, ..]
+        self.emit_native_ptr();
+        self.store_value(ty, None);
+        self.emit_all(&[
+            // Advance to the next element
+            //
+            // [dst + stride, count - 1, ..]
+            Op::AddImm(Felt::new(stride as u64)),
+            Op::Swap(1),
+            Op::SubImm(Felt::ONE),
+            Op::Swap(1),
+            // Continue while there are elements remaining
+            //
+            // [count != 0, dst, count, ..]
+            Op::Dup(1),
+            Op::NeqImm(Felt::ZERO),
+        ]);
+        self.switch_to_block(prev);
+
+        // Drop the operands
+        self.emit_all(&[Op::Drop, Op::Drop]);
+        if spilled.is_none() {
+            for _ in 0..size {
+                self.emit(Op::Drop);
+            }
+        }
     }
 
-    fn store_double_word(&mut self, _ptr: Option<NativePtr>) {
-        todo!()
+    /// Store a quartet of machine words (32-bit elements) from the operand stack
+    fn store_quad_word(&mut self, ptr: Option<NativePtr>) {
+        match ptr {
+            // Naturally-aligned
+            Some(imm) if imm.is_word_aligned() => {
+                self.emit_all(&[Op::MemStorewImm(imm.waddr), Op::Dropw]);
+            }
+            ptr => self.store_elements(16, ptr),
+        }
     }
 
-    fn store_word(&mut self, _ptr: Option<NativePtr>) {
-        todo!()
+    /// Store a pair of machine words (32-bit elements) from the operand stack
+    fn store_double_word(&mut self, ptr: Option<NativePtr>) {
+        self.store_elements(8, ptr)
     }
 
-    fn store_felt(&mut self, _ptr: Option<NativePtr>) {
-        todo!()
+    /// Stores a single 32-bit machine word, i.e. a single field element, not the Miden notion of a
+    /// word
+    ///
+    /// Expects a native pointer triplet on the stack if an immediate address is not given.
+    fn store_word(&mut self, ptr: Option<NativePtr>) {
+        match ptr {
+            Some(imm) if imm.is_element_aligned() => self.store_felt_imm(imm),
+            Some(imm) => self.store_partial_element(4, Some(imm)),
+            None => self.emit(Op::Exec("intrinsics::mem::store_sw".parse().unwrap())),
+        }
     }
 
-    fn store_small(&mut self, _ty: &Type, _ptr: Option<NativePtr>) {
-        todo!()
+    /// Store a field element to a naturally aligned address, either immediate or dynamic
+    ///
+    /// A native pointer triplet is expected on the stack if an immediate is not given.
+    fn store_felt(&mut self, ptr: Option<NativePtr>) {
+        if let Some(imm) = ptr {
+            return self.store_felt_imm(imm);
+        }
+
+        self.emit(Op::Exec("intrinsics::mem::store_felt".parse().unwrap()));
     }
 
-    fn store_array(&mut self, _element_ty: &Type, _ptr: Option<NativePtr>) {
-        todo!()
+    fn store_felt_imm(&mut self, ptr: NativePtr) {
+        assert!(ptr.is_element_aligned(), "felt values must be naturally aligned");
+        if ptr.index == 0 {
+            return self.emit(Op::MemStoreImm(ptr.waddr));
+        }
+
+        // Load the word containing the element to be replaced
+        //
+        // [w0, w1, w2, w3, value]
+        self.emit_all(&[Op::Padw, Op::MemLoadwImm(ptr.waddr)]);
+        // Replace the element with the value
+        match ptr.index {
+            // [w0, value, w2, w3]
+            1 => self.emit_all(&[Op::Swap(1), Op::Drop, Op::Movup(3), Op::Swap(1)]),
+            // [w0, w1, value, w3]
+            2 => self.emit_all(&[Op::Movup(2), Op::Drop, Op::Movup(3), Op::Movdn(2)]),
+            // [w0, w1, w2, value]
+            3 => self.emit_all(&[Op::Movup(3), Op::Drop]),
+            _ => unreachable!(),
+        }
+        // Write the modified word back to memory
+        self.emit_all(&[Op::MemStorewImm(ptr.waddr), Op::Dropw]);
     }
 
-    fn store_struct(&mut self, _ty: &StructType, _ptr: Option<NativePtr>) {
-        todo!()
+    /// Store a value smaller than a single 32-bit machine word, i.e. one which fits in a single
+    /// field element, such as `u8`, `i16`, or a small aggregate.
+    ///
+    /// The bytes of the element which are not part of the value are left untouched.
+    ///
+    /// Expects a native pointer triplet on the stack if an immediate address is not given.
+    fn store_small(&mut self, ty: &Type, ptr: Option<NativePtr>) {
+        self.store_partial_element(ty.size_in_bytes(), ptr)
+    }
+
+    /// Store an array value whose elements are of type `element_ty`.
+    ///
+    /// The array is expected to be on the operand stack as its raw in-memory elements, see
+    /// [OpEmitter::load_elements].
+    fn store_array(&mut self, element_ty: &Type, len: usize, ptr: Option<NativePtr>) {
+        let size = Type::Array(Box::new(element_ty.clone()), len).size_in_bytes();
+        self.store_elements(size, ptr)
+    }
+
+    /// Store a struct value of type `ty`.
+    ///
+    /// The struct is expected to be on the operand stack as its raw in-memory elements, see
+    /// [OpEmitter::load_elements].
+    fn store_struct(&mut self, ty: &StructType, ptr: Option<NativePtr>) {
+        self.store_elements(ty.size(), ptr)
+    }
+
+    /// Store the first `size` bytes of the element on top of the operand stack, starting at the
+    /// byte offset of the pointer, leaving the other bytes of the destination untouched.
+    ///
+    /// If the value does not fit in the remainder of the element containing its first byte, the
+    /// remaining bytes are written to the low bytes of the following element.
+    ///
+    /// Expects a native pointer triplet on the stack if an immediate address is not given.
+    fn store_partial_element(&mut self, size: usize, ptr: Option<NativePtr>) {
+        let mask = element_mask(size);
+        match ptr {
+            Some(imm) => {
+                let shift = imm.offset as u32 * 8;
+                let elem_ptr = NativePtr { offset: 0, ..imm };
+                let is_split = imm.offset as usize + size > 4;
+                // Clear the bits which are not part of the value
+                //
+                // [value]
+                if mask != u32::MAX {
+                    self.emit_all(&[Op::PushU32(mask), Op::U32And]);
+                }
+                // If the value spans two elements, we need a copy of it for each
+                if is_split {
+                    self.emit(Op::Dup(0));
+                }
+                // Shift the value into position
+                if shift > 0 {
+                    self.emit(Op::U32ShlImm(shift));
+                }
+                // Load the element to be modified, and clear the bits to be replaced
+                //
+                // [element, value]
+                self.load_felt_imm(elem_ptr);
+                self.emit_all(&[Op::PushU32(!(mask << shift)), Op::U32And, Op::U32Or]);
+                self.store_felt_imm(elem_ptr);
+                if is_split {
+                    // Shift the remaining bits of the value into position, and write them to the
+                    // following element in the same way
+                    let next_ptr = element_ptr(elem_ptr, 1);
+                    self.emit(Op::U32ShrImm(32 - shift));
+                    self.load_felt_imm(next_ptr);
+                    self.emit_all(&[Op::PushU32(!(mask >> (32 - shift))), Op::U32And, Op::U32Or]);
+                    self.store_felt_imm(next_ptr);
+                }
+            }
+            None => {
+                self.emit_all(&[
+                    // Compute the element address
+                    //
+                    // [addr, offset, mask, value]
+                    Op::MulImm(Felt::new(4)),
+                    Op::Add,
+                    Op::PushU32(mask),
+                    Op::Movdn(2),
+                    Op::Exec("intrinsics::mem::store_unaligned_element".parse().unwrap()),
+                ]);
+            }
+        }
+    }
+
+    /// Store a value of `size` bytes which spans one or more field elements, e.g. an array or
+    /// struct.
+    ///
+    /// The value is expected to be on the operand stack as its raw in-memory elements, with the
+    /// first element on top of the stack. If `size` is not a multiple of the element size, only
+    /// the bytes of the last element which are part of the value are written. If the pointer is
+    /// not element-aligned, each element is split across the two elements it spans, see
+    /// [OpEmitter::store_partial_element].
+    ///
+    /// Expects a native pointer triplet on the stack if an immediate address is not given.
+    fn store_elements(&mut self, size: usize, ptr: Option<NativePtr>) {
+        let num_elements = (size / 4) + (size % 4 > 0) as usize;
+        let trailing = size % 4;
+        match ptr {
+            Some(imm) => {
+                for i in 0..num_elements {
+                    let elem_ptr = element_ptr(imm, i);
+                    if i == num_elements - 1 && trailing > 0 {
+                        self.store_partial_element(trailing, Some(elem_ptr));
+                    } else if imm.is_element_aligned() {
+                        self.store_felt_imm(elem_ptr);
+                    } else {
+                        self.store_partial_element(4, Some(elem_ptr));
+                    }
+                }
+            }
+            None => {
+                self.emit_all(&[
+                    // Compute the element address of the first element
+                    //
+                    // [element_addr, offset, value..]
+                    Op::MulImm(Felt::new(4)),
+                    Op::Add,
+                ]);
+                for i in 0..num_elements {
+                    let mask = if i == num_elements - 1 && trailing > 0 {
+                        element_mask(trailing)
+                    } else {
+                        u32::MAX
+                    };
+                    self.emit_all(&[
+                        // Copy the element address and byte offset
+                        //
+                        // [element_addr, offset, mask, element_addr, offset, element, value..]
+                        Op::Dup(1),
+                        Op::Dup(1),
+                        Op::PushU32(mask),
+                        Op::Movdn(2),
+                        // [element_addr, offset, mask, element, element_addr, offset, value..]
+                        Op::Movup(5),
+                        Op::Movdn(3),
+                        // [element_addr, offset, value..]
+                        Op::Exec("intrinsics::mem::store_unaligned_element".parse().unwrap()),
+                    ]);
+                    if i < num_elements - 1 {
+                        self.emit(Op::Incr);
+                    }
+                }
+                // Drop the element address and byte offset
+                self.emit_all(&[Op::Drop, Op::Drop]);
+            }
+        }
+    }
+}
+
+/// Returns a mask covering the low `size` bytes of a 32-bit element
+fn element_mask(size: usize) -> u32 {
+    if size >= 4 {
+        u32::MAX
+    } else {
+        (1u32 << (size * 8)) - 1
+    }
+}
+
+/// Returns a pointer `n` elements, i.e. `n * 4` bytes, past `ptr`
+fn element_ptr(ptr: NativePtr, n: usize) -> NativePtr {
+    let addr = (ptr.waddr * 4) + ptr.index as u32 + n as u32;
+    NativePtr {
+        waddr: addr / 4,
+        index: (addr % 4) as u8,
+        offset: ptr.offset,
+        addrspace: ptr.addrspace,
     }
 }
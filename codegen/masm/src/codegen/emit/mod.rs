@@ -122,6 +122,11 @@ impl<'a> InstOpEmitter<'a> {
         self.emitter.exec(import);
     }
 
+    pub fn call(&mut self, callee: hir::FunctionIdent) {
+        let import = self.dfg.get_import(&callee).unwrap();
+        self.emitter.call(import);
+    }
+
     pub fn syscall(&mut self, callee: hir::FunctionIdent) {
         let import = self.dfg.get_import(&callee).unwrap();
         self.emitter.syscall(import);
@@ -558,7 +563,6 @@ impl<'a> OpEmitter<'a> {
         self.function.body.block_mut(self.current_block)
     }
 
-    #[allow(unused)]
     #[inline]
     pub fn switch_to_block(&mut self, block: masm::BlockId) -> masm::BlockId {
         let prev = self.current_block;
@@ -575,10 +579,17 @@ impl<'a> OpEmitter<'a> {
 
 #[cfg(test)]
 mod tests {
-    use miden_hir::{AbiParam, Felt, FieldElement, Overflow, Signature};
+    use miden_hir::{
+        testing::TestContext, AbiParam, AddressSpace, Felt, FieldElement, Ident, Overflow,
+        Signature, StarkField, StructType, Symbol,
+    };
 
     use super::*;
-    use crate::{codegen::TypedValue, masm::Function};
+    use crate::{
+        codegen::TypedValue,
+        masm::{intrinsics, Function, Module, NativePtr},
+        Emulator,
+    };
 
     #[test]
     fn op_emitter_stack_manipulation_test() {
@@ -1868,6 +1879,239 @@ mod tests {
         assert_eq!(emitter.stack()[1], Type::U32);
     }
 
+    #[test]
+    fn op_emitter_struct_store_test() {
+        let mut function = setup();
+        let entry = function.body.id();
+        let mut stack = OperandStack::default();
+        let mut emitter = OpEmitter::new(&mut function, entry, &mut stack);
+
+        let struct_ty = Type::Struct(StructType::new([Type::U32, Type::U64, Type::U8]));
+        let addr = Type::Ptr(Box::new(struct_ty.clone()));
+
+        emitter.push(addr.clone());
+        emitter.load(struct_ty.clone());
+        assert_eq!(emitter.stack_len(), 1);
+        assert_eq!(emitter.stack()[0], struct_ty);
+
+        emitter.push(addr);
+        assert_eq!(emitter.stack_len(), 2);
+
+        emitter.store();
+        assert_eq!(emitter.stack_len(), 0);
+
+        emitter.push(Type::U16);
+        emitter.store_imm(130);
+        assert_eq!(emitter.stack_len(), 0);
+    }
+
+    #[test]
+    fn op_emitter_unaligned_load_test() {
+        let bytes = (0..48).map(|i| (i * 7 + 1) as u8).collect::<Vec<_>>();
+        for ty in unaligned_test_types() {
+            let size = ty.size_in_bytes();
+            for offset in 0..16 {
+                let expected = to_elements(&bytes[offset..(offset + size)]);
+
+                // Dynamic address
+                let (results, memory) = emulate(&bytes, |emitter, addr| {
+                    emitter.push(Type::Ptr(Box::new(ty.clone())));
+                    emitter.load(ty.clone());
+                    vec![Felt::new((addr as usize + offset) as u64)]
+                });
+                assert_eq!(results, expected, "invalid load of {ty} at offset {offset}");
+                assert_eq!(memory, bytes, "unexpected write when loading {ty} at offset {offset}");
+
+                // Immediate address
+                let (results, _) = emulate(&bytes, |emitter, addr| {
+                    emitter.load_imm(addr + offset as u32, ty.clone());
+                    vec![]
+                });
+                assert_eq!(results, expected, "invalid load of {ty} at immediate offset {offset}");
+            }
+        }
+    }
+
+    #[test]
+    fn op_emitter_unaligned_store_test() {
+        let bytes = (0..48).map(|i| (i * 7 + 1) as u8).collect::<Vec<_>>();
+        let value_bytes = (0..16).map(|i| 0xa0 + i as u8).collect::<Vec<_>>();
+        for ty in unaligned_test_types() {
+            let size = ty.size_in_bytes();
+            let value =
+                to_elements(&value_bytes[..size]).into_iter().map(Felt::new).collect::<Vec<_>>();
+            for offset in 0..16 {
+                let mut expected = bytes.clone();
+                expected[offset..(offset + size)].copy_from_slice(&value_bytes[..size]);
+
+                // Dynamic address
+                let (results, memory) = emulate(&bytes, |emitter, addr| {
+                    emitter.push(ty.clone());
+                    emitter.push(Type::Ptr(Box::new(ty.clone())));
+                    emitter.store();
+                    let mut args = vec![Felt::new((addr as usize + offset) as u64)];
+                    args.extend_from_slice(&value);
+                    args
+                });
+                assert!(results.is_empty(), "expected store of {ty} to consume its operands");
+                assert_eq!(memory, expected, "invalid store of {ty} at offset {offset}");
+
+                // Immediate address
+                let (results, memory) = emulate(&bytes, |emitter, addr| {
+                    emitter.push(ty.clone());
+                    emitter.store_imm(addr + offset as u32);
+                    value.clone()
+                });
+                assert!(results.is_empty(), "expected store of {ty} to consume its operands");
+                assert_eq!(memory, expected, "invalid store of {ty} at immediate offset {offset}");
+            }
+        }
+    }
+
+    #[test]
+    fn op_emitter_native_ptr_memcpy_test() {
+        let bytes = (0..48).map(|i| (i * 7 + 1) as u8).collect::<Vec<_>>();
+        let ptr_ty = Type::NativePtr(Box::new(Type::U16), AddressSpace::Unknown);
+
+        // Copy 7 u16 values from offset 1 to offset 22
+        let mut expected = bytes.clone();
+        expected.copy_within(1..15, 22);
+
+        let (results, memory) = emulate(&bytes, |emitter, addr| {
+            emitter.push(Type::U32);
+            emitter.push(ptr_ty.clone());
+            emitter.push(ptr_ty.clone());
+            emitter.memcpy();
+            let mut args = native_ptr(addr + 1);
+            args.extend(native_ptr(addr + 22));
+            args.push(Felt::new(7));
+            args
+        });
+        assert!(results.is_empty(), "expected memcpy to consume its operands");
+        assert_eq!(memory, expected, "invalid memcpy between native pointers");
+    }
+
+    #[test]
+    fn op_emitter_native_ptr_memset_test() {
+        let bytes = (0..48).map(|i| (i * 7 + 1) as u8).collect::<Vec<_>>();
+
+        // Write 9 bytes starting at offset 3
+        let mut expected = bytes.clone();
+        expected[3..12].fill(0xab);
+        let (results, memory) = emulate(&bytes, |emitter, addr| {
+            emitter.push(Type::U8);
+            emitter.push(Type::U32);
+            emitter.push(Type::NativePtr(Box::new(Type::U8), AddressSpace::Unknown));
+            emitter.memset();
+            let mut args = native_ptr(addr + 3);
+            args.extend([Felt::new(9), Felt::new(0xab)]);
+            args
+        });
+        assert!(results.is_empty(), "expected memset to consume its operands");
+        assert_eq!(memory, expected, "invalid memset of u8 via a native pointer");
+
+        // Write 3 u32 values starting at offset 26
+        let mut expected = bytes.clone();
+        for i in 0..3 {
+            let offset = 26 + i * 4;
+            expected[offset..(offset + 4)].copy_from_slice(&0x12345678u32.to_le_bytes());
+        }
+        let (results, memory) = emulate(&bytes, |emitter, addr| {
+            emitter.push(Type::U32);
+            emitter.push(Type::U32);
+            emitter.push(Type::NativePtr(Box::new(Type::U32), AddressSpace::Unknown));
+            emitter.memset();
+            let mut args = native_ptr(addr + 26);
+            args.extend([Felt::new(3), Felt::new(0x12345678)]);
+            args
+        });
+        assert!(results.is_empty(), "expected memset to consume its operands");
+        assert_eq!(memory, expected, "invalid memset of u32 via a native pointer");
+    }
+
+    /// Convert the byte address `addr` to the native pointer triplet representing it on the
+    /// operand stack, with the word address first
+    fn native_ptr(addr: u32) -> Vec<Felt> {
+        let ptr = NativePtr::from_ptr(addr);
+        vec![
+            Felt::new(ptr.waddr as u64),
+            Felt::new(ptr.index as u64),
+            Felt::new(ptr.offset as u64),
+        ]
+    }
+
+    /// The types used to exercise loads and stores at every byte offset relative to a word
+    fn unaligned_test_types() -> [Type; 7] {
+        [
+            Type::U8,
+            Type::I16,
+            Type::Array(Box::new(Type::U8), 3),
+            Type::U32,
+            Type::U64,
+            Type::Array(Box::new(Type::U8), 11),
+            Type::U128,
+        ]
+    }
+
+    /// Convert `bytes` to the elements representing them on the operand stack, i.e. one element
+    /// per 4 bytes, in little-endian order, with the last element zero-padded
+    fn to_elements(bytes: &[u8]) -> Vec<u64> {
+        bytes
+            .chunks(4)
+            .map(|chunk| {
+                let mut elem = [0u8; 4];
+                elem[..chunk.len()].copy_from_slice(chunk);
+                u32::from_le_bytes(elem) as u64
+            })
+            .collect()
+    }
+
+    /// Emit a function using `emit`, and execute it in the emulator, with `bytes` written to
+    /// memory at a word-aligned address.
+    ///
+    /// The callback receives the address of the data, and returns the arguments to the function.
+    /// Returns the operand stack on exit, top first, and the final contents of the data.
+    fn emulate<F>(bytes: &[u8], emit: F) -> (Vec<u64>, Vec<u8>)
+    where
+        F: FnOnce(&mut OpEmitter<'_>, u32) -> Vec<Felt>,
+    {
+        let context = TestContext::default();
+        let mut emulator = Emulator::default();
+        let addr = emulator.malloc(bytes.len());
+        for (i, elem) in to_elements(bytes).into_iter().enumerate() {
+            emulator.store(addr as usize + i * 4, Felt::new(elem));
+        }
+
+        let mut function = setup();
+        let entry = function.body.id();
+        let mut stack = OperandStack::default();
+        let args = {
+            let mut emitter = OpEmitter::new(&mut function, entry, &mut stack);
+            emit(&mut emitter, addr)
+        };
+        let mut module = Box::new(Module::new(Ident::with_empty_span(Symbol::intern("test"))));
+        module.push_back(Box::new(function));
+
+        let mem = intrinsics::load("intrinsics::mem", &context.session.codemap)
+            .expect("undefined intrinsics module");
+        emulator
+            .load_module(Box::new(mem).freeze())
+            .expect("failed to load intrinsics::mem");
+        emulator.load_module(module.freeze()).expect("failed to load module");
+        let mut stack =
+            emulator.invoke("test::test".parse().unwrap(), &args).expect("execution failed");
+
+        let mut results = vec![];
+        while let Some(elem) = stack.pop() {
+            results.push(elem.as_int());
+        }
+        let memory = (0..bytes.len())
+            .step_by(4)
+            .flat_map(|i| (emulator.load(addr as usize + i).as_int() as u32).to_le_bytes())
+            .collect();
+        (results, memory)
+    }
+
     #[inline]
     fn setup() -> Function {
         Function::new(
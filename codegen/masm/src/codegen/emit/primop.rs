@@ -195,7 +195,48 @@ impl<'a> OpEmitter<'a> {
     ///
     /// A function called using this operation is invoked in the same memory context as the caller.
     pub fn exec(&mut self, callee: &hir::ExternalFunction) {
-        let import = callee;
+        assert!(
+            !callee.signature.is_kernel(),
+            "invalid exec to {}: kernel functions must be invoked via syscall",
+            callee.id
+        );
+        self.prepare_call(callee);
+        self.emit(Op::Exec(callee.id));
+    }
+
+    /// Call the given procedure in a new memory context.
+    ///
+    /// A function called using this operation cannot access the memory of the caller, and only
+    /// the top 16 elements of the operand stack are visible to it, which must be sufficient to hold
+    /// both its arguments and results.
+    pub fn call(&mut self, callee: &hir::ExternalFunction) {
+        assert!(
+            !callee.signature.is_kernel(),
+            "invalid call to {}: kernel functions must be invoked via syscall",
+            callee.id
+        );
+        self.prepare_call(callee);
+        self.emit(Op::Call(callee.id));
+    }
+
+    /// Execute the given kernel procedure as a syscall.
+    ///
+    /// A function called using this operation is invoked in the root context of the kernel, but
+    /// the operand stack is shared with the caller, so arguments and results are passed the same
+    /// way as with [Self::exec].
+    pub fn syscall(&mut self, callee: &hir::ExternalFunction) {
+        assert!(
+            callee.signature.is_kernel(),
+            "invalid syscall to {}: the callee must use the kernel calling convention",
+            callee.id
+        );
+        self.prepare_call(callee);
+        self.emit(Op::Syscall(callee.id));
+    }
+
+    /// Validate the arguments on the operand stack against the signature of `import`, applying
+    /// any extensions required by the callee's ABI, and then replace them with its results.
+    fn prepare_call(&mut self, import: &hir::ExternalFunction) {
         let callee = import.id;
         let signature = &import.signature;
         for i in 0..signature.arity() {
@@ -307,14 +348,5 @@ impl<'a> OpEmitter<'a> {
         for result in signature.results.iter() {
             self.stack.push(result.ty.clone());
         }
-
-        self.emit(Op::Exec(callee));
-    }
-
-    /// Execute the given procedure as a syscall.
-    ///
-    /// A function called using this operation is invoked in the same memory context as the caller.
-    pub fn syscall(&mut self, _callee: &hir::ExternalFunction) {
-        todo!()
     }
 }
@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::{collections::BTreeMap, rc::Rc};
 
 use cranelift_entity::SecondaryMap;
 use miden_hir::{self as hir, adt::SparseMap, assert_matches};
@@ -11,10 +11,13 @@ use super::{
     emit::{InstOpEmitter, OpEmitter},
     opt::{OperandMovementConstraintSolver, SolverError},
     scheduler::{BlockInfo, InstInfo, Schedule, ScheduleOp},
-    Constraint, OperandStack,
+    Constraint, OperandStack, TypedValue,
 };
 use crate::masm::{self, Op};
 
+/// The number of operand stack elements which are directly accessible via `dup`, `movup`, etc.
+const MAX_ACCESSIBLE_ELEMENTS: usize = 16;
+
 pub struct FunctionEmitter<'a> {
     f: &'a hir::Function,
     f_prime: &'a mut masm::Function,
@@ -23,6 +26,17 @@ pub struct FunctionEmitter<'a> {
     liveness: &'a LivenessAnalysis,
     globals: &'a GlobalVariableLayout,
     visited: SecondaryMap<hir::Block, bool>,
+    /// The procedure locals to which a value is written when spilled from the operand stack
+    ///
+    /// Since values are immutable, the slots allocated for a value are reused every time it
+    /// is spilled.
+    spills: BTreeMap<hir::Value, SmallVec<[masm::LocalId; 4]>>,
+    /// The state of the operand stack just before the terminator of each loop header, as
+    /// observed when the loop header was first emitted.
+    ///
+    /// Every time the loop header is emitted along a loopback edge, the operand stack must be
+    /// restored to this state, so that each iteration of the loop sees the same stack layout.
+    loop_header_stacks: BTreeMap<hir::Block, OperandStack>,
 }
 
 struct BlockEmitter<'b, 'f: 'b> {
@@ -103,6 +117,8 @@ impl<'a> FunctionEmitter<'a> {
             liveness,
             globals,
             visited: SecondaryMap::new(),
+            spills: Default::default(),
+            loop_header_stacks: Default::default(),
         }
     }
 
@@ -183,12 +199,10 @@ impl<'b, 'f: 'b> BlockEmitter<'b, 'f> {
                 ScheduleOp::Init(_) | ScheduleOp::Enter(_) | ScheduleOp::Exit => continue,
                 ScheduleOp::Inst(inst_info) => self.emit_inst(inst_info, tasks),
                 ScheduleOp::Drop(value) => {
-                    let mut emitter = self.emitter();
-                    let pos = emitter
-                        .stack()
-                        .find(value)
-                        .expect("could not find value on the operand stack");
-                    emitter.drop_operand_at_position(pos);
+                    // If the value was spilled, there is nothing left to drop
+                    if let Some(pos) = self.stack.find(value) {
+                        self.emitter().drop_operand_at_position(pos);
+                    }
                 }
             }
         }
@@ -197,6 +211,26 @@ impl<'b, 'f: 'b> BlockEmitter<'b, 'f> {
     fn emit_inst(&mut self, inst_info: &InstInfo, tasks: &mut Tasks) {
         use miden_hir::Instruction;
 
+        // Make sure all of the operands are on the operand stack and can be reached, spilling
+        // other values to procedure locals as necessary.
+        //
+        // The terminator of a loop header which is being emitted along a loopback edge must
+        // leave the stack in the same state as when the loop was entered, so in that case we
+        // restore that state instead.
+        let is_loop_header_terminator =
+            self.block_info.is_loop_header() && self.block_info.last_inst == inst_info.inst;
+        if is_loop_header_terminator && self.visited {
+            let stack = self.function.loop_header_stacks[&self.block_info.source].clone();
+            self.restore_operand_stack(&stack);
+        } else {
+            self.prepare_operands(inst_info);
+            if is_loop_header_terminator {
+                self.function
+                    .loop_header_stacks
+                    .insert(self.block_info.source, self.stack.clone());
+            }
+        }
+
         // Move instruction operands into place, minimizing unnecessary stack manipulation ops
         //
         // NOTE: This does not include block arguments for control flow instructions, those are
@@ -677,6 +711,11 @@ impl<'b, 'f: 'b> BlockEmitter<'b, 'f> {
                 assert_eq!(args.len(), 3);
                 emitter.memcpy();
             }
+            // Write `count` copies of a value to consecutive elements starting at an address
+            hir::Opcode::MemSet => {
+                assert_eq!(args.len(), 3);
+                emitter.memset();
+            }
             // Conditionally select between two values
             hir::Opcode::Select => {
                 assert_eq!(args.len(), 3);
@@ -699,6 +738,7 @@ impl<'b, 'f: 'b> BlockEmitter<'b, 'f> {
         match op.op {
             hir::Opcode::Syscall => emitter.syscall(op.callee),
             hir::Opcode::Call => emitter.exec(op.callee),
+            hir::Opcode::ContextCall => emitter.call(op.callee),
             opcode => unimplemented!("unrecognized procedure call opcode: '{opcode}'"),
         }
     }
@@ -756,7 +796,11 @@ impl<'b, 'f: 'b> BlockEmitter<'b, 'f> {
                         *body_blk = mapped_blocks[prev_body_blk];
                         rewrites.push((prev_body_blk, *body_blk));
                     }
-                    Op::LocAddr(_) | Op::LocStore(_) | Op::LocStorew(_) => {
+                    Op::LocAddr(_)
+                    | Op::LocLoad(_)
+                    | Op::LocLoadw(_)
+                    | Op::LocStore(_)
+                    | Op::LocStorew(_) => {
                         unimplemented!(
                             "locals are not currently supported in inline assembly blocks"
                         )
@@ -911,6 +955,171 @@ impl<'b, 'f: 'b> BlockEmitter<'b, 'f> {
         }
     }
 
+    /// Ensure the operands of `inst_info`, including any block arguments it passes to its
+    /// successors, are on the operand stack and within reach of the stack manipulation
+    /// instructions, spilling other values to procedure locals as necessary.
+    ///
+    /// Operands which were spilled previously are reloaded. Room is also reserved for any
+    /// copies the operand scheduler must make, and for the results of the instruction, so that
+    /// every value on the operand stack is still reachable once the instruction is emitted.
+    fn prepare_operands(&mut self, inst_info: &InstInfo) {
+        let inst = inst_info.inst;
+        let dfg = &self.function.f.dfg;
+        let args = dfg.inst_args(inst);
+        let mut operands = SmallVec::<[hir::Value; 8]>::from_slice(args);
+        let mut reserved = copied_size(dfg, args, inst_info.plain_arguments());
+        // Block arguments are scheduled separately for each successor, so we only need to
+        // reserve room for the successor requiring the most copies
+        match dfg.analyze_branch(inst) {
+            hir::BranchInfo::NotABranch => (),
+            hir::BranchInfo::SingleDest(block, args) => {
+                operands.extend_from_slice(args);
+                reserved += copied_size(dfg, args, inst_info.block_arguments(block));
+            }
+            hir::BranchInfo::MultiDest(ref jts) => {
+                let mut reserved_for_successors = 0;
+                for jt in jts.iter() {
+                    operands.extend_from_slice(jt.args);
+                    reserved_for_successors = core::cmp::max(
+                        reserved_for_successors,
+                        copied_size(dfg, jt.args, inst_info.block_arguments(jt.destination)),
+                    );
+                }
+                reserved += reserved_for_successors;
+            }
+        }
+        reserved += dfg
+            .inst_results(inst)
+            .iter()
+            .map(|result| dfg.value_type(*result).size_in_felts())
+            .sum::<usize>();
+        operands.sort();
+        operands.dedup();
+
+        let spilled = operands
+            .iter()
+            .copied()
+            .filter(|operand| self.stack.find(operand).is_none())
+            .collect::<SmallVec<[hir::Value; 4]>>();
+        let spilled_size = spilled
+            .iter()
+            .map(|value| dfg.value_type(*value).size_in_felts())
+            .sum::<usize>();
+
+        let pp = hir::ProgramPoint::Inst(inst);
+        while self.stack.raw_len() + spilled_size + reserved > MAX_ACCESSIBLE_ELEMENTS {
+            let Some(index) = self.select_spill_candidate(&operands, pp) else {
+                break;
+            };
+            let value = self.stack[index].as_value().unwrap();
+            let is_live = self.function.liveness.next_uses(pp).is_live(&value);
+            self.spill_operand(index, is_live);
+        }
+
+        for value in spilled {
+            let slots = self.function.spills.get(&value).cloned().unwrap_or_else(|| {
+                panic!("expected {value} to be on the operand stack, or to have been spilled")
+            });
+            let ty = self.function.f.dfg.value_type(value).clone();
+            self.emitter().reload(TypedValue { value, ty }, &slots);
+        }
+    }
+
+    /// Select the operand which should be spilled to make room on the operand stack at `pp`,
+    /// ignoring any of the `pinned` values.
+    ///
+    /// Redundant copies are preferred, as they can simply be dropped, followed by the value
+    /// whose next use is furthest away, as determined by liveness analysis.
+    fn select_spill_candidate(
+        &self,
+        pinned: &[hir::Value],
+        pp: hir::ProgramPoint,
+    ) -> Option<usize> {
+        let mut candidate = None;
+        let mut candidate_distance = 0;
+        let mut depth = 0;
+        for (index, operand) in self.stack.iter().rev().enumerate() {
+            depth += operand.size();
+            if depth > MAX_ACCESSIBLE_ELEMENTS {
+                break;
+            }
+            let Some(value) = operand.as_value() else {
+                continue;
+            };
+            if pinned.contains(&value) {
+                continue;
+            }
+            let distance = if self.count_copies(value) > 1 {
+                u32::MAX
+            } else {
+                self.function.liveness.next_use(&value, pp)
+            };
+            if candidate.is_none() || distance >= candidate_distance {
+                candidate = Some(index);
+                candidate_distance = distance;
+            }
+        }
+        candidate
+    }
+
+    /// Remove the `n`th operand from the operand stack.
+    ///
+    /// If the value is live, and this is the only copy of it on the stack, it is written to
+    /// the procedure locals reserved for it first, so that it can be reloaded later.
+    fn spill_operand(&mut self, n: usize, is_live: bool) {
+        let operand = &self.stack[n];
+        let value = operand.as_value().expect("expected a value operand");
+        let size = operand.size();
+        if !is_live || self.count_copies(value) > 1 {
+            self.emitter().drop_operand_at_position(n);
+            return;
+        }
+
+        let mut emitter = OpEmitter::new(self.function.f_prime, self.target, &mut self.stack);
+        let slots = self
+            .function
+            .spills
+            .entry(value)
+            .or_insert_with(|| emitter.alloc_spill_slots(size));
+        emitter.spill(n as u8, slots);
+    }
+
+    /// Restore the operand stack to `expected`, the state it was in at the same point in the
+    /// first emission of the current loop header.
+    ///
+    /// If values were spilled or reloaded since then, we write everything on the stack to
+    /// procedure locals, and reload the expected values in order.
+    fn restore_operand_stack(&mut self, expected: &OperandStack) {
+        let is_restored = self.stack.len() == expected.len()
+            && self
+                .stack
+                .iter()
+                .zip(expected.iter())
+                .all(|(a, b)| a.as_value() == b.as_value());
+        if is_restored {
+            return;
+        }
+
+        let expected_values = expected
+            .iter()
+            .map(|operand| operand.as_value().expect("expected a value operand"))
+            .collect::<SmallVec<[hir::Value; 16]>>();
+        while !self.stack.is_empty() {
+            let value = self.stack[0].as_value().expect("expected a value operand");
+            self.spill_operand(0, expected_values.contains(&value));
+        }
+        for (value, operand) in expected_values.into_iter().zip(expected.iter()) {
+            let slots = self.function.spills[&value].clone();
+            let ty = operand.ty();
+            self.emitter().reload(TypedValue { value, ty }, &slots);
+        }
+    }
+
+    /// Returns the number of times `value` appears on the operand stack
+    fn count_copies(&self, value: hir::Value) -> usize {
+        self.stack.iter().filter(|operand| operand.as_value() == Some(value)).count()
+    }
+
     fn schedule_operands(
         &mut self,
         expected: &[hir::Value],
@@ -1079,3 +1288,13 @@ impl<'b, 'f: 'b> BlockEmitter<'b, 'f> {
         OpEmitter::new(self.function.f_prime, self.target, &mut self.stack)
     }
 }
+
+/// Returns the number of elements pushed on the operand stack when copying the operands in
+/// `args` which have copy semantics
+fn copied_size(dfg: &hir::DataFlowGraph, args: &[hir::Value], constraints: &[Constraint]) -> usize {
+    args.iter()
+        .zip(constraints.iter())
+        .filter(|(_, constraint)| matches!(constraint, Constraint::Copy))
+        .map(|(arg, _)| dfg.value_type(*arg).size_in_felts())
+        .sum()
+}
@@ -6,6 +6,7 @@ mod stack;
 
 pub use self::{
     emitter::FunctionEmitter,
+    opt::PeepholeOptimizer,
     scheduler::Scheduler,
     stack::{Constraint, Operand, OperandStack, TypedValue},
 };
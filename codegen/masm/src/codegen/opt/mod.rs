@@ -1,3 +1,7 @@
 pub mod operands;
+mod peephole;
 
-pub use self::operands::{OperandMovementConstraintSolver, SolverError};
+pub use self::{
+    operands::{OperandMovementConstraintSolver, SolverError},
+    peephole::PeepholeOptimizer,
+};
@@ -0,0 +1,276 @@
+use miden_hir::{Felt, FieldElement, StarkField};
+use smallvec::{smallvec, SmallVec};
+
+use crate::masm::{self, cycles, Op};
+
+/// The replacement produced by a successful application of a [Rule]
+type Rewrite = SmallVec<[Op; 2]>;
+
+/// A peephole rewrite rule over a fixed-length window of instructions
+///
+/// Rules are declared using the `rules!` macro, see [RULES] for the full set.
+struct Rule {
+    /// A short description of the rule, used when tracing
+    name: &'static str,
+    /// The number of instructions matched by this rule
+    len: usize,
+    /// Attempts to match exactly `len` instructions, returning the replacement on success
+    rewrite: fn(&[Op]) -> Option<Rewrite>,
+}
+
+macro_rules! count {
+    () => { 0usize };
+    ($head:pat $(, $tail:pat)*) => { 1usize + count!($($tail),*) };
+}
+
+/// Declares a table of peephole rules.
+///
+/// Each rule has the form `"name": [pattern, ..] if guard => [replacement, ..];`, where
+/// the patterns are matched against a window of consecutive instructions, the guard is optional,
+/// and the replacement may refer to any bindings introduced by the patterns.
+///
+/// Every rule must produce fewer instructions than it matches, which is what guarantees that
+/// rewriting terminates.
+macro_rules! rules {
+    ($($name:literal: [$($pat:pat),+] $(if $guard:expr)? => [$($out:expr),*];)*) => {
+        &[$(
+            Rule {
+                name: $name,
+                len: count!($($pat),+),
+                rewrite: |ops| match ops {
+                    [$($pat),+] $(if $guard)? => Some(smallvec![$($out),*]),
+                    _ => None,
+                },
+            }
+        ),*]
+    };
+}
+
+/// The set of rules applied by the [PeepholeOptimizer]
+static RULES: &[Rule] = rules! {
+    "swap.n swap.n": [Op::Swap(a), Op::Swap(b)] if a == b => [];
+    "dup.n drop": [Op::Dup(_), Op::Drop] => [];
+    "movup.n movdn.n": [Op::Movup(a), Op::Movdn(b)] if a == b => [];
+    "movdn.n movup.n": [Op::Movdn(a), Op::Movup(b)] if a == b => [];
+    "padw dropw": [Op::Padw, Op::Dropw] => [];
+    "push drop": [push, Op::Drop] if is_push(push) => [];
+    "push.0 add": [push, Op::Add] if is_zero(push) => [];
+    "add.0": [Op::AddImm(imm)] if imm.as_int() == 0 => [];
+    "u32assert of u32": [prev, Op::U32Assert | Op::U32AssertWithError(_)] if produces_u32(prev) => [*prev];
+    "u32assert2 of u32 pair": [prev, Op::U32Assert2 | Op::U32Assert2WithError(_)] if produces_u32_pair(prev) => [*prev];
+};
+
+/// The maximum number of instructions matched by any rule in [RULES]
+const MAX_RULE_LEN: usize = 2;
+
+/// A rule-based peephole optimizer for Miden Assembly.
+///
+/// This is run over each function produced by the [crate::codegen::FunctionEmitter] when
+/// optimizations are enabled, and cleans up the redundant stack manipulation and assertions
+/// which fall out of emitting each instruction in isolation.
+#[derive(Default)]
+pub struct PeepholeOptimizer;
+impl PeepholeOptimizer {
+    /// Rewrite every block of `function` until no more rules apply.
+    ///
+    /// Returns the number of cycles saved, according to [crate::masm::cycles].
+    pub fn optimize(&self, function: &mut masm::Function) -> usize {
+        let mut saved = 0;
+        for block in function.body.blocks.values_mut() {
+            saved += self.optimize_block(block);
+        }
+        saved
+    }
+
+    /// Rewrite `block` until no more rules apply.
+    ///
+    /// Returns the number of cycles saved, according to [crate::masm::cycles].
+    pub fn optimize_block(&self, block: &mut masm::Block) -> usize {
+        let mut saved = 0;
+        let mut ip = 0;
+        while ip < block.ops.len() {
+            let window = &block.ops[ip..];
+            let Some((rule, rewrite)) =
+                RULES.iter().filter(|rule| rule.len <= window.len()).find_map(|rule| {
+                    (rule.rewrite)(&window[..rule.len]).map(|rewrite| (rule, rewrite))
+                })
+            else {
+                ip += 1;
+                continue;
+            };
+
+            log::trace!("applying peephole rule '{}' at {}:{ip}", rule.name, block.id);
+            let before = block.ops.drain(ip..(ip + rule.len)).map(|op| cycles(&op)).sum::<usize>();
+            let after = rewrite.iter().map(cycles).sum::<usize>();
+            block.ops.insert_many(ip, rewrite);
+            saved += before.saturating_sub(after);
+
+            // A rewrite may enable a match which begins before it, so back up far enough to
+            // revisit every window overlapping the rewritten instructions. Since each rule shrinks
+            // the block, this reaches a fixed point in a single pass.
+            ip = ip.saturating_sub(MAX_RULE_LEN - 1);
+        }
+        saved
+    }
+}
+
+fn is_push(op: &Op) -> bool {
+    matches!(op, Op::Push(_) | Op::PushU8(_) | Op::PushU16(_) | Op::PushU32(_))
+}
+
+fn is_zero(op: &Op) -> bool {
+    match op {
+        Op::Push(imm) => *imm == Felt::ZERO,
+        Op::PushU8(0) | Op::PushU16(0) | Op::PushU32(0) => true,
+        _ => false,
+    }
+}
+
+/// Returns true if `op` is guaranteed to leave a valid u32 value on top of the operand stack
+fn produces_u32(op: &Op) -> bool {
+    match op {
+        Op::Push(imm) => imm.as_int() <= u32::MAX as u64,
+        Op::PushU8(_)
+        | Op::PushU16(_)
+        | Op::PushU32(_)
+        | Op::Eq
+        | Op::EqImm(_)
+        | Op::Neq
+        | Op::NeqImm(_)
+        | Op::Eqw
+        | Op::IsOdd
+        | Op::Ilog2
+        | Op::U32Test
+        | Op::U32Testw
+        | Op::U32Assert
+        | Op::U32AssertWithError(_)
+        | Op::U32Cast
+        | Op::U32WrappingAdd
+        | Op::U32WrappingAddImm(_)
+        | Op::U32WrappingAdd3
+        | Op::U32WrappingSub
+        | Op::U32WrappingSubImm(_)
+        | Op::U32WrappingMul
+        | Op::U32WrappingMulImm(_)
+        | Op::U32WrappingMadd
+        | Op::U32Div
+        | Op::U32DivImm(_)
+        | Op::U32Mod
+        | Op::U32ModImm(_)
+        | Op::U32And
+        | Op::U32Or
+        | Op::U32Xor
+        | Op::U32Not
+        | Op::U32Shl
+        | Op::U32ShlImm(_)
+        | Op::U32Shr
+        | Op::U32ShrImm(_)
+        | Op::U32Rotl
+        | Op::U32RotlImm(_)
+        | Op::U32Rotr
+        | Op::U32RotrImm(_)
+        | Op::U32Popcnt
+        | Op::U32Clz
+        | Op::U32Ctz
+        | Op::U32Clo
+        | Op::U32Cto
+        | Op::U32Lt
+        | Op::U32Lte
+        | Op::U32Gt
+        | Op::U32Gte
+        | Op::U32Min
+        | Op::U32Max => true,
+        op => produces_u32_pair(op),
+    }
+}
+
+/// Returns true if `op` is guaranteed to leave valid u32 values in the top two elements of the
+/// operand stack
+fn produces_u32_pair(op: &Op) -> bool {
+    matches!(
+        op,
+        Op::U32Assert2
+            | Op::U32Assert2WithError(_)
+            | Op::U32Split
+            | Op::U32OverflowingAdd
+            | Op::U32OverflowingAddImm(_)
+            | Op::U32OverflowingAdd3
+            | Op::U32OverflowingSub
+            | Op::U32OverflowingSubImm(_)
+            | Op::U32OverflowingMul
+            | Op::U32OverflowingMulImm(_)
+            | Op::U32OverflowingMadd
+            | Op::U32DivMod
+            | Op::U32DivModImm(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_hir::Signature;
+
+    use super::*;
+
+    fn optimize(ops: &[Op]) -> (Vec<Op>, usize) {
+        let mut function =
+            masm::Function::new("test::peephole".parse().unwrap(), Signature::new([], []));
+        let body = function.body.id();
+        function.body.block_mut(body).extend_from_slice(ops);
+        let saved = PeepholeOptimizer.optimize(&mut function);
+        (function.body.block(body).ops.to_vec(), saved)
+    }
+
+    #[test]
+    fn peephole_removes_redundant_stack_ops() {
+        let (ops, saved) = optimize(&[
+            Op::Swap(1),
+            Op::Swap(1),
+            Op::Dup(0),
+            Op::Drop,
+            Op::Movup(2),
+            Op::Movdn(2),
+            Op::PushU8(0),
+            Op::Add,
+            Op::Mul,
+        ]);
+        assert_eq!(ops, vec![Op::Mul]);
+        assert_eq!(saved, 8);
+    }
+
+    #[test]
+    fn peephole_removes_redundant_u32_assertions() {
+        let (ops, saved) = optimize(&[
+            Op::U32WrappingAdd,
+            Op::U32Assert,
+            Op::U32OverflowingSub,
+            Op::U32Assert2,
+            Op::Add,
+            Op::U32Assert,
+        ]);
+        assert_eq!(ops, vec![Op::U32WrappingAdd, Op::U32OverflowingSub, Op::Add, Op::U32Assert]);
+        assert_eq!(saved, 4);
+    }
+
+    #[test]
+    fn peephole_runs_to_fixed_point() {
+        // Each rewrite exposes a new match which begins before it
+        let (ops, saved) = optimize(&[
+            Op::Movdn(3),
+            Op::Swap(1),
+            Op::Dup(2),
+            Op::Drop,
+            Op::Swap(1),
+            Op::Movup(3),
+            Op::Drop,
+        ]);
+        assert_eq!(ops, vec![Op::Drop]);
+        assert_eq!(saved, 6);
+    }
+
+    #[test]
+    fn peephole_preserves_semantics_of_other_ops() {
+        let (ops, saved) = optimize(&[Op::Swap(1), Op::Swap(2), Op::Dup(1), Op::Dropw]);
+        assert_eq!(ops, vec![Op::Swap(1), Op::Swap(2), Op::Dup(1), Op::Dropw]);
+        assert_eq!(saved, 0);
+    }
+}
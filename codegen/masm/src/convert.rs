@@ -1,13 +1,14 @@
+use miden_diagnostics::Severity;
 use miden_hir::{
     self as hir,
     pass::{AnalysisManager, ConversionPass, ConversionResult},
-    ConversionPassRegistration, PassInfo,
+    ConversionPassRegistration, FunctionIdent, Ident, PassInfo,
 };
 use miden_hir_analysis as analysis;
 use midenc_session::Session;
 
 use crate::{
-    codegen::{FunctionEmitter, OperandStack, Scheduler, TypedValue},
+    codegen::{FunctionEmitter, OperandStack, PeepholeOptimizer, Scheduler, TypedValue},
     masm,
 };
 
@@ -106,7 +107,11 @@ impl ConversionPass for ConvertHirToMasm<hir::Module> {
     ) -> ConversionResult<Self::To> {
         use miden_hir::ProgramAnalysisKey;
 
-        let mut masm_module = Box::new(masm::Module::new(module.name));
+        let mut masm_module = if module.is_kernel() {
+            Box::new(masm::Module::new_kernel(module.name))
+        } else {
+            Box::new(masm::Module::new(module.name))
+        };
 
         // Compute import information for this module
         masm_module.imports = module.imports();
@@ -121,12 +126,24 @@ impl ConversionPass for ConvertHirToMasm<hir::Module> {
         // the next function in the module. Once the end of the module
         // is reached, the cursor will point to the null object, and
         // `remove` will return `None`.
+        let mut cycles_saved = vec![];
         while let Some(function) = module.pop_front() {
             let mut convert_to_masm = ConvertHirToMasm::<&hir::Function>::default();
-            let masm_function = convert_to_masm.convert(&function, analyses, session)?;
+            let mut masm_function = convert_to_masm.convert(&function, analyses, session)?;
+
+            // Clean up the emitted code, if optimizations are enabled
+            if session.options.optimize.is_optimizing() {
+                let saved = PeepholeOptimizer.optimize(&mut masm_function);
+                if saved > 0 {
+                    cycles_saved.push((masm_function.name, saved));
+                }
+            }
+
             masm_module.push_back(Box::new(masm_function));
         }
 
+        report_cycles_saved(module.name, &cycles_saved, session);
+
         Ok(masm_module)
     }
 }
@@ -181,3 +198,22 @@ impl<'a> ConversionPass for ConvertHirToMasm<&'a hir::Function> {
         Ok(f_prime)
     }
 }
+
+/// Report the estimated number of cycles saved by the [PeepholeOptimizer] in each function of
+/// `module`, as a note diagnostic listing the savings of each function which was improved.
+fn report_cycles_saved(module: Ident, cycles_saved: &[(FunctionIdent, usize)], session: &Session) {
+    let total = cycles_saved.iter().map(|(_, saved)| saved).sum::<usize>();
+    if total == 0 {
+        return;
+    }
+
+    let diagnostic = session.diagnostics.diagnostic(Severity::Note).with_message(format!(
+        "peephole optimization of '{module}' saved an estimated {total} cycles"
+    ));
+    cycles_saved
+        .iter()
+        .fold(diagnostic, |diagnostic, (function, saved)| {
+            diagnostic.with_note(format!("{function}: {saved} cycles"))
+        })
+        .emit();
+}
@@ -5,11 +5,20 @@ use miden_hir::{Felt, FunctionIdent, OperandStack};
 use super::{Addr, InstructionPointer, InstructionWithOp};
 
 /// Represents basic information about a frame on the call stack
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct CallFrame {
     pub function: FunctionIdent,
     pub fp: Addr,
     pub ip: Option<InstructionPointer>,
 }
+impl fmt::Display for CallFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.ip {
+            Some(ip) => write!(f, "{} at {}:{}", self.function, ip.block, ip.index),
+            None => write!(f, "{}", self.function),
+        }
+    }
+}
 
 /// Represents the current state of the program being executed for use in debugging/troubleshooting
 pub struct DebugInfo<'a> {
@@ -1,13 +1,16 @@
 use std::{cell::RefCell, fmt, rc::Rc, sync::Arc};
 
-use miden_hir::Felt;
+use miden_hir::{Felt, FunctionIdent, Signature};
 use smallvec::{smallvec, SmallVec};
 
 use super::{Addr, ControlEffect, EmulationError, Emulator, InstructionPointer};
 use crate::{BlockId, Function, Op};
 
 /// The type signature for native Rust functions callable from MASM IR
-pub type NativeFn = dyn FnMut(&mut Emulator, &[Felt]) -> Result<(), EmulationError>;
+///
+/// The function is given the arguments popped from the operand stack, and returns the results to
+/// be pushed on the operand stack. In both cases, the first element is the top of the stack.
+pub type NativeFn = dyn FnMut(&mut Emulator, &[Felt]) -> Result<Vec<Felt>, EmulationError>;
 
 /// We allow functions in the emulator to be defined in either MASM IR, or native Rust.
 ///
@@ -19,7 +22,61 @@ pub enum Stub {
     /// This function has a definition in Miden Assembly
     Asm(Arc<Function>),
     /// This function has a native Rust implementation
-    Native(Rc<RefCell<Box<NativeFn>>>),
+    Native(Rc<NativeFunction>),
+}
+impl Stub {
+    pub fn signature(&self) -> &Signature {
+        match self {
+            Self::Asm(function) => &function.signature,
+            Self::Native(function) => &function.signature,
+        }
+    }
+}
+
+/// A function implemented in native Rust, see [Emulator::load_nif]
+pub struct NativeFunction {
+    pub name: FunctionIdent,
+    pub signature: Signature,
+    function: RefCell<Box<NativeFn>>,
+}
+impl NativeFunction {
+    pub fn new(name: FunctionIdent, signature: Signature, function: Box<NativeFn>) -> Self {
+        Self {
+            name,
+            signature,
+            function: RefCell::new(function),
+        }
+    }
+
+    /// The number of elements popped from the operand stack as arguments to this function
+    pub fn arity(&self) -> usize {
+        self.signature.params().iter().map(|param| param.ty.size_in_felts()).sum()
+    }
+
+    /// The number of elements pushed on the operand stack as results of this function
+    pub fn num_results(&self) -> usize {
+        self.signature.results().iter().map(|result| result.ty.size_in_felts()).sum()
+    }
+
+    /// Call this function with `args`, returning its results
+    pub fn call(
+        &self,
+        emulator: &mut Emulator,
+        args: &[Felt],
+    ) -> Result<Vec<Felt>, EmulationError> {
+        let mut function = self.function.borrow_mut();
+        function(emulator, args)
+    }
+}
+
+/// The activation record of a native function which has been called, but which has not yet run
+///
+/// Native functions run to completion in a single cycle, so unlike [Activation], there is no
+/// other state to track.
+#[derive(Clone)]
+pub struct NativeActivation {
+    pub function: Rc<NativeFunction>,
+    pub caller: Option<CallerContext>,
 }
 
 /// This enum represents a frame on the control stack
@@ -79,6 +136,16 @@ impl ControlFrame {
     }
 }
 
+/// The state of a caller which is saved when control is transferred to a callee executing in a
+/// different context, i.e. via `call` or `syscall`, and restored when the callee returns
+#[derive(Debug, Clone)]
+pub struct CallerContext {
+    /// The context the caller was executing in
+    pub ctx: u32,
+    /// The elements of the caller's operand stack which are not visible to the callee
+    pub overflow: Vec<Felt>,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct RepeatState {
     /// The instruction pointer in the repeat block
@@ -89,7 +156,7 @@ pub struct RepeatState {
     pub iterations: u8,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ControlStack {
     /// The control frame for the current instruction being executed
     current: ControlFrame,
@@ -368,10 +435,12 @@ pub struct InstructionWithOp {
 /// The activation record contains state about the execution of that function of interest
 /// to the emulator, in particular, the instruction pointer, the frame pointer for locals,
 /// and the function-local control stack
+#[derive(Clone)]
 pub struct Activation {
     function: Arc<Function>,
     fp: Addr,
     control_stack: ControlStack,
+    caller: Option<CallerContext>,
 }
 impl fmt::Debug for Activation {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -379,6 +448,7 @@ impl fmt::Debug for Activation {
             .field("function", &self.function.name)
             .field("fp", &self.fp)
             .field("control_stack", &self.control_stack)
+            .field("caller", &self.caller)
             .finish()
     }
 }
@@ -392,6 +462,7 @@ impl Activation {
             function,
             fp,
             control_stack,
+            caller: None,
         }
     }
 
@@ -401,9 +472,22 @@ impl Activation {
             function,
             fp,
             control_stack,
+            caller: None,
         }
     }
 
+    /// Mark this activation as executing in a different context than its caller, whose state is
+    /// restored from `caller` when this activation returns
+    pub fn with_caller_context(mut self, caller: CallerContext) -> Self {
+        self.caller = Some(caller);
+        self
+    }
+
+    /// Take the saved state of the caller, if this activation executes in a different context
+    pub fn take_caller_context(&mut self) -> Option<CallerContext> {
+        self.caller.take()
+    }
+
     #[inline(always)]
     pub fn function(&self) -> &Function {
         &self.function
@@ -0,0 +1,190 @@
+use std::rc::Rc;
+
+use miden_hir::{Felt, OperandStack};
+use rustc_hash::FxHashMap;
+
+use super::{
+    functions::{Activation, NativeActivation},
+    Emulator, InstructionPointer, Profiler,
+};
+
+/// The number of words in each page of a [MemorySnapshot]
+const PAGE_SIZE: usize = 256;
+
+/// A copy of a linear memory, split into pages which are shared with the snapshot taken by the
+/// previous [Checkpoint] if they have not changed since.
+#[derive(Clone)]
+pub(super) struct MemorySnapshot {
+    pages: Vec<Rc<[[Felt; 4]]>>,
+}
+impl MemorySnapshot {
+    /// Capture `memory`, sharing any unchanged pages with `previous`
+    fn new(memory: &[[Felt; 4]], previous: Option<&Self>) -> Self {
+        let pages = memory
+            .chunks(PAGE_SIZE)
+            .enumerate()
+            .map(|(index, page)| match previous.and_then(|previous| previous.pages.get(index)) {
+                Some(shared) if shared.as_ref() == page => shared.clone(),
+                _ => Rc::from(page),
+            })
+            .collect();
+        Self { pages }
+    }
+
+    /// Overwrite `memory` with the contents of this snapshot
+    fn restore(&self, memory: &mut Vec<[Felt; 4]>) {
+        memory.clear();
+        for page in self.pages.iter() {
+            memory.extend_from_slice(page);
+        }
+    }
+
+    fn to_vec(&self) -> Vec<[Felt; 4]> {
+        let mut memory = Vec::with_capacity(self.pages.len() * PAGE_SIZE);
+        self.restore(&mut memory);
+        memory
+    }
+}
+
+/// A snapshot of the execution state of the [Emulator] at the start of a given cycle
+///
+/// Loaded code and breakpoints are not part of the snapshot. The profile gathered so far is, if
+/// profiling is enabled, so that cycles which are executed again after restoring the snapshot are
+/// not counted twice.
+pub(super) struct Checkpoint {
+    pub clk: usize,
+    stack: OperandStack<Felt>,
+    advice_stack: OperandStack<Felt>,
+    memory: MemorySnapshot,
+    ctx: u32,
+    next_ctx: u32,
+    suspended_contexts: FxHashMap<u32, MemorySnapshot>,
+    callstack: Vec<Activation>,
+    native: Option<NativeActivation>,
+    hp: u32,
+    lp: u32,
+    step_over: Option<InstructionPointer>,
+    profiler: Option<Profiler>,
+}
+impl Checkpoint {
+    /// Capture the current state of `emulator`, sharing unchanged memory with `previous`
+    pub fn capture(emulator: &Emulator, previous: Option<&Self>) -> Self {
+        let suspended_contexts = emulator
+            .suspended_contexts
+            .iter()
+            .map(|(ctx, memory)| {
+                let previous = previous.and_then(|previous| previous.memory_of(*ctx));
+                (*ctx, MemorySnapshot::new(memory, previous))
+            })
+            .collect();
+        let previous = previous.and_then(|previous| previous.memory_of(emulator.ctx));
+        Self {
+            clk: emulator.clk,
+            stack: emulator.stack.clone(),
+            advice_stack: emulator.advice_stack.clone(),
+            memory: MemorySnapshot::new(&emulator.memory, previous),
+            ctx: emulator.ctx,
+            next_ctx: emulator.next_ctx,
+            suspended_contexts,
+            callstack: emulator.callstack.clone(),
+            native: emulator.native.clone(),
+            hp: emulator.hp,
+            lp: emulator.lp,
+            step_over: emulator.step_over,
+            profiler: emulator.profiler.clone(),
+        }
+    }
+
+    /// Restore `emulator` to the state captured by this checkpoint
+    pub fn restore(&self, emulator: &mut Emulator) {
+        emulator.clk = self.clk;
+        emulator.stack = self.stack.clone();
+        emulator.advice_stack = self.advice_stack.clone();
+        self.memory.restore(&mut emulator.memory);
+        emulator.ctx = self.ctx;
+        emulator.next_ctx = self.next_ctx;
+        emulator.suspended_contexts = self
+            .suspended_contexts
+            .iter()
+            .map(|(ctx, memory)| (*ctx, memory.to_vec()))
+            .collect();
+        emulator.callstack = self.callstack.clone();
+        emulator.native = self.native.clone();
+        emulator.executing = None;
+        emulator.hp = self.hp;
+        emulator.lp = self.lp;
+        emulator.step_over = self.step_over;
+        // The profile is left alone if profiling has been disabled since, and starts over from
+        // here if it was enabled after this checkpoint was recorded
+        if let Some(profiler) = emulator.profiler.as_mut() {
+            *profiler = self.profiler.clone().unwrap_or_default();
+        }
+    }
+
+    /// Get the snapshot of the memory of context `ctx`
+    fn memory_of(&self, ctx: u32) -> Option<&MemorySnapshot> {
+        if ctx == self.ctx {
+            Some(&self.memory)
+        } else {
+            self.suspended_contexts.get(&ctx)
+        }
+    }
+}
+
+/// The checkpoints recorded by the [Emulator] when checkpoints are enabled, from which any
+/// earlier cycle of the current execution can be restored by replaying from the nearest one.
+pub(super) struct History {
+    /// The number of cycles between checkpoints
+    interval: usize,
+    /// The checkpoints recorded so far, in order of increasing cycle count
+    checkpoints: Vec<Checkpoint>,
+}
+impl History {
+    pub fn new(interval: usize) -> Self {
+        assert!(interval > 0, "the checkpoint interval must be at least one cycle");
+        Self {
+            interval,
+            checkpoints: vec![],
+        }
+    }
+
+    /// Returns true if a checkpoint should be recorded at the start of cycle `clk`
+    pub fn is_due(&self, clk: usize) -> bool {
+        clk % self.interval == 0 && self.last().map(|checkpoint| checkpoint.clk) != Some(clk)
+    }
+
+    /// Get the most recent checkpoint
+    pub fn last(&self) -> Option<&Checkpoint> {
+        self.checkpoints.last()
+    }
+
+    /// Record `checkpoint`
+    pub fn push(&mut self, checkpoint: Checkpoint) {
+        if let Some(last) = self.last() {
+            debug_assert!(last.clk < checkpoint.clk, "checkpoints must be recorded in order");
+        }
+        self.checkpoints.push(checkpoint);
+    }
+
+    /// Get the checkpoints recorded before cycle `clk`, from most to least recent
+    pub fn before(&self, clk: usize) -> impl Iterator<Item = &Checkpoint> {
+        let end = self.checkpoints.partition_point(|checkpoint| checkpoint.clk < clk);
+        self.checkpoints[..end].iter().rev()
+    }
+
+    /// Get the most recent checkpoint at or before cycle `clk`
+    pub fn nearest(&self, clk: usize) -> Option<&Checkpoint> {
+        self.before(clk + 1).next()
+    }
+
+    /// Discard the checkpoints recorded after cycle `clk`
+    pub fn truncate(&mut self, clk: usize) {
+        let end = self.checkpoints.partition_point(|checkpoint| checkpoint.clk <= clk);
+        self.checkpoints.truncate(end);
+    }
+
+    /// Discard all checkpoints
+    pub fn clear(&mut self) {
+        self.checkpoints.clear();
+    }
+}
@@ -2,20 +2,28 @@ mod breakpoints;
 mod debug;
 mod events;
 mod functions;
+mod history;
+mod profiler;
 
-use std::{cell::RefCell, cmp, rc::Rc, sync::Arc};
+use std::{cmp, rc::Rc, sync::Arc};
 
+use miden_core::crypto::hash::Rpo256;
 use miden_hir::{
-    assert_matches, Felt, FieldElement, FunctionIdent, Ident, OperandStack, Stack, StarkField,
+    assert_matches, Felt, FieldElement, FunctionIdent, Ident, OperandStack, Signature, Stack,
+    StarkField,
 };
 use rustc_hash::{FxHashMap, FxHashSet};
 
-use self::functions::{Activation, Stub};
 pub use self::{
     breakpoints::*,
     debug::{CallFrame, DebugInfo, DebugInfoWithStack},
     events::{BreakpointEvent, ControlEffect, EmulatorEvent},
     functions::{Instruction, InstructionWithOp, NativeFn},
+    profiler::{FunctionProfile, InstructionProfile, Profiler},
+};
+use self::{
+    functions::{Activation, CallerContext, NativeActivation, NativeFunction, Stub},
+    history::{Checkpoint, History},
 };
 use crate::{Begin, BlockId, Function, Module, Op, Program};
 
@@ -45,12 +53,140 @@ pub enum EmulationError {
     /// An attempt was made to run the emulator without specifying an entrypoint
     #[error("unable to start the emulator without an entrypoint")]
     NoEntrypoint,
+    /// An attempt was made to load a kernel module when a kernel was already loaded
+    #[error("unable to load kernel module '{0}': a kernel is already loaded")]
+    KernelAlreadyLoaded(Ident),
+    /// A `syscall` was executed without a kernel loaded
+    #[error("unable to execute syscall to '{0}': no kernel is loaded")]
+    NoKernel(Ident),
+    /// A function executing in a different context than its caller returned with more elements on
+    /// the operand stack than are visible to it
+    #[error(
+        "invalid return from '{0}': at most 16 elements may be on the operand stack when \
+         returning to a different context"
+    )]
+    ContextStackOverflow(FunctionIdent),
+    /// An assertion failed during execution of the program
+    #[error("assertion failed with error code {code}")]
+    AssertionFailed {
+        /// The error code of the assertion, or 0 if it does not have one
+        code: u32,
+        /// The instruction which raised the error, or which called the native function that
+        /// raised it. This is `None` for native functions invoked directly, e.g. via `invoke`.
+        ip: Option<InstructionPointer>,
+        /// The call stack at the time the error was raised, innermost frame last
+        stacktrace: Vec<CallFrame>,
+    },
+    /// An instruction expected a u32 operand, but the value was out of range
+    #[error("invalid operand: {value} is not a valid u32")]
+    InvalidU32Operand {
+        value: u64,
+        ip: Option<InstructionPointer>,
+        stacktrace: Vec<CallFrame>,
+    },
+    /// An instruction expected a boolean operand, but the value was neither 0 nor 1
+    #[error("invalid operand: {value} is not a valid boolean")]
+    InvalidBoolOperand {
+        value: u64,
+        ip: Option<InstructionPointer>,
+        stacktrace: Vec<CallFrame>,
+    },
+    /// An instruction attempted to access memory outside the bounds of linear memory
+    #[error("out of bounds memory access: {addr:#x} is not a valid address")]
+    OutOfBoundsMemoryAccess {
+        addr: u32,
+        ip: Option<InstructionPointer>,
+        stacktrace: Vec<CallFrame>,
+    },
+    /// An instruction required more operands than were available on the operand stack
+    #[error("operand stack underflow")]
+    StackUnderflow {
+        ip: Option<InstructionPointer>,
+        stacktrace: Vec<CallFrame>,
+    },
+    /// An instruction required more elements than were available on the advice stack
+    #[error("advice stack underflow")]
+    AdviceStackUnderflow {
+        ip: Option<InstructionPointer>,
+        stacktrace: Vec<CallFrame>,
+    },
+    /// An instruction attempted to divide by zero
+    #[error("division by zero")]
+    DivisionByZero {
+        ip: Option<InstructionPointer>,
+        stacktrace: Vec<CallFrame>,
+    },
+    /// An instruction was given an operand outside of its domain, e.g. `ilog2` of zero
+    #[error("invalid operand: {value} is out of range for this instruction")]
+    InvalidOperand {
+        value: u64,
+        ip: Option<InstructionPointer>,
+        stacktrace: Vec<CallFrame>,
+    },
+    /// A `dynexec` or `dyncall` was executed with a hash that does not refer to a loaded function
+    #[error("unable to invoke procedure: no function has the hash on top of the operand stack")]
+    UndefinedProcedure {
+        ip: Option<InstructionPointer>,
+        stacktrace: Vec<CallFrame>,
+    },
+    /// A `caller` instruction was executed outside of a `syscall`
+    #[error("invalid use of 'caller': not executing in the context of a syscall")]
+    CallerNotInSyscall {
+        ip: Option<InstructionPointer>,
+        stacktrace: Vec<CallFrame>,
+    },
+    /// An attempt was made to reverse execution without enabling checkpoints first
+    #[error("unable to reverse execution: checkpoints are not enabled")]
+    CheckpointsDisabled,
+    /// An attempt was made to reverse execution while the emulator was not suspended, stopped, or
+    /// faulted, e.g. before execution has started
+    #[error(
+        "unable to reverse execution: the emulator must be suspended, stopped, or faulted first"
+    )]
+    NotSuspended,
+    /// An attempt was made to rewind to a cycle which has not been reached yet
+    #[error("unable to rewind to cycle {cycle}: execution has only reached cycle {clk}")]
+    CycleNotReached { cycle: usize, clk: usize },
+    /// An attempt was made to rewind to a cycle preceding the earliest recorded checkpoint
+    #[error("unable to rewind to cycle {0}: no earlier checkpoint was recorded")]
+    NoCheckpoint(usize),
+    /// A native function returned a different number of results than its signature specifies
+    #[error(
+        "invalid return from '{function}': expected {expected} results on the operand stack, got \
+         {actual}"
+    )]
+    InvalidNativeResults {
+        function: FunctionIdent,
+        expected: usize,
+        actual: usize,
+        ip: Option<InstructionPointer>,
+        stacktrace: Vec<CallFrame>,
+    },
+}
+impl EmulationError {
+    /// Get the stacktrace captured when this error was raised, if it was raised by an instruction
+    pub fn stacktrace(&self) -> Option<&[CallFrame]> {
+        match self {
+            Self::AssertionFailed { stacktrace, .. }
+            | Self::InvalidU32Operand { stacktrace, .. }
+            | Self::InvalidBoolOperand { stacktrace, .. }
+            | Self::OutOfBoundsMemoryAccess { stacktrace, .. }
+            | Self::StackUnderflow { stacktrace, .. }
+            | Self::AdviceStackUnderflow { stacktrace, .. }
+            | Self::DivisionByZero { stacktrace, .. }
+            | Self::InvalidOperand { stacktrace, .. }
+            | Self::UndefinedProcedure { stacktrace, .. }
+            | Self::CallerNotInSyscall { stacktrace, .. }
+            | Self::InvalidNativeResults { stacktrace, .. } => Some(stacktrace.as_slice()),
+            _ => None,
+        }
+    }
 }
 
 /// The size/type of pointers in the emulator
 pub type Addr = u32;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct InstructionPointer {
     /// The block in which the instruction pointer is located
     pub block: BlockId,
@@ -109,7 +245,10 @@ enum Status {
 /// [Emulator] is necessarily a more limited execution environment:
 ///
 /// * It only handles instructions which are defined in the [Op] enum
-/// * Anything related to proving, calling contracts, etc. is not supported
+/// * Anything related to proving is not supported
+/// * Execution contexts are modeled only to the extent that each context has its own linear
+/// memory, and only the top 16 elements of the operand stack are visible to a callee invoked via
+/// `call` or `syscall`
 /// * The default environment is empty, i.e. there are no Miden VM standard
 /// library functions available. Users must emit Miden IR for all functions
 /// they wish to call, or alternatively, provide native stubs.
@@ -119,10 +258,24 @@ pub struct Emulator {
     locals: FxHashMap<FunctionIdent, Addr>,
     modules_loaded: FxHashMap<Ident, Arc<Module>>,
     modules_pending: FxHashSet<Ident>,
+    kernel: Option<Ident>,
     memory: Vec<[Felt; 4]>,
+    ctx: u32,
+    next_ctx: u32,
+    suspended_contexts: FxHashMap<u32, Vec<[Felt; 4]>>,
     stack: OperandStack<Felt>,
     advice_stack: OperandStack<Felt>,
+    init_advice_stack: Vec<Felt>,
     callstack: Vec<Activation>,
+    /// The frame of the instruction currently being executed, whose activation record is not
+    /// on the callstack while it executes. This is used to report the location of errors.
+    executing: Option<CallFrame>,
+    /// A native function which has been called, and will be run on the next cycle
+    native: Option<NativeActivation>,
+    /// The profiler, if profiling has been enabled
+    profiler: Option<Profiler>,
+    /// The checkpoints used to reverse execution, if checkpoints have been enabled
+    history: Option<History>,
     hp_start: u32,
     hp: u32,
     lp_start: u32,
@@ -143,6 +296,9 @@ impl Emulator {
     pub const DEFAULT_LOCALS_START: u32 = (3 * Self::PAGE_SIZE) / 16;
     const EMPTY_WORD: [Felt; 4] = [Felt::ZERO; 4];
     const PAGE_SIZE: u32 = 64 * 1024;
+    const ROOT_CONTEXT: u32 = 0;
+    /// The number of operand stack elements visible to a callee in a different context
+    const VISIBLE_STACK_DEPTH: usize = 16;
 
     /// Construct a new, empty emulator with:
     ///
@@ -157,10 +313,19 @@ impl Emulator {
             locals: Default::default(),
             modules_loaded: Default::default(),
             modules_pending: Default::default(),
+            kernel: None,
             memory,
+            ctx: Self::ROOT_CONTEXT,
+            next_ctx: Self::ROOT_CONTEXT + 1,
+            suspended_contexts: Default::default(),
             stack: Default::default(),
             advice_stack: Default::default(),
+            init_advice_stack: vec![],
             callstack: vec![],
+            executing: None,
+            native: None,
+            profiler: None,
+            history: None,
             hp_start: hp,
             hp,
             lp_start: lp,
@@ -177,6 +342,39 @@ impl Emulator {
         self.clk_limit = max;
     }
 
+    /// Start profiling the cycles spent executing each function and instruction, see [Profiler]
+    pub fn enable_profiling(&mut self) {
+        self.profiler.get_or_insert_with(Profiler::default);
+    }
+
+    /// Get the profile gathered so far, if profiling is enabled
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+
+    /// Stop profiling, returning the profile gathered so far, if profiling was enabled
+    pub fn take_profiler(&mut self) -> Option<Profiler> {
+        self.profiler.take()
+    }
+
+    /// Record a checkpoint of the execution state every `interval` cycles, which allows execution
+    /// to be reversed with [Self::step_back], [Self::reverse_continue], and [Self::rewind].
+    ///
+    /// Smaller intervals make reversing execution faster, at the cost of more memory. This must be
+    /// enabled before execution starts in order to reverse execution all the way to the start.
+    ///
+    /// If profiling is enabled, each checkpoint also records the profile gathered so far, so that
+    /// reversing execution reverses the profile too, and cycles which are executed again after
+    /// reversing are not counted twice.
+    pub fn enable_checkpoints(&mut self, interval: usize) {
+        self.history = Some(History::new(interval));
+    }
+
+    /// Get the number of cycles executed so far
+    pub fn clk(&self) -> usize {
+        self.clk
+    }
+
     /// Returns all watchpoints that are currently managed by this [BreakpointManager]
     pub fn watchpoints(&self) -> impl Iterator<Item = Watchpoint> + '_ {
         self.breakpoints.watchpoints()
@@ -229,6 +427,15 @@ impl Emulator {
 
     /// Get's debug information about the current emulator state
     pub fn info(&self) -> Option<DebugInfo<'_>> {
+        if let Some(native) = self.native.as_ref() {
+            return Some(DebugInfo {
+                cycle: self.clk,
+                function: native.function.name,
+                fp: 0,
+                ip: None,
+                stack: &self.stack,
+            });
+        }
         let current = self.callstack.last()?;
         // This returns the pending activation state for the current function,
         // i.e. the next instruction to be executed, what control flow effects
@@ -253,17 +460,47 @@ impl Emulator {
                 ip: Some(frame.ip()),
             })
         }
+        if let Some(native) = self.native.as_ref() {
+            frames.push(CallFrame {
+                function: native.function.name,
+                fp: 0,
+                ip: None,
+            });
+        }
         frames
     }
 
+    /// Get the location of an error raised by the instruction currently being executed, i.e. its
+    /// instruction pointer, and the stacktrace up to and including the current frame.
+    ///
+    /// Native functions have no instructions of their own, so an error raised while running one
+    /// is attributed to the instruction which called it, or to no instruction at all if it was
+    /// invoked directly, e.g. via [Emulator::invoke].
+    fn fault_location(&self) -> (Option<InstructionPointer>, Vec<CallFrame>) {
+        let mut stacktrace = self.stacktrace();
+        if self.native.is_some() {
+            // The native function is already the innermost frame of the stacktrace
+            return (self.executing.and_then(|frame| frame.ip), stacktrace);
+        }
+        let frame = self.executing.expect("no instruction is currently being executed");
+        stacktrace.push(frame);
+        (frame.ip, stacktrace)
+    }
+
     /// Get the instruction pointer that will be next executed by the emulator
     pub fn current_ip(&self) -> Option<Instruction> {
+        if self.native.is_some() {
+            return None;
+        }
         self.callstack.last().and_then(|activation| activation.peek())
     }
 
     /// Get the name of the function that is currently executing
     pub fn current_function(&self) -> Option<FunctionIdent> {
-        self.callstack.last().map(|activation| activation.function().name)
+        match self.native.as_ref() {
+            Some(native) => Some(native.function.name),
+            None => self.callstack.last().map(|activation| activation.function().name),
+        }
     }
 
     /// Get access to the current state of the operand stack
@@ -279,6 +516,10 @@ impl Emulator {
     /// Load `program` into this emulator
     ///
     /// This resets the emulator state, as only one program may be loaded at a time.
+    ///
+    /// The data segments of `program` are made available on the advice stack, and are written to
+    /// memory when the program is started, before its entrypoint is invoked. They are not
+    /// available when invoking functions of the program directly.
     pub fn load_program(&mut self, program: Arc<Program>) -> Result<(), EmulationError> {
         // Ensure the emulator state is reset
         if !matches!(self.status, Status::Init) {
@@ -292,7 +533,10 @@ impl Emulator {
             cursor.move_next();
         }
 
-        // TODO: Load data segments
+        // The data segments of the program are written to memory by its initialization code, which
+        // reads them from the advice stack
+        self.init_advice_stack = program.advice_stack();
+        self.reset_advice_stack();
 
         if let Some(begin) = program.body.as_ref() {
             self.load_init(begin)?;
@@ -303,9 +547,28 @@ impl Emulator {
         Ok(())
     }
 
+    /// Load the modules of `library` into this emulator, alongside those already loaded
+    ///
+    /// Unlike `load_program`, this does not reset the emulator state, nor does it load any
+    /// data segments or initialization code from `library`. It is intended for loading the
+    /// libraries a program was linked against.
+    pub fn load_library(&mut self, library: Arc<Program>) -> Result<(), EmulationError> {
+        let modules = library.unwrap_frozen_modules();
+        let mut cursor = modules.front();
+        while let Some(module) = cursor.clone_pointer() {
+            self.load_module(module)?;
+            cursor.move_next();
+        }
+
+        Ok(())
+    }
+
     /// Load `module` into this emulator
     ///
-    /// An error is returned if a module with the same name is already loaded.
+    /// If `module` is a kernel module, it becomes the kernel against which `syscall` is resolved.
+    ///
+    /// An error is returned if a module with the same name is already loaded, or if `module` is a
+    /// kernel module and a kernel is already loaded.
     pub fn load_module(&mut self, module: Arc<Module>) -> Result<(), EmulationError> {
         use std::collections::hash_map::Entry;
 
@@ -316,6 +579,10 @@ impl Emulator {
              first"
         );
 
+        if module.is_kernel() && self.kernel.is_some() {
+            return Err(EmulationError::KernelAlreadyLoaded(module.name));
+        }
+
         match self.modules_loaded.entry(module.name) {
             Entry::Occupied(_) => return Err(EmulationError::AlreadyLoaded(module.name)),
             Entry::Vacant(entry) => {
@@ -323,6 +590,10 @@ impl Emulator {
             }
         }
 
+        if module.is_kernel() {
+            self.kernel = Some(module.name);
+        }
+
         // Register module dependencies
         for import in module.imports.iter() {
             let name = Ident::with_empty_span(import.name);
@@ -370,6 +641,10 @@ impl Emulator {
             .remove(&name)
             .expect("cannot reload a module that was not previously loaded");
 
+        if self.kernel == Some(name) {
+            self.kernel = None;
+        }
+
         // Unload all functions associated with the previous load
         for f in prev.functions() {
             self.functions.remove(&f.name);
@@ -388,7 +663,7 @@ impl Emulator {
 
     /// Load the `begin` block which constitutes the initialization region for a [Program]
     fn load_init(&mut self, init: &Begin) -> Result<(), EmulationError> {
-        use miden_hir::{attributes, Signature};
+        use miden_hir::attributes;
 
         let main_fn = FunctionIdent {
             module: miden_assembly::LibraryPath::EXEC_PATH.into(),
@@ -422,9 +697,15 @@ impl Emulator {
     /// Because we don't know the set of [FuncId] that have already been allocated,
     /// we leave the choice up to the caller. We assert that functions do
     /// not get defined twice to catch conflicts, just in case.
+    ///
+    /// When called, the arguments described by `signature` are popped from the operand stack and
+    /// passed to `function`, and the results it returns are pushed on the operand stack. Native
+    /// functions can be called like any other, including via `call` and `syscall`, which makes
+    /// them useful for mocking kernel and library procedures in tests.
     pub fn load_nif(
         &mut self,
         id: FunctionIdent,
+        signature: Signature,
         function: Box<NativeFn>,
     ) -> Result<(), EmulationError> {
         assert_matches!(
@@ -436,7 +717,8 @@ impl Emulator {
         if self.functions.contains_key(&id) {
             return Err(EmulationError::DuplicateFunction(id));
         }
-        self.functions.insert(id, Stub::Native(Rc::new(RefCell::new(function))));
+        self.functions
+            .insert(id, Stub::Native(Rc::new(NativeFunction::new(id, signature, function))));
 
         Ok(())
     }
@@ -505,6 +787,18 @@ impl Emulator {
         self.memory[addr][ptr.index as usize] = value;
     }
 
+    /// Read the element at `addr`, a byte-addressable, element-aligned address
+    pub fn load(&self, addr: usize) -> Felt {
+        use crate::NativePtr;
+
+        let ptr = NativePtr::from_ptr(addr.try_into().expect("invalid address"));
+        let addr = ptr.waddr as usize;
+        assert_eq!(ptr.offset, 0, "invalid load: unaligned address {addr:#?}");
+        assert!(addr < self.memory.len(), "invalid address");
+
+        self.memory[addr][ptr.index as usize]
+    }
+
     /// Start executing the current program by `invoke`ing the top-level initialization block (the
     /// entrypoint).
     ///
@@ -588,8 +882,20 @@ impl Emulator {
     /// If an attempt is made to run the emulator in the stopped state, a panic will occur
     pub fn stop(&mut self) {
         self.callstack.clear();
+        self.executing = None;
+        self.native = None;
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.stop();
+        }
+        if let Some(history) = self.history.as_mut() {
+            history.clear();
+        }
         self.stack.clear();
-        self.memory.clear();
+        self.reset_advice_stack();
+        self.memory.fill(Self::EMPTY_WORD);
+        self.ctx = Self::ROOT_CONTEXT;
+        self.next_ctx = Self::ROOT_CONTEXT + 1;
+        self.suspended_contexts.clear();
         self.hp = self.hp_start;
         self.lp = self.lp_start;
         self.step_over = None;
@@ -597,6 +903,14 @@ impl Emulator {
         self.status = Status::Loaded;
     }
 
+    /// Restore the advice stack to the state expected by the loaded program on startup
+    fn reset_advice_stack(&mut self) {
+        self.advice_stack.clear();
+        for elem in self.init_advice_stack.iter().rev().copied() {
+            self.advice_stack.push(elem);
+        }
+    }
+
     /// Reset the emulator state to its initial state at creation.
     ///
     /// In addition to resetting the cycle counter, operand stack, and linear memory,
@@ -610,6 +924,9 @@ impl Emulator {
         self.locals.clear();
         self.modules_loaded.clear();
         self.modules_pending.clear();
+        self.kernel = None;
+        self.init_advice_stack.clear();
+        self.advice_stack.clear();
         self.breakpoints.clear();
         self.status = Status::Init;
     }
@@ -640,25 +957,22 @@ impl Emulator {
             .cloned()
             .ok_or(EmulationError::UndefinedFunction(callee))?;
         self.status = Status::Started;
-        match fun {
-            Stub::Asm(ref function) => match self.invoke_function(function.clone(), args) {
-                done @ Ok(_) => {
-                    self.status = Status::Stopped;
-                    done
-                }
-                Err(err @ EmulationError::BreakpointHit(_)) => {
-                    self.status = Status::Suspended;
-                    Err(err)
-                }
-                Err(err) => {
-                    self.status = Status::Faulted(err.clone());
-                    Err(err)
-                }
-            },
-            Stub::Native(function) => {
-                let mut function = function.borrow_mut();
-                function(self, args)?;
-                Ok(self.stack.clone())
+        let result = match fun {
+            Stub::Asm(function) => self.invoke_function(function, args),
+            Stub::Native(function) => self.invoke_native(function, args),
+        };
+        match result {
+            done @ Ok(_) => {
+                self.status = Status::Stopped;
+                done
+            }
+            Err(err @ EmulationError::BreakpointHit(_)) => {
+                self.status = Status::Suspended;
+                Err(err)
+            }
+            Err(err) => {
+                self.status = Status::Faulted(err.clone());
+                Err(err)
             }
         }
     }
@@ -697,6 +1011,35 @@ impl Emulator {
         }
     }
 
+    /// Invoke a native function, placing the given arguments on the operand stack in FIFO order,
+    /// and suspending immediately if any breakpoints would have been triggered by the invocation.
+    fn invoke_native(
+        &mut self,
+        function: Rc<NativeFunction>,
+        args: &[Felt],
+    ) -> Result<OperandStack<Felt>, EmulationError> {
+        // Place the arguments on the operand stack
+        for arg in args.iter().copied().rev() {
+            self.stack.push(arg);
+        }
+
+        // Schedule `function`
+        let name = function.name;
+        self.schedule_native(function, None);
+
+        match self
+            .breakpoints
+            .handle_event(EmulatorEvent::EnterFunction(name), self.current_ip())
+        {
+            Some(bp) => Err(EmulationError::BreakpointHit(bp)),
+            None => {
+                self.run()?;
+
+                Ok(self.stack.clone())
+            }
+        }
+    }
+
     /// Run the emulator by invoking `callee` with `args` placed on the
     /// operand stack in FIFO order.
     ///
@@ -727,9 +1070,12 @@ impl Emulator {
         match fun {
             Stub::Asm(ref function) => self.enter_function(function.clone(), args),
             Stub::Native(function) => {
-                let mut function = function.borrow_mut();
-                function(self, args)?;
-                Ok(EmulatorEvent::ExitFunction(callee))
+                for arg in args.iter().copied().rev() {
+                    self.stack.push(arg);
+                }
+                self.schedule_native(function, None);
+                self.status = Status::Suspended;
+                Ok(EmulatorEvent::Suspended)
             }
         }
     }
@@ -770,96 +1116,154 @@ impl Emulator {
         );
         self.run()
     }
+
+    /// Switch execution to the context `ctx`, or a new context if `None`, in preparation for
+    /// invoking a callee in that context.
+    ///
+    /// Only the top 16 elements of the operand stack remain visible, the remainder are saved
+    /// along with the context of the caller, and are restored by [Self::exit_context].
+    fn enter_context(&mut self, ctx: Option<u32>) -> CallerContext {
+        let depth = self.stack.len().saturating_sub(Self::VISIBLE_STACK_DEPTH);
+        let overflow = self.stack.stack_mut().drain(..depth).collect();
+        let caller = CallerContext {
+            ctx: self.ctx,
+            overflow,
+        };
+
+        let ctx = ctx.unwrap_or_else(|| {
+            let ctx = self.next_ctx;
+            self.next_ctx += 1;
+            ctx
+        });
+        if ctx != self.ctx {
+            // New contexts start with zeroed memory
+            let memory = self
+                .suspended_contexts
+                .remove(&ctx)
+                .unwrap_or_else(|| vec![Self::EMPTY_WORD; self.memory.len()]);
+            let memory = core::mem::replace(&mut self.memory, memory);
+            self.suspended_contexts.insert(self.ctx, memory);
+            self.ctx = ctx;
+        }
+
+        caller
+    }
+
+    /// Schedule `function` to be run on the next cycle, returning to `caller` if it was entered
+    /// via `call` or `syscall`.
+    fn schedule_native(&mut self, function: Rc<NativeFunction>, caller: Option<CallerContext>) {
+        self.native = Some(NativeActivation { function, caller });
+    }
+
+    /// Return from `callee` to the context of its caller
+    fn exit_context(
+        &mut self,
+        callee: FunctionIdent,
+        caller: CallerContext,
+    ) -> Result<(), EmulationError> {
+        if self.stack.len() > Self::VISIBLE_STACK_DEPTH {
+            return Err(EmulationError::ContextStackOverflow(callee));
+        }
+        self.stack.stack_mut().splice(0..0, caller.overflow);
+
+        if caller.ctx != self.ctx {
+            let memory = self
+                .suspended_contexts
+                .remove(&caller.ctx)
+                .expect("caller context was not suspended");
+            let memory = core::mem::replace(&mut self.memory, memory);
+            // The memory of a context created by `call` is discarded once the callee returns,
+            // but the root context persists for the lifetime of the program
+            if self.ctx == Self::ROOT_CONTEXT {
+                self.suspended_contexts.insert(Self::ROOT_CONTEXT, memory);
+            }
+            self.ctx = caller.ctx;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns early with the given error, raised at the instruction currently being executed
+macro_rules! fault {
+    ($emu:ident, $kind:ident) => {{
+        let (ip, stacktrace) = $emu.fault_location();
+        return Err(EmulationError::$kind { ip, stacktrace });
+    }};
+
+    ($emu:ident, $kind:ident { $($field:ident: $value:expr),* }) => {{
+        let (ip, stacktrace) = $emu.fault_location();
+        return Err(EmulationError::$kind { $($field: $value,)* ip, stacktrace });
+    }};
+}
+
+/// Raises a stack underflow error if the operand stack contains fewer than `n` elements
+macro_rules! require {
+    ($emu:ident, $n:expr) => {{
+        if $emu.stack.len() < $n {
+            fault!($emu, StackUnderflow);
+        }
+    }};
 }
 
 /// Pops the top element off the advice stack
 macro_rules! adv_pop {
     ($emu:ident) => {
-        $emu.advice_stack.pop().expect("advice stack is empty")
-    };
-
-    ($emu:ident, $msg:literal) => {
-        $emu.advice_stack.pop().expect($msg)
-    };
-
-    ($emu:ident, $msg:literal, $($arg:expr),+) => {
         match $emu.advice_stack.pop() {
             Some(value) => value,
-            None => panic!($msg, $($arg),*),
+            None => fault!($emu, AdviceStackUnderflow),
         }
-    }
+    };
 }
 
 /// Pops the top word off the advice stack
 macro_rules! adv_popw {
     ($emu:ident) => {
-        $emu.advice_stack.popw().expect("advice stack does not contain a full word")
-    };
-
-    ($emu:ident, $msg:literal) => {
-        $emu.advice_stack.popw().expect($msg)
-    };
-
-    ($emu:ident, $msg:literal, $($arg:expr),+) => {{
         match $emu.advice_stack.popw() {
             Some(value) => value,
-            None => panic!($msg, $($arg),*),
+            None => fault!($emu, AdviceStackUnderflow),
         }
-    }}
+    };
 }
 
 /// Pops the top element off the stack
 macro_rules! pop {
     ($emu:ident) => {
-        $emu.stack.pop().expect("operand stack is empty")
-    };
-
-    ($emu:ident, $msg:literal) => {
-        $emu.stack.pop().expect($msg)
-    };
-
-    ($emu:ident, $msg:literal, $($arg:expr),+) => {
         match $emu.stack.pop() {
             Some(value) => value,
-            None => panic!($msg, $($arg),*),
+            None => fault!($emu, StackUnderflow),
         }
-    }
+    };
 }
 
 /// Peeks the top element of the stack
 macro_rules! peek {
     ($emu:ident) => {
-        $emu.stack.peek().expect("operand stack is empty")
-    };
-
-    ($emu:ident, $msg:literal) => {
-        $emu.stack.peek().expect($msg)
-    };
-
-    ($emu:ident, $msg:literal, $($arg:expr),+) => {
         match $emu.stack.peek() {
             Some(value) => value,
-            None => panic!($msg, $($arg),*),
+            None => fault!($emu, StackUnderflow),
         }
-    }
+    };
 }
 
 /// Pops the top word off the stack
 macro_rules! popw {
     ($emu:ident) => {
-        $emu.stack.popw().expect("operand stack does not contain a full word")
-    };
-
-    ($emu:ident, $msg:literal) => {
-        $emu.stack.popw().expect($msg)
+        match $emu.stack.popw() {
+            Some(value) => value,
+            None => fault!($emu, StackUnderflow),
+        }
     };
+}
 
-    ($emu:ident, $msg:literal, $($arg:expr),+) => {{
-        match $emu.stack.popw() {
+/// Peeks the top word of the stack
+macro_rules! peekw {
+    ($emu:ident) => {
+        match $emu.stack.peekw() {
             Some(value) => value,
-            None => panic!($msg, $($arg),*),
+            None => fault!($emu, StackUnderflow),
         }
-    }}
+    };
 }
 
 /// Pops the top two elements off the stack, returning them in order of appearance
@@ -871,57 +1275,71 @@ macro_rules! pop2 {
     }};
 }
 
-/// Pops a u32 value from the top of the stack, and asserts if it is out of range
+/// Pops a u32 value from the top of the stack, and raises an error if it is out of range
 macro_rules! pop_u32 {
     ($emu:ident) => {{
         let value = pop!($emu).as_int();
-        assert!(value < 2u64.pow(32), "assertion failed: {value} is not a valid u32, value is out of range");
+        if value >= 2u64.pow(32) {
+            fault!($emu, InvalidU32Operand { value });
+        }
         value as u32
     }};
-
-    ($emu:ident, $format:literal $(, $args:expr)*) => {{
-        let value = pop!($emu).as_int();
-        assert!(value < 2u64.pow(32), $format, value, $($args),*);
-        value as u32
-    }}
 }
 
-/// Peeks a u32 value from the top of the stack, and asserts if it is out of range
-macro_rules! peek_u32 {
-    ($emu:ident) => {{
-        let value = peek!($emu).as_int();
-        assert!(value < 2u64.pow(32), "assertion failed: {value} is not a valid u32, value is out of range");
-        value as u32
+/// Raises an error if `offset` is not the index of an element within a word
+macro_rules! check_offset {
+    ($emu:ident, $offset:expr) => {{
+        let offset = $offset as u64;
+        if offset > 3 {
+            fault!($emu, InvalidOperand { value: offset });
+        }
+        offset as usize
     }};
+}
 
-    ($emu:ident, $format:literal $(, $args:expr)*) => {{
-        let value = peek!($emu).as_int();
-        assert!(value < 2u64.pow(32), $format, value, $($args),*);
-        value as u32
-    }}
+/// Raises an error if `addr` is not a valid address in linear memory
+macro_rules! check_addr {
+    ($emu:ident, $addr:expr) => {{
+        let addr = $addr;
+        if addr >= $emu.memory.len() {
+            fault!($emu, OutOfBoundsMemoryAccess { addr: addr as u32 });
+        }
+        addr
+    }};
 }
 
-/// Pops a pointer value from the top of the stack, and asserts if it is not a valid boolean
+/// Pops a pointer value from the top of the stack, and raises an error if it is not a valid
+/// address
 macro_rules! pop_addr {
     ($emu:ident) => {{
-        let addr = pop_u32!($emu, "expected valid 32-bit address, got {}") as usize;
-        assert!(addr < $emu.memory.len(), "out of bounds memory access");
-        addr
+        let addr = pop_u32!($emu) as usize;
+        check_addr!($emu, addr)
     }};
 }
 
-/// Pops a boolean value from the top of the stack, and asserts if it is not a valid boolean
+/// Pops a boolean value from the top of the stack, and raises an error if it is not a valid
+/// boolean
 macro_rules! pop_bool {
     ($emu:ident) => {{
         let value = pop!($emu).as_int();
-        assert!(
-            value < 2,
-            "assertion failed: {value} is not a valid boolean, value must be either 1 or 0"
-        );
+        if value > 1 {
+            fault!($emu, InvalidBoolOperand { value });
+        }
         value == 1
     }};
 }
 
+/// Pops a non-zero divisor from the top of the stack, and raises an error if it is zero
+macro_rules! pop_divisor {
+    ($emu:ident) => {{
+        let value = pop!($emu);
+        if value == Felt::ZERO {
+            fault!($emu, DivisionByZero);
+        }
+        value
+    }};
+}
+
 /// Applies a binary operator that produces a result of the same input type:
 ///
 /// 1. The top two elements of the stack
@@ -1078,6 +1496,33 @@ macro_rules! comparison {
     }};
 }
 
+/// Get the word used as the hash of `id` by `procref`, `caller`, `dynexec` and `dyncall`.
+///
+/// The emulator does not compute MAST roots, so instead it identifies a function by the interned
+/// symbols of its name, which is unique among the functions loaded in the emulator.
+fn procedure_digest(id: FunctionIdent) -> [Felt; 4] {
+    [
+        Felt::new(id.module.as_symbol().as_u32() as u64),
+        Felt::new(id.function.as_symbol().as_u32() as u64),
+        Felt::ZERO,
+        Felt::ZERO,
+    ]
+}
+
+/// Get the error code of an assertion, which is 0 unless one was given explicitly
+fn error_code(op: &Op) -> u32 {
+    match op {
+        Op::AssertWithError(code)
+        | Op::AssertzWithError(code)
+        | Op::AssertEqWithError(code)
+        | Op::AssertEqwWithError(code)
+        | Op::U32AssertWithError(code)
+        | Op::U32Assert2WithError(code)
+        | Op::U32AssertwWithError(code) => *code,
+        _ => 0,
+    }
+}
+
 impl Emulator {
     /// Step the emulator forward one cycle, returning the type of event produced
     /// during that cycle, or an error.
@@ -1164,6 +1609,179 @@ impl Emulator {
         }
     }
 
+    /// Step the emulator back one cycle, restoring the state it was in before that cycle.
+    ///
+    /// Reversing execution requires checkpoints to be enabled with [Self::enable_checkpoints].
+    /// The state is restored from the nearest checkpoint, and execution is replayed from there to
+    /// the requested cycle with breakpoints disabled. Native functions are called again when
+    /// replayed, so they must be deterministic for the restored state to be accurate. Likewise,
+    /// changes made to the operand stack or memory while suspended are not replayed.
+    ///
+    /// Returns an error if checkpoints are not enabled, or if the emulator is not suspended,
+    /// stopped, or faulted.
+    pub fn step_back(&mut self) -> Result<EmulatorEvent, EmulationError> {
+        self.rewind(self.clk.saturating_sub(1))?;
+        Ok(EmulatorEvent::Suspended)
+    }
+
+    /// Run the emulator backwards to the most recent cycle at which a breakpoint or watchpoint
+    /// would have suspended execution, or to the start of execution if there is none.
+    ///
+    /// For example, when a watchpoint is hit, this restores the state following the previous
+    /// store to the watched memory. See [Self::step_back] for the requirements of reversing
+    /// execution.
+    pub fn reverse_continue(&mut self) -> Result<EmulatorEvent, EmulationError> {
+        let end = self.clk;
+        let history = self.take_history()?;
+
+        // Search the cycles between each checkpoint and the next, starting with the most recent,
+        // for the last cycle at which a breakpoint would be hit
+        let mut hit = None;
+        let mut result = Ok(());
+        let mut last = end.saturating_sub(1);
+        for checkpoint in history.before(end) {
+            checkpoint.restore(self);
+            // The breakpoints are cloned, as handling events may update their state
+            let mut breakpoints = self.breakpoints.clone();
+            breakpoints.break_on_return(false);
+            if checkpoint.clk == 0 {
+                hit = self.breakpoint_hit(&mut breakpoints, None).map(|bp| (0, bp));
+            }
+            result = self.replay(last, |emulator, event| {
+                if let Some(bp) = emulator.breakpoint_hit(&mut breakpoints, Some(event)) {
+                    hit = Some((emulator.clk, bp));
+                }
+            });
+            if result.is_err() || hit.is_some() {
+                break;
+            }
+            last = checkpoint.clk;
+        }
+
+        // Restore the state at the cycle we found, or the start of execution
+        let cycle = hit
+            .map(|(cycle, _)| cycle)
+            .or_else(|| history.before(end).last().map(|checkpoint| checkpoint.clk));
+        if let Some(cycle) = cycle.filter(|_| result.is_ok()) {
+            // The search above has already replaced the state of the emulator, so if there is no
+            // checkpoint to restore, the emulator is left faulted
+            result = match history.nearest(cycle) {
+                Some(checkpoint) => {
+                    checkpoint.restore(self);
+                    self.replay(cycle, |_, _| ())
+                }
+                None => Err(EmulationError::NoCheckpoint(cycle)),
+            };
+        }
+        self.end_rewind(history, result)?;
+
+        match hit {
+            Some((_, bp)) => Ok(EmulatorEvent::Breakpoint(bp)),
+            None => Ok(EmulatorEvent::Suspended),
+        }
+    }
+
+    /// Restore the state of the emulator after `cycle` cycles, which must not be later than the
+    /// current cycle. See [Self::step_back] for the requirements of reversing execution.
+    ///
+    /// Returns an error, leaving the state of the emulator unchanged, if `cycle` has not been
+    /// reached yet, or precedes the earliest checkpoint.
+    pub fn rewind(&mut self, cycle: usize) -> Result<(), EmulationError> {
+        if cycle > self.clk {
+            return Err(EmulationError::CycleNotReached {
+                cycle,
+                clk: self.clk,
+            });
+        }
+        let history = self.take_history()?;
+        let result = match history.nearest(cycle) {
+            Some(checkpoint) => {
+                checkpoint.restore(self);
+                self.replay(cycle, |_, _| ())
+            }
+            // There are no checkpoints if execution has not started yet
+            None if cycle == self.clk => Ok(()),
+            None => {
+                self.history = Some(history);
+                return Err(EmulationError::NoCheckpoint(cycle));
+            }
+        };
+        self.end_rewind(history, result)
+    }
+
+    /// Take the checkpoints recorded so far, in preparation for reversing execution
+    fn take_history(&mut self) -> Result<History, EmulationError> {
+        if !matches!(self.status, Status::Suspended | Status::Stopped | Status::Faulted(_)) {
+            return Err(EmulationError::NotSuspended);
+        }
+        self.history.take().ok_or(EmulationError::CheckpointsDisabled)
+    }
+
+    /// Finish reversing execution, discarding the checkpoints which follow the current cycle
+    fn end_rewind(
+        &mut self,
+        mut history: History,
+        result: Result<(), EmulationError>,
+    ) -> Result<(), EmulationError> {
+        history.truncate(self.clk);
+        self.history = Some(history);
+        match result {
+            Ok(()) => {
+                self.status = Status::Suspended;
+                Ok(())
+            }
+            Err(err) => {
+                self.status = Status::Faulted(err.clone());
+                Err(err)
+            }
+        }
+    }
+
+    /// Execute cycles until `cycle` cycles have been executed, or execution stops, calling
+    /// `visit` with the event produced by each cycle.
+    ///
+    /// Breakpoints are disabled while replaying, and the caller is expected to have taken the
+    /// checkpoints, so that none are recorded. Profiling remains enabled, so that the profile
+    /// restored from a checkpoint is brought up to date with the cycles which are replayed.
+    fn replay<F>(&mut self, cycle: usize, mut visit: F) -> Result<(), EmulationError>
+    where
+        F: FnMut(&Self, EmulatorEvent),
+    {
+        let breakpoints = core::mem::take(&mut self.breakpoints);
+        let mut result = Ok(());
+        while self.clk < cycle {
+            match self.run_once() {
+                Ok(EmulatorEvent::Stopped) => break,
+                Ok(event) => visit(self, event),
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+        }
+        self.breakpoints = breakpoints;
+        result
+    }
+
+    /// Get the breakpoint in `breakpoints` which would suspend execution in the current state, if
+    /// it was reached by forward execution producing `event`
+    fn breakpoint_hit(
+        &self,
+        breakpoints: &mut BreakpointManager,
+        event: Option<EmulatorEvent>,
+    ) -> Option<BreakpointEvent> {
+        let ip = self.current_ip();
+        event
+            .and_then(|event| breakpoints.handle_event(event, ip))
+            .or_else(|| breakpoints.handle_event(EmulatorEvent::CycleStart(self.clk), ip))
+            .or_else(|| {
+                let Instruction { ip, .. } = ip?;
+                breakpoints
+                    .should_break_at(ip.block, ip.index)
+                    .then_some(BreakpointEvent::Reached(ip))
+            })
+    }
+
     /// Run the emulator until all calls are completed, the cycle budget is exhausted,
     /// or a breakpoint is hit.
     ///
@@ -1214,19 +1832,78 @@ impl Emulator {
         }
     }
 
+    /// Run the pending native function, popping its arguments from the operand stack, and
+    /// pushing its results in their place.
+    fn run_native(&mut self) -> Result<EmulatorEvent, EmulationError> {
+        let native = self.native.as_mut().expect("no native function was scheduled");
+        let function = native.function.clone();
+        let caller = native.caller.take();
+        let name = function.name;
+
+        // The native function remains scheduled while it runs, so that it is visible in the
+        // stack trace of any error it raises
+        let arity = function.arity();
+        require!(self, arity);
+        let args = (0..arity).map(|_| self.stack.pop().unwrap()).collect::<Vec<_>>();
+        let results = function.call(self, &args)?;
+
+        let num_results = function.num_results();
+        if results.len() != num_results {
+            fault!(
+                self,
+                InvalidNativeResults {
+                    function: name,
+                    expected: num_results,
+                    actual: results.len()
+                }
+            );
+        }
+        self.native = None;
+        for result in results.into_iter().rev() {
+            self.stack.push(result);
+        }
+        if let Some(caller) = caller {
+            self.exit_context(name, caller)?;
+        }
+
+        Ok(EmulatorEvent::ExitFunction(name))
+    }
+
     #[inline(never)]
     fn run_once(&mut self) -> Result<EmulatorEvent, EmulationError> {
         const U32_P: u64 = 2u64.pow(32);
 
         // If there are no more activation records, we're done
-        if self.callstack.is_empty() {
+        if self.callstack.is_empty() && self.native.is_none() {
             return Ok(EmulatorEvent::Stopped);
         }
 
-        // Terminate execution early if we reach a predetermined number of cycles
-        self.clk += 1;
-        if self.clk > self.clk_limit {
-            return Err(EmulationError::CycleBudgetExceeded);
+        // Record the state at the start of this cycle, if a checkpoint is due
+        if let Some(mut history) = self.history.take() {
+            if history.is_due(self.clk) {
+                let checkpoint = Checkpoint::capture(self, history.last());
+                history.push(checkpoint);
+            }
+            self.history = Some(history);
+        }
+
+        // A native function is run to completion in a single cycle
+        if self.native.is_some() {
+            self.step_over = None;
+            if self.breakpoints.break_on_return {
+                self.breakpoints.break_on_return(false);
+                return Ok(EmulatorEvent::Breakpoint(BreakpointEvent::StepOut));
+            }
+            // Terminate execution early if we reach a predetermined number of cycles
+            self.clk += 1;
+            if self.clk > self.clk_limit {
+                return Err(EmulationError::CycleBudgetExceeded);
+            }
+            if let (Some(profiler), Some(native)) = (self.profiler.as_mut(), self.native.as_ref()) {
+                let callstack = self.callstack.iter().map(|activation| activation.function().name);
+                profiler.record_cycle(callstack.chain([native.function.name]), None, 1);
+            }
+            return self.run_native();
         }
 
         let mut state = self.callstack.pop().unwrap();
@@ -1254,11 +1931,33 @@ impl Emulator {
             }
         }
 
+        // Terminate execution early if we reach a predetermined number of cycles. The cycle is
+        // only counted once we know it will not be interrupted by a breakpoint, so that the same
+        // cycle count always refers to the same state when replaying from a checkpoint.
+        self.clk += 1;
+        if self.clk > self.clk_limit {
+            self.callstack.push(state);
+            return Err(EmulationError::CycleBudgetExceeded);
+        }
+
         // Advance the instruction pointer, returning the instruction
         // that it previously pointed to, along with what, if any,
         // control flow effect occurred to reach it
         let ix_with_op = state.next();
         if let Some(ix_with_op) = ix_with_op {
+            self.executing = Some(CallFrame {
+                function: current_function,
+                fp: state.fp(),
+                ip: Some(ix_with_op.ip),
+            });
+            if let Some(profiler) = self.profiler.as_mut() {
+                let callstack = self.callstack.iter().map(|activation| activation.function().name);
+                profiler.record_cycle(
+                    callstack.chain([current_function]),
+                    Some((ix_with_op.ip, ix_with_op.op)),
+                    crate::masm::cycles(&ix_with_op.op),
+                );
+            }
             match ix_with_op.op {
                 Op::Padw => {
                     self.stack.padw();
@@ -1283,33 +1982,43 @@ impl Emulator {
                     self.stack.push_u32(i);
                 }
                 Op::Drop => {
+                    require!(self, 1);
                     self.stack.drop();
                 }
                 Op::Dropw => {
+                    require!(self, 4);
                     self.stack.dropw();
                 }
                 Op::Dup(pos) => {
+                    require!(self, pos as usize + 1);
                     self.stack.dup(pos as usize);
                 }
                 Op::Dupw(pos) => {
+                    require!(self, 4 * (pos as usize + 1));
                     self.stack.dupw(pos as usize);
                 }
                 Op::Swap(pos) => {
+                    require!(self, pos as usize + 1);
                     self.stack.swap(pos as usize);
                 }
                 Op::Swapw(pos) => {
+                    require!(self, 4 * (pos as usize + 1));
                     self.stack.swapw(pos as usize);
                 }
                 Op::Movup(pos) => {
+                    require!(self, pos as usize + 1);
                     self.stack.movup(pos as usize);
                 }
                 Op::Movupw(pos) => {
+                    require!(self, 4 * (pos as usize + 1));
                     self.stack.movupw(pos as usize);
                 }
                 Op::Movdn(pos) => {
+                    require!(self, pos as usize + 1);
                     self.stack.movdn(pos as usize);
                 }
                 Op::Movdnw(pos) => {
+                    require!(self, 4 * (pos as usize + 1));
                     self.stack.movdnw(pos as usize);
                 }
                 Op::Cswap => {
@@ -1354,18 +2063,21 @@ impl Emulator {
                     }
                 }
                 Op::AdvLoadw => {
+                    require!(self, 4);
                     let word = adv_popw!(self);
                     self.stack.dropw();
                     self.stack.pushw(word);
                 }
                 Op::AdvPipe => {
                     // We're overwriting the first two words, C and B, so drop them
+                    require!(self, 8);
                     self.stack.dropw();
                     self.stack.dropw();
                     // The third word, A, is saved, but unused
                     let a = popw!(self);
                     // The memory address to write to is the first element of the fourth word
                     let addr = pop_addr!(self);
+                    check_addr!(self, addr + 1);
                     // We update the original address += 2, and restore A
                     self.stack.push_u32(addr as u32 + 2);
                     self.stack.pushw(a);
@@ -1385,44 +2097,98 @@ impl Emulator {
                         size: 16,
                     });
                 }
-                Op::Assert => {
+                Op::Hperm => {
+                    require!(self, 12);
+                    // The state consists of the capacity word, A, followed by the rate, B and C
+                    let c = popw!(self);
+                    let b = popw!(self);
+                    let a = popw!(self);
+                    let mut state = [Felt::ZERO; 12];
+                    for (i, elem) in a.into_iter().chain(b).chain(c).enumerate() {
+                        state[i] = elem;
+                    }
+                    Rpo256::apply_permutation(&mut state);
+                    for word in state.chunks_exact(4) {
+                        self.stack.pushw([word[0], word[1], word[2], word[3]]);
+                    }
+                }
+                Op::Assert | Op::AssertWithError(_) => {
                     let cond = pop_bool!(self);
-                    assert!(cond, "assertion failed: expected true, got false");
+                    if !cond {
+                        fault!(
+                            self,
+                            AssertionFailed {
+                                code: error_code(&ix_with_op.op)
+                            }
+                        );
+                    }
                 }
-                Op::Assertz => {
+                Op::Assertz | Op::AssertzWithError(_) => {
                     let cond = pop_bool!(self);
-                    assert!(!cond, "assertion failed: expected false, got true");
+                    if cond {
+                        fault!(
+                            self,
+                            AssertionFailed {
+                                code: error_code(&ix_with_op.op)
+                            }
+                        );
+                    }
                 }
-                Op::AssertEq => {
+                Op::AssertEq | Op::AssertEqWithError(_) => {
                     let (b, a) = pop2!(self);
-                    assert_eq!(a, b, "equality assertion failed");
+                    if a != b {
+                        fault!(
+                            self,
+                            AssertionFailed {
+                                code: error_code(&ix_with_op.op)
+                            }
+                        );
+                    }
                 }
-                Op::AssertEqw => {
+                Op::AssertEqw | Op::AssertEqwWithError(_) => {
                     let b = popw!(self);
                     let a = popw!(self);
-                    assert_eq!(a, b, "equality assertion failed");
+                    if a != b {
+                        fault!(
+                            self,
+                            AssertionFailed {
+                                code: error_code(&ix_with_op.op)
+                            }
+                        );
+                    }
                 }
                 Op::LocAddr(id) => {
                     let addr = state.fp() + id.as_usize() as u32;
                     debug_assert!(addr < self.memory.len() as u32);
                     self.stack.push_u32(addr * 16);
                 }
+                Op::LocLoad(id) => {
+                    let addr = (state.fp() + id.as_usize() as u32) as usize;
+                    debug_assert!(addr < self.memory.len());
+                    self.stack.push(self.memory[addr][0]);
+                }
+                Op::LocLoadw(id) => {
+                    let addr = check_addr!(self, (state.fp() + id.as_usize() as u32) as usize);
+                    require!(self, 4);
+                    self.stack.dropw();
+                    self.stack.pushw(self.memory[addr]);
+                }
                 Op::LocStore(id) => {
                     let addr = (state.fp() + id.as_usize() as u32) as usize;
                     debug_assert!(addr < self.memory.len());
                     let value = pop!(self);
                     self.memory[addr][0] = value;
+                    self.callstack.push(state);
                     return Ok(EmulatorEvent::MemoryWrite {
                         addr: addr as u32,
                         size: 4,
                     });
                 }
                 Op::LocStorew(id) => {
-                    let addr = (state.fp() + id.as_usize() as u32) as usize;
-                    assert!(addr < self.memory.len() - 4, "out of bounds memory access");
-                    let word =
-                        self.stack.peekw().expect("operand stack does not contain a full word");
+                    let addr = check_addr!(self, (state.fp() + id.as_usize() as u32) as usize);
+                    let word = peekw!(self);
                     self.memory[addr] = word;
+                    self.callstack.push(state);
                     return Ok(EmulatorEvent::MemoryWrite {
                         addr: addr as u32,
                         size: 16,
@@ -1433,30 +2199,28 @@ impl Emulator {
                     self.stack.push(self.memory[addr][0]);
                 }
                 Op::MemLoadOffset => {
-                    let offset = pop_u32!(self) as usize;
-                    assert!(offset < 4, "expected valid element offset, got {offset}");
+                    let offset = check_offset!(self, pop_u32!(self));
                     let addr = pop_addr!(self);
                     self.stack.push(self.memory[addr][offset]);
                 }
                 Op::MemLoadImm(addr) => {
-                    let addr = addr as usize;
-                    assert!(addr < self.memory.len(), "out of bounds memory access");
+                    let addr = check_addr!(self, addr as usize);
                     self.stack.push(self.memory[addr][0]);
                 }
                 Op::MemLoadOffsetImm(addr, offset) => {
-                    let addr = addr as usize;
-                    let offset = offset as usize;
-                    assert!(addr < self.memory.len(), "out of bounds memory access");
+                    let addr = check_addr!(self, addr as usize);
+                    let offset = check_offset!(self, offset);
                     self.stack.push(self.memory[addr][offset]);
                 }
                 Op::MemLoadw => {
                     let addr = pop_addr!(self);
+                    require!(self, 4);
                     self.stack.dropw();
                     self.stack.pushw(self.memory[addr]);
                 }
                 Op::MemLoadwImm(addr) => {
-                    let addr = addr as usize;
-                    assert!(addr < self.memory.len() - 4, "out of bounds memory access");
+                    let addr = check_addr!(self, addr as usize);
+                    require!(self, 4);
                     self.stack.dropw();
                     self.stack.pushw(self.memory[addr]);
                 }
@@ -1471,11 +2235,9 @@ impl Emulator {
                     });
                 }
                 Op::MemStoreOffset => {
-                    let offset = pop_u32!(self);
-                    assert!(offset < 4, "expected valid element offset, got {offset}");
+                    let offset = check_offset!(self, pop_u32!(self));
                     let addr = pop_addr!(self);
                     let value = pop!(self);
-                    let offset = offset as usize;
                     self.memory[addr][offset] = value;
                     self.callstack.push(state);
                     return Ok(EmulatorEvent::MemoryWrite {
@@ -1484,9 +2246,8 @@ impl Emulator {
                     });
                 }
                 Op::MemStoreImm(addr) => {
-                    let addr = addr as usize;
-                    assert!(addr < self.memory.len(), "out of bounds memory access");
-                    let value = self.stack.pop().expect("operand stack is empty");
+                    let addr = check_addr!(self, addr as usize);
+                    let value = pop!(self);
                     self.memory[addr][0] = value;
                     self.callstack.push(state);
                     return Ok(EmulatorEvent::MemoryWrite {
@@ -1495,10 +2256,9 @@ impl Emulator {
                     });
                 }
                 Op::MemStoreOffsetImm(addr, offset) => {
-                    let addr = addr as usize;
-                    let offset = offset as usize;
-                    assert!(addr < self.memory.len(), "out of bounds memory access");
-                    let value = self.stack.pop().expect("operand stack is empty");
+                    let addr = check_addr!(self, addr as usize);
+                    let offset = check_offset!(self, offset);
+                    let value = pop!(self);
                     self.memory[addr][offset] = value;
                     self.callstack.push(state);
                     return Ok(EmulatorEvent::MemoryWrite {
@@ -1508,8 +2268,7 @@ impl Emulator {
                 }
                 Op::MemStorew => {
                     let addr = pop_addr!(self);
-                    let word =
-                        self.stack.peekw().expect("operand stack does not contain a full word");
+                    let word = peekw!(self);
                     self.memory[addr] = word;
                     self.callstack.push(state);
                     return Ok(EmulatorEvent::MemoryWrite {
@@ -1518,10 +2277,8 @@ impl Emulator {
                     });
                 }
                 Op::MemStorewImm(addr) => {
-                    let addr = addr as usize;
-                    assert!(addr < self.memory.len() - 4, "out of bounds memory access");
-                    let word =
-                        self.stack.peekw().expect("operand stack does not contain a full word");
+                    let addr = check_addr!(self, addr as usize);
+                    let word = peekw!(self);
                     self.memory[addr] = word;
                     self.callstack.push(state);
                     return Ok(EmulatorEvent::MemoryWrite {
@@ -1529,6 +2286,23 @@ impl Emulator {
                         size: 16,
                     });
                 }
+                Op::MemStream => {
+                    // We're overwriting the first two words, C and B, so drop them
+                    require!(self, 8);
+                    self.stack.dropw();
+                    self.stack.dropw();
+                    // The third word, A, is saved, but unused
+                    let a = popw!(self);
+                    // The memory address to read from is the first element of the fourth word
+                    let addr = pop_addr!(self);
+                    check_addr!(self, addr + 1);
+                    // We update the original address += 2, and restore A
+                    self.stack.push_u32(addr as u32 + 2);
+                    self.stack.pushw(a);
+                    // We then load the words D and E, starting at `addr`, on to the operand stack
+                    self.stack.pushw(self.memory[addr]);
+                    self.stack.pushw(self.memory[addr + 1]);
+                }
                 Op::If(then_blk, else_blk) => {
                     self.step_over = Some(state.ip());
                     let cond = pop_bool!(self);
@@ -1573,58 +2347,195 @@ impl Emulator {
                             self.callstack.push(callee_state);
                             return Ok(EmulatorEvent::EnterFunction(function.name));
                         }
-                        Stub::Native(_function) => unimplemented!(),
+                        Stub::Native(function) => {
+                            require!(self, function.arity());
+                            // Suspend caller and schedule callee next
+                            self.callstack.push(state);
+                            self.schedule_native(function, None);
+                            return Ok(EmulatorEvent::EnterFunction(callee));
+                        }
+                    }
+                }
+                Op::Call(callee) => {
+                    let fun = self
+                        .functions
+                        .get(&callee)
+                        .cloned()
+                        .ok_or(EmulationError::UndefinedFunction(callee))?;
+                    self.step_over = Some(state.ip());
+                    match fun {
+                        Stub::Asm(ref function) => {
+                            // The callee is executed in a new context
+                            let caller = self.enter_context(None);
+                            let fp = self.locals[&function.name];
+                            let callee_state =
+                                Activation::new(function.clone(), fp).with_caller_context(caller);
+                            // Suspend caller and scheduled callee next
+                            self.callstack.push(state);
+                            self.callstack.push(callee_state);
+                            return Ok(EmulatorEvent::EnterFunction(function.name));
+                        }
+                        Stub::Native(function) => {
+                            require!(self, function.arity());
+                            // The callee is executed in a new context
+                            let caller = self.enter_context(None);
+                            // Suspend caller and schedule callee next
+                            self.callstack.push(state);
+                            self.schedule_native(function, Some(caller));
+                            return Ok(EmulatorEvent::EnterFunction(callee));
+                        }
                     }
                 }
-                Op::Syscall(_callee) => unimplemented!(),
+                Op::Syscall(callee) => {
+                    // Kernel procedures are resolved by name against the loaded kernel. Only
+                    // exported procedures may be invoked this way. When no kernel is loaded,
+                    // native functions may stand in for kernel procedures, e.g. to mock the
+                    // transaction kernel in tests.
+                    let callee = match self.kernel {
+                        Some(kernel) => FunctionIdent {
+                            module: kernel,
+                            function: callee.function,
+                        },
+                        None if matches!(self.functions.get(&callee), Some(Stub::Native(_))) => {
+                            callee
+                        }
+                        None => return Err(EmulationError::NoKernel(callee.function)),
+                    };
+                    let fun = match self.functions.get(&callee) {
+                        Some(stub) if stub.signature().is_public() => stub.clone(),
+                        _ => return Err(EmulationError::UndefinedFunction(callee)),
+                    };
+                    self.step_over = Some(state.ip());
+                    if let Stub::Native(ref function) = fun {
+                        require!(self, function.arity());
+                    }
+                    // Kernel procedures are always executed in the root context
+                    let caller = self.enter_context(Some(Self::ROOT_CONTEXT));
+                    self.callstack.push(state);
+                    match fun {
+                        Stub::Asm(function) => {
+                            let fp = self.locals[&function.name];
+                            let callee_state =
+                                Activation::new(function, fp).with_caller_context(caller);
+                            self.callstack.push(callee_state);
+                        }
+                        Stub::Native(function) => self.schedule_native(function, Some(caller)),
+                    }
+                    return Ok(EmulatorEvent::EnterFunction(callee));
+                }
+                op @ (Op::DynExec | Op::DynCall) => {
+                    // The hash of the callee is left on the operand stack
+                    let digest = peekw!(self);
+                    let callee = self
+                        .functions
+                        .iter()
+                        .find(|(id, _)| procedure_digest(**id) == digest)
+                        .map(|(id, stub)| (*id, stub.clone()));
+                    let Some((callee, fun)) = callee else {
+                        fault!(self, UndefinedProcedure);
+                    };
+                    self.step_over = Some(state.ip());
+                    if let Stub::Native(ref function) = fun {
+                        require!(self, function.arity());
+                    }
+                    // The callee of `dyncall` is executed in a new context
+                    let caller = (op == Op::DynCall).then(|| self.enter_context(None));
+                    self.callstack.push(state);
+                    match fun {
+                        Stub::Asm(function) => {
+                            let fp = self.locals[&function.name];
+                            let mut callee_state = Activation::new(function, fp);
+                            if let Some(caller) = caller {
+                                callee_state = callee_state.with_caller_context(caller);
+                            }
+                            self.callstack.push(callee_state);
+                        }
+                        Stub::Native(function) => self.schedule_native(function, caller),
+                    }
+                    return Ok(EmulatorEvent::EnterFunction(callee));
+                }
+                Op::ProcRef(callee) => {
+                    if !self.functions.contains_key(&callee) {
+                        return Err(EmulationError::UndefinedFunction(callee));
+                    }
+                    self.stack.pushw(procedure_digest(callee));
+                }
+                Op::Caller => {
+                    // Kernel procedures can only be entered via syscall, so the caller is the
+                    // function which entered the outermost kernel frame on the call stack
+                    require!(self, 4);
+                    let frames = self
+                        .callstack
+                        .iter()
+                        .map(|activation| activation.function().name)
+                        .chain([current_function])
+                        .collect::<Vec<_>>();
+                    let syscall = frames
+                        .iter()
+                        .position(|frame| Some(frame.module) == self.kernel)
+                        .filter(|index| *index > 0);
+                    let Some(index) = syscall else {
+                        fault!(self, CallerNotInSyscall);
+                    };
+                    self.stack.dropw();
+                    self.stack.pushw(procedure_digest(frames[index - 1]));
+                }
                 Op::Add => binop!(self, add),
                 Op::AddImm(imm) => binop!(self, add, imm),
                 Op::Sub => binop!(self, sub),
                 Op::SubImm(imm) => binop!(self, sub, imm),
                 Op::Mul => binop!(self, mul),
                 Op::MulImm(imm) => binop!(self, mul, imm),
-                Op::Div => binop!(self, div),
-                Op::DivImm(imm) => binop!(self, div, imm),
+                Op::Div => {
+                    let b = pop_divisor!(self);
+                    let a = pop!(self);
+                    self.stack.push(a / b);
+                }
+                Op::DivImm(imm) => {
+                    if imm == Felt::ZERO {
+                        fault!(self, DivisionByZero);
+                    }
+                    binop!(self, div, imm)
+                }
                 Op::Neg => {
-                    let a = self.stack.pop().expect("operand stack is empty");
+                    let a = pop!(self);
                     self.stack.push(-a);
                 }
                 Op::Inv => {
-                    let a = self.stack.pop().expect("operand stack is empty");
+                    let a = pop_divisor!(self);
                     self.stack.push(a.inv());
                 }
                 Op::Incr => binop!(self, add, Felt::ONE),
                 Op::Ilog2 => {
-                    let a = peek!(self).as_int();
-                    assert!(a > 0, "invalid ilog2 argument: expected {a} to be > 0");
-                    self.advice_stack.push_u32(a.ilog2());
+                    let a = pop!(self).as_int();
+                    if a == 0 {
+                        fault!(self, InvalidOperand { value: a });
+                    }
+                    self.stack.push_u32(a.ilog2());
                 }
                 Op::Pow2 => {
                     let a = pop!(self).as_int();
-                    assert!(
-                        a < 64,
-                        "invalid power of two: expected {a} to be a value less than 64"
-                    );
+                    if a > 63 {
+                        fault!(self, InvalidOperand { value: a });
+                    }
                     let two = Felt::new(2);
                     self.stack.push(two.exp(a));
                 }
                 Op::Exp => {
                     let (b, a) = pop2!(self);
-                    let b = b.as_int();
-                    assert!(
-                        b < 64,
-                        "invalid power of two: expected {b} to be a value less than 64"
-                    );
-                    self.stack.push(a.exp(b));
+                    self.stack.push(a.exp(b.as_int()));
                 }
                 Op::ExpImm(pow) => {
-                    let pow = pow as u64;
                     let a = pop!(self);
-                    assert!(
-                        pow < 64,
-                        "invalid power of two: expected {pow} to be a value less than 64"
-                    );
-                    self.stack.push(a.exp(pow));
+                    self.stack.push(a.exp(pow as u64));
+                }
+                Op::ExpBitLength(bits) => {
+                    let (b, a) = pop2!(self);
+                    let b = b.as_int();
+                    if bits > 64 || (bits < 64 && b >> bits != 0) {
+                        fault!(self, InvalidOperand { value: b });
+                    }
+                    self.stack.push(a.exp(b));
                 }
                 Op::Not => {
                     let a = pop_bool!(self);
@@ -1671,7 +2582,7 @@ impl Emulator {
                 Op::LteImm(imm) => comparison!(self, le, imm.as_int()),
                 Op::IsOdd => {
                     let a = pop!(self).as_int();
-                    self.stack.push_u8((a % 2 == 0) as u8);
+                    self.stack.push_u8((a % 2 == 1) as u8);
                 }
                 Op::Eqw => {
                     let b = popw!(self);
@@ -1682,30 +2593,46 @@ impl Emulator {
                     self.stack.push(Felt::new(self.clk as u64));
                 }
                 Op::U32Test => {
-                    let top = self.stack.peek().expect("operand stack is empty").as_int();
+                    let top = peek!(self).as_int();
                     self.stack.push_u8((top < U32_P) as u8);
                 }
                 Op::U32Testw => {
-                    let word = self.stack.peekw().expect("operand stack is empty");
+                    let word = peekw!(self);
                     let is_true = word.iter().all(|elem| elem.as_int() < U32_P);
                     self.stack.push_u8(is_true as u8);
                 }
-                Op::U32Assert => {
-                    let top = self.stack.peek().expect("operand stack is empty").as_int();
-                    assert!(top < U32_P, "assertion failed: {top} is larger than 2^32");
-                }
-                Op::U32Assert2 => {
-                    let a = self.stack.peek().expect("operand stack is empty").as_int();
-                    let b = self.stack.peek().expect("operand stack is empty").as_int();
-                    assert!(a < U32_P, "assertion failed: {a} is larger than 2^32");
-                    assert!(b < U32_P, "assertion failed: {b} is larger than 2^32");
-                }
-                Op::U32Assertw => {
-                    let word = self.stack.peekw().expect("operand stack is empty");
-                    for elem in word.into_iter() {
-                        assert!(
-                            elem.as_int() < U32_P,
-                            "assertion failed: {elem} is larger than 2^32"
+                Op::U32Assert | Op::U32AssertWithError(_) => {
+                    let top = peek!(self).as_int();
+                    if top >= U32_P {
+                        fault!(
+                            self,
+                            AssertionFailed {
+                                code: error_code(&ix_with_op.op)
+                            }
+                        );
+                    }
+                }
+                Op::U32Assert2 | Op::U32Assert2WithError(_) => {
+                    require!(self, 2);
+                    let b = self.stack[0].as_int();
+                    let a = self.stack[1].as_int();
+                    if a >= U32_P || b >= U32_P {
+                        fault!(
+                            self,
+                            AssertionFailed {
+                                code: error_code(&ix_with_op.op)
+                            }
+                        );
+                    }
+                }
+                Op::U32Assertw | Op::U32AssertwWithError(_) => {
+                    let word = peekw!(self);
+                    if word.iter().any(|elem| elem.as_int() >= U32_P) {
+                        fault!(
+                            self,
+                            AssertionFailed {
+                                code: error_code(&ix_with_op.op)
+                            }
                         );
                     }
                 }
@@ -1724,8 +2651,20 @@ impl Emulator {
                 Op::U32OverflowingAddImm(imm) => binop_overflowing_u32!(self, add, imm),
                 Op::U32WrappingAdd => binop_wrapping_u32!(self, add),
                 Op::U32WrappingAddImm(imm) => binop_wrapping_u32!(self, add, imm),
-                Op::U32OverflowingAdd3 => todo!(),
-                Op::U32WrappingAdd3 => todo!(),
+                Op::U32OverflowingAdd3 => {
+                    let c = pop_u32!(self) as u64;
+                    let b = pop_u32!(self) as u64;
+                    let a = pop_u32!(self) as u64;
+                    let result = a + b + c;
+                    self.stack.push(Felt::new(result % U32_P));
+                    self.stack.push(Felt::new(result / U32_P));
+                }
+                Op::U32WrappingAdd3 => {
+                    let c = pop_u32!(self) as u64;
+                    let b = pop_u32!(self) as u64;
+                    let a = pop_u32!(self) as u64;
+                    self.stack.push(Felt::new((a + b + c) % U32_P));
+                }
                 Op::U32OverflowingSub => binop_overflowing_u32!(self, sub),
                 Op::U32OverflowingSubImm(imm) => binop_overflowing_u32!(self, sub, imm),
                 Op::U32WrappingSub => binop_wrapping_u32!(self, sub),
@@ -1751,24 +2690,39 @@ impl Emulator {
                     let d = (a * b + c) % 2u64.pow(32);
                     self.stack.push(Felt::new(d));
                 }
-                Op::U32Div => binop_unchecked_u32!(self, div),
-                Op::U32DivImm(imm) => binop_unchecked_u32!(self, div, imm as u64),
+                Op::U32Div => {
+                    let b = pop_divisor!(self).as_int();
+                    let a = pop!(self).as_int();
+                    self.stack.push(Felt::new(a / b));
+                }
+                Op::U32DivImm(imm) => {
+                    if imm == 0 {
+                        fault!(self, DivisionByZero);
+                    }
+                    binop_unchecked_u32!(self, div, imm as u64)
+                }
                 Op::U32Mod => {
-                    let b = pop!(self).as_int();
+                    let b = pop_divisor!(self).as_int();
                     let a = pop!(self).as_int();
                     self.stack.push(Felt::new(a % b));
                 }
                 Op::U32ModImm(imm) => {
+                    if imm == 0 {
+                        fault!(self, DivisionByZero);
+                    }
                     let a = pop!(self).as_int();
                     self.stack.push(Felt::new(a % imm as u64));
                 }
                 Op::U32DivMod => {
-                    let b = pop!(self).as_int();
+                    let b = pop_divisor!(self).as_int();
                     let a = pop!(self).as_int();
                     self.stack.push(Felt::new(a / b));
                     self.stack.push(Felt::new(a % b));
                 }
                 Op::U32DivModImm(b) => {
+                    if b == 0 {
+                        fault!(self, DivisionByZero);
+                    }
                     let b = b as u64;
                     let a = pop!(self).as_int();
                     self.stack.push(Felt::new(a / b));
@@ -1808,20 +2762,20 @@ impl Emulator {
                     self.stack.push_u32(a.count_ones());
                 }
                 Op::U32Clz => {
-                    let a = peek_u32!(self);
-                    self.advice_stack.push_u32(a.leading_zeros());
+                    let a = pop_u32!(self);
+                    self.stack.push_u32(a.leading_zeros());
                 }
                 Op::U32Clo => {
-                    let a = peek_u32!(self);
-                    self.advice_stack.push_u32(a.leading_ones());
+                    let a = pop_u32!(self);
+                    self.stack.push_u32(a.leading_ones());
                 }
                 Op::U32Ctz => {
-                    let a = peek_u32!(self);
-                    self.advice_stack.push_u32(a.trailing_zeros());
+                    let a = pop_u32!(self);
+                    self.stack.push_u32(a.trailing_zeros());
                 }
                 Op::U32Cto => {
-                    let a = peek_u32!(self);
-                    self.advice_stack.push_u32(a.trailing_ones());
+                    let a = pop_u32!(self);
+                    self.stack.push_u32(a.trailing_ones());
                 }
                 Op::U32Gt => comparison!(self, gt),
                 Op::U32Gte => comparison!(self, ge),
@@ -1837,7 +2791,6 @@ impl Emulator {
                     let a = pop!(self).as_int();
                     self.stack.push(Felt::new(cmp::max(a, b)));
                 }
-                op => unimplemented!("missing opcode implementation for {op:?}"),
             }
 
             match ix_with_op.effect {
@@ -1862,6 +2815,9 @@ impl Emulator {
             Ok(EmulatorEvent::Suspended)
         } else {
             // No more code left in the current function
+            if let Some(caller) = state.take_caller_context() {
+                self.exit_context(current_function, caller)?;
+            }
             Ok(EmulatorEvent::ExitFunction(current_function))
         }
     }
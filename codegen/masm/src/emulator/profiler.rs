@@ -0,0 +1,244 @@
+use std::{fmt, io};
+
+use miden_hir::FunctionIdent;
+use rustc_hash::FxHashMap;
+
+use super::InstructionPointer;
+use crate::Op;
+
+/// The number of instructions listed in the summary printed by [Profiler]
+const HOT_INSTRUCTIONS: usize = 10;
+
+/// The cycles attributed to a single function by the [Profiler]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct FunctionProfile {
+    /// The number of times the function was called
+    pub calls: usize,
+    /// The number of cycles spent executing the function itself
+    pub self_cycles: usize,
+    /// The number of cycles spent executing the function, including the functions it calls
+    pub total_cycles: usize,
+}
+
+/// The number of times a single instruction was executed, see [Profiler::instructions]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InstructionProfile {
+    pub function: FunctionIdent,
+    pub ip: InstructionPointer,
+    pub op: Op,
+    pub count: usize,
+}
+
+/// An opt-in profiler for the [super::Emulator], enabled with
+/// [super::Emulator::enable_profiling].
+///
+/// Every instruction executed by the emulator is charged the number of cycles it takes in the
+/// Miden VM, as estimated by [crate::masm::cycles], while every call to a native function is
+/// charged one cycle. The cycles are attributed to the function which executed the instruction,
+/// and to every function on the call stack at the time, from which the profiler derives the cost
+/// of each function, both with and without its callees, and the collapsed call stacks used to
+/// render a flamegraph.
+///
+/// The profile accumulates across executions until the profiler is taken from the emulator.
+#[derive(Debug, Default, Clone)]
+pub struct Profiler {
+    /// The total number of cycles profiled
+    cycles: usize,
+    /// The depth of the call stack when the previous instruction was executed, used to detect
+    /// calls
+    depth: usize,
+    functions: FxHashMap<FunctionIdent, FunctionProfile>,
+    instructions: FxHashMap<(FunctionIdent, InstructionPointer), (Op, usize)>,
+    /// The number of cycles spent with each call stack, outermost frame first
+    stacks: FxHashMap<Vec<FunctionIdent>, usize>,
+    /// A scratch buffer for the call stack of the current cycle
+    stack: Vec<FunctionIdent>,
+}
+impl Profiler {
+    /// Attribute `cycles` cycles to the innermost frame of `callstack`, which executed
+    /// `instruction`, or is a native function if `None`.
+    pub(super) fn record_cycle<I>(
+        &mut self,
+        callstack: I,
+        instruction: Option<(InstructionPointer, Op)>,
+        cycles: usize,
+    ) where
+        I: IntoIterator<Item = FunctionIdent>,
+    {
+        let mut stack = core::mem::take(&mut self.stack);
+        stack.clear();
+        stack.extend(callstack);
+        let function = *stack.last().expect("expected at least one frame on the call stack");
+
+        self.cycles += cycles;
+        // A call always executes in the caller, so the call stack can only grow by one frame
+        // between instructions, when the first instruction of the callee is executed
+        let called = stack.len() > self.depth;
+        self.depth = stack.len();
+
+        let profile = self.functions.entry(function).or_default();
+        profile.self_cycles += cycles;
+        if called {
+            profile.calls += 1;
+        }
+        // Recursive functions are only charged once for each cycle
+        for (i, caller) in stack.iter().enumerate() {
+            if !stack[..i].contains(caller) {
+                self.functions.entry(*caller).or_default().total_cycles += cycles;
+            }
+        }
+
+        if let Some((ip, op)) = instruction {
+            self.instructions.entry((function, ip)).or_insert((op, 0)).1 += 1;
+        }
+
+        // Instructions which take no cycles, e.g. `exec`, do not contribute to the flamegraph
+        if cycles > 0 {
+            match self.stacks.get_mut(stack.as_slice()) {
+                Some(total) => *total += cycles,
+                None => {
+                    self.stacks.insert(stack.clone(), cycles);
+                }
+            }
+        }
+        self.stack = stack;
+    }
+
+    /// Called when the emulator is stopped, so that the next execution starts a new call stack
+    pub(super) fn stop(&mut self) {
+        self.depth = 0;
+    }
+
+    /// Get the total number of cycles profiled
+    pub fn cycles(&self) -> usize {
+        self.cycles
+    }
+
+    /// Get the profile of `function`, if it was executed
+    pub fn function(&self, function: &FunctionIdent) -> Option<&FunctionProfile> {
+        self.functions.get(function)
+    }
+
+    /// Get the profile of every function executed, from most to least expensive, excluding
+    /// the cost of callees
+    pub fn functions(&self) -> Vec<(FunctionIdent, FunctionProfile)> {
+        let mut functions =
+            self.functions.iter().map(|(id, profile)| (*id, *profile)).collect::<Vec<_>>();
+        functions.sort_by(|(a, a_profile), (b, b_profile)| {
+            b_profile
+                .self_cycles
+                .cmp(&a_profile.self_cycles)
+                .then_with(|| a.to_string().cmp(&b.to_string()))
+        });
+        functions
+    }
+
+    /// Get the number of times the instruction at `ip` in `function` was executed
+    pub fn instruction_count(&self, function: &FunctionIdent, ip: InstructionPointer) -> usize {
+        self.instructions
+            .get(&(*function, ip))
+            .map(|(_, count)| *count)
+            .unwrap_or_default()
+    }
+
+    /// Get the execution count of every instruction executed, from most to least executed
+    pub fn instructions(&self) -> Vec<InstructionProfile> {
+        let mut instructions = self
+            .instructions
+            .iter()
+            .map(|((function, ip), (op, count))| InstructionProfile {
+                function: *function,
+                ip: *ip,
+                op: *op,
+                count: *count,
+            })
+            .collect::<Vec<_>>();
+        instructions.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.function.to_string().cmp(&b.function.to_string()))
+                .then_with(|| a.ip.block.cmp(&b.ip.block))
+                .then_with(|| a.ip.index.cmp(&b.ip.index))
+        });
+        instructions
+    }
+
+    /// Write the profiled call stacks in the collapsed stack format, i.e. one line per call stack
+    /// of the form `outer;inner <cycles>`, which is understood by standard flamegraph tools, e.g.
+    /// `inferno-flamegraph` or `flamegraph.pl`.
+    pub fn write_collapsed_stacks<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut stacks = self
+            .stacks
+            .iter()
+            .map(|(stack, cycles)| {
+                let stack =
+                    stack.iter().map(|function| function.to_string()).collect::<Vec<_>>().join(";");
+                (stack, *cycles)
+            })
+            .collect::<Vec<_>>();
+        stacks.sort();
+        for (stack, cycles) in stacks {
+            writeln!(writer, "{stack} {cycles}")?;
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for Profiler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let functions = self.functions();
+        let width = functions
+            .iter()
+            .map(|(id, _)| id.to_string().len())
+            .max()
+            .unwrap_or_default()
+            .max("function".len());
+        let percent = |cycles: usize| {
+            if self.cycles == 0 {
+                0.0
+            } else {
+                (cycles as f64 / self.cycles as f64) * 100.0
+            }
+        };
+
+        writeln!(
+            f,
+            "{:width$}  {:>8}  {:>10}  {:>7}  {:>10}  {:>7}",
+            "function", "calls", "self", "self %", "total", "total %"
+        )?;
+        for (id, profile) in functions.iter() {
+            writeln!(
+                f,
+                "{:width$}  {:>8}  {:>10}  {:>6.2}%  {:>10}  {:>6.2}%",
+                id.to_string(),
+                profile.calls,
+                profile.self_cycles,
+                percent(profile.self_cycles),
+                profile.total_cycles,
+                percent(profile.total_cycles)
+            )?;
+        }
+        writeln!(f, "total cycles: {}", self.cycles)?;
+
+        let instructions = self.instructions();
+        if !instructions.is_empty() {
+            writeln!(f)?;
+            writeln!(
+                f,
+                "{:width$}  {:>12}  {:16}  {:>10}",
+                "function", "ip", "instruction", "count"
+            )?;
+            for instruction in instructions.iter().take(HOT_INSTRUCTIONS) {
+                let ip = format!("{}:{}", instruction.ip.block, instruction.ip.index);
+                writeln!(
+                    f,
+                    "{:width$}  {ip:>12}  {:16}  {:>10}",
+                    instruction.function.to_string(),
+                    instruction.op.to_string(),
+                    instruction.count
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -15,7 +15,8 @@ pub use self::{
     convert::ConvertHirToMasm,
     emulator::{
         Breakpoint, BreakpointEvent, CallFrame, DebugInfo, DebugInfoWithStack, EmulationError,
-        Emulator, EmulatorEvent, InstructionPointer, WatchMode, Watchpoint, WatchpointId,
+        Emulator, EmulatorEvent, FunctionProfile, InstructionPointer, InstructionProfile, Profiler,
+        WatchMode, Watchpoint, WatchpointId,
     },
     masm::*,
 };
@@ -0,0 +1,456 @@
+use std::{fmt, ops::Add};
+
+use miden_hir::{FunctionIdent, StarkField, Symbol};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use super::*;
+
+/// The number of cycles spent on entry to, and exit from, a procedure with locals, which must
+/// bump the frame pointer on the way in, and restore it on the way out.
+const LOCALS_OVERHEAD: usize = 4;
+
+/// A static estimate of the number of cycles required to execute some Miden Assembly
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cost {
+    /// The minimum number of cycles required, i.e. on the cheapest path through the code
+    pub best: usize,
+    /// The maximum number of cycles required, i.e. on the most expensive path through the code
+    ///
+    /// This is `None` when no bound can be determined statically, e.g. if the code contains a
+    /// `while` loop, recursion, or calls a procedure whose cost is unknown.
+    pub worst: Option<usize>,
+}
+impl Cost {
+    /// The cost of executing nothing at all
+    pub const ZERO: Self = Self::exact(0);
+
+    /// A cost which is known to be exactly `cycles`
+    pub const fn exact(cycles: usize) -> Self {
+        Self {
+            best: cycles,
+            worst: Some(cycles),
+        }
+    }
+
+    /// A cost which is at least `best` cycles, but has no upper bound
+    pub const fn unbounded(best: usize) -> Self {
+        Self { best, worst: None }
+    }
+
+    /// Returns true if the best and worst case costs are the same
+    pub fn is_exact(&self) -> bool {
+        self.worst == Some(self.best)
+    }
+
+    /// The cost of executing either the code described by `self`, or that described by `other`
+    pub fn either(self, other: Self) -> Self {
+        Self {
+            best: self.best.min(other.best),
+            worst: self.worst.zip(other.worst).map(|(a, b)| a.max(b)),
+        }
+    }
+
+    /// The cost of executing the code described by `self`, `n` times
+    pub fn times(self, n: usize) -> Self {
+        Self {
+            best: self.best * n,
+            worst: self.worst.map(|worst| worst * n),
+        }
+    }
+}
+impl Default for Cost {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+impl Add for Cost {
+    type Output = Cost;
+
+    /// The cost of executing the code described by `self`, followed by that of `rhs`
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            best: self.best + rhs.best,
+            worst: self.worst.zip(rhs.worst).map(|(a, b)| a + b),
+        }
+    }
+}
+impl fmt::Display for Cost {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.worst {
+            Some(worst) if worst == self.best => write!(f, "{worst}"),
+            Some(worst) => write!(f, "{}..={worst}", self.best),
+            None => write!(f, "{}..", self.best),
+        }
+    }
+}
+
+/// Returns the number of cycles required to execute `op` itself.
+///
+/// For instructions which are expanded by the assembler into a sequence of VM operations, this is
+/// the cost of that sequence. For control flow instructions, this is only the overhead of the
+/// control flow itself, i.e. excluding the cost of any blocks or procedures it executes, see
+/// [CostModel] for a model which accounts for those.
+///
+/// The costs here are those of the Miden VM assembler at the time of writing, and do not account
+/// for the `noop` padding which is occasionally inserted when assembling basic blocks.
+pub fn cycles(op: &Op) -> usize {
+    match op {
+        Op::Padw => 4,
+        Op::Push(imm) => push_cycles(imm.as_int()),
+        Op::PushU8(imm) => push_cycles(*imm as u64),
+        Op::PushU16(imm) => push_cycles(*imm as u64),
+        Op::PushU32(imm) => push_cycles(*imm as u64),
+        Op::Push2(imms) => imms.iter().map(|imm| push_cycles(imm.as_int())).sum(),
+        Op::Pushw(imms) => imms.iter().map(|imm| push_cycles(imm.as_int())).sum(),
+        Op::Drop => 1,
+        Op::Dropw => 4,
+        Op::Dup(8 | 10 | 12 | 14) => 3,
+        Op::Dup(_) => 1,
+        Op::Dupw(_) => 4,
+        Op::Swap(1) => 1,
+        Op::Swap(2..=8) => 2,
+        Op::Swap(_) => 6,
+        Op::Swapw(_) => 1,
+        Op::Movup(2..=8) | Op::Movdn(2..=8) => 1,
+        Op::Movup(_) | Op::Movdn(_) => 4,
+        Op::Movupw(2) | Op::Movdnw(2) => 2,
+        Op::Movupw(_) | Op::Movdnw(_) => 3,
+        Op::Cswap | Op::Cswapw => 1,
+        Op::Cdrop => 2,
+        Op::Cdropw => 5,
+        Op::Assert | Op::AssertWithError(_) => 1,
+        Op::Assertz | Op::AssertzWithError(_) => 2,
+        Op::AssertEq | Op::AssertEqWithError(_) => 2,
+        Op::AssertEqw | Op::AssertEqwWithError(_) => 11,
+        Op::LocAddr(_) => 2,
+        Op::LocLoad(_) | Op::LocLoadw(_) | Op::LocStorew(_) => 3,
+        Op::LocStore(_) => 4,
+        Op::MemLoad | Op::MemLoadw | Op::MemLoadOffset | Op::MemStorew => 1,
+        Op::MemLoadImm(_) | Op::MemLoadwImm(_) | Op::MemLoadOffsetImm(..) | Op::MemStorewImm(_) => {
+            2
+        }
+        Op::MemStore | Op::MemStoreOffset => 2,
+        Op::MemStoreImm(_) | Op::MemStoreOffsetImm(..) => 3,
+        Op::MemStream | Op::AdvPipe | Op::AdvLoadw | Op::Hperm => 1,
+        Op::AdvPush(n) => *n as usize,
+        // The cost of entering and exiting the block(s), the blocks themselves are accounted
+        // for separately
+        Op::If(..) | Op::While(_) => 2,
+        Op::Repeat(..) => 0,
+        // Procedures executed via `exec` are inlined
+        Op::Exec(_) => 0,
+        Op::Call(_) | Op::Syscall(_) => 2,
+        Op::DynExec | Op::DynCall => 2,
+        Op::ProcRef(_) => 4,
+        Op::Add | Op::Mul | Op::Neg | Op::Inv | Op::Incr => 1,
+        Op::AddImm(imm) if imm.as_int() == 1 => 1,
+        Op::AddImm(_) | Op::MulImm(_) => 2,
+        Op::Sub | Op::SubImm(_) | Op::Div | Op::DivImm(_) => 2,
+        Op::Ilog2 => 44,
+        Op::Pow2 => 16,
+        // `exp` defaults to a 64-bit exponent, with a cost proportional to the exponent size
+        Op::Exp => 9 + 64,
+        Op::ExpImm(exponent) => 9 + (u8::BITS - exponent.leading_zeros()) as usize,
+        Op::ExpBitLength(bits) => 9 + *bits as usize,
+        Op::Not | Op::And | Op::Or => 1,
+        Op::AndImm(_) | Op::OrImm(_) => 2,
+        Op::Xor => 7,
+        Op::XorImm(_) => 8,
+        Op::Eq => 1,
+        Op::EqImm(imm) if imm.as_int() == 0 => 1,
+        Op::EqImm(_) | Op::Neq => 2,
+        Op::NeqImm(_) => 3,
+        Op::Lt => 17,
+        Op::LtImm(_) | Op::Lte | Op::Gt => 18,
+        Op::LteImm(_) | Op::GtImm(_) | Op::Gte => 19,
+        Op::GteImm(_) => 20,
+        Op::IsOdd => 5,
+        Op::Eqw => 15,
+        Op::Caller | Op::Clk => 1,
+        Op::U32Test => 5,
+        Op::U32Testw => 23,
+        Op::U32Assert | Op::U32AssertWithError(_) => 3,
+        Op::U32Assert2 | Op::U32Assert2WithError(_) => 1,
+        Op::U32Assertw | Op::U32AssertwWithError(_) => 6,
+        Op::U32Cast => 2,
+        Op::U32Split => 1,
+        Op::U32OverflowingAdd
+        | Op::U32OverflowingAdd3
+        | Op::U32OverflowingSub
+        | Op::U32OverflowingMul
+        | Op::U32OverflowingMadd => 1,
+        Op::U32OverflowingAddImm(_) | Op::U32OverflowingSubImm(_) | Op::U32OverflowingMulImm(_) => {
+            2
+        }
+        Op::U32WrappingAdd
+        | Op::U32WrappingAdd3
+        | Op::U32WrappingSub
+        | Op::U32WrappingMul
+        | Op::U32WrappingMadd => 2,
+        Op::U32WrappingAddImm(_) | Op::U32WrappingSubImm(_) | Op::U32WrappingMulImm(_) => 3,
+        Op::U32Div => 2,
+        Op::U32DivImm(_) | Op::U32Mod => 3,
+        Op::U32ModImm(_) => 4,
+        Op::U32DivMod => 1,
+        Op::U32DivModImm(_) => 2,
+        Op::U32And | Op::U32Xor => 1,
+        Op::U32Or => 6,
+        Op::U32Not => 5,
+        Op::U32Shl | Op::U32Shr | Op::U32Rotl => 18,
+        Op::U32Rotr => 22,
+        Op::U32ShlImm(_) | Op::U32ShrImm(_) | Op::U32RotlImm(_) | Op::U32RotrImm(_) => 3,
+        Op::U32Popcnt | Op::U32Cto => 33,
+        Op::U32Clz => 37,
+        Op::U32Ctz => 34,
+        Op::U32Clo => 36,
+        Op::U32Lt => 3,
+        Op::U32Lte => 5,
+        Op::U32Gt | Op::U32Gte => 4,
+        Op::U32Min => 8,
+        Op::U32Max => 9,
+    }
+}
+
+/// A static cost model for the functions of a set of Miden Assembly modules.
+///
+/// The cost of a function is computed from [cycles] for each of its instructions, accounting for
+/// control flow as follows:
+///
+/// * `if.true` costs the cheapest of its branches in the best case, and the most expensive in the
+///   worst case
+/// * `repeat.n` costs `n` times its body
+/// * `while.true` is assumed to execute zero times in the best case, and is unbounded in the worst
+///   case
+/// * `exec`, `call` and `syscall` cost the same as the callee, including intrinsics, if the callee
+///   is defined in one of the modules given to the model. Otherwise the callee, like the target of
+///   a dynamic call, is assumed to cost nothing in the best case, and is unbounded in the worst.
+/// * Recursive calls are unbounded in the worst case
+pub struct CostModel<'a> {
+    functions: FxHashMap<FunctionIdent, &'a Function>,
+    costs: FxHashMap<FunctionIdent, Cost>,
+    /// The set of functions whose cost is currently being computed, used to detect recursion
+    visiting: FxHashSet<FunctionIdent>,
+}
+impl<'a> CostModel<'a> {
+    /// Create a cost model for the functions defined in `modules`
+    pub fn new<I>(modules: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Module>,
+    {
+        let functions = modules
+            .into_iter()
+            .flat_map(|module| module.functions())
+            .map(|function| (function.name, function))
+            .collect();
+        Self {
+            functions,
+            costs: Default::default(),
+            visiting: Default::default(),
+        }
+    }
+
+    /// Get the estimated cost of the function `id`, or `None` if it is not known to this model
+    pub fn function_cost(&mut self, id: &FunctionIdent) -> Option<Cost> {
+        if let Some(cost) = self.costs.get(id) {
+            return Some(*cost);
+        }
+
+        let function = self.functions.get(id).copied()?;
+        if !self.visiting.insert(*id) {
+            return Some(Cost::unbounded(0));
+        }
+        let mut cost = self.block_cost(function, function.body.id());
+        if !function.locals().is_empty() {
+            cost = cost + Cost::exact(LOCALS_OVERHEAD);
+        }
+        self.visiting.remove(id);
+        self.costs.insert(*id, cost);
+
+        Some(cost)
+    }
+
+    /// Get the estimated cost of executing `op`, including any blocks or procedures it executes
+    fn op_cost(&mut self, function: &Function, op: &Op) -> Cost {
+        let cost = Cost::exact(cycles(op));
+        match op {
+            Op::If(then_blk, else_blk) => {
+                let then_cost = self.block_cost(function, *then_blk);
+                let else_cost = self.block_cost(function, *else_blk);
+                cost + then_cost.either(else_cost)
+            }
+            Op::While(_) => Cost::unbounded(cost.best),
+            Op::Repeat(n, blk) => cost + self.block_cost(function, *blk).times(*n as usize),
+            Op::Exec(callee) | Op::Call(callee) | Op::Syscall(callee) => {
+                cost + self.function_cost(callee).unwrap_or(Cost::unbounded(0))
+            }
+            Op::DynExec | Op::DynCall => Cost::unbounded(cost.best),
+            _ => cost,
+        }
+    }
+
+    fn block_cost(&mut self, function: &Function, id: BlockId) -> Cost {
+        function
+            .block(id)
+            .ops
+            .iter()
+            .fold(Cost::ZERO, |cost, op| cost + self.op_cost(function, op))
+    }
+}
+
+/// A report of the estimated cost of each function in a program, see [CostModel]
+pub struct CostReport {
+    name: Symbol,
+    functions: Vec<(FunctionIdent, Cost)>,
+}
+impl CostReport {
+    /// Estimate the cost of every function in `program`, under the given report name
+    pub fn new(name: Symbol, program: &Program) -> Self {
+        let mut model = CostModel::new(program.modules());
+        let functions = program
+            .modules()
+            .flat_map(|module| module.functions())
+            .map(|function| {
+                let cost = model
+                    .function_cost(&function.name)
+                    .expect("expected function to be known to the cost model");
+                (function.name, cost)
+            })
+            .collect();
+        Self { name, functions }
+    }
+
+    /// Get the estimated cost of each function in this report
+    pub fn functions(&self) -> &[(FunctionIdent, Cost)] {
+        self.functions.as_slice()
+    }
+}
+impl fmt::Display for CostReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self
+            .functions
+            .iter()
+            .map(|(id, _)| id.to_string().len())
+            .max()
+            .unwrap_or_default()
+            .max("function".len());
+
+        writeln!(f, "{:width$}  {:>8}  {:>8}", "function", "best", "worst")?;
+        for (id, cost) in self.functions.iter() {
+            let worst = cost
+                .worst
+                .map(|worst| worst.to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            writeln!(f, "{:width$}  {:>8}  {worst:>8}", id.to_string(), cost.best)?;
+        }
+
+        Ok(())
+    }
+}
+impl midenc_session::Emit for CostReport {
+    fn name(&self) -> Option<Symbol> {
+        Some(self.name)
+    }
+
+    fn output_type(&self) -> midenc_session::OutputType {
+        midenc_session::OutputType::Cost
+    }
+
+    fn write_to<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_fmt(format_args!("{}", self))
+    }
+}
+
+/// Returns the number of cycles required to push `value` on the operand stack.
+///
+/// Pushing `1` is assembled to `pad incr`, while all other values take a single operation.
+fn push_cycles(value: u64) -> usize {
+    if value == 1 {
+        2
+    } else {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_hir::{Felt, Ident, Signature};
+
+    use super::*;
+
+    fn function(name: &str, build: impl FnOnce(&mut Function) -> Vec<Op>) -> Box<Function> {
+        let mut function = Function::new(name.parse().unwrap(), Signature::new([], []));
+        let ops = build(&mut function);
+        let body = function.body.id();
+        function.block_mut(body).extend(ops);
+        Box::new(function)
+    }
+
+    fn block(function: &mut Function, ops: &[Op]) -> BlockId {
+        let id = function.create_block();
+        function.block_mut(id).extend_from_slice(ops);
+        id
+    }
+
+    #[test]
+    fn push_cycles_depend_on_the_value_pushed() {
+        let one = Felt::new(1);
+        let two = Felt::new(2);
+        assert_eq!(cycles(&Op::Push(one)), 2);
+        assert_eq!(cycles(&Op::Push(two)), 1);
+        assert_eq!(cycles(&Op::PushU8(1)), 2);
+        assert_eq!(cycles(&Op::PushU8(0)), 1);
+        assert_eq!(cycles(&Op::PushU16(1)), 2);
+        assert_eq!(cycles(&Op::PushU16(u16::MAX)), 1);
+        assert_eq!(cycles(&Op::PushU32(1)), 2);
+        assert_eq!(cycles(&Op::PushU32(42)), 1);
+        assert_eq!(cycles(&Op::Push2([one, two])), 3);
+        assert_eq!(cycles(&Op::Pushw([one, two, one, two])), 6);
+    }
+
+    #[test]
+    fn cost_model_accounts_for_control_flow_and_calls() {
+        let mut module = Module::new(Ident::with_empty_span(Symbol::intern("test")));
+        module.push_back(function("test::callee", |_| vec![Op::Add, Op::Mul]));
+        module.push_back(function("test::main", |f| {
+            let then_blk = block(f, &[Op::Drop]);
+            let else_blk = block(f, &[Op::U32Assert, Op::Drop]);
+            let body = block(f, &[Op::Incr]);
+            vec![
+                Op::PushU32(1),
+                Op::If(then_blk, else_blk),
+                Op::Repeat(3, body),
+                Op::Exec("test::callee".parse().unwrap()),
+            ]
+        }));
+        module.push_back(function("test::looping", |f| {
+            let body = block(f, &[Op::Drop]);
+            vec![Op::While(body)]
+        }));
+        module.push_back(function("test::external", |_| {
+            vec![Op::Exec("other::unknown".parse().unwrap())]
+        }));
+        module.push_back(function("test::recursive", |_| {
+            vec![Op::Add, Op::Exec("test::recursive".parse().unwrap())]
+        }));
+
+        let mut model = CostModel::new([&module]);
+        let mut cost = |name: &str| model.function_cost(&name.parse().unwrap()).unwrap();
+
+        assert_eq!(cost("test::callee"), Cost::exact(2));
+        // push.1 + if.true + either branch + repeat.3 + exec
+        assert_eq!(
+            cost("test::main"),
+            Cost {
+                best: 2 + 2 + 1 + 3 + 2,
+                worst: Some(2 + 2 + 4 + 3 + 2),
+            }
+        );
+        assert_eq!(cost("test::looping"), Cost::unbounded(2));
+        assert_eq!(cost("test::external"), Cost::unbounded(0));
+        assert_eq!(cost("test::recursive"), Cost::unbounded(1));
+
+        assert_eq!(cost("test::main").to_string(), "10..=13");
+        assert_eq!(cost("test::looping").to_string(), "2..");
+        assert_eq!(cost("test::callee").to_string(), "2");
+    }
+}
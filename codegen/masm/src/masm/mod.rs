@@ -1,3 +1,4 @@
+mod cost;
 mod function;
 pub mod intrinsics;
 mod module;
@@ -10,9 +11,10 @@ pub use miden_hir::{
 };
 
 pub use self::{
+    cost::{cycles, Cost, CostModel, CostReport},
     function::{FrozenFunctionList, Function, FunctionList},
     module::{FrozenModuleTree, LoadModuleError, Module, ModuleTree},
-    program::Program,
+    program::{MissingEntrypointError, Program},
     region::{Begin, Region},
 };
 
@@ -40,10 +40,22 @@ pub struct Module {
     pub imports: ModuleImportInfo,
     /// The functions defined in this module
     functions: Functions,
+    /// True if this module is a kernel module, i.e. its exported procedures are invoked via
+    /// `syscall`, and it is assembled into the kernel of the program, rather than as a library
+    is_kernel: bool,
 }
 impl Module {
     /// Create a new, empty [Module] with the given name.
     pub fn new(name: Ident) -> Self {
+        Self::make(name, /* is_kernel= */ false)
+    }
+
+    /// Create a new, empty kernel [Module] with the given name.
+    pub fn new_kernel(name: Ident) -> Self {
+        Self::make(name, /* is_kernel= */ true)
+    }
+
+    fn make(name: Ident, is_kernel: bool) -> Self {
         Self {
             link: Default::default(),
             span: SourceSpan::UNKNOWN,
@@ -51,9 +63,16 @@ impl Module {
             docs: None,
             imports: Default::default(),
             functions: Default::default(),
+            is_kernel,
         }
     }
 
+    /// Returns true if this module is a kernel module
+    #[inline]
+    pub const fn is_kernel(&self) -> bool {
+        self.is_kernel
+    }
+
     /// If this module contains a function marked with the `entrypoint` attribute,
     /// return the fully-qualified name of that function
     pub fn entrypoint(&self) -> Option<FunctionIdent> {
@@ -1,11 +1,17 @@
 use core::fmt;
 use std::{collections::BTreeMap, path::Path, sync::Arc};
 
-use miden_hir::{self as hir, DataSegmentTable, FunctionIdent, Ident};
+use miden_core::crypto::hash::Rpo256;
+use miden_hir::{self as hir, DataSegmentTable, Felt, FieldElement, FunctionIdent, Ident};
 use rustc_hash::FxHashMap;
 
 use super::{module::Modules, *};
 
+/// Raised when a [Program] with no entrypoint is converted to an executable program
+#[derive(Debug, thiserror::Error)]
+#[error("cannot emit a program without an entrypoint, emit a library instead")]
+pub struct MissingEntrypointError;
+
 /// A [Program] represents a complete set of modules which are intended to
 /// be shipped together as an artifact, either as an executable, or as a library
 /// to be integrated into a larger executable.
@@ -50,12 +56,139 @@ impl Program {
         self.modules.insert(module);
     }
 
+    /// Make this program executable, using `entry` as the program entrypoint.
+    ///
+    /// This replaces the top-level initialization code of this program, if present.
+    ///
+    /// The generated initialization code writes the contents of the data segments of this program
+    /// to memory before `entry` is invoked, see [Program::advice_stack] for details.
+    pub fn set_entrypoint(&mut self, entry: FunctionIdent) {
+        self.body = Some(self.make_begin(entry));
+    }
+
+    /// Construct the top-level initialization code for this program, using `entry` as the
+    /// program entrypoint.
+    fn make_begin(&self, entry: FunctionIdent) -> Begin {
+        let mut begin = Begin::default();
+        self.emit_data_initializers(&mut begin.body);
+        begin.imports.add(entry);
+        let entry_module = begin.imports.alias(&entry.module);
+        begin.body.block_mut(begin.body.body).ops.push(Op::Exec(FunctionIdent {
+            module: entry_module.unwrap_or(entry.module),
+            function: entry.function,
+        }));
+        begin
+    }
+
+    /// Emit code into `region` which writes the initialized data of this program to memory.
+    ///
+    /// Rather than encoding the data in the program itself, it is read from the advice stack using
+    /// `adv_pipe`, two words at a time, so the advice stack must be initialized with the contents
+    /// of [Program::advice_stack] when executing the program.
+    ///
+    /// The data is hashed as it is read, and the program commits to the RPO digest of each region
+    /// of initialized memory, which the hash of the data read from the advice stack must match,
+    /// so a prover cannot substitute different data without the program failing.
+    fn emit_data_initializers(&self, region: &mut Region) {
+        let body = region.body;
+        for (addr, words) in self.initialized_memory() {
+            // adv_pipe expects the stack to contain [C, B, A, addr], where A, B, and C are the
+            // hasher state. Each region consists of an even number of words, so the capacity
+            // word, A, is initially zero
+            {
+                let ops = &mut region.block_mut(body).ops;
+                ops.push(Op::PushU32(addr));
+                ops.extend([Op::Padw, Op::Padw, Op::Padw]);
+            }
+            let mut pairs = words.len() / 2;
+            while pairs > 0 {
+                let n = core::cmp::min(pairs, u8::MAX as usize);
+                let pipe_blk = region.create_block();
+                region.block_mut(pipe_blk).ops.extend([Op::AdvPipe, Op::Hperm]);
+                region.block_mut(body).ops.push(Op::Repeat(n as u8, pipe_blk));
+                pairs -= n;
+            }
+            // The digest of the data is the second word of the hasher state, i.e. B
+            let elements = words.iter().flatten().copied().collect::<Vec<_>>();
+            let digest: [Felt; 4] = Rpo256::hash_elements(&elements).into();
+            region.block_mut(body).ops.extend([
+                Op::Dropw,
+                Op::Swapw(1),
+                Op::Dropw,
+                Op::Pushw(digest),
+                Op::AssertEqw,
+                Op::Drop,
+            ]);
+        }
+    }
+
+    /// Returns the initial contents of the advice stack required to execute this program, the
+    /// first element of which is the top of the stack.
+    ///
+    /// This consists of the contents of every data segment of this program, including global
+    /// variable initializers, which the top-level initialization code of the program consumes
+    /// when writing that data to memory. Any additional advice inputs must be placed beneath it.
+    pub fn advice_stack(&self) -> Vec<Felt> {
+        self.initialized_memory()
+            .into_iter()
+            .flat_map(|(_, words)| words.into_iter().flatten())
+            .collect()
+    }
+
+    /// Computes the initial contents of memory described by the data segments of this program.
+    ///
+    /// The result is a set of contiguous, non-zero regions of memory, each consisting of the
+    /// address (in words) at which the region starts, and the words in that region. Each region
+    /// has an even number of words, as `adv_pipe` writes two words at a time, so regions are
+    /// padded with a zero word as needed. This is always safe, as memory is zeroed initially, and
+    /// the word following a region is never part of another region.
+    fn initialized_memory(&self) -> Vec<(u32, Vec<[Felt; 4]>)> {
+        // Gather the non-zero elements of memory, keyed by word address
+        let mut image = BTreeMap::<u32, [u32; 4]>::default();
+        for segment in self.segments.iter() {
+            for (i, byte) in segment.init().as_slice().iter().copied().enumerate() {
+                if byte == 0 {
+                    continue;
+                }
+                let addr = segment.offset() + i as u32;
+                let word = image.entry(addr / 16).or_default();
+                word[((addr % 16) / 4) as usize] |= (byte as u32) << ((addr % 4) * 8);
+            }
+        }
+
+        // Split the image into contiguous regions
+        let mut regions = Vec::<(u32, Vec<[Felt; 4]>)>::default();
+        for (addr, word) in image.into_iter() {
+            let word = word.map(|elem| Felt::new(elem as u64));
+            match regions.last_mut() {
+                Some((start, words)) if *start + words.len() as u32 == addr => words.push(word),
+                _ => regions.push((addr, vec![word])),
+            }
+        }
+        for (_, words) in regions.iter_mut() {
+            if words.len() % 2 != 0 {
+                words.push([Felt::ZERO; 4]);
+            }
+        }
+
+        regions
+    }
+
+    /// Returns true if this program has an entrypoint, either because one was set explicitly via
+    /// [Program::set_entrypoint], or because one of its modules has a function with the
+    /// `entrypoint` attribute.
     pub fn is_executable(&self) -> bool {
-        self.body.is_some()
+        self.body.is_some() || self.entrypoint().is_some()
     }
 
+    /// Returns true if this program has no entrypoint, and can only be used as a library
     pub fn is_library(&self) -> bool {
-        self.body.is_none()
+        !self.is_executable()
+    }
+
+    /// Get the entrypoint of this program as declared by its modules, if present
+    fn entrypoint(&self) -> Option<FunctionIdent> {
+        self.modules.iter().find_map(|m| m.entrypoint())
     }
 
     /// Get a reference to a module in this program by name
@@ -67,6 +200,31 @@ impl Program {
         self.modules.get(name)
     }
 
+    /// Get a reference to the kernel module of this program, if it has one
+    pub fn kernel(&self) -> Option<&Module> {
+        self.modules.iter().find(|m| m.is_kernel())
+    }
+
+    /// Compile the kernel module of this program, if it has one, into a Miden kernel, installing
+    /// it in `assembler`.
+    ///
+    /// The resulting assembler must be used to assemble any code which invokes the kernel via
+    /// `syscall`, and the MAST roots of the exported kernel procedures can be obtained from
+    /// [miden_assembly::Assembler::kernel]. Any modules imported by the kernel must already be
+    /// available to `assembler` via a library.
+    ///
+    /// If this program has no kernel, `assembler` is returned unmodified.
+    pub fn assemble_kernel(
+        &self,
+        assembler: miden_assembly::Assembler,
+        codemap: &miden_diagnostics::CodeMap,
+    ) -> Result<miden_assembly::Assembler, miden_assembly::AssemblyError> {
+        match self.kernel() {
+            None => Ok(assembler),
+            Some(kernel) => assembler.with_kernel_module(kernel.to_module_ast(codemap).ast),
+        }
+    }
+
     /// Returns true if this program contains a [Module] named `name`
     pub fn contains<N>(&self, name: N) -> bool
     where
@@ -88,8 +246,10 @@ impl Program {
         let path = path.as_ref();
         assert!(path.is_dir());
 
-        let program = self.to_program_ast(codemap);
-        program.write_to_file(path.join(masm::LibraryPath::EXEC_PATH))?;
+        // Only executable programs have a program body to write
+        if let Ok(program) = self.to_program_ast(codemap) {
+            program.write_to_file(path.join(masm::LibraryPath::EXEC_PATH))?;
+        }
 
         for module in self.modules.iter() {
             module.write_to_directory(codemap, path)?;
@@ -99,66 +259,54 @@ impl Program {
     }
 
     /// Convert this program to its [miden_assembly::ast::ProgramAst] representation
+    ///
+    /// Returns an error if the program is not executable, see [Program::is_executable].
     pub fn to_program_ast(
         &self,
         codemap: &miden_diagnostics::CodeMap,
-    ) -> miden_assembly::ast::ProgramAst {
+    ) -> Result<miden_assembly::ast::ProgramAst, MissingEntrypointError> {
         use miden_assembly::{
             self as masm,
-            ast::{Instruction, ModuleImports, Node, ProgramAst},
+            ast::{ModuleImports, ProgramAst},
         };
 
-        if let Some(begin) = &self.body {
-            // Create module import table
-            let mut imported = BTreeMap::<String, masm::LibraryPath>::default();
-            let mut invoked = BTreeMap::<masm::ProcedureId, _>::default();
-            let mut proc_ids = FxHashMap::<FunctionIdent, masm::ProcedureId>::default();
-            for import in begin.imports.iter() {
-                let path =
-                    masm::LibraryPath::new(import.name.as_str()).expect("invalid module name");
-                imported.insert(import.alias.to_string(), path.clone());
-                if let Some(imported_fns) = begin.imports.imported(&import.alias) {
-                    for import_fn in imported_fns.iter().copied() {
-                        let name = masm::ProcedureName::try_from(import_fn.function.as_str())
-                            .expect("invalid function name");
-                        let id = masm::ProcedureId::from_name(import_fn.function.as_str(), &path);
-                        invoked.insert(id, (name, path.clone()));
-                        proc_ids.insert(import_fn, id);
-                    }
+        let entry_begin;
+        let begin = match self.body.as_ref() {
+            Some(begin) => begin,
+            None => {
+                let entry = self.entrypoint().ok_or(MissingEntrypointError)?;
+                entry_begin = self.make_begin(entry);
+                &entry_begin
+            }
+        };
+
+        // Create module import table
+        let mut imported = BTreeMap::<String, masm::LibraryPath>::default();
+        let mut invoked = BTreeMap::<masm::ProcedureId, _>::default();
+        let mut proc_ids = FxHashMap::<FunctionIdent, masm::ProcedureId>::default();
+        for import in begin.imports.iter() {
+            let path = masm::LibraryPath::new(import.name.as_str()).expect("invalid module name");
+            imported.insert(import.alias.to_string(), path.clone());
+            if let Some(imported_fns) = begin.imports.imported(&import.alias) {
+                for import_fn in imported_fns.iter().copied() {
+                    let name = masm::ProcedureName::try_from(import_fn.function.as_str())
+                        .expect("invalid function name");
+                    let id = masm::ProcedureId::from_name(import_fn.function.as_str(), &path);
+                    invoked.insert(id, (name, path.clone()));
+                    proc_ids.insert(import_fn, id);
                 }
             }
-            let imports = ModuleImports::new(imported, invoked);
-            let local_ids = Default::default();
-            let (nodes, _) = begin
-                .body
-                .to_code_body(codemap, &begin.imports, &local_ids, &proc_ids)
-                .into_parts();
-
-            ProgramAst::new(nodes, vec![])
-                .expect("invalid program")
-                .with_import_info(imports)
-        } else if let Some(entry) = self.modules.iter().find_map(|m| m.entrypoint()) {
-            let entry_import = Import::try_from(entry.module).expect("invalid module name");
-            let entry_module_path =
-                masm::LibraryPath::new(entry_import.name.as_str()).expect("invalid module path");
-            let entry_id =
-                masm::ProcedureId::from_name(entry.function.as_str(), &entry_module_path);
-            let entry_name = masm::ProcedureName::try_from(entry.function.as_str())
-                .expect("invalid entrypoint function name");
-            let imported =
-                BTreeMap::from([(entry_import.alias.to_string(), entry_module_path.clone())]);
-            let invoked = BTreeMap::from([(entry_id, (entry_name, entry_module_path))]);
-            let imports = ModuleImports::new(imported, invoked);
-
-            // TODO: Write data segments, initialize function table
-            let body = vec![Node::Instruction(Instruction::ExecImported(entry_id))];
-
-            ProgramAst::new(body, vec![])
-                .expect("invalid program")
-                .with_import_info(imports)
-        } else {
-            todo!("0xPolygonMiden/miden-vm#1108")
         }
+        let imports = ModuleImports::new(imported, invoked);
+        let local_ids = Default::default();
+        let (nodes, _) = begin
+            .body
+            .to_code_body(codemap, &begin.imports, &local_ids, &proc_ids)
+            .into_parts();
+
+        Ok(ProgramAst::new(nodes, vec![])
+            .expect("invalid program")
+            .with_import_info(imports))
     }
 
     /// Load a [Program] from a `.masl` file
@@ -192,40 +340,52 @@ impl Program {
         Ok(Self::from_masl_library(&library, codemap))
     }
 
-    pub fn to_masl_library<S: AsRef<str>>(
+    /// Convert the modules of this program into a set of [miden_assembly::MaslLibrary], one for
+    /// each distinct root namespace of those modules.
+    ///
+    /// For example, a program with the modules `foo`, `foo::bar`, and `intrinsics::mem`, will be
+    /// converted into two libraries, `foo`, containing `foo` and `foo::bar`, and `intrinsics`,
+    /// containing `intrinsics::mem`. Each library depends on the namespaces of any modules it
+    /// imports from outside of itself, including those of other libraries in the set.
+    pub fn to_masl_libraries(
         &self,
-        root_ns: S,
         codemap: &miden_diagnostics::CodeMap,
-    ) -> Result<miden_assembly::MaslLibrary, miden_assembly::LibraryError> {
+    ) -> Result<Vec<miden_assembly::MaslLibrary>, miden_assembly::LibraryError> {
         use std::collections::BTreeSet;
 
         use miden_assembly::{LibraryNamespace, MaslLibrary, Version};
 
-        let ns = LibraryNamespace::new(root_ns)?;
-        let version = Version::default();
-        let has_source_locations = false;
-        let mut modules = Vec::with_capacity(self.modules.iter().count());
-        let mut dependencies = BTreeSet::default();
+        let mut namespaces = BTreeMap::<&str, Vec<&Module>>::default();
         for module in self.modules() {
-            for import in module.imports.iter() {
-                if self.modules.get(&import.name).is_some() {
-                    continue;
+            namespaces.entry(root_namespace(module.name.as_str())).or_default().push(module);
+        }
+
+        let mut libraries = Vec::with_capacity(namespaces.len());
+        for (root_ns, modules) in namespaces.into_iter() {
+            let ns = LibraryNamespace::new(root_ns)?;
+            let version = Version::default();
+            let has_source_locations = false;
+            let mut dependencies = BTreeSet::default();
+            let mut asts = Vec::with_capacity(modules.len());
+            for module in modules.into_iter() {
+                for import in module.imports.iter() {
+                    let import_ns = root_namespace(import.name.as_str());
+                    if import_ns != root_ns {
+                        dependencies.insert(LibraryNamespace::new(import_ns)?);
+                    }
                 }
-                let root = match import.name.as_str().split_once("::") {
-                    None => LibraryNamespace::new(import.name.as_str())?,
-                    Some((root, _)) => LibraryNamespace::new(root)?,
-                };
-                dependencies.insert(root);
+                asts.push(module.to_module_ast(codemap));
             }
-            modules.push(module.to_module_ast(codemap));
+            libraries.push(MaslLibrary::new(
+                ns,
+                version,
+                has_source_locations,
+                asts,
+                dependencies.into_iter().collect(),
+            )?);
         }
-        MaslLibrary::new(
-            ns,
-            version,
-            has_source_locations,
-            modules,
-            dependencies.into_iter().collect(),
-        )
+
+        Ok(libraries)
     }
 
     /// Convert a [miden_assembly::MaslLibrary] into a [Program]
@@ -251,24 +411,33 @@ impl Program {
 }
 impl From<&hir::Program> for Program {
     fn from(program: &hir::Program) -> Self {
-        let segments = program.segments().clone();
-        let body = if let Some(entry) = program.entrypoint() {
-            let mut begin = Begin::default();
-            begin.imports.add(entry);
-            let entry_module = begin.imports.alias(&entry.module);
-            begin.body.block_mut(begin.body.body).ops.push(Op::Exec(FunctionIdent {
-                module: entry_module.unwrap_or(entry.module),
-                function: entry.function,
-            }));
-            Some(begin)
-        } else {
-            None
-        };
-        Self {
+        let mut segments = program.segments().clone();
+
+        // Global variables are laid out immediately following the data segments, so we translate
+        // their initializers into data segments of their own, so that all of the static data of
+        // the program is initialized the same way.
+        let globals = program.globals();
+        let global_table_offset = segments.next_available_offset();
+        for gv in globals.iter() {
+            let Some(init) = gv.initializer() else {
+                continue;
+            };
+            let offset = global_table_offset + unsafe { globals.offset_of(gv.id()) };
+            let size = gv.layout().size() as u32;
+            segments
+                .declare(offset, size, globals.get_constant(init).clone(), false)
+                .expect("invalid global variable initializer");
+        }
+
+        let mut masm_program = Self {
             modules: Default::default(),
             segments,
-            body,
+            body: None,
+        };
+        if let Some(entry) = program.entrypoint() {
+            masm_program.set_entrypoint(entry);
         }
+        masm_program
     }
 }
 impl fmt::Display for Program {
@@ -291,3 +460,8 @@ impl fmt::Display for Program {
         Ok(())
     }
 }
+
+/// Get the root namespace of the module path `name`, i.e. `foo` in `foo::bar`
+fn root_namespace(name: &str) -> &str {
+    name.split_once("::").map(|(root, _)| root).unwrap_or(name)
+}
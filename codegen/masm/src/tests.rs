@@ -1,10 +1,12 @@
 use std::{cell::RefCell, sync::Arc};
 
 use miden_hir::{
+    assert_matches,
     pass::{AnalysisManager, ConversionPass},
     testing::{self, TestContext},
     AbiParam, CallConv, Felt, FieldElement, FunctionIdent, Immediate, InstBuilder, Linkage,
-    OperandStack, ProgramBuilder, Signature, SourceSpan, Stack, StarkField, Type,
+    ModuleFunctionBuilder, OperandStack, ProgramBuilder, Signature, SourceSpan, Stack, StarkField,
+    Type, Value,
 };
 use proptest::prelude::*;
 use smallvec::{smallvec, SmallVec};
@@ -300,9 +302,10 @@ fn codegen_fundamental_if() {
     let a = Felt::new(3);
     let b = Felt::new(4);
 
+    // `a` is odd, so the sum is returned
     let mut stack = harness.execute_program(program.freeze(), &[a, b]).expect("execution failed");
     assert_eq!(stack.len(), 1);
-    assert_eq!(stack.pop().map(|e| e.as_int()), Some(12));
+    assert_eq!(stack.pop().map(|e| e.as_int()), Some(7));
 }
 
 /// Test the code generator on a very simple program with a loop as a sanity check
@@ -371,6 +374,138 @@ fn codegen_fundamental_loops() {
     assert_eq!(stack.pop().map(|e| e.as_int()), Some(7));
 }
 
+/// Test the code generator on a program which keeps more values live than can be reached on
+/// the operand stack, requiring some of them to be spilled to procedure locals
+#[test]
+fn codegen_spills() {
+    let mut harness = TestByEmulationHarness::default();
+
+    // Build a simple program
+    let mut builder = ProgramBuilder::new(&harness.context.session.diagnostics);
+
+    // Build test module with a function that derives 20 values from its argument, sums
+    // them, and then adds each of them to the sum a second time, keeping all of them live
+    // until the second sum is computed
+    let mut mb = builder.module("test");
+    let id = {
+        let mut fb = mb
+            .function(
+                "sum_twice",
+                Signature::new([AbiParam::new(Type::U32)], [AbiParam::new(Type::U32)]),
+            )
+            .expect("unexpected symbol conflict");
+        let entry = fb.current_block();
+        let a = fb.block_params(entry)[0];
+        let values = (0..20u32)
+            .map(|i| fb.ins().add_imm_checked(a, Immediate::U32(i), SourceSpan::UNKNOWN))
+            .collect::<Vec<_>>();
+        let mut sum = values[0];
+        for value in values.iter().skip(1).copied() {
+            sum = fb.ins().add_checked(sum, value, SourceSpan::UNKNOWN);
+        }
+        for value in values.iter().copied() {
+            sum = fb.ins().add_checked(sum, value, SourceSpan::UNKNOWN);
+        }
+        fb.ins().ret(Some(sum), SourceSpan::UNKNOWN);
+        fb.build().expect("unexpected error building function")
+    };
+
+    mb.build().expect("unexpected error constructing test module");
+
+    // Link the program
+    let program = builder.with_entrypoint(id).link().expect("failed to link program");
+
+    let mut compiler = MasmCompiler::new(&harness.context.session);
+    let program = compiler.compile(program).expect("compilation failed");
+
+    println!("{}", program.get("test").unwrap());
+
+    // 2 * ((20 * 1) + (0 + 1 + .. + 19)) == 420
+    let a = Felt::new(1);
+
+    let mut stack = harness.execute_program(program.freeze(), &[a]).expect("execution failed");
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack.pop().map(|e| e.as_int()), Some(420));
+}
+
+/// Test the code generator on a program which keeps more values live across a loop than
+/// can be reached on the operand stack, requiring the operand stack to be restored to the
+/// same state on every iteration of the loop
+#[test]
+fn codegen_spills_in_loop() {
+    let mut harness = TestByEmulationHarness::default();
+
+    // Build a simple program
+    let mut builder = ProgramBuilder::new(&harness.context.session.diagnostics);
+
+    // Build test module with a function that derives 20 values from its first argument,
+    // and then adds all of them to an accumulator `n` times
+    let mut mb = builder.module("test");
+    let id = {
+        let mut fb = mb
+            .function(
+                "sum_n_times",
+                Signature::new(
+                    [AbiParam::new(Type::U32), AbiParam::new(Type::U32)],
+                    [AbiParam::new(Type::U32)],
+                ),
+            )
+            .expect("unexpected symbol conflict");
+        let entry = fb.current_block();
+        let (a, n) = {
+            let args = fb.block_params(entry);
+            (args[0], args[1])
+        };
+        let loop_header_blk = fb.create_block();
+        let acc1 = fb.append_block_param(loop_header_blk, Type::U32, SourceSpan::UNKNOWN);
+        let n1 = fb.append_block_param(loop_header_blk, Type::U32, SourceSpan::UNKNOWN);
+        let loop_body_blk = fb.create_block();
+        let loop_exit_blk = fb.create_block();
+        let result0 = fb.append_block_param(loop_exit_blk, Type::U32, SourceSpan::UNKNOWN);
+        let values = (0..20u32)
+            .map(|i| fb.ins().add_imm_checked(a, Immediate::U32(i), SourceSpan::UNKNOWN))
+            .collect::<Vec<_>>();
+        let acc0 = fb.ins().u32(0, SourceSpan::UNKNOWN);
+        fb.ins().br(loop_header_blk, &[acc0, n], SourceSpan::UNKNOWN);
+
+        fb.switch_to_block(loop_header_blk);
+        let is_zero = fb.ins().eq_imm(n1, Immediate::U32(0), SourceSpan::UNKNOWN);
+        fb.ins()
+            .cond_br(is_zero, loop_exit_blk, &[acc1], loop_body_blk, &[], SourceSpan::UNKNOWN);
+
+        fb.switch_to_block(loop_body_blk);
+        let mut acc2 = acc1;
+        for value in values.iter().copied() {
+            acc2 = fb.ins().add_checked(acc2, value, SourceSpan::UNKNOWN);
+        }
+        let n2 = fb.ins().sub_imm_checked(n1, Immediate::U32(1), SourceSpan::UNKNOWN);
+        fb.ins().br(loop_header_blk, &[acc2, n2], SourceSpan::UNKNOWN);
+
+        fb.switch_to_block(loop_exit_blk);
+        fb.ins().ret(Some(result0), SourceSpan::UNKNOWN);
+
+        fb.build().expect("unexpected error building function")
+    };
+
+    mb.build().expect("unexpected error constructing test module");
+
+    // Link the program
+    let program = builder.with_entrypoint(id).link().expect("failed to link program");
+
+    let mut compiler = MasmCompiler::new(&harness.context.session);
+    let program = compiler.compile(program).expect("compilation failed");
+
+    println!("{}", program.get("test").unwrap());
+
+    // 3 * ((20 * 1) + (0 + 1 + .. + 19)) == 630
+    let a = Felt::new(1);
+    let n = Felt::new(3);
+
+    let mut stack = harness.execute_program(program.freeze(), &[a, n]).expect("execution failed");
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack.pop().map(|e| e.as_int()), Some(630));
+}
+
 /// Test the code generator on a simple program containing [testing::sum_matrix].
 #[test]
 fn codegen_sum_matrix() {
@@ -424,8 +559,623 @@ fn codegen_sum_matrix() {
     assert_eq!(stack.pop().map(|e| e.as_int()), Some(6));
 }
 
+/// Compile a program whose entrypoint, `test::<name>`, takes `arity` u32 arguments and returns
+/// the u32 value produced by `build` from those arguments
+fn compile_memory_test_program<F>(
+    harness: &TestByEmulationHarness,
+    name: &str,
+    arity: usize,
+    build: F,
+) -> Box<Program>
+where
+    F: FnOnce(&mut ModuleFunctionBuilder<'_>, &[Value]) -> Value,
+{
+    let mut builder = ProgramBuilder::new(&harness.context.session.diagnostics);
+
+    let mut mb = builder.module("test");
+    let id = {
+        let signature = Signature::new(
+            (0..arity).map(|_| AbiParam::new(Type::U32)),
+            [AbiParam::new(Type::U32)],
+        );
+        let mut fb = mb.function(name, signature).expect("unexpected symbol conflict");
+        let entry = fb.current_block();
+        let args = fb.block_params(entry).to_vec();
+        let result = build(&mut fb, &args);
+        fb.ins().ret(Some(result), SourceSpan::UNKNOWN);
+        fb.build().expect("unexpected error building function")
+    };
+
+    mb.build().expect("unexpected error constructing test module");
+
+    // Link the program
+    let program = builder.with_entrypoint(id).link().expect("failed to link program");
+
+    let mut compiler = MasmCompiler::new(&harness.context.session);
+    compiler.compile(program).expect("compilation failed")
+}
+
+/// Emit a checksum of the first 24 elements following `base`, each weighted by its index + 1
+fn emit_checksum(fb: &mut ModuleFunctionBuilder<'_>, base: Value) -> Value {
+    let mut checksum = fb.ins().u32(0, SourceSpan::UNKNOWN);
+    for i in 0..24u32 {
+        let addr = fb.ins().add_imm_checked(base, Immediate::U32(i * 4), SourceSpan::UNKNOWN);
+        let ptr = fb.ins().inttoptr(addr, Type::Ptr(Box::new(Type::U32)), SourceSpan::UNKNOWN);
+        let elem = fb.ins().load(ptr, SourceSpan::UNKNOWN);
+        let elem = fb.ins().mul_imm_wrapping(elem, Immediate::U32(i + 1), SourceSpan::UNKNOWN);
+        checksum = fb.ins().add_wrapping(checksum, elem, SourceSpan::UNKNOWN);
+    }
+    checksum
+}
+
+/// Compute the checksum emitted by [emit_checksum] over `bytes`
+fn checksum(bytes: &[u8; 96]) -> u64 {
+    (0..24).fold(0u32, |checksum, i| {
+        let elem = u32::from_le_bytes(bytes[(i * 4)..(i * 4 + 4)].try_into().unwrap());
+        checksum.wrapping_add(elem.wrapping_mul(i as u32 + 1))
+    }) as u64
+}
+
+/// Allocate a region of memory in the emulator initialized with `bytes`, returning its address
+fn store_bytes(harness: &mut TestByEmulationHarness, bytes: &[u8; 96]) -> u32 {
+    let addr = harness.malloc(bytes.len());
+    for i in 0..24 {
+        let elem = u32::from_le_bytes(bytes[(i * 4)..(i * 4 + 4)].try_into().unwrap());
+        harness.store(addr as usize + i * 4, Felt::new(elem as u64));
+    }
+    addr
+}
+
+/// Test the code generator on a program which stores values smaller than an element to
+/// unaligned addresses, and reads them back as a single 32-bit value
+#[test]
+fn codegen_mem_store_small() {
+    let mut harness = TestByEmulationHarness::default();
+
+    // Build a function that writes the bytes of a u32 value one at a time
+    let program = compile_memory_test_program(&harness, "store_bytes", 1, |fb, args| {
+        let addr = args[0];
+        let ptr = fb.ins().inttoptr(addr, Type::Ptr(Box::new(Type::U8)), SourceSpan::UNKNOWN);
+        let value = fb.ins().u8(0x11, SourceSpan::UNKNOWN);
+        fb.ins().store(ptr, value, SourceSpan::UNKNOWN);
+        let addr1 = fb.ins().add_imm_checked(addr, Immediate::U32(1), SourceSpan::UNKNOWN);
+        let ptr = fb.ins().inttoptr(addr1, Type::Ptr(Box::new(Type::U8)), SourceSpan::UNKNOWN);
+        let value = fb.ins().u8(0x22, SourceSpan::UNKNOWN);
+        fb.ins().store(ptr, value, SourceSpan::UNKNOWN);
+        let addr2 = fb.ins().add_imm_checked(addr, Immediate::U32(2), SourceSpan::UNKNOWN);
+        let ptr = fb.ins().inttoptr(addr2, Type::Ptr(Box::new(Type::U16)), SourceSpan::UNKNOWN);
+        let value = fb.ins().u16(0x4433, SourceSpan::UNKNOWN);
+        fb.ins().store(ptr, value, SourceSpan::UNKNOWN);
+        let ptr = fb.ins().inttoptr(addr, Type::Ptr(Box::new(Type::U32)), SourceSpan::UNKNOWN);
+        fb.ins().load(ptr, SourceSpan::UNKNOWN)
+    });
+
+    // Prep emulator, filling the destination with garbage that must be overwritten
+    let addr = harness.malloc(core::mem::size_of::<u32>());
+    harness.store(addr as usize, Felt::new(0xffffffff));
+
+    let mut stack = harness
+        .execute_program(program.freeze(), &[Felt::new(addr as u64)])
+        .expect("execution failed");
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack.pop().map(|e| e.as_int()), Some(0x44332211));
+}
+
+/// Test the code generator on a program which copies an aggregate whose size is not a
+/// multiple of the element size, ensuring that the bytes following it are left untouched
+#[test]
+fn codegen_mem_copy_aggregate() {
+    let mut harness = TestByEmulationHarness::default();
+
+    // Build a function that copies a [u8; 6] from `src` to `dst`, and returns the second
+    // element of `dst`
+    let program = compile_memory_test_program(&harness, "copy_array", 2, |fb, args| {
+        let (src, dst) = (args[0], args[1]);
+        let array_ty = Type::Array(Box::new(Type::U8), 6);
+        let src_ptr =
+            fb.ins()
+                .inttoptr(src, Type::Ptr(Box::new(array_ty.clone())), SourceSpan::UNKNOWN);
+        let dst_ptr = fb.ins().inttoptr(dst, Type::Ptr(Box::new(array_ty)), SourceSpan::UNKNOWN);
+        let value = fb.ins().load(src_ptr, SourceSpan::UNKNOWN);
+        fb.ins().store(dst_ptr, value, SourceSpan::UNKNOWN);
+        let dst4 = fb.ins().add_imm_checked(dst, Immediate::U32(4), SourceSpan::UNKNOWN);
+        let ptr = fb.ins().inttoptr(dst4, Type::Ptr(Box::new(Type::U32)), SourceSpan::UNKNOWN);
+        fb.ins().load(ptr, SourceSpan::UNKNOWN)
+    });
+
+    // Prep emulator
+    //
+    // The bytes following the array in `src` must not be copied, and the bytes
+    // following the array in `dst` must not be modified
+    let src = harness.malloc(core::mem::size_of::<u32>() * 2);
+    let dst = harness.malloc(core::mem::size_of::<u32>() * 2);
+    harness.store(src as usize, Felt::new(0x04030201));
+    harness.store(src as usize + 4, Felt::new(0xaaaa0605));
+    harness.store(dst as usize + 4, Felt::new(0xbbbb0000));
+
+    let mut stack = harness
+        .execute_program(program.freeze(), &[Felt::new(src as u64), Felt::new(dst as u64)])
+        .expect("execution failed");
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack.pop().map(|e| e.as_int()), Some(0xbbbb0605));
+}
+
+/// Test the code generator on a program which copies a floating-point value, which has the
+/// same in-memory representation as a 64-bit integer
+#[test]
+fn codegen_mem_copy_f64() {
+    let mut harness = TestByEmulationHarness::default();
+
+    // Build a function that copies an f64 from `src` to `dst`, and returns the second element of
+    // `dst`
+    let program = compile_memory_test_program(&harness, "copy_f64", 2, |fb, args| {
+        let (src, dst) = (args[0], args[1]);
+        let src_ptr = fb.ins().inttoptr(src, Type::Ptr(Box::new(Type::F64)), SourceSpan::UNKNOWN);
+        let dst_ptr = fb.ins().inttoptr(dst, Type::Ptr(Box::new(Type::F64)), SourceSpan::UNKNOWN);
+        let value = fb.ins().load(src_ptr, SourceSpan::UNKNOWN);
+        fb.ins().store(dst_ptr, value, SourceSpan::UNKNOWN);
+        let dst4 = fb.ins().add_imm_checked(dst, Immediate::U32(4), SourceSpan::UNKNOWN);
+        let ptr = fb.ins().inttoptr(dst4, Type::Ptr(Box::new(Type::U32)), SourceSpan::UNKNOWN);
+        fb.ins().load(ptr, SourceSpan::UNKNOWN)
+    });
+
+    let bits = 1.5f64.to_bits();
+    let src = harness.malloc(core::mem::size_of::<f64>());
+    let dst = harness.malloc(core::mem::size_of::<f64>());
+    harness.store(src as usize, Felt::new(bits & 0xffffffff));
+    harness.store(src as usize + 4, Felt::new(bits >> 32));
+
+    let mut stack = harness
+        .execute_program(program.freeze(), &[Felt::new(src as u64), Felt::new(dst as u64)])
+        .expect("execution failed");
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack.pop().map(|e| e.as_int()), Some(bits >> 32));
+}
+
+/// Test the code generator on a program which copies regions of memory that are both equally
+/// and differently aligned, exercising both the word-at-a-time and byte-at-a-time paths
+#[test]
+fn codegen_memcpy() {
+    let mut harness = TestByEmulationHarness::default();
+
+    // Build a function that performs two copies from `src` to `dst`, and returns a checksum
+    // of the first 24 elements of `dst`
+    let program = compile_memory_test_program(&harness, "copy", 2, |fb, args| {
+        let (src, dst) = (args[0], args[1]);
+        let byte_ptr_ty = Type::Ptr(Box::new(Type::U8));
+        for (src_offset, dst_offset, count) in [(3u32, 3u32, 37u32), (1, 70, 21)] {
+            let src_addr =
+                fb.ins().add_imm_checked(src, Immediate::U32(src_offset), SourceSpan::UNKNOWN);
+            let dst_addr =
+                fb.ins().add_imm_checked(dst, Immediate::U32(dst_offset), SourceSpan::UNKNOWN);
+            let src_ptr = fb.ins().inttoptr(src_addr, byte_ptr_ty.clone(), SourceSpan::UNKNOWN);
+            let dst_ptr = fb.ins().inttoptr(dst_addr, byte_ptr_ty.clone(), SourceSpan::UNKNOWN);
+            let count = fb.ins().u32(count, SourceSpan::UNKNOWN);
+            fb.ins().memcpy(src_ptr, dst_ptr, count, SourceSpan::UNKNOWN);
+        }
+        emit_checksum(fb, dst)
+    });
+
+    // Prep emulator, filling both regions with distinct byte patterns
+    let mut src_bytes = [0u8; 96];
+    let mut dst_bytes = [0u8; 96];
+    for i in 0..96 {
+        src_bytes[i] = i as u8 + 1;
+        dst_bytes[i] = 0xff - i as u8;
+    }
+    let src = store_bytes(&mut harness, &src_bytes);
+    let dst = store_bytes(&mut harness, &dst_bytes);
+
+    // Compute the expected checksum
+    dst_bytes[3..40].copy_from_slice(&src_bytes[3..40]);
+    dst_bytes[70..91].copy_from_slice(&src_bytes[1..22]);
+    let expected = checksum(&dst_bytes);
+
+    let mut stack = harness
+        .execute_program(program.freeze(), &[Felt::new(src as u64), Felt::new(dst as u64)])
+        .expect("execution failed");
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack.pop().map(|e| e.as_int()), Some(expected));
+}
+
+/// Test the code generator on a program which copies regions of memory that overlap, both
+/// with the destination following the source, which must be copied in reverse, and preceding it
+#[test]
+fn codegen_memcpy_overlapping() {
+    let mut harness = TestByEmulationHarness::default();
+
+    // Build a function that performs three overlapping copies within `buf`, and returns a
+    // checksum of its first 24 elements
+    let program = compile_memory_test_program(&harness, "copy", 1, |fb, args| {
+        let buf = args[0];
+        let byte_ptr_ty = Type::Ptr(Box::new(Type::U8));
+        for (src_offset, dst_offset, count) in [(2u32, 9u32, 30u32), (50, 40, 20), (16, 32, 36)] {
+            let src_addr =
+                fb.ins().add_imm_checked(buf, Immediate::U32(src_offset), SourceSpan::UNKNOWN);
+            let dst_addr =
+                fb.ins().add_imm_checked(buf, Immediate::U32(dst_offset), SourceSpan::UNKNOWN);
+            let src_ptr = fb.ins().inttoptr(src_addr, byte_ptr_ty.clone(), SourceSpan::UNKNOWN);
+            let dst_ptr = fb.ins().inttoptr(dst_addr, byte_ptr_ty.clone(), SourceSpan::UNKNOWN);
+            let count = fb.ins().u32(count, SourceSpan::UNKNOWN);
+            fb.ins().memcpy(src_ptr, dst_ptr, count, SourceSpan::UNKNOWN);
+        }
+        emit_checksum(fb, buf)
+    });
+
+    // Prep emulator, filling the buffer with a distinct byte pattern
+    let mut bytes = [0u8; 96];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = i as u8 + 1;
+    }
+    let buf = store_bytes(&mut harness, &bytes);
+
+    // Compute the expected checksum, `copy_within` has the semantics of `memmove`
+    bytes.copy_within(2..32, 9);
+    bytes.copy_within(50..70, 40);
+    bytes.copy_within(16..52, 32);
+    let expected = checksum(&bytes);
+
+    let mut stack = harness
+        .execute_program(program.freeze(), &[Felt::new(buf as u64)])
+        .expect("execution failed");
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack.pop().map(|e| e.as_int()), Some(expected));
+}
+
+/// Test the code generator on a program which fills regions of memory with byte-sized and
+/// larger values
+#[test]
+fn codegen_memset() {
+    let mut harness = TestByEmulationHarness::default();
+
+    // Build a function that performs two fills of `dst`, and returns a checksum of the first
+    // 24 elements of `dst`
+    let program = compile_memory_test_program(&harness, "fill", 1, |fb, args| {
+        let dst = args[0];
+        let addr = fb.ins().add_imm_checked(dst, Immediate::U32(5), SourceSpan::UNKNOWN);
+        let ptr = fb.ins().inttoptr(addr, Type::Ptr(Box::new(Type::U8)), SourceSpan::UNKNOWN);
+        let count = fb.ins().u32(40, SourceSpan::UNKNOWN);
+        let value = fb.ins().u8(0xab, SourceSpan::UNKNOWN);
+        fb.ins().memset(ptr, count, value, SourceSpan::UNKNOWN);
+        let addr = fb.ins().add_imm_checked(dst, Immediate::U32(64), SourceSpan::UNKNOWN);
+        let ptr = fb.ins().inttoptr(addr, Type::Ptr(Box::new(Type::U32)), SourceSpan::UNKNOWN);
+        let count = fb.ins().u32(5, SourceSpan::UNKNOWN);
+        let value = fb.ins().u32(0x12345678, SourceSpan::UNKNOWN);
+        fb.ins().memset(ptr, count, value, SourceSpan::UNKNOWN);
+        emit_checksum(fb, dst)
+    });
+
+    // Prep emulator, filling the region with a distinct byte pattern
+    let mut dst_bytes = [0u8; 96];
+    for (i, byte) in dst_bytes.iter_mut().enumerate() {
+        *byte = 0xff - i as u8;
+    }
+    let dst = store_bytes(&mut harness, &dst_bytes);
+
+    // Compute the expected checksum
+    dst_bytes[5..45].fill(0xab);
+    for i in 0..5 {
+        let offset = 64 + i * 4;
+        dst_bytes[offset..(offset + 4)].copy_from_slice(&0x12345678u32.to_le_bytes());
+    }
+    let expected = checksum(&dst_bytes);
+
+    let mut stack = harness
+        .execute_program(program.freeze(), &[Felt::new(dst as u64)])
+        .expect("execution failed");
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack.pop().map(|e| e.as_int()), Some(expected));
+}
+
+/// Test that kernel functions can be invoked via `syscall`
+#[test]
+fn codegen_syscall() {
+    let mut harness = TestByEmulationHarness::default();
+
+    let mut builder = ProgramBuilder::new(&harness.context.session.diagnostics);
+
+    // Build a kernel module with a single kernel function which subtracts its arguments
+    let mut signature = Signature::new(
+        [AbiParam::new(Type::U32), AbiParam::new(Type::U32)],
+        [AbiParam::new(Type::U32)],
+    );
+    signature.cc = CallConv::Kernel;
+    let mut kb = hir::ModuleBuilder::new_kernel("kernel");
+    let sub = {
+        let mut fb = kb.function("sub", signature.clone()).expect("unexpected symbol conflict");
+        let entry = fb.current_block();
+        let (a, b) = {
+            let args = fb.block_params(entry);
+            (args[0], args[1])
+        };
+        let c = fb.ins().sub_checked(a, b, SourceSpan::UNKNOWN);
+        fb.ins().ret(Some(c), SourceSpan::UNKNOWN);
+        fb.build(&harness.context.session.diagnostics)
+            .expect("unexpected error building function")
+    };
+    builder.add_module(kb.build()).expect("unexpected module conflict");
+
+    // Build a user module which calls the kernel function, and then modifies the result
+    let mut mb = builder.module("test");
+    let id = {
+        let mut fb = mb
+            .function(
+                "main",
+                Signature::new(
+                    [AbiParam::new(Type::U32), AbiParam::new(Type::U32)],
+                    [AbiParam::new(Type::U32)],
+                ),
+            )
+            .expect("unexpected symbol conflict");
+        let entry = fb.current_block();
+        let (a, b) = {
+            let args = fb.block_params(entry);
+            (args[0], args[1])
+        };
+        let sub = fb
+            .import_function(sub.module, sub.function, signature)
+            .expect("unexpected symbol conflict");
+        let call = fb.ins().syscall(sub, &[a, b], SourceSpan::UNKNOWN);
+        let c = fb.first_result(call);
+        let d = fb.ins().add_imm_checked(c, Immediate::U32(1), SourceSpan::UNKNOWN);
+        fb.ins().ret(Some(d), SourceSpan::UNKNOWN);
+        fb.build().expect("unexpected error building function")
+    };
+
+    mb.build().expect("unexpected error constructing test module");
+
+    // Link the program
+    let program = builder.with_entrypoint(id).link().expect("failed to link program");
+
+    let mut compiler = MasmCompiler::new(&harness.context.session);
+    let program = compiler.compile(program).expect("compilation failed");
+
+    let kernel = program.kernel().expect("expected program to have a kernel");
+    assert_eq!(kernel.name.as_str(), "kernel");
+    assert!(program.get("test").unwrap().imports.get(&kernel.name).is_none());
+    let main = program.get("test").unwrap().to_string();
+    assert!(main.contains("syscall.sub"), "expected syscall in:\n{main}");
+
+    let mut stack = harness
+        .execute_program(program.freeze(), &[Felt::new(10), Felt::new(3)])
+        .expect("execution failed");
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack.pop().map(|e| e.as_int()), Some(8));
+}
+
+/// Test that functions invoked via `call` execute in a memory context isolated from the caller
+#[test]
+fn codegen_context_call() {
+    let mut harness = TestByEmulationHarness::default();
+
+    let mut builder = ProgramBuilder::new(&harness.context.session.diagnostics);
+
+    // Build a module with a function which replaces the value at the given address, returning the
+    // previous value
+    let signature = Signature::new(
+        [AbiParam::new(Type::U32), AbiParam::new(Type::U32)],
+        [AbiParam::new(Type::U32)],
+    );
+    let mut mb = builder.module("account");
+    let swap = {
+        let mut fb = mb.function("swap", signature.clone()).expect("unexpected symbol conflict");
+        let entry = fb.current_block();
+        let (addr, value) = {
+            let args = fb.block_params(entry);
+            (args[0], args[1])
+        };
+        let ptr = fb.ins().inttoptr(addr, Type::Ptr(Box::new(Type::U32)), SourceSpan::UNKNOWN);
+        let prev = fb.ins().load(ptr, SourceSpan::UNKNOWN);
+        fb.ins().store(ptr, value, SourceSpan::UNKNOWN);
+        fb.ins().ret(Some(prev), SourceSpan::UNKNOWN);
+        fb.build().expect("unexpected error building function")
+    };
+    mb.build().expect("unexpected error constructing account module");
+
+    // Build a module which writes a value to memory, then calls `swap` in a new context on the
+    // same address, and returns the sum of the value returned by `swap`, and the value in memory
+    // after the call
+    let mut mb = builder.module("test");
+    let id = {
+        let mut fb = mb
+            .function(
+                "main",
+                Signature::new([AbiParam::new(Type::U32)], [AbiParam::new(Type::U32)]),
+            )
+            .expect("unexpected symbol conflict");
+        let entry = fb.current_block();
+        let addr = fb.block_params(entry)[0];
+        let swap = fb
+            .import_function(swap.module, swap.function, signature)
+            .expect("unexpected symbol conflict");
+        let ptr = fb.ins().inttoptr(addr, Type::Ptr(Box::new(Type::U32)), SourceSpan::UNKNOWN);
+        let value = fb.ins().u32(42, SourceSpan::UNKNOWN);
+        fb.ins().store(ptr, value, SourceSpan::UNKNOWN);
+        let replacement = fb.ins().u32(7, SourceSpan::UNKNOWN);
+        let call = fb.ins().context_call(swap, &[addr, replacement], SourceSpan::UNKNOWN);
+        let prev = fb.first_result(call);
+        let ptr = fb.ins().inttoptr(addr, Type::Ptr(Box::new(Type::U32)), SourceSpan::UNKNOWN);
+        let current = fb.ins().load(ptr, SourceSpan::UNKNOWN);
+        let result = fb.ins().add_checked(prev, current, SourceSpan::UNKNOWN);
+        fb.ins().ret(Some(result), SourceSpan::UNKNOWN);
+        fb.build().expect("unexpected error building function")
+    };
+    mb.build().expect("unexpected error constructing test module");
+
+    // Link the program
+    let program = builder.with_entrypoint(id).link().expect("failed to link program");
+
+    let mut compiler = MasmCompiler::new(&harness.context.session);
+    let program = compiler.compile(program).expect("compilation failed");
+
+    let main = program.get("test").unwrap().to_string();
+    assert!(main.contains("call.account::swap"), "expected call in:\n{main}");
+
+    // If `swap` shared memory with its caller, the result would be 42 + 7, but as it does not,
+    // it observes zeroed memory, and its write is not visible to the caller
+    let addr = harness.malloc(core::mem::size_of::<u32>());
+    let mut stack = harness
+        .execute_program(program.freeze(), &[Felt::new(addr as u64)])
+        .expect("execution failed");
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack.pop().map(|e| e.as_int()), Some(42));
+}
+
+/// Compile a program with a data segment, and an initialized global variable, which are read
+/// and summed by the entrypoint
+fn compile_data_segments_program(harness: &TestByEmulationHarness) -> Box<Program> {
+    let mut builder = ProgramBuilder::new(&harness.context.session.diagnostics);
+
+    let mut mb = builder.module("test");
+    mb.declare_data_segment(4, 4, vec![0x11, 0x22, 0x33, 0x44], true)
+        .expect("unexpected data segment error");
+    mb.declare_global_variable(
+        "answer",
+        Type::U32,
+        Linkage::External,
+        Some(42u32.to_le_bytes().into()),
+        SourceSpan::UNKNOWN,
+    )
+    .expect("unexpected global variable error");
+    let id = {
+        let mut fb = mb
+            .function("main", Signature::new([], [AbiParam::new(Type::U32)]))
+            .expect("unexpected symbol conflict");
+        let addr = fb.ins().u32(4, SourceSpan::UNKNOWN);
+        let ptr = fb.ins().inttoptr(addr, Type::Ptr(Box::new(Type::U32)), SourceSpan::UNKNOWN);
+        let a = fb.ins().load(ptr, SourceSpan::UNKNOWN);
+        let b = fb.ins().load_symbol("answer", Type::U32, SourceSpan::UNKNOWN);
+        let c = fb.ins().add_checked(a, b, SourceSpan::UNKNOWN);
+        fb.ins().ret(Some(c), SourceSpan::UNKNOWN);
+        fb.build().expect("unexpected error building function")
+    };
+
+    mb.build().expect("unexpected error constructing test module");
+
+    // Link the program
+    let program = builder.with_entrypoint(id).link().expect("failed to link program");
+
+    let mut compiler = MasmCompiler::new(&harness.context.session);
+    compiler.compile(program).expect("compilation failed")
+}
+
+/// Test that the data segments and global variable initializers of a program are written to
+/// memory before its entrypoint is invoked
+#[test]
+fn codegen_data_segments() {
+    let mut harness = TestByEmulationHarness::default();
+
+    let program = compile_data_segments_program(&harness);
+
+    // The data is provided via the advice stack, rather than in the program itself, but the
+    // program commits to its digest
+    let advice = program.advice_stack();
+    assert_eq!(advice.len() % 8, 0);
+    assert_eq!(advice[1].as_int(), 0x44332211);
+    assert!(advice.iter().any(|elem| elem.as_int() == 42));
+    let output = program.to_string();
+    assert!(output.contains("adv_pipe"), "expected data initialization in:\n{output}");
+    assert!(output.contains("hperm"), "expected data to be hashed in:\n{output}");
+    assert!(output.contains("assert_eqw"), "expected data to be verified in:\n{output}");
+
+    let mut stack = harness.execute_program(program.freeze(), &[]).expect("execution failed");
+    assert_eq!(stack.len(), 1);
+    assert_eq!(stack.pop().map(|e| e.as_int()), Some(0x44332211 + 42));
+}
+
+/// Test that the program fails if the data provided via the advice stack does not match the
+/// data the program was compiled with
+#[test]
+fn codegen_data_segments_tampered() {
+    let mut harness = TestByEmulationHarness::default();
+
+    let mut program = compile_data_segments_program(&harness);
+
+    // Replace the data segment after the initialization code was generated, so that the advice
+    // stack provided to the program no longer matches the digest it commits to
+    let original = program.advice_stack();
+    let segment = program.segments.pop_front().expect("expected a data segment");
+    assert_eq!(segment.offset(), 4);
+    program
+        .segments
+        .declare(4, 4, vec![0x11, 0x22, 0x33, 0x45].into(), true)
+        .expect("unexpected data segment error");
+    assert_ne!(program.advice_stack(), original);
+
+    let err = harness
+        .execute_program(program.freeze(), &[])
+        .expect_err("expected tampered data to be rejected");
+    assert_matches!(err, EmulationError::AssertionFailed { .. });
+}
+
+/// Test that a program without an entrypoint is compiled to a set of libraries, one per root
+/// namespace of its modules
+#[test]
+fn codegen_library() {
+    use miden_assembly::Library;
+
+    let harness = TestByEmulationHarness::default();
+
+    let mut builder = ProgramBuilder::new(&harness.context.session.diagnostics);
+
+    // Build two modules under the same root namespace, one of which calls the other
+    let signature = Signature::new(
+        [AbiParam::new(Type::U32), AbiParam::new(Type::U32)],
+        [AbiParam::new(Type::U32)],
+    );
+    let mut mb = builder.module("math::ops");
+    let add = {
+        let mut fb = mb.function("add", signature.clone()).expect("unexpected symbol conflict");
+        let entry = fb.current_block();
+        let (a, b) = {
+            let args = fb.block_params(entry);
+            (args[0], args[1])
+        };
+        let c = fb.ins().add_checked(a, b, SourceSpan::UNKNOWN);
+        fb.ins().ret(Some(c), SourceSpan::UNKNOWN);
+        fb.build().expect("unexpected error building function")
+    };
+    mb.build().expect("unexpected error constructing test module");
+
+    let mut mb = builder.module("math::api");
+    {
+        let mut fb = mb.function("sum", signature.clone()).expect("unexpected symbol conflict");
+        let entry = fb.current_block();
+        let (a, b) = {
+            let args = fb.block_params(entry);
+            (args[0], args[1])
+        };
+        let add = fb
+            .import_function(add.module, add.function, signature)
+            .expect("unexpected symbol conflict");
+        let call = fb.ins().call(add, &[a, b], SourceSpan::UNKNOWN);
+        let c = fb.first_result(call);
+        fb.ins().ret(Some(c), SourceSpan::UNKNOWN);
+        fb.build().expect("unexpected error building function");
+    }
+    mb.build().expect("unexpected error constructing test module");
+
+    let program = builder.link().expect("failed to link program");
+
+    let mut compiler = MasmCompiler::new(&harness.context.session);
+    let program = compiler.compile(program).expect("compilation failed");
+
+    assert!(program.is_library());
+    assert_matches!(
+        program.to_program_ast(&harness.context.session.codemap),
+        Err(MissingEntrypointError)
+    );
+    let libraries = program
+        .to_masl_libraries(&harness.context.session.codemap)
+        .expect("invalid library");
+    let math = libraries
+        .iter()
+        .find(|lib| lib.root_ns().as_str() == "math")
+        .expect("expected a library for the 'math' namespace");
+    assert_eq!(math.modules().count(), 2);
+}
+
 #[test]
-#[should_panic(expected = "assertion failed: expected false, got true")]
 fn i32_checked_neg() {
     let mut harness = TestByEmulationHarness::default();
 
@@ -444,7 +1194,529 @@ fn i32_checked_neg() {
 
     let neg = "intrinsics::i32::checked_neg".parse().unwrap();
     // i32::MIN
-    harness.invoke(neg, &[min]).expect("execution failed");
+    let err = harness.invoke(neg, &[min]).expect_err("expected assertion to fail");
+    assert_matches!(err, EmulationError::AssertionFailed { code: 0, .. });
+    let frame = err.stacktrace().and_then(|frames| frames.last()).copied();
+    assert_eq!(frame.map(|frame| frame.function), Some(neg));
+}
+
+/// Test that faults raised during emulation are reported as errors which identify the faulting
+/// instruction, rather than panicking
+#[test]
+fn emulator_faults() {
+    use miden_hir::{Ident, Symbol};
+
+    let mut harness = TestByEmulationHarness::default();
+
+    let function = |name: &str, ops: &[Op]| {
+        let mut function = Function::new(name.parse().unwrap(), Signature::new([], []));
+        let body = function.body.id();
+        function.block_mut(body).extend_from_slice(ops);
+        Box::new(function)
+    };
+
+    let main = "test::main".parse().unwrap();
+    let check = "test::check".parse().unwrap();
+    let mut module = Module::new(Ident::with_empty_span(Symbol::intern("test")));
+    module.push_back(function("test::main", &[Op::Exec(check), Op::Drop]));
+    module
+        .push_back(function("test::check", &[Op::PushU32(10), Op::U32Lt, Op::AssertWithError(42)]));
+    module.push_back(function("test::underflow", &[Op::Drop, Op::Drop]));
+    module.push_back(function("test::oob", &[Op::MemLoadImm(u32::MAX)]));
+    module.push_back(function("test::load_offset", &[Op::MemLoadOffset]));
+    module.push_back(function("test::store_offset", &[Op::MemStoreOffset]));
+    harness
+        .emulator
+        .load_module(Box::new(module).freeze())
+        .expect("failed to load module");
+
+    // 11 < 10 is false, so the assertion in `check` fails
+    let err = harness.invoke(main, &[Felt::new(11)]).expect_err("expected assertion to fail");
+    let EmulationError::AssertionFailed {
+        code,
+        ip,
+        stacktrace,
+    } = err
+    else {
+        panic!("expected assertion failure, got {err}");
+    };
+    assert_eq!(code, 42);
+    assert_eq!(ip.map(|ip| ip.index), Some(2));
+    let functions = stacktrace.iter().map(|frame| frame.function).collect::<Vec<_>>();
+    assert_eq!(functions, vec![main, check]);
+    assert_eq!(stacktrace[1].ip, ip);
+
+    // The emulator can be reused once stopped
+    harness.emulator.stop();
+    let stack = harness.invoke(main, &[Felt::new(9), Felt::ZERO]).expect("execution failed");
+    assert!(stack.is_empty());
+
+    harness.emulator.stop();
+    let underflow = "test::underflow".parse().unwrap();
+    let err = harness.invoke(underflow, &[Felt::ONE]).expect_err("expected stack underflow");
+    assert_matches!(err, EmulationError::StackUnderflow { ip: Some(ip), .. } if ip.index == 1);
+
+    harness.emulator.stop();
+    let oob = "test::oob".parse().unwrap();
+    let err = harness.invoke(oob, &[]).expect_err("expected out of bounds access");
+    assert_matches!(err, EmulationError::OutOfBoundsMemoryAccess { addr: u32::MAX, .. });
+
+    // The element offset of a word is taken from the operand stack, so must be checked
+    harness.emulator.stop();
+    let load_offset = "test::load_offset".parse().unwrap();
+    let err = harness
+        .invoke(load_offset, &[Felt::new(4), Felt::ZERO])
+        .expect_err("expected invalid offset");
+    assert_matches!(err, EmulationError::InvalidOperand { value: 4, .. });
+
+    harness.emulator.stop();
+    let store_offset = "test::store_offset".parse().unwrap();
+    let err = harness
+        .invoke(store_offset, &[Felt::new(5), Felt::ZERO, Felt::ONE])
+        .expect_err("expected invalid offset");
+    assert_matches!(err, EmulationError::InvalidOperand { value: 5, .. });
+}
+
+/// Test the instructions which refer to functions by hash rather than by name, i.e. `procref`,
+/// `dynexec`, `dyncall` and `caller`
+#[test]
+fn emulator_dynamic_calls() {
+    use miden_hir::{Ident, Symbol};
+
+    let mut harness = TestByEmulationHarness::default();
+
+    let function = |name: &str, ops: &[Op]| {
+        let mut function = Function::new(name.parse().unwrap(), Signature::new([], []));
+        let body = function.body.id();
+        function.block_mut(body).extend_from_slice(ops);
+        Box::new(function)
+    };
+
+    let mut kernel = Module::new_kernel(Ident::with_empty_span(Symbol::intern("kernel")));
+    kernel.push_back(function("kernel::whoami", &[Op::Caller]));
+    harness
+        .emulator
+        .load_module(Box::new(kernel).freeze())
+        .expect("failed to load kernel");
+
+    let double = "test::double".parse().unwrap();
+    let identify = "test::identify".parse().unwrap();
+    let mut module = Module::new(Ident::with_empty_span(Symbol::intern("test")));
+    // The hash of the callee is left on the stack by dynexec/dyncall
+    module.push_back(function(
+        "test::double",
+        &[Op::Movup(4), Op::MulImm(Felt::new(2)), Op::Movdn(4)],
+    ));
+    module.push_back(function("test::dynexec", &[Op::ProcRef(double), Op::DynExec, Op::Dropw]));
+    module.push_back(function("test::dyncall", &[Op::ProcRef(double), Op::DynCall, Op::Dropw]));
+    module.push_back(function(
+        "test::identify",
+        &[Op::Padw, Op::Syscall("kernel::whoami".parse().unwrap())],
+    ));
+    module.push_back(function("test::procref", &[Op::ProcRef(identify)]));
+    module.push_back(function("test::invalid_dynexec", &[Op::Padw, Op::DynExec]));
+    module.push_back(function("test::invalid_caller", &[Op::Padw, Op::Caller]));
+    harness
+        .emulator
+        .load_module(Box::new(module).freeze())
+        .expect("failed to load module");
+
+    let mut run = |name: &str, args: &[Felt]| {
+        harness.emulator.stop();
+        harness.invoke(name.parse().unwrap(), args)
+    };
+
+    let stack = run("test::dynexec", &[Felt::new(21)]).expect("execution failed");
+    assert_eq!(stack.stack(), &vec![Felt::new(42)]);
+    let stack = run("test::dyncall", &[Felt::new(21)]).expect("execution failed");
+    assert_eq!(stack.stack(), &vec![Felt::new(42)]);
+
+    // `caller` produces the same hash as `procref` for the function which issued the syscall
+    let caller = run("test::identify", &[]).expect("execution failed");
+    let procref = run("test::procref", &[]).expect("execution failed");
+    assert_eq!(caller.len(), 4);
+    assert_eq!(caller.stack(), procref.stack());
+
+    let err = run("test::invalid_dynexec", &[]).expect_err("expected undefined procedure");
+    assert_matches!(err, EmulationError::UndefinedProcedure { .. });
+    let err = run("test::invalid_caller", &[]).expect_err("expected caller to fail");
+    assert_matches!(err, EmulationError::CallerNotInSyscall { .. });
+}
+
+/// Test calling native functions loaded into the emulator from Miden Assembly
+#[test]
+fn emulator_native_functions() {
+    use std::rc::Rc;
+
+    use miden_hir::{Ident, Symbol};
+
+    let mut harness = TestByEmulationHarness::default();
+
+    // A native function which records the call stack it was invoked with
+    let add = "test::add".parse().unwrap();
+    let frames = Rc::new(RefCell::new(vec![]));
+    let recorded = frames.clone();
+    harness
+        .emulator
+        .load_nif(
+            add,
+            Signature::new(
+                [AbiParam::new(Type::Felt), AbiParam::new(Type::Felt)],
+                [AbiParam::new(Type::Felt)],
+            ),
+            Box::new(move |emulator: &mut Emulator, args: &[Felt]| {
+                *recorded.borrow_mut() =
+                    emulator.stacktrace().into_iter().map(|frame| frame.function).collect();
+                Ok(vec![args[0] + args[1]])
+            }),
+        )
+        .expect("failed to load native function");
+
+    // Native functions can stand in for kernel procedures when no kernel is loaded
+    let get_nonce = "tx::get_nonce".parse().unwrap();
+    harness
+        .emulator
+        .load_nif(
+            get_nonce,
+            Signature::new([], [AbiParam::new(Type::Felt)]),
+            Box::new(|_, _| Ok(vec![Felt::new(7)])),
+        )
+        .expect("failed to load native function");
+
+    let fail = "test::fail".parse().unwrap();
+    harness
+        .emulator
+        .load_nif(fail, Signature::new([], []), Box::new(|_, _| Err(EmulationError::OutOfMemory)))
+        .expect("failed to load native function");
+
+    // A native function which does not return the result its signature specifies
+    let no_results = "test::no_results".parse().unwrap();
+    harness
+        .emulator
+        .load_nif(
+            no_results,
+            Signature::new([], [AbiParam::new(Type::Felt)]),
+            Box::new(|_, _| Ok(vec![])),
+        )
+        .expect("failed to load native function");
+
+    let main = "test::main".parse().unwrap();
+    let mut module = Module::new(Ident::with_empty_span(Symbol::intern("test")));
+    for (name, ops) in [
+        ("test::main", vec![Op::Exec(add), Op::Syscall(get_nonce), Op::Add]),
+        ("test::call_fail", vec![Op::Call(fail)]),
+        ("test::call_no_results", vec![Op::PushU32(1), Op::Exec(no_results)]),
+    ] {
+        let mut function = Function::new(name.parse().unwrap(), Signature::new([], []));
+        let body = function.body.id();
+        function.block_mut(body).extend_from_slice(&ops);
+        module.push_back(Box::new(function));
+    }
+    harness
+        .emulator
+        .load_module(Box::new(module).freeze())
+        .expect("failed to load module");
+
+    let stack = harness.invoke(main, &[Felt::new(1), Felt::new(2)]).expect("execution failed");
+    assert_eq!(stack.stack(), &vec![Felt::new(10)]);
+    assert_eq!(frames.borrow().as_slice(), &[main, add]);
+
+    // Breakpoints on native functions suspend execution before they are run
+    harness.emulator.stop();
+    harness.set_breakpoint(Breakpoint::Called(add));
+    harness.enter(main, &[Felt::new(1), Felt::new(2)]);
+    assert_matches!(
+        harness.emulator.resume(),
+        Ok(EmulatorEvent::Breakpoint(BreakpointEvent::Called(id))) if id == add
+    );
+    assert_eq!(harness.emulator.current_function(), Some(add));
+    assert_eq!(harness.emulator.current_ip(), None);
+    assert_eq!(harness.emulator.stack().len(), 2);
+    assert_matches!(harness.emulator.resume(), Ok(EmulatorEvent::Stopped));
+    assert_eq!(harness.emulator.stack().stack(), &vec![Felt::new(10)]);
+    harness.emulator.clear_breakpoints();
+
+    harness.emulator.stop();
+    let err = harness
+        .invoke("test::call_fail".parse().unwrap(), &[])
+        .expect_err("expected native function to fail");
+    assert_matches!(err, EmulationError::OutOfMemory);
+    let stacktrace = harness.emulator.stacktrace();
+    assert_eq!(stacktrace.last().map(|frame| frame.function), Some(fail));
+
+    // Errors raised while running a native function are attributed to the instruction which
+    // called it, if any
+    harness.emulator.stop();
+    let err = harness
+        .invoke("test::call_no_results".parse().unwrap(), &[])
+        .expect_err("expected native function to return too few results");
+    assert_matches!(
+        err,
+        EmulationError::InvalidNativeResults { function, expected: 1, actual: 0, ip: Some(ip), .. }
+            if function == no_results && ip.index == 1
+    );
+
+    harness.emulator.stop();
+    let err = harness.invoke(add, &[Felt::new(1)]).expect_err("expected stack underflow");
+    assert_matches!(err, EmulationError::StackUnderflow { ip: None, .. });
+    let stacktrace = harness.emulator.stacktrace();
+    assert_eq!(stacktrace.last().map(|frame| frame.function), Some(add));
+}
+
+/// Test profiling the cycles spent in each function and instruction
+#[test]
+fn emulator_profiler() {
+    use miden_hir::{Ident, Symbol};
+
+    let mut harness = TestByEmulationHarness::default();
+    harness.emulator.enable_profiling();
+
+    let main = "test::main".parse().unwrap();
+    let incr = "test::incr".parse().unwrap();
+    let mut module = Module::new(Ident::with_empty_span(Symbol::intern("test")));
+    let mut function = Function::new(main, Signature::new([], []));
+    let body = function.body.id();
+    function
+        .block_mut(body)
+        .extend_from_slice(&[Op::PushU32(1), Op::Exec(incr), Op::Exec(incr)]);
+    module.push_back(Box::new(function));
+    let mut function = Function::new(incr, Signature::new([], []));
+    let incr_body = function.body.id();
+    function.block_mut(incr_body).push(Op::Incr);
+    module.push_back(Box::new(function));
+    harness
+        .emulator
+        .load_module(Box::new(module).freeze())
+        .expect("failed to load module");
+
+    let stack = harness.invoke(main, &[]).expect("execution failed");
+    assert_eq!(stack.stack(), &vec![Felt::new(3)]);
+
+    // Each instruction is charged the cycles it takes in the VM: `push.1` takes 2 cycles, `exec`
+    // none, as it is inlined by the assembler, and `incr` 1 cycle
+    let profiler = harness.emulator.profiler().expect("expected profiling to be enabled");
+    assert_eq!(profiler.cycles(), 4);
+    assert_eq!(
+        profiler.functions(),
+        vec![
+            (
+                incr,
+                FunctionProfile {
+                    calls: 2,
+                    self_cycles: 2,
+                    total_cycles: 2,
+                }
+            ),
+            (
+                main,
+                FunctionProfile {
+                    calls: 1,
+                    self_cycles: 2,
+                    total_cycles: 4,
+                }
+            ),
+        ]
+    );
+    let ip = InstructionPointer::new(incr_body);
+    assert_eq!(profiler.instruction_count(&incr, ip), 2);
+    // Instructions which take no cycles are still counted
+    let exec = InstructionPointer {
+        block: body,
+        index: 1,
+    };
+    assert_eq!(profiler.instruction_count(&main, exec), 1);
+    assert_eq!(profiler.instructions()[0].op, Op::Incr);
+
+    let mut collapsed = vec![];
+    profiler.write_collapsed_stacks(&mut collapsed).unwrap();
+    assert_eq!(String::from_utf8(collapsed).unwrap(), "test::main 2\ntest::main;test::incr 2\n");
+
+    // Profiles accumulate across executions
+    harness.emulator.stop();
+    harness.invoke(main, &[]).expect("execution failed");
+    let profiler = harness.emulator.take_profiler().expect("expected profiling to be enabled");
+    assert_eq!(profiler.function(&main).map(|profile| profile.calls), Some(2));
+    assert_eq!(profiler.cycles(), 8);
+}
+
+/// Test reversing execution to an earlier cycle, and to the previous watchpoint hit
+#[test]
+fn emulator_reverse_execution() {
+    use miden_hir::{Ident, Symbol};
+
+    let mut harness = TestByEmulationHarness::default();
+    harness.emulator.enable_checkpoints(2);
+
+    let main = "test::main".parse().unwrap();
+    let mut module = Module::new(Ident::with_empty_span(Symbol::intern("test")));
+    let mut function = Function::new(main, Signature::new([], []));
+    let body = function.body.id();
+    function.block_mut(body).extend_from_slice(&[
+        Op::PushU32(1),
+        Op::MemStoreImm(100),
+        Op::PushU32(2),
+        Op::MemStoreImm(200),
+        Op::PushU32(3),
+        Op::MemStoreImm(100),
+        Op::PushU32(4),
+        Op::MemStoreImm(200),
+    ]);
+    module.push_back(Box::new(function));
+    harness
+        .emulator
+        .load_module(Box::new(module).freeze())
+        .expect("failed to load module");
+    harness.emulator.set_watchpoint(100, 1, WatchMode::Break);
+
+    let load = |emulator: &Emulator, addr: usize| emulator.load(addr * 16).as_int();
+
+    // Run forward to the second store to the watched address
+    harness.enter(main, &[]);
+    assert_matches!(
+        harness.emulator.resume(),
+        Ok(EmulatorEvent::Breakpoint(BreakpointEvent::Watch(_)))
+    );
+    assert_eq!(harness.emulator.clk(), 2);
+    assert_matches!(
+        harness.emulator.resume(),
+        Ok(EmulatorEvent::Breakpoint(BreakpointEvent::Watch(_)))
+    );
+    assert_eq!(harness.emulator.clk(), 6);
+    assert_eq!(load(&harness.emulator, 100), 3);
+    assert_eq!(load(&harness.emulator, 200), 2);
+
+    // Walk back to the previous store to the watched address
+    assert_matches!(
+        harness.emulator.reverse_continue(),
+        Ok(EmulatorEvent::Breakpoint(BreakpointEvent::Watch(_)))
+    );
+    assert_eq!(harness.emulator.clk(), 2);
+    assert_eq!(load(&harness.emulator, 100), 1);
+    assert_eq!(load(&harness.emulator, 200), 0);
+
+    // Step back before that store
+    assert_matches!(harness.emulator.step_back(), Ok(EmulatorEvent::Suspended));
+    assert_eq!(harness.emulator.clk(), 1);
+    assert_eq!(harness.emulator.stack().stack(), &vec![Felt::new(1)]);
+    assert_eq!(load(&harness.emulator, 100), 0);
+
+    // There are no earlier watchpoint hits, so we go back to the start
+    assert_matches!(harness.emulator.reverse_continue(), Ok(EmulatorEvent::Suspended));
+    assert_eq!(harness.emulator.clk(), 0);
+    assert!(harness.emulator.stack().is_empty());
+
+    // Execution proceeds forward again as before
+    harness.emulator.clear_watchpoints();
+    assert_matches!(harness.emulator.resume(), Ok(EmulatorEvent::Stopped));
+    // One cycle for each instruction, and one to return from `main`
+    assert_eq!(harness.emulator.clk(), 9);
+    assert_eq!(load(&harness.emulator, 100), 3);
+    assert_eq!(load(&harness.emulator, 200), 4);
+
+    // Reversing from the end of execution restores the call stack
+    harness.emulator.rewind(5).expect("failed to rewind");
+    assert_eq!(harness.emulator.current_function(), Some(main));
+    assert_eq!(harness.emulator.stack().stack(), &vec![Felt::new(3)]);
+    assert_eq!(load(&harness.emulator, 100), 1);
+    assert_eq!(load(&harness.emulator, 200), 2);
+}
+
+/// Test that invalid attempts to reverse execution are reported as errors, and that cycles which
+/// are executed again after rewinding are only profiled once
+#[test]
+fn emulator_rewind_errors_and_profile() {
+    use miden_hir::{Ident, Symbol};
+
+    let mut harness = TestByEmulationHarness::default();
+    harness.emulator.enable_profiling();
+
+    let main = "test::main".parse().unwrap();
+    let mut module = Module::new(Ident::with_empty_span(Symbol::intern("test")));
+    let mut function = Function::new(main, Signature::new([], []));
+    let body = function.body.id();
+    function.block_mut(body).extend_from_slice(&[
+        Op::PushU32(1),
+        Op::MemStoreImm(100),
+        Op::PushU32(2),
+        Op::MemStoreImm(200),
+        Op::PushU32(3),
+        Op::MemStoreImm(100),
+        Op::PushU32(4),
+        Op::MemStoreImm(200),
+    ]);
+    module.push_back(Box::new(function));
+    harness
+        .emulator
+        .load_module(Box::new(module).freeze())
+        .expect("failed to load module");
+
+    // Execution has not started yet
+    assert_matches!(harness.emulator.rewind(0), Err(EmulationError::NotSuspended));
+
+    harness.enter(main, &[]);
+    harness.step().expect("execution failed");
+    assert_matches!(harness.emulator.rewind(0), Err(EmulationError::CheckpointsDisabled));
+    assert_matches!(harness.emulator.step_back(), Err(EmulationError::CheckpointsDisabled));
+
+    // Checkpoints enabled after the first cycle cannot reverse execution before the first one
+    harness.emulator.enable_checkpoints(4);
+    assert_matches!(harness.emulator.resume(), Ok(EmulatorEvent::Stopped));
+    assert_eq!(harness.emulator.clk(), 9);
+    let cycles = harness.emulator.profiler().unwrap().cycles();
+    assert_matches!(
+        harness.emulator.rewind(10),
+        Err(EmulationError::CycleNotReached { cycle: 10, clk: 9 })
+    );
+    assert_matches!(harness.emulator.rewind(2), Err(EmulationError::NoCheckpoint(2)));
+    assert_eq!(harness.emulator.clk(), 9);
+
+    // The checkpoints survive failed attempts, and rewinding also rewinds the profile
+    harness.emulator.rewind(5).expect("failed to rewind");
+    assert_eq!(harness.emulator.clk(), 5);
+    assert!(harness.emulator.profiler().unwrap().cycles() < cycles);
+    assert_matches!(harness.emulator.resume(), Ok(EmulatorEvent::Stopped));
+    assert_eq!(harness.emulator.profiler().unwrap().cycles(), cycles);
+}
+
+/// Test the u32 instructions which take three operands
+#[test]
+fn emulator_u32_ternary_ops() {
+    use miden_hir::{Ident, Symbol};
+
+    let mut harness = TestByEmulationHarness::default();
+
+    let mut module = Module::new(Ident::with_empty_span(Symbol::intern("test")));
+    for (name, op) in [
+        ("test::overflowing_add3", Op::U32OverflowingAdd3),
+        ("test::wrapping_add3", Op::U32WrappingAdd3),
+        ("test::overflowing_madd", Op::U32OverflowingMadd),
+        ("test::wrapping_madd", Op::U32WrappingMadd),
+    ] {
+        let mut function = Function::new(name.parse().unwrap(), Signature::new([], []));
+        let body = function.body.id();
+        function.block_mut(body).push(op);
+        module.push_back(Box::new(function));
+    }
+    harness
+        .emulator
+        .load_module(Box::new(module).freeze())
+        .expect("failed to load module");
+
+    let max = Felt::new(u32::MAX as u64);
+    let mut run = |name: &str| {
+        harness.emulator.stop();
+        let stack = harness
+            .invoke(name.parse().unwrap(), &[max, max, max])
+            .expect("execution failed");
+        (0..stack.len()).map(|index| stack[index].as_int()).collect::<Vec<_>>()
+    };
+
+    // 3 * (2^32 - 1) = 2 * 2^32 + (2^32 - 3)
+    assert_eq!(run("test::overflowing_add3"), vec![2, u32::MAX as u64 - 2]);
+    assert_eq!(run("test::wrapping_add3"), vec![u32::MAX as u64 - 2]);
+    // (2^32 - 1)^2 + (2^32 - 1) = (2^32 - 1) * 2^32
+    assert_eq!(run("test::overflowing_madd"), vec![u32::MAX as u64, 0]);
+    assert_eq!(run("test::wrapping_madd"), vec![0]);
 }
 
 macro_rules! proptest_unary_numeric_op {
@@ -151,11 +151,12 @@ pub fn translate_operator(
         Operator::MemoryCopy { dst_mem, src_mem } => {
             // See semantics at https://github.com/WebAssembly/bulk-memory-operations/blob/master/proposals/bulk-memory-operations/Overview.md#memorycopy-instruction
             if *src_mem == 0 && src_mem == dst_mem {
-                let len = state.pop1();
+                let len = state.pop1_casted(U32, builder, span);
                 let src_i32 = state.pop1();
                 let dst_i32 = state.pop1();
                 let dst = prepare_addr(dst_i32, &U8, None, builder, span);
                 let src = prepare_addr(src_i32, &U8, None, builder, span);
+                // The regions may overlap, which `memcpy` permits
                 builder.ins().memcpy(src, dst, len, span);
             } else {
                 unsupported_diag!(diagnostics, "MemoryCopy: only single memory is supported");
@@ -163,22 +164,15 @@ pub fn translate_operator(
         }
         Operator::MemoryFill { mem } => {
             // See semantics at https://webassembly.github.io/spec/core/exec/instructions.html#exec-memory-fill
-
-            // This is a temporary workaround until we have this instruction
-            // properly implemented in IR. I encountered the Wasm `memory.fill`
-            // instruction in the `GlobalAlloc::alloc_zeroed` function, which is
-            // used to zero out the memory allocated by `GlobalAlloc::alloc`, but
-            // since the memory in Miden VM is guaranteed to be initialized to
-            // zeros we can ignore this instruction in this case for now
-            // see https://github.com/0xPolygonMiden/compiler/issues/156
             if *mem != 0 {
                 unsupported_diag!(diagnostics, "MemoryFill: only single memory is supported");
             }
-            let _num_bytes = state.pop1();
+            let num_bytes = state.pop1_casted(U32, builder, span);
             let val = state.pop1();
-            let _dst_i32 = state.pop1();
-            // Fail if the value is not zero, i.e. the memory is not zeroed
-            builder.ins().assert_eq_imm(Immediate::I32(0), val, span);
+            let dst_i32 = state.pop1();
+            let val = builder.ins().trunc(val, U8, span);
+            let dst = prepare_addr(dst_i32, &U8, None, builder, span);
+            builder.ins().memset(dst, num_bytes, val, span);
         }
         /******************************* Load instructions ***********************************/
         Operator::I32Load8U { memarg } => {
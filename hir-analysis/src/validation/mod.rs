@@ -450,4 +450,36 @@ mod tests {
         let analysis = ModuleValidationAnalysis::validate(&module, &context.session);
         analysis.expect("module was expected to be valid")
     }
+
+    #[test]
+    fn context_call_stack_limit_test() {
+        let context = TestContext::default();
+
+        // Define a function which calls a function in a new context with 18 elements of arguments
+        let mut builder = ModuleBuilder::new("test");
+        builder.with_span(context.current_span());
+        let callee_signature = Signature::new((0..9).map(|_| AbiParam::new(Type::U64)), []);
+        let call = {
+            let mut fb = builder
+                .function("caller", Signature::new([], []))
+                .expect("unexpected symbol conflict");
+            let callee = fb
+                .import_function("account", "receive", callee_signature)
+                .expect("unexpected symbol conflict");
+            let value = fb.ins().u64(1, SourceSpan::UNKNOWN);
+            let call = fb.ins().context_call(callee, &[value; 9], SourceSpan::UNKNOWN);
+            fb.ins().ret(None, SourceSpan::UNKNOWN);
+            fb.build(&context.session.diagnostics)
+                .expect("unexpected error building function");
+            call
+        };
+        let module = builder.build();
+
+        let err = ModuleValidationAnalysis::validate(&module, &context.session)
+            .expect_err("module was expected to be invalid");
+        assert!(
+            matches!(err, ValidationError::InvalidInstruction { inst, .. } if inst == call),
+            "unexpected validation error: {err}"
+        );
+    }
 }
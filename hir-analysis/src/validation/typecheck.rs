@@ -128,6 +128,15 @@ impl<'a> Rule<BlockData> for TypeCheck<'a> {
                     }
                 },
                 Instruction::Load(LoadOp { ref ty, addr, .. }) => {
+                    if !has_memory_representation(ty) {
+                        invalid_instruction!(
+                            diagnostics,
+                            node.key,
+                            span,
+                            "cannot load a value of type {ty}, as it has no representation in \
+                             memory"
+                        );
+                    }
                     if ty.size_in_felts() > 4 {
                         invalid_instruction!(
                             diagnostics,
@@ -146,6 +155,25 @@ impl<'a> Rule<BlockData> for TypeCheck<'a> {
                     let args = args.as_slice(&self.dfg.value_lists);
                     typechecker.check_immediate(args, imm, results)?;
                 }
+                Instruction::PrimOp(PrimOp { args, .. })
+                    if matches!(opcode, Opcode::Store | Opcode::MemSet) =>
+                {
+                    let args = args.as_slice(&self.dfg.value_lists);
+                    // The value to be written is always the last argument
+                    if let Some(value) = args.last() {
+                        let ty = self.dfg.value_type(*value);
+                        if !has_memory_representation(ty) {
+                            invalid_instruction!(
+                                diagnostics,
+                                node.key,
+                                span,
+                                "cannot store a value of type {ty}, as it has no representation \
+                                 in memory"
+                            );
+                        }
+                    }
+                    typechecker.check(args, results)?;
+                }
                 Instruction::GlobalValue(_)
                 | Instruction::BinaryOp(_)
                 | Instruction::PrimOp(_)
@@ -331,6 +359,11 @@ impl<'a> Rule<BlockData> for TypeCheck<'a> {
     }
 }
 
+/// Returns true if values of type `ty` can be loaded from, or stored to, memory
+fn has_memory_representation(ty: &Type) -> bool {
+    !matches!(ty, Type::Unknown | Type::Unit | Type::Never | Type::List(_))
+}
+
 /// This type represents a match pattern over kinds of types.
 ///
 /// This is quite useful in the type checker, as otherwise we would have to handle many
@@ -1034,6 +1067,10 @@ impl<'a> InstTypeChecker<'a> {
                 vec![TypePattern::Pointer, TypePattern::Pointer, Type::U32.into()],
                 vec![],
             ),
+            Opcode::MemSet => InstPattern::Exact(
+                vec![TypePattern::Pointer, Type::U32.into(), TypePattern::Any],
+                vec![],
+            ),
             Opcode::PtrToInt => InstPattern::UnaryMap(TypePattern::Pointer, TypePattern::Int),
             Opcode::IntToPtr => InstPattern::UnaryMap(TypePattern::Uint, TypePattern::Pointer),
             Opcode::Cast => InstPattern::UnaryMap(TypePattern::Int, TypePattern::Int),
@@ -1073,9 +1110,32 @@ impl<'a> InstTypeChecker<'a> {
             }
             Opcode::IsOdd => InstPattern::Exact(vec![TypePattern::Int], vec![Type::I1.into()]),
             Opcode::Min | Opcode::Max => InstPattern::BinaryMatching(TypePattern::Int),
-            Opcode::Call | Opcode::Syscall => match node.as_ref() {
+            Opcode::Call | Opcode::ContextCall | Opcode::Syscall => match node.as_ref() {
                 Instruction::Call(Call { ref callee, .. }) => {
                     if let Some(import) = dfg.get_import(callee) {
+                        // Only the top 16 elements of the operand stack are visible to a callee
+                        // in a new context, so the arguments and results must fit within them
+                        if opcode == Opcode::ContextCall {
+                            let felts = |params: &[AbiParam]| -> usize {
+                                params.iter().map(|p| p.ty.size_in_felts()).sum()
+                            };
+                            let params_size = felts(&import.signature.params);
+                            let results_size = felts(&import.signature.results);
+                            if params_size > 16 || results_size > 16 {
+                                invalid_instruction!(
+                                    diagnostics,
+                                    node.key,
+                                    span,
+                                    "invalid call to {callee}: arguments and results must each \
+                                     fit in 16 field elements, but this function requires \
+                                     {params_size} elements for its arguments and {results_size} \
+                                     elements for its results",
+                                    "Calls which switch to a new memory context only have access \
+                                     to the top 16 elements of the operand stack, values larger \
+                                     than this must be passed through the advice provider"
+                                );
+                            }
+                        }
                         let args = import
                             .signature
                             .params
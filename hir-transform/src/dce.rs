@@ -0,0 +1,255 @@
+use miden_hir::{
+    self as hir,
+    pass::{AnalysisManager, RewritePass, RewriteResult},
+    *,
+};
+use miden_hir_analysis::{ControlFlowGraph, DominatorTree};
+use midenc_session::Session;
+use rustc_hash::FxHashSet;
+use smallvec::{smallvec, SmallVec};
+
+/// This pass performs aggressive dead code elimination on a function.
+///
+/// Rather than removing instructions which are trivially dead, i.e. those without uses, this
+/// pass assumes that everything is dead until proven otherwise. Instructions with side effects,
+/// which includes all terminators, are live by definition. Any value used by a live instruction
+/// is live, along with the instruction or block parameter which defines it. A live block
+/// parameter in turn makes the corresponding argument of each predecessor live. Everything
+/// which is not found to be live by this process is removed, specifically:
+///
+/// * Blocks which are unreachable from the entry block
+/// * Instructions without side effects whose results are never used by a live instruction
+/// * Block parameters which are never used by a live instruction, along with the arguments
+/// provided for them by each predecessor. This includes parameters which are only used to
+/// pass themselves around a loop.
+///
+/// The parameters of the entry block are never removed, as they are part of the function
+/// signature.
+#[derive(Default, PassInfo, ModuleRewritePassAdapter)]
+pub struct DeadCodeElimination;
+impl RewritePass for DeadCodeElimination {
+    type Entity = hir::Function;
+
+    fn apply(
+        &mut self,
+        function: &mut Self::Entity,
+        analyses: &mut AnalysisManager,
+        session: &Session,
+    ) -> RewriteResult {
+        let cfg = analyses.get_or_compute::<ControlFlowGraph>(function, session)?;
+        let domtree = analyses.get_or_compute::<DominatorTree>(function, session)?;
+
+        let entry = function.dfg.entry_block();
+        let mut reachable = SmallVec::<[Block; 8]>::default();
+        let mut unreachable = SmallVec::<[Block; 8]>::default();
+        for (block, _) in function.dfg.blocks() {
+            if block == entry || domtree.is_reachable(block) {
+                reachable.push(block);
+            } else {
+                unreachable.push(block);
+            }
+        }
+
+        let live = Liveness::compute(&function.dfg, &cfg, &reachable);
+
+        let mut changed = !unreachable.is_empty();
+        for block in unreachable.into_iter() {
+            function.dfg.detach_block(block);
+        }
+
+        for block in reachable.iter().copied() {
+            // Remove dead block parameters, in reverse order so that the indices of the
+            // parameters we have yet to visit are unaffected by the removal
+            if block != entry {
+                let params = SmallVec::<[Value; 4]>::from_slice(function.dfg.block_params(block));
+                for (index, param) in params.into_iter().enumerate().rev() {
+                    if live.values.contains(&param) {
+                        continue;
+                    }
+                    for pred in cfg.pred_iter(block) {
+                        function.dfg.remove_branch_destination_argument(pred.inst, block, index);
+                    }
+                    function.dfg.remove_block_param(param);
+                    changed = true;
+                }
+            }
+
+            // Remove dead instructions
+            let mut cursor = function.dfg.block_mut(block).cursor_mut();
+            while let Some(node) = cursor.get() {
+                if live.insts.contains(&node.key) {
+                    cursor.move_next();
+                } else {
+                    cursor.remove();
+                    changed = true;
+                }
+            }
+        }
+
+        if !changed {
+            analyses.mark_all_preserved::<hir::Function>(&function.id);
+        }
+
+        Ok(())
+    }
+}
+
+/// The set of live instructions and values in a function
+#[derive(Default)]
+struct Liveness {
+    insts: FxHashSet<Inst>,
+    values: FxHashSet<Value>,
+    worklist: Vec<Value>,
+}
+impl Liveness {
+    /// Compute the live instructions and values of the given blocks
+    fn compute(dfg: &DataFlowGraph, cfg: &ControlFlowGraph, blocks: &[Block]) -> Self {
+        let mut live = Self::default();
+
+        // Instructions with side effects are always live
+        for block in blocks.iter().copied() {
+            for inst in dfg.block_insts(block) {
+                if dfg[inst].has_side_effects() {
+                    live.mark_inst(dfg, inst);
+                }
+            }
+        }
+
+        let entry = dfg.entry_block();
+        while let Some(value) = live.worklist.pop() {
+            match dfg.value_data(value) {
+                ValueData::Inst { inst, .. } => live.mark_inst(dfg, *inst),
+                ValueData::Param { block, .. } if *block == entry => (),
+                ValueData::Param { block, num, .. } => {
+                    // The arguments provided for this parameter by all predecessors are live
+                    let (block, index) = (*block, *num as usize);
+                    for pred in cfg.pred_iter(block) {
+                        let args: SmallVec<[Value; 2]> = match dfg.analyze_branch(pred.inst) {
+                            BranchInfo::SingleDest(_, args) => smallvec![args[index]],
+                            BranchInfo::MultiDest(jts) => jts
+                                .into_iter()
+                                .filter(|jt| jt.destination == block)
+                                .map(|jt| jt.args[index])
+                                .collect(),
+                            BranchInfo::NotABranch => unreachable!(),
+                        };
+                        for arg in args.into_iter() {
+                            live.mark_value(arg);
+                        }
+                    }
+                }
+            }
+        }
+
+        live
+    }
+
+    fn mark_inst(&mut self, dfg: &DataFlowGraph, inst: Inst) {
+        if !self.insts.insert(inst) {
+            return;
+        }
+        // Successor arguments are only live if the corresponding block parameter is, so
+        // we only need to consider the fixed arguments of the instruction here
+        for arg in dfg.inst_args(inst).iter().copied() {
+            self.mark_value(arg);
+        }
+    }
+
+    fn mark_value(&mut self, value: Value) {
+        if self.values.insert(value) {
+            self.worklist.push(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_hir::{
+        pass::{AnalysisManager, RewritePass},
+        testing::TestContext,
+        AbiParam, Function, FunctionBuilder, InstBuilder, Signature, SourceSpan, Type,
+    };
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    use crate::DeadCodeElimination;
+
+    /// Run dead code elimination on the following IR:
+    ///
+    /// ```text,ignore
+    /// pub fn test(u32, u32) -> u32 {
+    /// entry(a: u32, b: u32):
+    ///    sum = add.checked a, b;
+    ///    br blk0(a, sum);
+    ///
+    /// blk0(x: u32, y: u32):
+    ///    square = mul.checked x, x;
+    ///    ret x;
+    ///
+    /// blk1:
+    ///    ret b;
+    /// }
+    /// ```
+    ///
+    /// We expect `sum` and `square` to be removed, as they have no uses, as well as the
+    /// parameter `y`, whose only use is the dead instruction `sum`. Lastly, `blk1` has no
+    /// predecessors, so it is unreachable, and should be removed.
+    #[test]
+    fn dce_simple_test() {
+        let context = TestContext::default();
+        let id = "test::dce".parse().unwrap();
+        let mut function = Function::new(
+            id,
+            Signature::new(
+                [AbiParam::new(Type::U32), AbiParam::new(Type::U32)],
+                [AbiParam::new(Type::U32)],
+            ),
+        );
+
+        {
+            let mut builder = FunctionBuilder::new(&mut function);
+            let entry = builder.current_block();
+            let (a, b) = {
+                let args = builder.block_params(entry);
+                (args[0], args[1])
+            };
+
+            let blk0 = builder.create_block(); // blk0(x: u32, y: u32)
+            let x = builder.append_block_param(blk0, Type::U32, SourceSpan::UNKNOWN);
+            builder.append_block_param(blk0, Type::U32, SourceSpan::UNKNOWN);
+            let blk1 = builder.create_block(); // blk1
+
+            // entry
+            let sum = builder.ins().add_checked(a, b, SourceSpan::UNKNOWN);
+            builder.ins().br(blk0, &[a, sum], SourceSpan::UNKNOWN);
+
+            // blk0
+            builder.switch_to_block(blk0);
+            builder.ins().mul_checked(x, x, SourceSpan::UNKNOWN);
+            builder.ins().ret(Some(x), SourceSpan::UNKNOWN);
+
+            // blk1
+            builder.switch_to_block(blk1);
+            builder.ins().ret(Some(b), SourceSpan::UNKNOWN);
+        }
+
+        let original = function.to_string();
+        let mut analyses = AnalysisManager::default();
+        let mut rewrite = DeadCodeElimination;
+        rewrite
+            .apply(&mut function, &mut analyses, &context.session)
+            .expect("dead code elimination failed");
+
+        let expected = "\
+(func (export #dce) (param u32) (param u32) (result u32)
+    (block 0 (param v0 u32) (param v1 u32)
+        (br (block 1 v0)))
+
+    (block 1 (param v2 u32)
+        (ret v2))
+)";
+
+        let transformed = function.to_string();
+        assert_ne!(transformed, original);
+        assert_eq!(transformed.as_str(), expected);
+    }
+}
@@ -0,0 +1,354 @@
+use std::rc::Rc;
+
+use miden_hir::{
+    self as hir,
+    pass::{AnalysisManager, RewritePass, RewriteResult},
+    *,
+};
+use miden_hir_analysis::{DominatorTree, DominatorTreePreorder};
+use midenc_session::Session;
+use rustc_hash::{FxHashMap, FxHashSet};
+use smallvec::{smallvec, SmallVec};
+
+use crate::{adt::ScopedMap, inline_blocks::rewrite_use};
+
+/// This pass performs global value numbering on a function, eliminating redundant computations
+/// of the same pure expression.
+///
+/// Blocks are visited in a preorder traversal of the dominator tree, and each pure instruction
+/// is assigned a key derived from its opcode, overflow behavior, result type, operands, and
+/// any immediate or type arguments. If an instruction with an equivalent key was already seen
+/// in a block which dominates the current one, then the current instruction is redundant: all
+/// uses of its results are rewritten to use the results of the dominating instruction, and it
+/// is removed. Arguments of commutative operations are put in a canonical order before being
+/// keyed, so that e.g. `add a, b` and `add b, a` are recognized as equivalent.
+///
+/// Global value references are numbered structurally, so two references to the same symbol,
+/// at the same offset, are treated as the same address even though they are distinct entities
+/// in the function. This is particularly important for code translated from WebAssembly,
+/// which recomputes the address of `__stack_pointer` and other globals on each access.
+///
+/// The following are never numbered:
+///
+/// * Instructions with side effects
+/// * Loads, and global values derived from a load, as they depend on the state of memory
+/// * Constants, as it is cheaper to materialize them on demand than to keep them live on the
+/// operand stack
+#[derive(Default, PassInfo, ModuleRewritePassAdapter)]
+pub struct GlobalValueNumbering;
+impl RewritePass for GlobalValueNumbering {
+    type Entity = hir::Function;
+
+    fn apply(
+        &mut self,
+        function: &mut Self::Entity,
+        analyses: &mut AnalysisManager,
+        session: &Session,
+    ) -> RewriteResult {
+        let domtree = analyses.get_or_compute::<DominatorTree>(function, session)?;
+        let preorder = DominatorTreePreorder::with_function(&domtree, function);
+
+        let mut globals = GlobalValueTable::default();
+        let mut rewrites = ScopedMap::<Value, Value>::default();
+        let mut visited = FxHashSet::<Block>::default();
+        let mut changed = false;
+
+        // Each block is visited with the scope of expressions available in its immediate
+        // dominator, so only instructions which dominate the current block are candidates
+        let mut worklist = vec![(function.dfg.entry_block(), None)];
+        while let Some((block, parent)) = worklist.pop() {
+            visited.insert(block);
+
+            let mut scope = ScopedMap::<Expr, Inst>::new(parent);
+            let mut redundant = FxHashSet::<Inst>::default();
+            let insts = function.dfg.block_insts(block).collect::<SmallVec<[Inst; 16]>>();
+            for inst in insts.into_iter() {
+                // Any use of a redundant value is dominated by its definition, and thus by the
+                // block in which it was found to be redundant, so by the time we reach a use,
+                // the rewrite for it is known.
+                rewrite_use(
+                    &mut function.dfg.insts[inst],
+                    &mut function.dfg.value_lists,
+                    &rewrites,
+                );
+
+                let Some(expr) = Expr::new(&function.dfg, inst, &mut globals) else {
+                    continue;
+                };
+                match scope.get(&expr).copied() {
+                    Some(leader) => {
+                        let results = function.dfg.inst_results(inst).iter().copied();
+                        let replacements = function.dfg.inst_results(leader).iter().copied();
+                        rewrites.extend(results.zip(replacements));
+                        redundant.insert(inst);
+                    }
+                    None => scope.insert(expr, inst),
+                }
+            }
+
+            if !redundant.is_empty() {
+                changed = true;
+                let mut cursor = function.dfg.block_mut(block).cursor_mut();
+                while let Some(node) = cursor.get() {
+                    if redundant.contains(&node.key) {
+                        cursor.remove();
+                    } else {
+                        cursor.move_next();
+                    }
+                }
+            }
+
+            let scope = Rc::new(scope);
+            for child in preorder.children(block) {
+                worklist.push((child, Some(Rc::clone(&scope))));
+            }
+        }
+
+        // Blocks which are unreachable are not part of the dominator tree, but they may still
+        // refer to values we've removed, so make sure those uses are rewritten as well
+        if changed {
+            let unreachable = function
+                .dfg
+                .blocks()
+                .map(|(block, _)| block)
+                .filter(|block| !visited.contains(block))
+                .collect::<SmallVec<[Block; 4]>>();
+            for block in unreachable.into_iter() {
+                let insts = function.dfg.block_insts(block).collect::<SmallVec<[Inst; 16]>>();
+                for inst in insts.into_iter() {
+                    rewrite_use(
+                        &mut function.dfg.insts[inst],
+                        &mut function.dfg.value_lists,
+                        &rewrites,
+                    );
+                }
+            }
+        } else {
+            analyses.mark_all_preserved::<hir::Function>(&function.id);
+        }
+
+        Ok(())
+    }
+}
+
+/// The key used to identify equivalent pure expressions
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct Expr {
+    op: Opcode,
+    overflow: Option<Overflow>,
+    ty: Type,
+    args: SmallVec<[Value; 2]>,
+    operand: Operand,
+}
+
+/// The non-value operand of an expression, if applicable
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum Operand {
+    None,
+    Imm(Immediate),
+    Type(Type),
+    Global(GlobalValue),
+}
+
+impl Expr {
+    /// Get the key for `inst`, or `None` if it is not eligible for value numbering
+    fn new(dfg: &DataFlowGraph, inst: Inst, globals: &mut GlobalValueTable) -> Option<Self> {
+        let data = &dfg[inst];
+        if data.has_side_effects() {
+            return None;
+        }
+
+        let (mut args, operand) = match data {
+            Instruction::GlobalValue(GlobalValueOp { global, .. }) => {
+                (smallvec![], Operand::Global(globals.canonicalize(dfg, *global)?))
+            }
+            Instruction::BinaryOp(BinaryOp { args, .. }) => {
+                (SmallVec::from_slice(args.as_slice()), Operand::None)
+            }
+            Instruction::BinaryOpImm(BinaryOpImm { arg, imm, .. }) => {
+                (smallvec![*arg], Operand::Imm(*imm))
+            }
+            Instruction::UnaryOp(UnaryOp { arg, .. }) => (smallvec![*arg], Operand::None),
+            Instruction::Test(Test { arg, ty, .. }) => (smallvec![*arg], Operand::Type(ty.clone())),
+            Instruction::PrimOp(PrimOp { args, .. }) => {
+                (SmallVec::from_slice(args.as_slice(&dfg.value_lists)), Operand::None)
+            }
+            Instruction::PrimOpImm(PrimOpImm { imm, args, .. }) => {
+                (SmallVec::from_slice(args.as_slice(&dfg.value_lists)), Operand::Imm(*imm))
+            }
+            // Constants and loads are never numbered, everything else has side effects
+            _ => return None,
+        };
+
+        let op = data.opcode();
+        if op.is_commutative() {
+            args.sort_unstable();
+        }
+
+        let ty = dfg.value_type(*dfg.inst_results(inst).first()?).clone();
+
+        Some(Self {
+            op,
+            overflow: data.overflow(),
+            ty,
+            args,
+            operand,
+        })
+    }
+}
+
+/// The structural identity of a global value which represents a constant address
+#[derive(PartialEq, Eq, Hash)]
+enum GlobalValueKey {
+    Symbol(Ident, i32),
+    IAddImm(GlobalValue, i32, Type),
+}
+
+/// Maps global values to a canonical representative with the same structure
+#[derive(Default)]
+struct GlobalValueTable {
+    keys: FxHashMap<GlobalValueKey, GlobalValue>,
+    canonical: FxHashMap<GlobalValue, Option<GlobalValue>>,
+}
+impl GlobalValueTable {
+    /// Get the canonical representative of `gv`, or `None` if its value depends on memory
+    fn canonicalize(&mut self, dfg: &DataFlowGraph, gv: GlobalValue) -> Option<GlobalValue> {
+        if let Some(canonical) = self.canonical.get(&gv) {
+            return *canonical;
+        }
+
+        let key = match dfg.global_value(gv) {
+            GlobalValueData::Symbol { name, offset } => {
+                Some(GlobalValueKey::Symbol(*name, *offset))
+            }
+            GlobalValueData::IAddImm { base, offset, ty } => self
+                .canonicalize(dfg, *base)
+                .map(|base| GlobalValueKey::IAddImm(base, *offset, ty.clone())),
+            GlobalValueData::Load { .. } => None,
+        };
+        let canonical = key.map(|key| *self.keys.entry(key).or_insert(gv));
+        self.canonical.insert(gv, canonical);
+        canonical
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_hir::{
+        pass::{AnalysisManager, RewritePass},
+        testing::TestContext,
+        AbiParam, Function, FunctionBuilder, InstBuilder, Signature, SourceSpan, Type,
+    };
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    use crate::GlobalValueNumbering;
+
+    /// Run global value numbering on the following IR:
+    ///
+    /// ```text,ignore
+    /// pub fn test(u32, u32) -> u32 {
+    /// entry(a: u32, b: u32):
+    ///    sum0 = add.checked a, b;
+    ///    sp0 = global.symbol @__stack_pointer : *mut u8;
+    ///    addr0 = ptrtoint sp0 : u32;
+    ///    is_eq = eq a, b;
+    ///    condbr is_eq, blk0, blk1;
+    ///
+    /// blk0:
+    ///    sum1 = add.checked b, a;
+    ///    sp1 = global.symbol @__stack_pointer : *mut u8;
+    ///    addr1 = ptrtoint sp1 : u32;
+    ///    product0 = mul.checked a, b;
+    ///    offset = add.checked sum1, addr1;
+    ///    result = add.checked offset, product0;
+    ///    ret result;
+    ///
+    /// blk1:
+    ///    product1 = mul.checked a, b;
+    ///    ret product1;
+    /// }
+    /// ```
+    ///
+    /// We expect `sum1`, `sp1` and `addr1` to be replaced with `sum0`, `sp0` and `addr0`
+    /// respectively, as the entry block dominates `blk0`. However both `product0` and
+    /// `product1` must be kept, as neither `blk0` nor `blk1` dominates the other.
+    #[test]
+    fn gvn_simple_test() {
+        let context = TestContext::default();
+        let id = "test::gvn".parse().unwrap();
+        let mut function = Function::new(
+            id,
+            Signature::new(
+                [AbiParam::new(Type::U32), AbiParam::new(Type::U32)],
+                [AbiParam::new(Type::U32)],
+            ),
+        );
+
+        {
+            let mut builder = FunctionBuilder::new(&mut function);
+            let entry = builder.current_block();
+            let (a, b) = {
+                let args = builder.block_params(entry);
+                (args[0], args[1])
+            };
+
+            let blk0 = builder.create_block();
+            let blk1 = builder.create_block();
+            let sp = "__stack_pointer";
+            let ptr_ty = Type::Ptr(Box::new(Type::U8));
+
+            // entry
+            builder.ins().add_checked(a, b, SourceSpan::UNKNOWN);
+            let sp0 = builder.ins().symbol_addr(sp, ptr_ty.clone(), SourceSpan::UNKNOWN);
+            builder.ins().ptrtoint(sp0, Type::U32, SourceSpan::UNKNOWN);
+            let is_eq = builder.ins().eq(a, b, SourceSpan::UNKNOWN);
+            builder.ins().cond_br(is_eq, blk0, &[], blk1, &[], SourceSpan::UNKNOWN);
+
+            // blk0
+            builder.switch_to_block(blk0);
+            let sum1 = builder.ins().add_checked(b, a, SourceSpan::UNKNOWN);
+            let sp1 = builder.ins().symbol_addr(sp, ptr_ty, SourceSpan::UNKNOWN);
+            let addr1 = builder.ins().ptrtoint(sp1, Type::U32, SourceSpan::UNKNOWN);
+            let product0 = builder.ins().mul_checked(a, b, SourceSpan::UNKNOWN);
+            let offset = builder.ins().add_checked(sum1, addr1, SourceSpan::UNKNOWN);
+            let result = builder.ins().add_checked(offset, product0, SourceSpan::UNKNOWN);
+            builder.ins().ret(Some(result), SourceSpan::UNKNOWN);
+
+            // blk1
+            builder.switch_to_block(blk1);
+            let product1 = builder.ins().mul_checked(a, b, SourceSpan::UNKNOWN);
+            builder.ins().ret(Some(product1), SourceSpan::UNKNOWN);
+        }
+
+        let original = function.to_string();
+        let mut analyses = AnalysisManager::default();
+        let mut rewrite = GlobalValueNumbering;
+        rewrite
+            .apply(&mut function, &mut analyses, &context.session)
+            .expect("global value numbering failed");
+
+        let expected = "\
+(func (export #gvn) (param u32) (param u32) (result u32)
+    (block 0 (param v0 u32) (param v1 u32)
+        (let (v2 u32) (add.checked v0 v1))
+        (let (v3 (ptr u8)) (global.symbol #__stack_pointer))
+        (let (v4 u32) (ptrtoint v3))
+        (let (v5 i1) (eq v0 v1))
+        (condbr v5 (block 1) (block 2)))
+
+    (block 1
+        (let (v9 u32) (mul.checked v0 v1))
+        (let (v10 u32) (add.checked v2 v4))
+        (let (v11 u32) (add.checked v10 v9))
+        (ret v11))
+
+    (block 2
+        (let (v12 u32) (mul.checked v0 v1))
+        (ret v12))
+)";
+
+        let transformed = function.to_string();
+        assert_ne!(transformed, original);
+        assert_eq!(transformed.as_str(), expected);
+    }
+}
@@ -218,7 +218,7 @@ fn rewrite_uses(root: Block, function: &mut hir::Function, rewrites: &ScopedMap<
     }
 }
 
-fn rewrite_use(
+pub(crate) fn rewrite_use(
     inst: &mut Instruction,
     pool: &mut hir::ValueListPool,
     rewrites: &ScopedMap<Value, Value>,
@@ -0,0 +1,953 @@
+use std::ops::{Add, AddAssign};
+
+use miden_hir::{
+    self as hir,
+    pass::{AnalysisManager, RewritePass, RewriteResult},
+    *,
+};
+use midenc_session::{OptLevel, Session};
+use rustc_hash::{FxHashMap, FxHashSet};
+use smallvec::SmallVec;
+
+use crate::{adt::ScopedMap, inline_blocks::rewrite_use, sccp::immediate_opcode};
+
+/// This pass inlines calls to functions defined in the same module, when doing so is
+/// estimated to be profitable.
+///
+/// Code produced from WebAssembly tends to contain deep chains of tiny functions, i.e. wrappers
+/// which do little more than forward their arguments to another function. Each of those calls
+/// must shuffle arguments and results into position on the operand stack, which can easily cost
+/// more than the callee itself.
+///
+/// The decision to inline a call is made as follows:
+///
+/// * Functions with the `noinline` attribute are never inlined
+/// * Functions which are part of a cycle in the call graph are never inlined
+/// * Functions with the `inline` attribute are always inlined
+/// * Otherwise, only functions with internal linkage are considered. If a function has a single
+/// call site, it is always inlined, as it can be removed afterwards. If it has multiple call
+/// sites, it is inlined only if its estimated size and cycle count exceed the overhead of
+/// calling it by no more than the threshold for the current optimization level.
+///
+/// Functions are visited in a bottom-up traversal of the call graph, so callees have already
+/// had their own calls inlined by the time their cost is estimated. Functions with internal
+/// linkage which have been inlined at every call site are removed from the module.
+#[derive(Default, PassInfo, RewritePassRegistration)]
+pub struct InlineFunctions;
+impl RewritePass for InlineFunctions {
+    type Entity = hir::Module;
+
+    fn apply(
+        &mut self,
+        module: &mut Self::Entity,
+        analyses: &mut AnalysisManager,
+        session: &Session,
+    ) -> RewriteResult {
+        let threshold = InlineThreshold::new(session.options.optimize);
+        let callgraph = CallGraph::new(module);
+
+        let mut costs = FxHashMap::<FunctionIdent, Option<InlineCost>>::default();
+        let mut inlined = FxHashSet::<FunctionIdent>::default();
+        let mut changed = false;
+        for caller_id in callgraph.postorder.iter().copied() {
+            let mut candidates = SmallVec::<[(Inst, FunctionIdent); 4]>::default();
+            for (inst, callee_id) in callgraph.calls(caller_id).iter().copied() {
+                if callgraph.recursive.contains(&callee_id) {
+                    continue;
+                }
+                let callee = module.function(callee_id.function).unwrap();
+                let cost =
+                    *costs.entry(callee_id).or_insert_with(|| InlineCost::of_function(callee));
+                let Some(cost) = cost else {
+                    continue;
+                };
+                if threshold.should_inline(callee, callgraph.uses(callee_id), cost) {
+                    candidates.push((inst, callee_id));
+                }
+            }
+
+            if candidates.is_empty() {
+                continue;
+            }
+
+            // Detach the caller from the module while we modify it, so that we can continue to
+            // refer to the callees, but make sure it is put back in its original position
+            let next =
+                module.cursor_at(caller_id.function).peek_next().get().map(|f| f.id.function);
+            let mut caller = module.unlink(caller_id.function);
+            for (inst, callee_id) in candidates.iter().copied() {
+                let callee = module.function(callee_id.function).unwrap();
+                inline(&mut caller, inst, callee);
+                inlined.insert(callee_id);
+            }
+            // The linker treats every import as a reference to the imported function, so the
+            // callees which are no longer called from the caller must no longer be imported by it
+            let mut referenced = FxHashSet::default();
+            function_references(&caller, &mut referenced);
+            for (_, callee_id) in candidates.into_iter() {
+                if !referenced.contains(&callee_id) {
+                    caller.dfg.imports.remove(&callee_id);
+                }
+            }
+            let result = match next {
+                Some(next) => module.insert_before(caller, next),
+                None => module.push(caller),
+            };
+            result.expect("unexpected symbol conflict");
+
+            analyses.invalidate::<hir::Function>(&caller_id);
+            changed = true;
+        }
+
+        // Remove any functions which are no longer referenced, as they have been inlined into
+        // all of their callers, and cannot be referenced from outside this module. A function
+        // which is still imported anywhere in the module is kept, as the linker would otherwise
+        // fail to resolve the import
+        if !inlined.is_empty() {
+            let referenced = referenced_functions(module);
+            for id in inlined.into_iter() {
+                let function = module.function(id.function).unwrap();
+                if function.signature.is_private()
+                    && !function.has_attribute(&symbols::Entrypoint)
+                    && !referenced.contains(&id)
+                {
+                    module.unlink(id.function);
+                    analyses.invalidate::<hir::Function>(&id);
+                }
+            }
+        }
+
+        if !changed {
+            analyses.mark_all_preserved::<hir::Module>(&module.name);
+        }
+
+        Ok(())
+    }
+}
+
+/// Inline the body of `callee` in place of the call instruction `call` in `caller`
+fn inline(caller: &mut hir::Function, call: Inst, callee: &hir::Function) {
+    let dfg = &mut caller.dfg;
+    let block = dfg.inst_block(call).expect("cannot inline a detached call instruction");
+    let span = dfg.inst_span(call);
+    let args = SmallVec::<[Value; 4]>::from_slice(dfg.inst_args(call));
+    let results = SmallVec::<[Value; 2]>::from_slice(dfg.inst_results(call));
+
+    // Split the block containing the call, moving everything after the call to a new block,
+    // which receives the results of the call as block arguments
+    let continuation = dfg.create_block_after(block);
+    let has_results = !results.is_empty();
+    let mut returned = ScopedMap::<Value, Value>::default();
+    for result in results.into_iter() {
+        let ty = dfg.value_type(result).clone();
+        let param = dfg.append_block_param(continuation, ty, span);
+        returned.insert(result, param);
+    }
+    split_block(dfg, call, continuation);
+
+    // Any functions called by the callee must now be imported by the caller
+    for import in callee.dfg.imports() {
+        dfg.imports.entry(import.id).or_insert_with(|| import.clone());
+    }
+
+    // Copy the body of the callee between the two halves of the original block
+    let entry = {
+        let mut copier = BodyCopier::new(dfg, &callee.dfg, continuation);
+        copier.copy_blocks(block);
+        copier.copy_insts();
+        copier.blocks[&callee.dfg.entry_block()]
+    };
+
+    // Replace the call with a branch to the copy of the callee entry block, and rewrite uses
+    // of the call results to refer to the continuation block parameters
+    let args = ValueList::from_slice(&args, &mut dfg.value_lists);
+    dfg.replace(call).Br(Opcode::Br, Type::Unit, entry, args, span);
+    if has_results {
+        let blocks = dfg.blocks().map(|(b, _)| b).collect::<SmallVec<[Block; 8]>>();
+        for b in blocks.into_iter() {
+            let insts = dfg.block_insts(b).collect::<SmallVec<[Inst; 16]>>();
+            for inst in insts.into_iter() {
+                rewrite_use(&mut dfg.insts[inst], &mut dfg.value_lists, &returned);
+            }
+        }
+    }
+}
+
+/// Move all instructions following `inst` in its block, to the end of `dest`
+fn split_block(dfg: &mut DataFlowGraph, inst: Inst, dest: Block) {
+    let block = dfg.inst_block(inst).unwrap();
+    let mut moved = SmallVec::<[UnsafeRef<InstNode>; 8]>::default();
+    {
+        let mut cursor = dfg.block_mut(block).cursor_mut();
+        while let Some(key) = cursor.get().map(|node| node.key) {
+            cursor.move_next();
+            if key == inst {
+                break;
+            }
+        }
+        while let Some(node) = cursor.remove() {
+            moved.push(node);
+        }
+    }
+    for node in moved.into_iter() {
+        let key = node.key;
+        dfg.blocks[dest].append(node);
+        dfg.insts[key].block = dest;
+    }
+}
+
+/// This is used to copy the body of a callee into the body of a caller
+struct BodyCopier<'a> {
+    dfg: &'a mut DataFlowGraph,
+    callee: &'a DataFlowGraph,
+    /// The block to which returns from the callee are redirected
+    continuation: Block,
+    /// Maps callee blocks to their copies in the caller
+    blocks: FxHashMap<Block, Block>,
+    /// Maps callee values to their copies in the caller
+    values: ScopedMap<Value, Value>,
+    /// Maps callee global values to their copies in the caller
+    globals: FxHashMap<GlobalValue, GlobalValue>,
+}
+impl<'a> BodyCopier<'a> {
+    fn new(dfg: &'a mut DataFlowGraph, callee: &'a DataFlowGraph, continuation: Block) -> Self {
+        Self {
+            dfg,
+            callee,
+            continuation,
+            blocks: Default::default(),
+            values: Default::default(),
+            globals: Default::default(),
+        }
+    }
+
+    /// Create a copy of each callee block, along with its parameters, after `after`
+    fn copy_blocks(&mut self, mut after: Block) {
+        let callee = self.callee;
+        for (block, _) in callee.blocks() {
+            let copy = self.dfg.create_block_after(after);
+            for param in callee.block_params(block).iter().copied() {
+                let ty = callee.value_type(param).clone();
+                let span = callee.value_span(param);
+                let value = self.dfg.append_block_param(copy, ty, span);
+                self.values.insert(param, value);
+            }
+            self.blocks.insert(block, copy);
+            after = copy;
+        }
+    }
+
+    /// Copy the instructions of each callee block into its copy
+    ///
+    /// The callee is not guaranteed to define values before they are used in layout order, so
+    /// the instructions are first copied with their original operands, and rewritten once the
+    /// mapping for all of the callee values is known.
+    fn copy_insts(&mut self) {
+        let callee = self.callee;
+        let mut copied = Vec::<Inst>::default();
+        for (block, _) in callee.blocks() {
+            let copy = self.blocks[&block];
+            for inst in callee.block_insts(block) {
+                let span = callee.inst_span(inst);
+                let data = match callee.inst(inst) {
+                    // Returns are replaced with a branch to the continuation block
+                    Instruction::Ret(Ret { args, .. }) => Instruction::Br(Br {
+                        op: Opcode::Br,
+                        destination: self.continuation,
+                        args: self.copy_list(args),
+                    }),
+                    Instruction::RetImm(RetImm { arg, .. }) => {
+                        // This instruction refers to a caller value, so it must not be rewritten
+                        let op = immediate_opcode(arg).unwrap();
+                        let imm = Instruction::UnaryOpImm(UnaryOpImm {
+                            op,
+                            overflow: None,
+                            imm: *arg,
+                        });
+                        let imm = self.dfg.append_inst(copy, imm, arg.ty(), span);
+                        let value = self.dfg.first_result(imm);
+                        let args = ValueList::from_slice(&[value], &mut self.dfg.value_lists);
+                        let br = Instruction::Br(Br {
+                            op: Opcode::Br,
+                            destination: self.continuation,
+                            args,
+                        });
+                        self.dfg.append_inst(copy, br, Type::Unit, span);
+                        continue;
+                    }
+                    data => self.copy_inst(data),
+                };
+
+                // The controlling type is only used to derive the types of the results, which
+                // we overwrite with the types of the original results below
+                let results = callee.inst_results(inst);
+                let ctrl_ty = results
+                    .last()
+                    .map(|result| callee.value_type(*result).clone())
+                    .unwrap_or(Type::Unit);
+                let new_inst = self.dfg.append_inst(copy, data, ctrl_ty, span);
+                let new_results =
+                    SmallVec::<[Value; 2]>::from_slice(self.dfg.inst_results(new_inst));
+                assert_eq!(results.len(), new_results.len());
+                for (result, new_result) in results.iter().copied().zip(new_results) {
+                    self.dfg.set_value_type(new_result, callee.value_type(result).clone());
+                    self.values.insert(result, new_result);
+                }
+                copied.push(new_inst);
+            }
+        }
+
+        for inst in copied.into_iter() {
+            rewrite_use(&mut self.dfg.insts[inst], &mut self.dfg.value_lists, &self.values);
+        }
+    }
+
+    /// Copy `data` from the callee, remapping everything but the values it refers to
+    fn copy_inst(&mut self, data: &Instruction) -> Instruction {
+        match data {
+            Instruction::GlobalValue(op) => Instruction::GlobalValue(GlobalValueOp {
+                global: self.copy_global(op.global),
+                ..op.clone()
+            }),
+            Instruction::BinaryOp(op) => Instruction::BinaryOp(op.clone()),
+            Instruction::BinaryOpImm(op) => Instruction::BinaryOpImm(op.clone()),
+            Instruction::UnaryOp(op) => Instruction::UnaryOp(op.clone()),
+            Instruction::UnaryOpImm(op) => Instruction::UnaryOpImm(op.clone()),
+            Instruction::Load(op) => Instruction::Load(op.clone()),
+            Instruction::Test(op) => Instruction::Test(op.clone()),
+            Instruction::Call(op) => Instruction::Call(Call {
+                args: self.copy_list(&op.args),
+                ..op.clone()
+            }),
+            Instruction::Br(op) => Instruction::Br(Br {
+                op: op.op,
+                destination: self.blocks[&op.destination],
+                args: self.copy_list(&op.args),
+            }),
+            Instruction::CondBr(op) => Instruction::CondBr(CondBr {
+                op: op.op,
+                cond: op.cond,
+                then_dest: (self.blocks[&op.then_dest.0], self.copy_list(&op.then_dest.1)),
+                else_dest: (self.blocks[&op.else_dest.0], self.copy_list(&op.else_dest.1)),
+            }),
+            Instruction::Switch(op) => Instruction::Switch(Switch {
+                op: op.op,
+                arg: op.arg,
+                arms: op.arms.iter().map(|(value, block)| (*value, self.blocks[block])).collect(),
+                default: self.blocks[&op.default],
+            }),
+            Instruction::PrimOp(op) => Instruction::PrimOp(PrimOp {
+                op: op.op,
+                args: self.copy_list(&op.args),
+            }),
+            Instruction::PrimOpImm(op) => Instruction::PrimOpImm(PrimOpImm {
+                args: self.copy_list(&op.args),
+                ..op.clone()
+            }),
+            Instruction::InlineAsm(op) => Instruction::InlineAsm(InlineAsm {
+                args: self.copy_list(&op.args),
+                ..op.clone()
+            }),
+            Instruction::Ret(_) | Instruction::RetImm(_) => unreachable!(),
+        }
+    }
+
+    /// Copy a list of values from the callee value list pool to that of the caller
+    fn copy_list(&mut self, list: &ValueList) -> ValueList {
+        let values = list.as_slice(&self.callee.value_lists);
+        ValueList::from_slice(values, &mut self.dfg.value_lists)
+    }
+
+    /// Copy the global value `gv` from the callee, along with any global values it depends on
+    fn copy_global(&mut self, gv: GlobalValue) -> GlobalValue {
+        if let Some(copy) = self.globals.get(&gv) {
+            return *copy;
+        }
+
+        let callee = self.callee;
+        let data = match callee.global_value(gv) {
+            GlobalValueData::Symbol { name, offset } => GlobalValueData::Symbol {
+                name: *name,
+                offset: *offset,
+            },
+            GlobalValueData::Load { base, offset, ty } => GlobalValueData::Load {
+                base: self.copy_global(*base),
+                offset: *offset,
+                ty: ty.clone(),
+            },
+            GlobalValueData::IAddImm { base, offset, ty } => GlobalValueData::IAddImm {
+                base: self.copy_global(*base),
+                offset: *offset,
+                ty: ty.clone(),
+            },
+        };
+        let copy = self.dfg.create_global_value(data);
+        self.globals.insert(gv, copy);
+        copy
+    }
+}
+
+/// The call graph of the functions in a single module
+struct CallGraph {
+    /// The inlineable calls in each function to other functions in the same module
+    calls: FxHashMap<FunctionIdent, SmallVec<[(Inst, FunctionIdent); 4]>>,
+    /// The number of call sites for each function, including those which cannot be inlined
+    uses: FxHashMap<FunctionIdent, usize>,
+    /// The set of functions which are part of a cycle in the call graph
+    recursive: FxHashSet<FunctionIdent>,
+    /// The functions of the module, ordered such that callees precede their callers, except
+    /// when they are part of the same cycle
+    postorder: Vec<FunctionIdent>,
+}
+impl CallGraph {
+    fn new(module: &hir::Module) -> Self {
+        let mut calls = FxHashMap::<FunctionIdent, SmallVec<[(Inst, FunctionIdent); 4]>>::default();
+        let mut uses = FxHashMap::<FunctionIdent, usize>::default();
+        for function in module.functions() {
+            let sites = calls.entry(function.id).or_default();
+            for (block, _) in function.dfg.blocks() {
+                for inst in function.dfg.block_insts(block) {
+                    let Instruction::Call(Call { op, callee, .. }) = function.dfg.inst(inst) else {
+                        continue;
+                    };
+                    if callee.module != module.name || !module.contains(callee.function) {
+                        continue;
+                    }
+                    // Only `exec`-style calls can be inlined, as a context call executes the
+                    // callee in a new context, but those calls are still uses of the callee
+                    match op {
+                        Opcode::Call => {
+                            sites.push((inst, *callee));
+                            *uses.entry(*callee).or_default() += 1;
+                        }
+                        Opcode::ContextCall => {
+                            *uses.entry(*callee).or_default() += 1;
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        }
+
+        let mut sccs = StronglyConnectedComponents::new(&calls);
+        for function in module.functions() {
+            if !sccs.indices.contains_key(&function.id) {
+                sccs.visit(function.id);
+            }
+        }
+
+        let mut recursive = FxHashSet::<FunctionIdent>::default();
+        let mut postorder = Vec::with_capacity(calls.len());
+        for scc in sccs.components.into_iter() {
+            let is_recursive =
+                scc.len() > 1 || calls[&scc[0]].iter().any(|(_, callee)| *callee == scc[0]);
+            if is_recursive {
+                recursive.extend(scc.iter().copied());
+            }
+            postorder.extend(scc);
+        }
+
+        Self {
+            calls,
+            uses,
+            recursive,
+            postorder,
+        }
+    }
+
+    /// Get the calls in `function` to other functions in the same module
+    fn calls(&self, function: FunctionIdent) -> &[(Inst, FunctionIdent)] {
+        self.calls[&function].as_slice()
+    }
+
+    /// Get the number of call sites for `function`
+    fn uses(&self, function: FunctionIdent) -> usize {
+        self.uses.get(&function).copied().unwrap_or(0)
+    }
+}
+
+/// Computes the strongly connected components of a call graph using Tarjan's algorithm
+///
+/// The components are produced in reverse topological order, i.e. callees before callers.
+struct StronglyConnectedComponents<'a> {
+    calls: &'a FxHashMap<FunctionIdent, SmallVec<[(Inst, FunctionIdent); 4]>>,
+    indices: FxHashMap<FunctionIdent, usize>,
+    lowlinks: FxHashMap<FunctionIdent, usize>,
+    stack: Vec<FunctionIdent>,
+    on_stack: FxHashSet<FunctionIdent>,
+    components: Vec<SmallVec<[FunctionIdent; 1]>>,
+}
+impl<'a> StronglyConnectedComponents<'a> {
+    fn new(calls: &'a FxHashMap<FunctionIdent, SmallVec<[(Inst, FunctionIdent); 4]>>) -> Self {
+        Self {
+            calls,
+            indices: Default::default(),
+            lowlinks: Default::default(),
+            stack: vec![],
+            on_stack: Default::default(),
+            components: vec![],
+        }
+    }
+
+    fn visit(&mut self, function: FunctionIdent) {
+        let index = self.indices.len();
+        self.indices.insert(function, index);
+        self.lowlinks.insert(function, index);
+        self.stack.push(function);
+        self.on_stack.insert(function);
+
+        let calls = self.calls;
+        for (_, callee) in calls[&function].iter() {
+            if !self.indices.contains_key(callee) {
+                self.visit(*callee);
+                let lowlink = self.lowlinks[&function].min(self.lowlinks[callee]);
+                self.lowlinks.insert(function, lowlink);
+            } else if self.on_stack.contains(callee) {
+                let lowlink = self.lowlinks[&function].min(self.indices[callee]);
+                self.lowlinks.insert(function, lowlink);
+            }
+        }
+
+        if self.lowlinks[&function] == index {
+            let mut component = SmallVec::default();
+            loop {
+                let member = self.stack.pop().unwrap();
+                self.on_stack.remove(&member);
+                component.push(member);
+                if member == function {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+/// Get the set of functions referenced from within `module`
+fn referenced_functions(module: &hir::Module) -> FxHashSet<FunctionIdent> {
+    let mut referenced = FxHashSet::default();
+    for function in module.functions() {
+        function_references(function, &mut referenced);
+        referenced.extend(function.imports().map(|import| import.id));
+    }
+    referenced
+}
+
+/// Add the functions called or otherwise referenced from the body of `function` to `referenced`
+fn function_references(function: &hir::Function, referenced: &mut FxHashSet<FunctionIdent>) {
+    for (block, _) in function.dfg.blocks() {
+        for inst in function.dfg.block_insts(block) {
+            match function.dfg.inst(inst) {
+                Instruction::Call(Call { callee, .. }) => {
+                    referenced.insert(*callee);
+                }
+                Instruction::InlineAsm(asm) => {
+                    for op in asm.blocks.values().flat_map(|block| block.ops.iter()) {
+                        if let MasmOp::Exec(callee)
+                        | MasmOp::Call(callee)
+                        | MasmOp::Syscall(callee)
+                        | MasmOp::ProcRef(callee) = op
+                        {
+                            referenced.insert(*callee);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Determines how much larger and more expensive than the overhead of a call, a function
+/// with multiple call sites may be for it to be inlined.
+#[derive(Debug, Copy, Clone)]
+struct InlineThreshold {
+    size: usize,
+    cycles: usize,
+}
+impl InlineThreshold {
+    fn new(level: OptLevel) -> Self {
+        match level {
+            // Only inline when doing so does not increase code size
+            OptLevel::None | OptLevel::Basic | OptLevel::SizeMin => Self {
+                size: 0,
+                cycles: usize::MAX,
+            },
+            OptLevel::Size => Self {
+                size: 8,
+                cycles: usize::MAX,
+            },
+            // Inlining expensive functions saves comparatively little, and is not worth
+            // the increase in code size
+            OptLevel::Balanced => Self {
+                size: 32,
+                cycles: 64,
+            },
+            OptLevel::Max => Self {
+                size: 128,
+                cycles: usize::MAX,
+            },
+        }
+    }
+
+    /// Returns true if calls to `callee`, a function with `uses` call sites and an estimated
+    /// cost of `cost`, should be inlined.
+    fn should_inline(&self, callee: &hir::Function, uses: usize, cost: InlineCost) -> bool {
+        if callee.has_attribute(&symbols::Noinline) || callee.is_kernel() {
+            return false;
+        }
+        if callee.has_attribute(&symbols::Inline) {
+            return true;
+        }
+        if !callee.signature.is_private() || callee.has_attribute(&symbols::Entrypoint) {
+            return false;
+        }
+        // The callee is removed once inlined, so this can only make the program smaller
+        if uses == 1 {
+            return true;
+        }
+
+        let overhead = InlineCost::of_call(callee.signature());
+        cost.size <= overhead.size.saturating_add(self.size)
+            && cost.cycles <= overhead.cycles.saturating_add(self.cycles)
+    }
+}
+
+/// An estimate of the cost of some code, in terms of Miden Assembly.
+///
+/// The `cycles` estimate is the number of VM cycles needed to execute the code once, while
+/// `size` is the number of Miden Assembly instructions it would be expected to lower to.
+/// Both are approximate, as the actual cost of an operation depends on the state of the
+/// operand stack at that point, which is not known until code generation.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+struct InlineCost {
+    cycles: usize,
+    size: usize,
+}
+impl InlineCost {
+    const fn new(cycles: usize, size: usize) -> Self {
+        Self { cycles, size }
+    }
+
+    /// Estimate the cost of a call to a function with `signature`.
+    ///
+    /// The `exec` instruction itself is cheap, most of the overhead is due to moving the
+    /// arguments into position on the operand stack before the call, and the results
+    /// afterwards.
+    fn of_call(signature: &Signature) -> Self {
+        let moves = signature.arity() + signature.results().len();
+        Self::new(2 + moves, 1 + moves)
+    }
+
+    /// Estimate the cost of the body of `function`, or `None` if it cannot be inlined
+    fn of_function(function: &hir::Function) -> Option<Self> {
+        let mut cost = Self::default();
+        for (block, _) in function.dfg.blocks() {
+            for inst in function.dfg.block_insts(block) {
+                cost += Self::of_inst(&function.dfg, inst)?;
+            }
+        }
+        Some(cost)
+    }
+
+    /// Estimate the cost of `inst`, or `None` if it cannot be inlined
+    fn of_inst(dfg: &DataFlowGraph, inst: Inst) -> Option<Self> {
+        let cost = match dfg.inst(inst) {
+            Instruction::RetImm(RetImm { arg, .. }) => {
+                immediate_opcode(arg)?;
+                Self::new(2, 2)
+            }
+            Instruction::InlineAsm(asm) => {
+                let ops = asm.blocks.values().map(|block| block.ops.len()).sum::<usize>();
+                Self::new(ops, ops)
+            }
+            Instruction::Switch(Switch { arms, .. }) => {
+                let n = arms.len() + 1;
+                Self::new(2 * n, 2 * n)
+            }
+            data => match data.opcode() {
+                Opcode::ImmI1
+                | Opcode::ImmU8
+                | Opcode::ImmI8
+                | Opcode::ImmU16
+                | Opcode::ImmI16
+                | Opcode::ImmU32
+                | Opcode::ImmI32
+                | Opcode::ImmU64
+                | Opcode::ImmI64
+                | Opcode::ImmFelt
+                | Opcode::ImmF64
+                | Opcode::GlobalValue
+                | Opcode::PtrToInt
+                | Opcode::IntToPtr
+                | Opcode::Select
+                | Opcode::Assert
+                | Opcode::Assertz
+                | Opcode::Br
+                | Opcode::Ret
+                | Opcode::Unreachable => Self::new(1, 1),
+                Opcode::AssertEq | Opcode::Alloca | Opcode::CondBr => Self::new(2, 2),
+                Opcode::Not
+                | Opcode::And
+                | Opcode::Or
+                | Opcode::Xor
+                | Opcode::Bnot
+                | Opcode::Band
+                | Opcode::Bor
+                | Opcode::Bxor => Self::new(1, 1).scaled(dfg, inst),
+                Opcode::Add
+                | Opcode::Sub
+                | Opcode::Mul
+                | Opcode::Neg
+                | Opcode::Inv
+                | Opcode::Incr
+                | Opcode::Min
+                | Opcode::Max
+                | Opcode::Eq
+                | Opcode::Neq
+                | Opcode::Gt
+                | Opcode::Gte
+                | Opcode::Lt
+                | Opcode::Lte
+                | Opcode::IsOdd => Self::new(2, 2).scaled(dfg, inst),
+                Opcode::Cast | Opcode::Trunc | Opcode::Zext | Opcode::Sext | Opcode::Test => {
+                    Self::new(3, 3)
+                }
+                Opcode::Div | Opcode::Mod | Opcode::DivMod | Opcode::Shl | Opcode::Shr => {
+                    Self::new(4, 3).scaled(dfg, inst)
+                }
+                Opcode::Rotl
+                | Opcode::Rotr
+                | Opcode::Ilog2
+                | Opcode::Pow2
+                | Opcode::Exp
+                | Opcode::Popcnt
+                | Opcode::Clz
+                | Opcode::Ctz
+                | Opcode::Clo
+                | Opcode::Cto => Self::new(10, 5).scaled(dfg, inst),
+                // Memory is word-addressable, so byte addresses must be converted
+                Opcode::Load | Opcode::Store => Self::new(12, 8),
+                Opcode::MemCpy | Opcode::MemSet | Opcode::MemGrow => Self::new(40, 20),
+                Opcode::Call => dfg.call_signature(inst).map(Self::of_call).unwrap_or_default(),
+                // A syscall or context call requires a context switch
+                Opcode::Syscall | Opcode::ContextCall => {
+                    Self::new(20, 1)
+                        + dfg.call_signature(inst).map(Self::of_call).unwrap_or_default()
+                }
+                Opcode::Switch | Opcode::InlineAsm => unreachable!(),
+            },
+        };
+        Some(cost)
+    }
+
+    /// Scale this cost by the width of the operands of `inst`
+    ///
+    /// Operations on 64-bit integers are implemented using pairs of 32-bit limbs, and are
+    /// significantly more expensive than their 32-bit equivalents.
+    fn scaled(self, dfg: &DataFlowGraph, inst: Inst) -> Self {
+        let is_wide = dfg
+            .inst_args(inst)
+            .first()
+            .is_some_and(|arg| dfg.value_type(*arg).size_in_bits() > 32);
+        if is_wide {
+            Self::new(self.cycles * 4, self.size * 2)
+        } else {
+            self
+        }
+    }
+}
+impl Add for InlineCost {
+    type Output = InlineCost;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.cycles + rhs.cycles, self.size + rhs.size)
+    }
+}
+impl AddAssign for InlineCost {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_hir::{
+        pass::{AnalysisManager, RewritePass},
+        testing::TestContext,
+        AbiParam, CallConv, Immediate, InstBuilder, Instruction, Linkage, ModuleBuilder, Opcode,
+        ProgramBuilder, Signature, SourceSpan, Type,
+    };
+    use pretty_assertions::assert_eq;
+
+    use crate::InlineFunctions;
+
+    fn internal_signature() -> Signature {
+        Signature {
+            params: vec![AbiParam::new(Type::U32)],
+            results: vec![AbiParam::new(Type::U32)],
+            cc: CallConv::SystemV,
+            linkage: Linkage::Internal,
+        }
+    }
+
+    /// Run the inliner on a module with the following functions:
+    ///
+    /// ```text,ignore
+    /// fn inc(u32) -> u32 {
+    /// entry(x: u32):
+    ///    y = add.checked x, 1;
+    ///    ret y;
+    /// }
+    ///
+    /// #[noinline]
+    /// fn square(u32) -> u32 {
+    /// entry(x: u32):
+    ///    y = mul.checked x, x;
+    ///    ret y;
+    /// }
+    ///
+    /// pub fn main(u32) -> u32 {
+    /// entry(a: u32):
+    ///    b = call inc(a);
+    ///    c = call square(b);
+    ///    ret c;
+    /// }
+    /// ```
+    ///
+    /// We expect `inc` to be inlined into `main`, as it has a single call site, and removed,
+    /// as it is no longer used. However, `square` must be left alone, as it is `noinline`.
+    /// The result must still link, i.e. `main` must no longer import `inc`.
+    #[test]
+    fn inline_functions_test() {
+        let context = TestContext::default();
+        let mut builder = ModuleBuilder::new("test");
+
+        let inc = {
+            let mut fb = builder.function("inc", internal_signature()).unwrap();
+            let x = fb.block_params(fb.entry_block())[0];
+            let y = fb.ins().add_imm_checked(x, Immediate::U32(1), SourceSpan::UNKNOWN);
+            fb.ins().ret(Some(y), SourceSpan::UNKNOWN);
+            fb.build(&context.session.diagnostics).unwrap()
+        };
+
+        let square = {
+            let mut fb = builder.function("square", internal_signature()).unwrap();
+            fb.set_attribute(miden_hir::symbols::Noinline, ());
+            let x = fb.block_params(fb.entry_block())[0];
+            let y = fb.ins().mul_checked(x, x, SourceSpan::UNKNOWN);
+            fb.ins().ret(Some(y), SourceSpan::UNKNOWN);
+            fb.build(&context.session.diagnostics).unwrap()
+        };
+
+        {
+            let signature = Signature::new([AbiParam::new(Type::U32)], [AbiParam::new(Type::U32)]);
+            let mut fb = builder.function("main", signature).unwrap();
+            fb.import_function(inc.module, inc.function, internal_signature()).unwrap();
+            fb.import_function(square.module, square.function, internal_signature())
+                .unwrap();
+            let a = fb.block_params(fb.entry_block())[0];
+            let call = fb.ins().call(inc, &[a], SourceSpan::UNKNOWN);
+            let b = fb.first_result(call);
+            let call = fb.ins().call(square, &[b], SourceSpan::UNKNOWN);
+            let c = fb.first_result(call);
+            fb.ins().ret(Some(c), SourceSpan::UNKNOWN);
+            fb.build(&context.session.diagnostics).unwrap();
+        }
+
+        let mut module = builder.build();
+        let mut analyses = AnalysisManager::default();
+        let mut rewrite = InlineFunctions;
+        rewrite
+            .apply(&mut module, &mut analyses, &context.session)
+            .expect("inlining failed");
+
+        assert!(!module.contains(inc.function));
+        assert!(module.contains(square.function));
+
+        let main = module.function("main".into()).unwrap();
+        let imports = main.imports().map(|import| import.id).collect::<Vec<_>>();
+        assert_eq!(imports, vec![square]);
+
+        ProgramBuilder::new(&context.session.diagnostics)
+            .with_module(module)
+            .unwrap()
+            .link()
+            .expect("linking the inlined program failed");
+    }
+
+    /// A function which is the target of a context call, as well as of an ordinary call, has
+    /// two uses, and so is treated like any other function with multiple call sites. It must
+    /// not be removed from the module, as the context call cannot be inlined.
+    ///
+    /// ```text,ignore
+    /// fn helper(u32) -> u32 {
+    /// entry(x: u32):
+    ///    y = add.checked x, 1;
+    ///    z = mul.checked y, y;
+    ///    ret z;
+    /// }
+    ///
+    /// pub fn main(u32) -> u32 {
+    /// entry(a: u32):
+    ///    b = call helper(a);
+    ///    c = call.context helper(b);
+    ///    ret c;
+    /// }
+    /// ```
+    #[test]
+    fn inline_functions_counts_context_calls() {
+        let context = TestContext::default();
+        let mut builder = ModuleBuilder::new("test");
+
+        let helper = {
+            let mut fb = builder.function("helper", internal_signature()).unwrap();
+            let x = fb.block_params(fb.entry_block())[0];
+            let y = fb.ins().add_imm_checked(x, Immediate::U32(1), SourceSpan::UNKNOWN);
+            let z = fb.ins().mul_checked(y, y, SourceSpan::UNKNOWN);
+            fb.ins().ret(Some(z), SourceSpan::UNKNOWN);
+            fb.build(&context.session.diagnostics).unwrap()
+        };
+
+        {
+            let signature = Signature::new([AbiParam::new(Type::U32)], [AbiParam::new(Type::U32)]);
+            let mut fb = builder.function("main", signature).unwrap();
+            fb.import_function(helper.module, helper.function, internal_signature())
+                .unwrap();
+            let a = fb.block_params(fb.entry_block())[0];
+            let call = fb.ins().call(helper, &[a], SourceSpan::UNKNOWN);
+            let b = fb.first_result(call);
+            let call = fb.ins().context_call(helper, &[b], SourceSpan::UNKNOWN);
+            let c = fb.first_result(call);
+            fb.ins().ret(Some(c), SourceSpan::UNKNOWN);
+            fb.build(&context.session.diagnostics).unwrap();
+        }
+
+        let mut module = builder.build();
+        let mut analyses = AnalysisManager::default();
+        let mut rewrite = InlineFunctions;
+        rewrite
+            .apply(&mut module, &mut analyses, &context.session)
+            .expect("inlining failed");
+
+        // `helper` is too large to be inlined at more than one call site, so both calls remain
+        assert!(module.contains(helper.function));
+        let main = module.function("main".into()).unwrap();
+        let calls = main
+            .dfg
+            .blocks()
+            .flat_map(|(block, _)| main.dfg.block_insts(block))
+            .filter_map(|inst| match main.dfg.inst(inst) {
+                Instruction::Call(call) if call.callee == helper => Some(call.op),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(calls, vec![Opcode::Call, Opcode::ContextCall]);
+
+        ProgramBuilder::new(&context.session.diagnostics)
+            .with_module(module)
+            .unwrap()
+            .link()
+            .expect("linking the inlined program failed");
+    }
+}
@@ -1,8 +1,16 @@
 pub(crate) mod adt;
+mod dce;
+mod gvn;
 mod inline_blocks;
+mod inline_functions;
+mod promote_locals;
+mod sccp;
 mod split_critical_edges;
 mod treeify;
 
 pub use self::{
-    inline_blocks::InlineBlocks, split_critical_edges::SplitCriticalEdges, treeify::Treeify,
+    dce::DeadCodeElimination, gvn::GlobalValueNumbering, inline_blocks::InlineBlocks,
+    inline_functions::InlineFunctions, promote_locals::PromoteLocals,
+    sccp::SparseConditionalConstantPropagation, split_critical_edges::SplitCriticalEdges,
+    treeify::Treeify,
 };
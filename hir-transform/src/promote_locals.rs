@@ -0,0 +1,1021 @@
+use miden_hir::{
+    self as hir,
+    pass::{AnalysisManager, RewritePass, RewriteResult},
+    *,
+};
+use miden_hir_analysis::{
+    ControlFlowGraph, DominanceFrontier, DominatorTree, DominatorTreePreorder,
+};
+use midenc_session::Session;
+use rustc_hash::{FxHashMap, FxHashSet};
+use smallvec::{smallvec, SmallVec};
+
+use crate::{adt::ScopedMap, inline_blocks::rewrite_use, sccp::immediate_opcode};
+
+/// The name of the global used as the shadow stack pointer by code translated from WebAssembly
+const STACK_POINTER: &str = "__stack_pointer";
+
+/// The maximum number of scalar fields an `alloca` may be split into
+const MAX_FIELDS: usize = 32;
+
+/// This pass promotes function-local memory to SSA values, i.e. it performs both "mem2reg" and
+/// scalar replacement of aggregates.
+///
+/// Two kinds of local memory are recognized:
+///
+/// * The memory allocated by an `alloca`. Aggregates are split into their scalar fields, using
+/// the layout of the allocated type, so that each field can be promoted independently.
+/// * The shadow stack frame allocated in the prologue of a function translated from
+/// WebAssembly, i.e. by subtracting a constant from `__stack_pointer`. The frame is untyped, so
+/// each slot is identified by the offset and type of the loads and stores which access it.
+///
+/// Memory is only promoted if its address does not escape the function: every use of the
+/// address, or of a constant offset from it, must be a load or a store to that address. Each
+/// load must also agree with every store to the same offset on the type of the value stored
+/// there, and no two slots may overlap. Otherwise, the memory is left untouched.
+///
+/// Promotion is done using the classic SSA construction algorithm: block parameters are placed
+/// in the iterated dominance frontier of the blocks which store to a slot, pruned to the blocks
+/// where the slot is live on entry, and loads are then replaced with the reaching value while
+/// visiting blocks in a preorder traversal of the dominator tree. A slot which is loaded before
+/// it is ever stored to is given the "null" value of its type, i.e. zero, as per the semantics
+/// of `alloca`. Once promoted, the allocation and all of the address computations, loads, and
+/// stores which referred to it are removed, along with the stack pointer adjustments of a
+/// shadow stack frame.
+#[derive(Default, PassInfo, ModuleRewritePassAdapter)]
+pub struct PromoteLocals;
+impl RewritePass for PromoteLocals {
+    type Entity = hir::Function;
+
+    fn apply(
+        &mut self,
+        function: &mut Self::Entity,
+        analyses: &mut AnalysisManager,
+        session: &Session,
+    ) -> RewriteResult {
+        let cfg = analyses.get_or_compute::<ControlFlowGraph>(function, session)?;
+        let domtree = analyses.get_or_compute::<DominatorTree>(function, session)?;
+        let frontier = DominanceFrontier::compute(&domtree, &cfg, function);
+
+        let dfg = &function.dfg;
+        let uses = Uses::compute(dfg);
+        let mut promotion = Promotion::default();
+        for root in find_roots(dfg, &uses).into_iter() {
+            if !domtree.is_reachable(dfg.inst_block(root.def).unwrap()) {
+                continue;
+            }
+            let mut derived = SmallVec::<[Inst; 4]>::default();
+            let Some(accesses) = find_accesses(dfg, &uses, &domtree, &root, &mut derived) else {
+                continue;
+            };
+            let Some(slots) = assign_slots(&root, &accesses) else {
+                continue;
+            };
+            promotion.try_insert(dfg, &cfg, &domtree, &frontier, root, derived, accesses, slots);
+        }
+
+        if promotion.dead.is_empty() {
+            analyses.mark_all_preserved::<hir::Function>(&function.id);
+            return Ok(());
+        }
+
+        let preorder = DominatorTreePreorder::with_function(&domtree, function);
+        promotion.apply(&mut function.dfg, &preorder);
+
+        Ok(())
+    }
+}
+
+/// The set of instructions which use each value in a function
+#[derive(Default)]
+struct Uses(FxHashMap<Value, SmallVec<[Inst; 2]>>);
+impl Uses {
+    fn compute(dfg: &DataFlowGraph) -> Self {
+        let mut uses = Self::default();
+        for (block, _) in dfg.blocks() {
+            for inst in dfg.block_insts(block) {
+                let mut args = SmallVec::<[Value; 4]>::from_slice(dfg.inst_args(inst));
+                match dfg.analyze_branch(inst) {
+                    BranchInfo::SingleDest(_, dest_args) => args.extend_from_slice(dest_args),
+                    BranchInfo::MultiDest(ref jts) => {
+                        for jt in jts.iter() {
+                            args.extend_from_slice(jt.args);
+                        }
+                    }
+                    BranchInfo::NotABranch => (),
+                }
+                for arg in args.into_iter() {
+                    let users = uses.0.entry(arg).or_default();
+                    if !users.contains(&inst) {
+                        users.push(inst);
+                    }
+                }
+            }
+        }
+        uses
+    }
+
+    fn get(&self, value: Value) -> &[Inst] {
+        self.0.get(&value).map(|users| users.as_slice()).unwrap_or(&[])
+    }
+}
+
+/// A region of memory which is private to the current function
+struct Root {
+    /// The instruction which allocates the region, at which point its contents are undefined
+    def: Inst,
+    /// The address of the start of the region
+    base: Value,
+    /// The shape of the region
+    shape: Shape,
+    /// The instructions, other than address computations, loads and stores, which are used to
+    /// manage the region, and which are removed along with it.
+    bookkeeping: SmallVec<[Inst; 4]>,
+}
+
+enum Shape {
+    /// The region holds a value of known type, given as the offset and type of its scalar fields
+    Typed(SmallVec<[(u32, Type); 4]>),
+    /// The region is an untyped shadow stack frame of the given size in bytes
+    Frame(u32),
+}
+
+/// A load or store of a value of type `ty`, at `offset` bytes from the base of a [Root]
+struct Access {
+    inst: Inst,
+    offset: u32,
+    ty: Type,
+    kind: AccessKind,
+}
+
+#[derive(Copy, Clone)]
+enum AccessKind {
+    /// A load, producing the given value
+    Load(Value),
+    /// A store of the given value
+    Store(Value),
+}
+
+/// Find all of the candidates for promotion in the current function
+fn find_roots(dfg: &DataFlowGraph, uses: &Uses) -> Vec<Root> {
+    let mut roots = vec![];
+    let mut stack_pointer = StackPointerRefs::default();
+    for (block, _) in dfg.blocks() {
+        for inst in dfg.block_insts(block) {
+            match dfg.inst(inst) {
+                Instruction::PrimOp(PrimOp {
+                    op: Opcode::Alloca, ..
+                }) => {
+                    let base = dfg.first_result(inst);
+                    let ty = dfg.value_type(base).pointee().expect("expected pointer type");
+                    let mut fields = SmallVec::default();
+                    if scalar_fields(ty, 0, &mut fields) {
+                        roots.push(Root {
+                            def: inst,
+                            base,
+                            shape: Shape::Typed(fields),
+                            bookkeeping: smallvec![inst],
+                        });
+                    }
+                }
+                Instruction::GlobalValue(GlobalValueOp { global, .. }) => {
+                    stack_pointer.insert(dfg, inst, *global);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    roots.extend(find_frame(dfg, uses, &stack_pointer));
+    roots
+}
+
+/// Flatten `ty`, located at `offset`, into its scalar fields, returning false if it contains
+/// a value which cannot be promoted.
+fn scalar_fields(ty: &Type, offset: u32, fields: &mut SmallVec<[(u32, Type); 4]>) -> bool {
+    match ty {
+        Type::Struct(struct_ty) => struct_ty
+            .fields()
+            .iter()
+            .all(|field| scalar_fields(&field.ty, offset + field.offset, fields)),
+        Type::Array(element_ty, len) => {
+            let align = element_ty.min_alignment() as u32;
+            let stride = (element_ty.size_in_bytes() as u32).align_up(align);
+            (0..(*len as u32)).all(|i| scalar_fields(element_ty, offset + i * stride, fields))
+        }
+        ty if is_promotable(ty) && fields.len() < MAX_FIELDS => {
+            fields.push((offset, ty.clone()));
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if a value of type `ty` can be promoted, i.e. it is a scalar for which we can
+/// materialize a null value.
+fn is_promotable(ty: &Type) -> bool {
+    ty.is_pointer() || null_value(ty).is_some()
+}
+
+/// Get the null value for the scalar type `ty`, if it can be represented as an immediate
+fn null_value(ty: &Type) -> Option<Immediate> {
+    Some(match ty {
+        Type::I1 => Immediate::I1(false),
+        Type::U8 => Immediate::U8(0),
+        Type::I8 => Immediate::I8(0),
+        Type::U16 => Immediate::U16(0),
+        Type::I16 => Immediate::I16(0),
+        Type::U32 => Immediate::U32(0),
+        Type::I32 => Immediate::I32(0),
+        Type::U64 => Immediate::U64(0),
+        Type::I64 => Immediate::I64(0),
+        Type::Felt => Immediate::Felt(Felt::new(0)),
+        Type::F64 => Immediate::F64(0.0),
+        _ => return None,
+    })
+}
+
+/// The references to `__stack_pointer` in the current function
+#[derive(Default)]
+struct StackPointerRefs {
+    /// Instructions which read the value of the stack pointer
+    reads: SmallVec<[Inst; 1]>,
+    /// Instructions which produce the address of the stack pointer
+    addrs: SmallVec<[Inst; 2]>,
+    /// Set if the stack pointer is referenced in any other way
+    other: bool,
+}
+impl StackPointerRefs {
+    fn insert(&mut self, dfg: &DataFlowGraph, inst: Inst, global: GlobalValue) {
+        let is_stack_pointer = |gv: GlobalValue| match dfg.global_value(gv) {
+            GlobalValueData::Symbol { name, offset: 0 } => name.as_str() == STACK_POINTER,
+            _ => false,
+        };
+        let refers_to_stack_pointer = |mut gv: GlobalValue| loop {
+            match dfg.global_value(gv) {
+                GlobalValueData::Symbol { name, .. } => break name.as_str() == STACK_POINTER,
+                GlobalValueData::Load { base, .. } | GlobalValueData::IAddImm { base, .. } => {
+                    gv = *base;
+                }
+            }
+        };
+
+        match dfg.global_value(global) {
+            _ if is_stack_pointer(global) => self.addrs.push(inst),
+            GlobalValueData::Load {
+                base, offset: 0, ..
+            } if is_stack_pointer(*base) => self.reads.push(inst),
+            _ if refers_to_stack_pointer(global) => self.other = true,
+            _ => (),
+        }
+    }
+}
+
+/// Recognize the shadow stack frame allocated in the prologue of a function translated from
+/// WebAssembly, which has the following shape, where `N` is a constant:
+///
+/// ```text,ignore
+/// sp = global.load @__stack_pointer;
+/// frame = sub sp, N;
+/// store @__stack_pointer, frame;
+/// ...
+/// restore = add frame, N;
+/// store @__stack_pointer, restore;
+/// ```
+///
+/// The stack pointer may be restored using either `sp` or `restore`, but it must not be read or
+/// written anywhere else, as the frame could then be accessed by means we cannot see.
+fn find_frame(dfg: &DataFlowGraph, uses: &Uses, stack_pointer: &StackPointerRefs) -> Option<Root> {
+    let [read] = stack_pointer.reads.as_slice() else {
+        return None;
+    };
+    if stack_pointer.other {
+        return None;
+    }
+
+    let addrs = stack_pointer
+        .addrs
+        .iter()
+        .map(|addr| dfg.first_result(*addr))
+        .collect::<SmallVec<[Value; 2]>>();
+    let is_stack_pointer_store = |inst: Inst, value: Value| {
+        as_store(dfg, inst).is_some_and(|store| addrs.contains(&store.0) && store.1 == value)
+    };
+
+    let sp = dfg.first_result(*read);
+    let mut frame = None;
+    for user in uses.get(sp).iter().copied() {
+        if is_stack_pointer_store(user, sp) {
+            continue;
+        }
+        match constant_offset(dfg, user, sp) {
+            Some(offset) if offset < 0 && frame.is_none() => {
+                frame = Some((user, u32::try_from(offset.unsigned_abs()).ok()?));
+            }
+            _ => return None,
+        }
+    }
+    let (def, size) = frame?;
+    let base = dfg.first_result(def);
+
+    let mut bookkeeping = smallvec![*read, def];
+    let mut saved = SmallVec::<[Value; 4]>::from_slice(&[sp, base]);
+    for user in uses.get(base).iter().copied() {
+        if constant_offset(dfg, user, base) != Some(size as i64) {
+            continue;
+        }
+        let restore = dfg.first_result(user);
+        let restores_stack_pointer =
+            uses.get(restore).iter().all(|user| is_stack_pointer_store(*user, restore));
+        if restores_stack_pointer {
+            bookkeeping.push(user);
+            saved.push(restore);
+        }
+    }
+
+    // Every write to the stack pointer must allocate or deallocate the frame
+    for addr in stack_pointer.addrs.iter().copied() {
+        bookkeeping.push(addr);
+        let addr = dfg.first_result(addr);
+        for user in uses.get(addr).iter().copied() {
+            match as_store(dfg, user) {
+                Some((ptr, value)) if ptr == addr && saved.contains(&value) => {
+                    bookkeeping.push(user)
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    Some(Root {
+        def,
+        base,
+        shape: Shape::Frame(size),
+        bookkeeping,
+    })
+}
+
+/// If `inst` is a store, returns the address and value being stored
+fn as_store(dfg: &DataFlowGraph, inst: Inst) -> Option<(Value, Value)> {
+    match dfg.inst(inst) {
+        Instruction::PrimOp(PrimOp {
+            op: Opcode::Store,
+            args,
+        }) => match args.as_slice(&dfg.value_lists) {
+            [addr, value] => Some((*addr, *value)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// If `inst` adds a constant to, or subtracts a constant from `value`, returns the constant
+/// offset that it applies.
+fn constant_offset(dfg: &DataFlowGraph, inst: Inst, value: Value) -> Option<i64> {
+    let (op, overflow, lhs, rhs) = match dfg.inst(inst) {
+        Instruction::BinaryOpImm(BinaryOpImm {
+            op,
+            overflow,
+            arg,
+            imm,
+        }) => (*op, *overflow, *arg, imm.as_u32()?),
+        // NOTE: The arguments are in stack order
+        Instruction::BinaryOp(BinaryOp {
+            op,
+            overflow,
+            args: [rhs, lhs],
+        }) => match constant_value(dfg, *rhs) {
+            Some(rhs) => (*op, *overflow, *lhs, rhs),
+            // Addition is commutative, so the constant may be on either side
+            None if *op == Opcode::Add => (*op, *overflow, *rhs, constant_value(dfg, *lhs)?),
+            None => return None,
+        },
+        _ => return None,
+    };
+    if lhs != value || overflow == Some(Overflow::Overflowing) {
+        return None;
+    }
+    match op {
+        Opcode::Add => Some(rhs as i64),
+        Opcode::Sub => Some(-(rhs as i64)),
+        _ => None,
+    }
+}
+
+/// If `value` is produced by a constant, returns the value of that constant as a u32
+fn constant_value(dfg: &DataFlowGraph, value: Value) -> Option<u32> {
+    match dfg.value_data(value) {
+        ValueData::Inst { inst, .. } => match dfg.inst(*inst) {
+            Instruction::UnaryOpImm(UnaryOpImm { imm, .. }) => imm.as_u32(),
+            _ => None,
+        },
+        ValueData::Param { .. } => None,
+    }
+}
+
+/// Find every load and store of the memory belonging to `root`, along with the instructions
+/// used to compute their addresses, which are pushed to `derived`.
+///
+/// Returns `None` if the address of `root` escapes.
+fn find_accesses(
+    dfg: &DataFlowGraph,
+    uses: &Uses,
+    domtree: &DominatorTree,
+    root: &Root,
+    derived: &mut SmallVec<[Inst; 4]>,
+) -> Option<Vec<Access>> {
+    let mut accesses = vec![];
+    let mut worklist = vec![(root.base, 0i64)];
+    while let Some((value, offset)) = worklist.pop() {
+        for user in uses.get(value).iter().copied() {
+            if root.bookkeeping.contains(&user) {
+                continue;
+            }
+            // Uses in unreachable blocks are not renamed, so we must leave them alone
+            if !domtree.is_reachable(dfg.inst_block(user).unwrap()) {
+                return None;
+            }
+            match dfg.inst(user) {
+                Instruction::Load(LoadOp { ty, .. }) => accesses.push(Access {
+                    inst: user,
+                    offset: u32::try_from(offset).ok()?,
+                    ty: ty.clone(),
+                    kind: AccessKind::Load(dfg.first_result(user)),
+                }),
+                Instruction::PrimOp(PrimOp {
+                    op: Opcode::Store, ..
+                }) => {
+                    let (addr, stored) = as_store(dfg, user)?;
+                    if addr != value || stored == value {
+                        return None;
+                    }
+                    accesses.push(Access {
+                        inst: user,
+                        offset: u32::try_from(offset).ok()?,
+                        ty: dfg.value_type(stored).clone(),
+                        kind: AccessKind::Store(stored),
+                    });
+                }
+                Instruction::UnaryOp(UnaryOp {
+                    op: Opcode::Cast | Opcode::PtrToInt | Opcode::IntToPtr,
+                    ..
+                }) => {
+                    derived.push(user);
+                    worklist.push((dfg.first_result(user), offset));
+                }
+                _ => {
+                    let delta = constant_offset(dfg, user, value)?;
+                    derived.push(user);
+                    worklist.push((dfg.first_result(user), offset + delta));
+                }
+            }
+        }
+    }
+
+    Some(accesses)
+}
+
+/// Assign each access of `root` to a slot, identified by its offset and type.
+///
+/// Returns the offset and type of each slot, or `None` if the accesses do not agree on the
+/// layout of `root`.
+fn assign_slots(root: &Root, accesses: &[Access]) -> Option<SmallVec<[(u32, Type); 4]>> {
+    let mut slots = SmallVec::<[(u32, Type); 4]>::default();
+    for access in accesses.iter() {
+        match slots.iter().find(|(offset, _)| *offset == access.offset) {
+            Some((_, ty)) if ty != &access.ty => return None,
+            Some(_) => continue,
+            None => slots.push((access.offset, access.ty.clone())),
+        }
+    }
+    slots.sort_unstable_by_key(|(offset, _)| *offset);
+
+    match root.shape {
+        Shape::Typed(ref fields) => {
+            if !slots.iter().all(|slot| fields.contains(slot)) {
+                return None;
+            }
+        }
+        Shape::Frame(size) => {
+            let mut end = 0;
+            for (offset, ty) in slots.iter() {
+                if *offset < end || !is_promotable(ty) {
+                    return None;
+                }
+                end = offset + ty.size_in_bytes() as u32;
+            }
+            if end > size {
+                return None;
+            }
+        }
+    }
+
+    Some(slots)
+}
+
+/// A slot of local memory which is being promoted
+struct Local {
+    ty: Type,
+    /// The instruction which allocates the memory for this local
+    def: Inst,
+    /// The null value of this local, if it has been materialized
+    zero: Option<Value>,
+}
+
+/// The set of locals being promoted in a function, and the information needed to do so
+#[derive(Default)]
+struct Promotion {
+    locals: Vec<Local>,
+    /// Maps each load or store of a local, to the local and the kind of access
+    accesses: FxHashMap<Inst, (usize, AccessKind)>,
+    /// Maps each allocation to the locals it defines
+    defs: FxHashMap<Inst, SmallVec<[usize; 4]>>,
+    /// The blocks which require a parameter for each local
+    phis: Vec<SmallVec<[Block; 2]>>,
+    /// The instructions to remove once promotion is complete
+    dead: FxHashSet<Inst>,
+}
+impl Promotion {
+    /// Promote the slots of `root`, as long as it is possible to place the block parameters
+    /// they require.
+    #[allow(clippy::too_many_arguments)]
+    fn try_insert(
+        &mut self,
+        dfg: &DataFlowGraph,
+        cfg: &ControlFlowGraph,
+        domtree: &DominatorTree,
+        frontier: &DominanceFrontier,
+        root: Root,
+        derived: SmallVec<[Inst; 4]>,
+        accesses: Vec<Access>,
+        slots: SmallVec<[(u32, Type); 4]>,
+    ) {
+        let mut phis = Vec::with_capacity(slots.len());
+        for (offset, _) in slots.iter() {
+            let slot_accesses = accesses.iter().filter(|access| access.offset == *offset);
+            match place_phis(dfg, cfg, domtree, frontier, root.def, slot_accesses) {
+                Some(blocks) => phis.push(blocks),
+                None => return,
+            }
+        }
+
+        let first = self.locals.len();
+        for ((offset, ty), phis) in slots.into_iter().zip(phis) {
+            let local = self.locals.len();
+            self.locals.push(Local {
+                ty,
+                def: root.def,
+                zero: None,
+            });
+            self.phis.push(phis);
+            for access in accesses.iter().filter(|access| access.offset == offset) {
+                self.accesses.insert(access.inst, (local, access.kind));
+            }
+        }
+        self.defs.insert(root.def, (first..self.locals.len()).collect());
+        self.dead.extend(accesses.iter().map(|access| access.inst));
+        self.dead.extend(derived);
+        self.dead.extend(root.bookkeeping);
+    }
+
+    /// Rewrite the function to replace memory accesses with SSA values
+    fn apply(mut self, dfg: &mut DataFlowGraph, preorder: &DominatorTreePreorder) {
+        // Add a block parameter to each block which requires one
+        let mut params = FxHashMap::<Block, SmallVec<[(usize, Value); 2]>>::default();
+        for (local, blocks) in self.phis.iter().enumerate() {
+            for block in blocks.iter().copied() {
+                let span = dfg.inst_span(self.locals[local].def);
+                let param = dfg.append_block_param(block, self.locals[local].ty.clone(), span);
+                params.entry(block).or_default().push((local, param));
+            }
+        }
+
+        // Visit blocks in a preorder traversal of the dominator tree, tracking the current value
+        // of each local, so that loads can be replaced with the reaching value, and the value of
+        // each local at the end of a block passed as an argument to its successors.
+        let mut rewrites = ScopedMap::<Value, Value>::default();
+        let mut worklist = vec![(dfg.entry_block(), vec![None; self.locals.len()])];
+        while let Some((block, mut current)) = worklist.pop() {
+            if let Some(params) = params.get(&block) {
+                for (local, param) in params.iter().copied() {
+                    current[local] = Some(param);
+                }
+            }
+
+            let insts = dfg.block_insts(block).collect::<SmallVec<[Inst; 16]>>();
+            for inst in insts.into_iter() {
+                if let Some(locals) = self.defs.get(&inst) {
+                    for local in locals.iter().copied() {
+                        current[local] = None;
+                    }
+                }
+                match self.accesses.get(&inst).copied() {
+                    Some((local, AccessKind::Load(result))) => {
+                        let value = self.value_of(dfg, local, current[local]);
+                        rewrites.insert(result, value);
+                    }
+                    Some((local, AccessKind::Store(value))) => {
+                        let value = rewrites.get(&value).copied().unwrap_or(value);
+                        current[local] = Some(value);
+                    }
+                    None => (),
+                }
+            }
+
+            let terminator = dfg.last_inst(block).expect("expected block to be terminated");
+            let mut successors = match dfg.analyze_branch(terminator) {
+                BranchInfo::NotABranch => SmallVec::<[Block; 2]>::default(),
+                BranchInfo::SingleDest(dest, _) => smallvec![dest],
+                BranchInfo::MultiDest(ref jts) => jts.iter().map(|jt| jt.destination).collect(),
+            };
+            successors.dedup();
+            for successor in successors.into_iter() {
+                let Some(params) = params.get(&successor) else {
+                    continue;
+                };
+                for (local, _) in params.iter().copied() {
+                    let value = self.value_of(dfg, local, current[local]);
+                    append_successor_argument(dfg, terminator, successor, value);
+                }
+            }
+
+            for child in preorder.children(block) {
+                worklist.push((child, current.clone()));
+            }
+        }
+
+        // Remove the promoted memory, and rewrite any remaining uses of the loads we removed
+        let blocks = dfg.blocks().map(|(block, _)| block).collect::<SmallVec<[Block; 8]>>();
+        for block in blocks.into_iter() {
+            let mut cursor = dfg.block_mut(block).cursor_mut();
+            while let Some(node) = cursor.get() {
+                if self.dead.contains(&node.key) {
+                    cursor.remove();
+                } else {
+                    cursor.move_next();
+                }
+            }
+
+            let insts = dfg.block_insts(block).collect::<SmallVec<[Inst; 16]>>();
+            for inst in insts.into_iter() {
+                rewrite_use(&mut dfg.insts[inst], &mut dfg.value_lists, &rewrites);
+            }
+        }
+    }
+
+    /// Get the value of `local`, given its `current` value, materializing its null value if it
+    /// has not been stored to since it was allocated.
+    fn value_of(&mut self, dfg: &mut DataFlowGraph, local: usize, current: Option<Value>) -> Value {
+        if let Some(value) = current {
+            return value;
+        }
+
+        let local = &mut self.locals[local];
+        if let Some(zero) = local.zero {
+            return zero;
+        }
+
+        // The allocation dominates every access to the local, so we place the value after it
+        let span = dfg.inst_span(local.def);
+        let ip = InsertionPoint::after(ProgramPoint::Inst(local.def));
+        let value = match local.ty {
+            Type::Ptr(_) => {
+                let imm = Immediate::U32(0);
+                let addr = constant(dfg, ip, imm, span);
+                let data = Instruction::UnaryOp(UnaryOp {
+                    op: Opcode::IntToPtr,
+                    overflow: None,
+                    arg: dfg.first_result(addr),
+                });
+                let ip = InsertionPoint::after(ProgramPoint::Inst(addr));
+                dfg.insert_inst(ip, data, local.ty.clone(), span)
+            }
+            ref ty => constant(dfg, ip, null_value(ty).unwrap(), span),
+        };
+        let value = dfg.first_result(value);
+        local.zero = Some(value);
+        value
+    }
+}
+
+/// Insert a constant with value `imm` at `ip`
+fn constant(dfg: &mut DataFlowGraph, ip: InsertionPoint, imm: Immediate, span: SourceSpan) -> Inst {
+    let data = Instruction::UnaryOpImm(UnaryOpImm {
+        op: immediate_opcode(&imm).unwrap(),
+        overflow: None,
+        imm,
+    });
+    dfg.insert_inst(ip, data, imm.ty(), span)
+}
+
+/// Compute the blocks which require a parameter to carry the value of a local, given the
+/// instruction which allocates it, and all of the accesses to it.
+///
+/// Returns `None` if one of those blocks has a predecessor which cannot pass it an argument.
+fn place_phis<'a>(
+    dfg: &DataFlowGraph,
+    cfg: &ControlFlowGraph,
+    domtree: &DominatorTree,
+    frontier: &DominanceFrontier,
+    def: Inst,
+    accesses: impl Iterator<Item = &'a Access>,
+) -> Option<SmallVec<[Block; 2]>> {
+    // Maps each instruction which reads or writes the local, to true if it is a read
+    let mut is_load = FxHashMap::<Inst, bool>::default();
+    let mut defs = FxHashSet::<Block>::default();
+    let mut loads = FxHashSet::<Block>::default();
+    is_load.insert(def, false);
+    defs.insert(dfg.inst_block(def).unwrap());
+    for access in accesses {
+        let block = dfg.inst_block(access.inst).unwrap();
+        match access.kind {
+            AccessKind::Load(_) => {
+                is_load.insert(access.inst, true);
+                loads.insert(block);
+            }
+            AccessKind::Store(_) => {
+                is_load.insert(access.inst, false);
+                defs.insert(block);
+            }
+        }
+    }
+
+    // The local is live on entry to any block where it is loaded before it is stored, and to
+    // every block from which such a block is reachable without an intervening store.
+    let mut worklist = loads
+        .into_iter()
+        .filter(|block| {
+            dfg.block_insts(*block).find_map(|inst| is_load.get(&inst).copied()) == Some(true)
+        })
+        .collect::<Vec<_>>();
+    let mut live_in = FxHashSet::<Block>::default();
+    while let Some(block) = worklist.pop() {
+        if !live_in.insert(block) {
+            continue;
+        }
+        for pred in cfg.pred_iter(block) {
+            if !defs.contains(&pred.block) {
+                worklist.push(pred.block);
+            }
+        }
+    }
+
+    // Place a parameter in the iterated dominance frontier of the blocks which define the local,
+    // wherever the local is live.
+    let mut phis = SmallVec::<[Block; 2]>::default();
+    let mut visited = defs.clone();
+    let mut worklist = defs.into_iter().collect::<Vec<_>>();
+    while let Some(block) = worklist.pop() {
+        for succ in frontier.iter(&block) {
+            if !live_in.contains(&succ) || phis.contains(&succ) {
+                continue;
+            }
+            let can_pass_arguments = cfg.pred_iter(succ).all(|pred| {
+                domtree.is_reachable(pred.block)
+                    && !matches!(dfg.inst(pred.inst), Instruction::Switch(_))
+            });
+            if !can_pass_arguments {
+                return None;
+            }
+            phis.push(succ);
+            if visited.insert(succ) {
+                worklist.push(succ);
+            }
+        }
+    }
+
+    Some(phis)
+}
+
+/// Append `value` to the arguments passed by `branch` on every edge to `dest`
+fn append_successor_argument(dfg: &mut DataFlowGraph, branch: Inst, dest: Block, value: Value) {
+    match *dfg.insts[branch] {
+        Instruction::Br(Br {
+            destination,
+            ref mut args,
+            ..
+        }) => {
+            if destination == dest {
+                args.push(value, &mut dfg.value_lists);
+            }
+        }
+        Instruction::CondBr(CondBr {
+            then_dest: (then_dest, ref mut then_args),
+            else_dest: (else_dest, ref mut else_args),
+            ..
+        }) => {
+            if then_dest == dest {
+                then_args.push(value, &mut dfg.value_lists);
+            }
+            if else_dest == dest {
+                else_args.push(value, &mut dfg.value_lists);
+            }
+        }
+        _ => panic!("{branch} cannot pass arguments to {dest}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_hir::{
+        pass::{AnalysisManager, RewritePass},
+        testing::TestContext,
+        AbiParam, Function, FunctionBuilder, Immediate, InstBuilder, Signature, SourceSpan,
+        StructType, Type,
+    };
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    use crate::PromoteLocals;
+
+    /// Run local promotion on the following IR:
+    ///
+    /// ```text,ignore
+    /// pub fn test(u32, u32) -> u32 {
+    /// entry(a: u32, b: u32):
+    ///    pair = alloca { u32, u32 } : *mut { u32, u32 };
+    ///    base = ptrtoint pair : u32;
+    ///    second_addr = add.checked base, 4;
+    ///    second = inttoptr second_addr : *mut u32;
+    ///    first = inttoptr base : *mut u32;
+    ///    store first, a;
+    ///    is_lt = lt a, b;
+    ///    condbr is_lt, blk0, blk1;
+    ///
+    /// blk0:
+    ///    store second, b;
+    ///    br blk2;
+    ///
+    /// blk1:
+    ///    br blk2;
+    ///
+    /// blk2:
+    ///    x = load first;
+    ///    y = load second;
+    ///    sum = add.checked x, y;
+    ///    ret sum;
+    /// }
+    /// ```
+    ///
+    /// We expect `pair` to be split into its two fields, and both of them promoted. The first
+    /// field is only stored to in the entry block, so `x` is simply replaced with `a`. The second
+    /// field is only stored to along one path to `blk2`, so `blk2` gets a new parameter, for
+    /// which `blk0` passes `b`, and `blk1` passes the null value of the field, i.e. zero.
+    #[test]
+    fn promote_locals_alloca_test() {
+        let context = TestContext::default();
+        let id = "test::sroa".parse().unwrap();
+        let mut function = Function::new(
+            id,
+            Signature::new(
+                [AbiParam::new(Type::U32), AbiParam::new(Type::U32)],
+                [AbiParam::new(Type::U32)],
+            ),
+        );
+
+        {
+            let mut builder = FunctionBuilder::new(&mut function);
+            let entry = builder.current_block();
+            let (a, b) = {
+                let args = builder.block_params(entry);
+                (args[0], args[1])
+            };
+
+            let blk0 = builder.create_block();
+            let blk1 = builder.create_block();
+            let blk2 = builder.create_block();
+            let pair_ty = Type::Struct(StructType::new([Type::U32, Type::U32]));
+            let field_ty = Type::Ptr(Box::new(Type::U32));
+
+            // entry
+            let pair = builder.ins().alloca(pair_ty, SourceSpan::UNKNOWN);
+            let base = builder.ins().ptrtoint(pair, Type::U32, SourceSpan::UNKNOWN);
+            let second_addr =
+                builder.ins().add_imm_checked(base, Immediate::U32(4), SourceSpan::UNKNOWN);
+            let second = builder.ins().inttoptr(second_addr, field_ty.clone(), SourceSpan::UNKNOWN);
+            let first = builder.ins().inttoptr(base, field_ty, SourceSpan::UNKNOWN);
+            builder.ins().store(first, a, SourceSpan::UNKNOWN);
+            let is_lt = builder.ins().lt(a, b, SourceSpan::UNKNOWN);
+            builder.ins().cond_br(is_lt, blk0, &[], blk1, &[], SourceSpan::UNKNOWN);
+
+            // blk0
+            builder.switch_to_block(blk0);
+            builder.ins().store(second, b, SourceSpan::UNKNOWN);
+            builder.ins().br(blk2, &[], SourceSpan::UNKNOWN);
+
+            // blk1
+            builder.switch_to_block(blk1);
+            builder.ins().br(blk2, &[], SourceSpan::UNKNOWN);
+
+            // blk2
+            builder.switch_to_block(blk2);
+            let x = builder.ins().load(first, SourceSpan::UNKNOWN);
+            let y = builder.ins().load(second, SourceSpan::UNKNOWN);
+            let sum = builder.ins().add_checked(x, y, SourceSpan::UNKNOWN);
+            builder.ins().ret(Some(sum), SourceSpan::UNKNOWN);
+        }
+
+        let original = function.to_string();
+        let mut analyses = AnalysisManager::default();
+        let mut rewrite = PromoteLocals;
+        rewrite
+            .apply(&mut function, &mut analyses, &context.session)
+            .expect("local promotion failed");
+
+        let expected = "\
+(func (export #sroa) (param u32) (param u32) (result u32)
+    (block 0 (param v0 u32) (param v1 u32)
+        (let (v12 u32) (const.u32 0))
+        (let (v7 i1) (lt v0 v1))
+        (condbr v7 (block 1) (block 2)))
+
+    (block 1
+        (br (block 3 v1)))
+
+    (block 2
+        (br (block 3 v12)))
+
+    (block 3 (param v11 u32)
+        (let (v10 u32) (add.checked v0 v11))
+        (ret v10))
+)";
+
+        let transformed = function.to_string();
+        assert_ne!(transformed, original);
+        assert_eq!(transformed.as_str(), expected);
+    }
+
+    /// Run local promotion on the following IR, which is representative of the code produced
+    /// by the Wasm frontend for a function with a shadow stack frame:
+    ///
+    /// ```text,ignore
+    /// pub fn test(i32) -> i32 {
+    /// entry(a: i32):
+    ///    sp = global.load @__stack_pointer : i32;
+    ///    size = const.i32 16;
+    ///    frame = sub.wrapping sp, size;
+    ///    store @__stack_pointer, frame;
+    ///    base = cast frame : u32;
+    ///    slot_addr = add.checked base, 12;
+    ///    slot = inttoptr slot_addr : *mut i32;
+    ///    store slot, a;
+    ///    x = load slot;
+    ///    restore = add.wrapping frame, size;
+    ///    store @__stack_pointer, restore;
+    ///    ret x;
+    /// }
+    /// ```
+    ///
+    /// We expect `x` to be replaced with `a`, and the frame to be removed entirely, as nothing
+    /// else refers to it.
+    #[test]
+    fn promote_locals_shadow_stack_test() {
+        let context = TestContext::default();
+        let id = "test::shadow_stack".parse().unwrap();
+        let mut function = Function::new(
+            id,
+            Signature::new([AbiParam::new(Type::I32)], [AbiParam::new(Type::I32)]),
+        );
+
+        {
+            let mut builder = FunctionBuilder::new(&mut function);
+            let entry = builder.current_block();
+            let a = builder.block_params(entry)[0];
+            let sp_name = "__stack_pointer";
+            let ptr_ty = Type::Ptr(Box::new(Type::I32));
+
+            let sp = builder.ins().load_symbol(sp_name, Type::I32, SourceSpan::UNKNOWN);
+            let size = builder.ins().i32(16, SourceSpan::UNKNOWN);
+            let frame = builder.ins().sub_wrapping(sp, size, SourceSpan::UNKNOWN);
+            let sp_addr = builder.ins().symbol_addr(sp_name, ptr_ty.clone(), SourceSpan::UNKNOWN);
+            builder.ins().store(sp_addr, frame, SourceSpan::UNKNOWN);
+            let base = builder.ins().cast(frame, Type::U32, SourceSpan::UNKNOWN);
+            let slot_addr =
+                builder.ins().add_imm_checked(base, Immediate::U32(12), SourceSpan::UNKNOWN);
+            let slot = builder.ins().inttoptr(slot_addr, ptr_ty.clone(), SourceSpan::UNKNOWN);
+            builder.ins().store(slot, a, SourceSpan::UNKNOWN);
+            let x = builder.ins().load(slot, SourceSpan::UNKNOWN);
+            let restore = builder.ins().add_wrapping(frame, size, SourceSpan::UNKNOWN);
+            let sp_addr = builder.ins().symbol_addr(sp_name, ptr_ty, SourceSpan::UNKNOWN);
+            builder.ins().store(sp_addr, restore, SourceSpan::UNKNOWN);
+            builder.ins().ret(Some(x), SourceSpan::UNKNOWN);
+        }
+
+        let original = function.to_string();
+        let mut analyses = AnalysisManager::default();
+        let mut rewrite = PromoteLocals;
+        rewrite
+            .apply(&mut function, &mut analyses, &context.session)
+            .expect("local promotion failed");
+
+        let expected = "\
+(func (export #shadow_stack) (param i32) (result i32)
+    (block 0 (param v0 i32)
+        (let (v2 i32) (const.i32 16))
+        (ret v0))
+)";
+
+        let transformed = function.to_string();
+        assert_ne!(transformed, original);
+        assert_eq!(transformed.as_str(), expected);
+    }
+}
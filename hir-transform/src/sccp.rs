@@ -0,0 +1,928 @@
+use std::collections::VecDeque;
+
+use miden_hir::{
+    self as hir,
+    pass::{AnalysisManager, RewritePass, RewriteResult},
+    *,
+};
+use midenc_session::Session;
+use rustc_hash::{FxHashMap, FxHashSet};
+use smallvec::{smallvec, SmallVec};
+
+/// This pass performs sparse conditional constant propagation (SCCP) on a function.
+///
+/// The analysis optimistically assumes that all values are undefined, and that all blocks other
+/// than the entry are unreachable, and then iterates to a fixpoint, visiting only those blocks
+/// which have been shown to be reachable from the entry, and evaluating instructions whose
+/// operands are all known constants. Conditional branches on a known condition are treated as
+/// unconditional, so that constants which flow only along feasible edges are not pessimized by
+/// those which could never be taken.
+///
+/// Once the fixpoint is reached, the following rewrites are performed:
+///
+/// * Instructions without side effects whose results are known constants are replaced with
+/// the equivalent immediate, and block parameters which are known to be constant have their
+/// uses rewritten to refer to a materialized immediate.
+/// * Conditional branches and switches on a known condition are replaced with an unconditional
+/// branch to the taken successor.
+/// * Blocks which were never shown to be reachable are removed from the function.
+///
+/// Folding respects the [Overflow] mode of each instruction, as well as the semantics of its
+/// type. For example, a checked operation whose result would overflow is never folded, as it
+/// must trap at runtime, whereas a wrapping operation is folded to the wrapped value. Operations
+/// which cannot be precisely evaluated at compile-time, such as those on 128-bit integers or
+/// floating-point values, are left as-is.
+///
+/// NOTE: This pass does not remove the instructions or block parameters which are left without
+/// uses once the constants are propagated, that is left to dead code elimination.
+#[derive(Default, PassInfo, ModuleRewritePassAdapter)]
+pub struct SparseConditionalConstantPropagation;
+impl RewritePass for SparseConditionalConstantPropagation {
+    type Entity = hir::Function;
+
+    fn apply(
+        &mut self,
+        function: &mut Self::Entity,
+        analyses: &mut AnalysisManager,
+        _session: &Session,
+    ) -> RewriteResult {
+        let mut solver = Solver::new(&function.dfg);
+        solver.solve(&function.dfg);
+
+        let changed = rewrite(function, &solver);
+        if !changed {
+            analyses.mark_all_preserved::<hir::Function>(&function.id);
+        }
+
+        Ok(())
+    }
+}
+
+/// The abstract value of an SSA value during constant propagation
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum LatticeValue {
+    /// No information is known about the value yet, i.e. its definition hasn't been reached
+    Undefined,
+    /// The value is known to always be the given constant
+    Constant(Immediate),
+    /// The value may take on more than one value at runtime
+    Overdefined,
+}
+impl LatticeValue {
+    fn meet(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Undefined, value) | (value, Self::Undefined) => value,
+            (Self::Constant(a), Self::Constant(b)) if a == b => self,
+            _ => Self::Overdefined,
+        }
+    }
+}
+
+/// The sparse dataflow solver which computes the reachable blocks of a function, and the
+/// abstract value of every SSA value defined in those blocks.
+struct Solver {
+    values: FxHashMap<Value, LatticeValue>,
+    users: FxHashMap<Value, SmallVec<[Inst; 2]>>,
+    executable: FxHashSet<Block>,
+    block_worklist: VecDeque<Block>,
+    value_worklist: VecDeque<Value>,
+}
+impl Solver {
+    fn new(dfg: &DataFlowGraph) -> Self {
+        // Compute the users of every value in the function, so that we can revisit them
+        // when the abstract value of one of their operands changes
+        let mut users = FxHashMap::<Value, SmallVec<[Inst; 2]>>::default();
+        for (block, _) in dfg.blocks() {
+            for inst in dfg.block_insts(block) {
+                for operand in inst_operands(dfg, inst) {
+                    users.entry(operand).or_default().push(inst);
+                }
+            }
+        }
+
+        Self {
+            values: Default::default(),
+            users,
+            executable: Default::default(),
+            block_worklist: Default::default(),
+            value_worklist: Default::default(),
+        }
+    }
+
+    /// Iterate to a fixpoint, starting from the entry block of the function
+    fn solve(&mut self, dfg: &DataFlowGraph) {
+        // The function parameters are not known
+        let entry = dfg.entry_block();
+        for param in dfg.block_params(entry).iter().copied() {
+            self.values.insert(param, LatticeValue::Overdefined);
+        }
+        self.mark_executable(entry);
+
+        loop {
+            if let Some(block) = self.block_worklist.pop_front() {
+                for inst in dfg.block_insts(block) {
+                    self.visit(dfg, inst);
+                }
+                continue;
+            }
+
+            if let Some(value) = self.value_worklist.pop_front() {
+                let users = self.users.get(&value).cloned().unwrap_or_default();
+                for inst in users.into_iter() {
+                    // Instructions in unreachable blocks are visited once they become reachable
+                    let is_executable = dfg
+                        .inst_block(inst)
+                        .map(|block| self.executable.contains(&block))
+                        .unwrap_or(false);
+                    if is_executable {
+                        self.visit(dfg, inst);
+                    }
+                }
+                continue;
+            }
+
+            break;
+        }
+    }
+
+    #[inline]
+    fn get(&self, value: Value) -> LatticeValue {
+        self.values.get(&value).copied().unwrap_or(LatticeValue::Undefined)
+    }
+
+    #[inline]
+    fn is_executable(&self, block: Block) -> bool {
+        self.executable.contains(&block)
+    }
+
+    fn users(&self, value: Value) -> &[Inst] {
+        self.users.get(&value).map(|users| users.as_slice()).unwrap_or(&[])
+    }
+
+    fn mark_executable(&mut self, block: Block) {
+        if self.executable.insert(block) {
+            self.block_worklist.push_back(block);
+        }
+    }
+
+    /// Lower the abstract value of `value` by `new`, revisiting its users if it changed
+    fn update(&mut self, value: Value, new: LatticeValue) {
+        let old = self.get(value);
+        let merged = old.meet(new);
+        if merged != old {
+            self.values.insert(value, merged);
+            self.value_worklist.push_back(value);
+        }
+    }
+
+    fn visit(&mut self, dfg: &DataFlowGraph, inst: Inst) {
+        if dfg[inst].opcode().is_branch() {
+            return self.visit_branch(dfg, inst);
+        }
+
+        let results = dfg.inst_results(inst);
+        if results.is_empty() {
+            return;
+        }
+
+        let Some(evaluated) = self.evaluate(dfg, inst) else {
+            return;
+        };
+
+        // Guard against folding to a constant which does not match the type of the result
+        let is_valid = evaluated.len() == results.len()
+            && evaluated.iter().zip(results.iter()).all(|(value, result)| match value {
+                LatticeValue::Constant(imm) => &imm.ty() == dfg.value_type(*result),
+                _ => true,
+            });
+        for (i, result) in results.iter().copied().enumerate() {
+            let value = if is_valid {
+                evaluated[i]
+            } else {
+                LatticeValue::Overdefined
+            };
+            self.update(result, value);
+        }
+    }
+
+    /// Mark the feasible successors of a branch instruction as executable, and propagate the
+    /// values of the successor arguments to the parameters of the successor blocks
+    fn visit_branch(&mut self, dfg: &DataFlowGraph, inst: Inst) {
+        let feasible = match self.feasible_successor(dfg, inst) {
+            Feasible::None => return,
+            feasible => feasible,
+        };
+        let successors = match dfg.analyze_branch(inst) {
+            BranchInfo::SingleDest(destination, args) => {
+                smallvec![JumpTable::new(destination, args)]
+            }
+            BranchInfo::MultiDest(jts) => SmallVec::<[JumpTable; 2]>::from_vec(jts),
+            BranchInfo::NotABranch => unreachable!(),
+        };
+        for (index, successor) in successors.into_iter().enumerate() {
+            if let Feasible::One(taken) = feasible {
+                if taken != index {
+                    continue;
+                }
+            }
+            self.mark_executable(successor.destination);
+            let params = dfg.block_params(successor.destination);
+            for (param, arg) in params.iter().copied().zip(successor.args.iter().copied()) {
+                let value = self.get(arg);
+                self.update(param, value);
+            }
+        }
+    }
+
+    /// Determine which successors of the branch instruction `inst` may be taken, given what is
+    /// currently known about its condition.
+    ///
+    /// Successors are identified by their index in the jump tables of the branch.
+    fn feasible_successor(&self, dfg: &DataFlowGraph, inst: Inst) -> Feasible {
+        match &dfg[inst] {
+            Instruction::Br(_) => Feasible::One(0),
+            Instruction::CondBr(CondBr { cond, .. }) => match self.get(*cond) {
+                LatticeValue::Undefined => Feasible::None,
+                LatticeValue::Constant(imm) => match imm.as_bool() {
+                    Some(true) => Feasible::One(0),
+                    Some(false) => Feasible::One(1),
+                    None => Feasible::All,
+                },
+                LatticeValue::Overdefined => Feasible::All,
+            },
+            Instruction::Switch(Switch { arg, arms, .. }) => match self.get(*arg) {
+                LatticeValue::Undefined => Feasible::None,
+                LatticeValue::Constant(imm) => match imm.as_u32() {
+                    Some(value) => Feasible::One(
+                        arms.iter().position(|(arm, _)| *arm == value).unwrap_or(arms.len()),
+                    ),
+                    None => Feasible::All,
+                },
+                LatticeValue::Overdefined => Feasible::All,
+            },
+            _ => Feasible::None,
+        }
+    }
+
+    /// Evaluate the results of `inst`, given what is currently known about its operands.
+    ///
+    /// Returns `None` if there isn't enough information to evaluate the instruction yet.
+    fn evaluate(&self, dfg: &DataFlowGraph, inst: Inst) -> Option<SmallVec<[LatticeValue; 2]>> {
+        let num_results = dfg.inst_results(inst).len();
+        let overdefined = smallvec![LatticeValue::Overdefined; num_results];
+        let ix = &dfg[inst];
+        if ix.has_side_effects() {
+            return Some(overdefined);
+        }
+
+        let result_ty = dfg.value_type(dfg.first_result(inst));
+        let folded = match ix {
+            Instruction::UnaryOpImm(UnaryOpImm { op, imm, .. }) if is_immediate(*op) => {
+                Some(smallvec![*imm])
+            }
+            Instruction::UnaryOpImm(UnaryOpImm { op, overflow, imm }) => {
+                fold_unary(*op, *overflow, &imm.ty(), result_ty, *imm)
+            }
+            Instruction::UnaryOp(UnaryOp { op, overflow, arg }) => {
+                let arg_ty = dfg.value_type(*arg);
+                match self.constant_operands(&[*arg]) {
+                    Ok(operands) => fold_unary(*op, *overflow, arg_ty, result_ty, operands[0]),
+                    Err(value) => return lattice_results(value, num_results),
+                }
+            }
+            Instruction::BinaryOp(BinaryOp {
+                op,
+                overflow,
+                args: [rhs, lhs],
+            }) => {
+                let ty = dfg.value_type(*lhs);
+                match self.constant_operands(&[*lhs, *rhs]) {
+                    Ok(operands) => fold_binary(*op, *overflow, ty, operands[0], operands[1]),
+                    Err(value) => return lattice_results(value, num_results),
+                }
+            }
+            Instruction::BinaryOpImm(BinaryOpImm {
+                op,
+                overflow,
+                arg,
+                imm,
+            }) => {
+                let ty = dfg.value_type(*arg);
+                match self.constant_operands(&[*arg]) {
+                    Ok(operands) => fold_binary(*op, *overflow, ty, operands[0], *imm),
+                    Err(value) => return lattice_results(value, num_results),
+                }
+            }
+            Instruction::Test(Test { arg, ty, .. }) => match self.constant_operands(&[*arg]) {
+                Ok(operands) => fold_test(ty, operands[0]),
+                Err(value) => return lattice_results(value, num_results),
+            },
+            Instruction::PrimOp(PrimOp {
+                op: Opcode::Select,
+                args,
+            }) => {
+                // A select on a known condition takes on the value of the selected operand
+                let args = args.as_slice(&dfg.value_lists);
+                let (cond, a, b) = (args[0], args[1], args[2]);
+                let value = match self.get(cond) {
+                    LatticeValue::Undefined => return None,
+                    LatticeValue::Constant(imm) => match imm.as_bool() {
+                        Some(true) => self.get(a),
+                        Some(false) => self.get(b),
+                        None => LatticeValue::Overdefined,
+                    },
+                    LatticeValue::Overdefined => self.get(a).meet(self.get(b)),
+                };
+                return lattice_results(value, num_results);
+            }
+            _ => None,
+        };
+
+        match folded {
+            Some(imms) => Some(imms.into_iter().map(LatticeValue::Constant).collect()),
+            None => Some(overdefined),
+        }
+    }
+
+    /// Get the constant values of `operands`, if they are all constant.
+    ///
+    /// Otherwise, returns the abstract value that the results of the instruction they belong to
+    /// must take on, i.e. undefined if any operand is undefined, or overdefined.
+    fn constant_operands(
+        &self,
+        operands: &[Value],
+    ) -> Result<SmallVec<[Immediate; 2]>, LatticeValue> {
+        let mut constants = SmallVec::<[Immediate; 2]>::default();
+        let mut result = Ok(());
+        for operand in operands.iter().copied() {
+            match self.get(operand) {
+                LatticeValue::Undefined => return Err(LatticeValue::Undefined),
+                LatticeValue::Constant(imm) => constants.push(imm),
+                LatticeValue::Overdefined => result = Err(LatticeValue::Overdefined),
+            }
+        }
+        result.map(|_| constants)
+    }
+}
+
+/// The successors of a branch which are feasible, see [Solver::feasible_successor]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Feasible {
+    None,
+    One(usize),
+    All,
+}
+
+fn lattice_results(value: LatticeValue, num_results: usize) -> Option<SmallVec<[LatticeValue; 2]>> {
+    match value {
+        LatticeValue::Undefined => None,
+        value => Some(smallvec![value; num_results]),
+    }
+}
+
+/// Get all of the values used by `inst`, including successor arguments
+fn inst_operands(dfg: &DataFlowGraph, inst: Inst) -> SmallVec<[Value; 4]> {
+    let mut operands = SmallVec::<[Value; 4]>::from_slice(dfg.inst_args(inst));
+    match dfg.analyze_branch(inst) {
+        BranchInfo::NotABranch => (),
+        BranchInfo::SingleDest(_, args) => operands.extend_from_slice(args),
+        BranchInfo::MultiDest(jts) => {
+            for jt in jts.into_iter() {
+                operands.extend_from_slice(jt.args);
+            }
+        }
+    }
+    operands
+}
+
+/// Rewrite `function` using the results of the analysis, returning true if it was modified
+fn rewrite(function: &mut hir::Function, solver: &Solver) -> bool {
+    let mut changed = false;
+    let blocks = function.dfg.blocks().map(|(block, _)| block).collect::<Vec<_>>();
+    for block in blocks.into_iter() {
+        if !solver.is_executable(block) {
+            function.dfg.detach_block(block);
+            changed = true;
+            continue;
+        }
+
+        // Materialize constant block parameters at the start of the block
+        let params = SmallVec::<[Value; 4]>::from_slice(function.dfg.block_params(block));
+        let ip = InsertionPoint::before(ProgramPoint::Block(block));
+        for param in params.into_iter() {
+            if let LatticeValue::Constant(imm) = solver.get(param) {
+                let span = function.dfg.value_data(param).span();
+                changed |= materialize(function, solver, param, imm, ip, span);
+            }
+        }
+
+        let insts = function.dfg.block_insts(block).collect::<SmallVec<[Inst; 8]>>();
+        for inst in insts.into_iter() {
+            let span = function.dfg.inst_span(inst);
+            let ix = &function.dfg[inst];
+            let is_conditional_branch =
+                matches!(ix, Instruction::CondBr(_) | Instruction::Switch(_));
+            let is_constant =
+                matches!(ix, Instruction::UnaryOpImm(UnaryOpImm { op, .. }) if is_immediate(*op));
+            let has_side_effects = ix.has_side_effects();
+
+            // Replace conditional branches on a known condition with an unconditional branch
+            if is_conditional_branch {
+                if let Feasible::One(index) = solver.feasible_successor(&function.dfg, inst) {
+                    let (destination, args) = match function.dfg.analyze_branch(inst) {
+                        BranchInfo::MultiDest(jts) => (
+                            jts[index].destination,
+                            SmallVec::<[Value; 4]>::from_slice(jts[index].args),
+                        ),
+                        _ => unreachable!(),
+                    };
+                    let args = ValueList::from_slice(&args, &mut function.dfg.value_lists);
+                    function.dfg.replace(inst).Br(Opcode::Br, Type::Unit, destination, args, span);
+                    changed = true;
+                }
+                continue;
+            }
+
+            if is_constant || has_side_effects {
+                continue;
+            }
+
+            let results = SmallVec::<[Value; 2]>::from_slice(function.dfg.inst_results(inst));
+            match results.as_slice() {
+                [] => (),
+                // When there is a single result, we can replace the instruction in-place
+                [result] => {
+                    if let LatticeValue::Constant(imm) = solver.get(*result) {
+                        if let Some(op) = immediate_opcode(&imm) {
+                            function.dfg.replace(inst).UnaryImm(op, imm.ty(), imm, span);
+                            changed = true;
+                        }
+                    }
+                }
+                // Otherwise, we materialize the constant results just before the
+                // instruction, and rewrite their uses
+                results => {
+                    let ip = InsertionPoint::before(ProgramPoint::Inst(inst));
+                    for result in results.iter().copied() {
+                        if let LatticeValue::Constant(imm) = solver.get(result) {
+                            changed |= materialize(function, solver, result, imm, ip, span);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    changed
+}
+
+/// Materialize `imm` at `ip`, and replace all uses of `value` with it.
+///
+/// Returns false if `value` has no uses, in which case there is nothing to do.
+fn materialize(
+    function: &mut hir::Function,
+    solver: &Solver,
+    value: Value,
+    imm: Immediate,
+    ip: InsertionPoint,
+    span: SourceSpan,
+) -> bool {
+    let users = solver.users(value);
+    let Some(op) = immediate_opcode(&imm).filter(|_| !users.is_empty()) else {
+        return false;
+    };
+    let inst = function.dfg.insert_inst(
+        ip,
+        Instruction::UnaryOpImm(UnaryOpImm {
+            op,
+            overflow: None,
+            imm,
+        }),
+        imm.ty(),
+        span,
+    );
+    let replacement = function.dfg.first_result(inst);
+    for user in users.iter().copied() {
+        function.dfg.replace_uses(user, value, replacement);
+    }
+    true
+}
+
+fn is_immediate(op: Opcode) -> bool {
+    matches!(
+        op,
+        Opcode::ImmI1
+            | Opcode::ImmU8
+            | Opcode::ImmI8
+            | Opcode::ImmU16
+            | Opcode::ImmI16
+            | Opcode::ImmU32
+            | Opcode::ImmI32
+            | Opcode::ImmU64
+            | Opcode::ImmI64
+            | Opcode::ImmFelt
+            | Opcode::ImmF64
+    )
+}
+
+/// Get the opcode used to materialize `imm`, if it can be materialized
+pub(crate) fn immediate_opcode(imm: &Immediate) -> Option<Opcode> {
+    Some(match imm {
+        Immediate::I1(_) => Opcode::ImmI1,
+        Immediate::U8(_) => Opcode::ImmU8,
+        Immediate::I8(_) => Opcode::ImmI8,
+        Immediate::U16(_) => Opcode::ImmU16,
+        Immediate::I16(_) => Opcode::ImmI16,
+        Immediate::U32(_) => Opcode::ImmU32,
+        Immediate::I32(_) => Opcode::ImmI32,
+        Immediate::U64(_) => Opcode::ImmU64,
+        Immediate::I64(_) => Opcode::ImmI64,
+        Immediate::Felt(_) => Opcode::ImmFelt,
+        Immediate::F64(_) => Opcode::ImmF64,
+        Immediate::I128(_) => return None,
+    })
+}
+
+/// Evaluate the binary operator `op` with operands of type `ty`
+fn fold_binary(
+    op: Opcode,
+    overflow: Option<Overflow>,
+    ty: &Type,
+    lhs: Immediate,
+    rhs: Immediate,
+) -> Option<SmallVec<[Immediate; 2]>> {
+    if ty == &Type::Felt {
+        return fold_felt_binary(op, lhs.as_felt()?, rhs.as_felt()?).map(|imm| smallvec![imm]);
+    }
+
+    let bits = int_bits(ty)?;
+    let l = lhs.as_i128()?;
+    let r = rhs.as_i128()?;
+    // Unchecked operations on signed integers are performed on the field element
+    // representation of their operands, so we cannot predict the result of those
+    // operations once negative values are involved
+    if overflow == Some(Overflow::Unchecked) && (l < 0 || r < 0) {
+        return None;
+    }
+    let value = match op {
+        Opcode::Add => l + r,
+        Opcode::Sub => l - r,
+        Opcode::Mul => l.checked_mul(r)?,
+        Opcode::Div if r != 0 => l / r,
+        Opcode::Mod if r != 0 => l % r,
+        Opcode::Min => l.min(r),
+        Opcode::Max => l.max(r),
+        Opcode::Exp => l.checked_pow(u32::try_from(r).ok()?)?,
+        Opcode::And | Opcode::Band => l & r,
+        Opcode::Or | Opcode::Bor => l | r,
+        Opcode::Xor | Opcode::Bxor => l ^ r,
+        Opcode::Shl if (0..bits as i128).contains(&r) => l.checked_mul(1 << r)?,
+        Opcode::Shr if (0..bits as i128).contains(&r) => l >> r,
+        Opcode::Rotl | Opcode::Rotr if r >= 0 => {
+            let x = to_bits(l, bits);
+            let shift = (r % bits as i128) as u32;
+            let rotated = if op == Opcode::Rotl {
+                (x << shift) | (x >> (bits - shift))
+            } else {
+                (x >> shift) | (x << (bits - shift))
+            };
+            from_bits(rotated & mask(bits), ty)?
+        }
+        Opcode::Eq => return Some(smallvec![Immediate::I1(l == r)]),
+        Opcode::Neq => return Some(smallvec![Immediate::I1(l != r)]),
+        Opcode::Gt => return Some(smallvec![Immediate::I1(l > r)]),
+        Opcode::Gte => return Some(smallvec![Immediate::I1(l >= r)]),
+        Opcode::Lt => return Some(smallvec![Immediate::I1(l < r)]),
+        Opcode::Lte => return Some(smallvec![Immediate::I1(l <= r)]),
+        _ => return None,
+    };
+
+    with_overflow(value, ty, overflow)
+}
+
+/// Evaluate the binary operator `op` on field elements
+fn fold_felt_binary(op: Opcode, lhs: Felt, rhs: Felt) -> Option<Immediate> {
+    let felt = match op {
+        Opcode::Add => lhs + rhs,
+        Opcode::Sub => lhs - rhs,
+        Opcode::Mul => lhs * rhs,
+        Opcode::Exp => lhs.exp(rhs.as_int()),
+        Opcode::Min => core::cmp::min_by_key(lhs, rhs, |f| f.as_int()),
+        Opcode::Max => core::cmp::max_by_key(lhs, rhs, |f| f.as_int()),
+        Opcode::Eq => return Some(Immediate::I1(lhs == rhs)),
+        Opcode::Neq => return Some(Immediate::I1(lhs != rhs)),
+        Opcode::Gt => return Some(Immediate::I1(lhs.as_int() > rhs.as_int())),
+        Opcode::Gte => return Some(Immediate::I1(lhs.as_int() >= rhs.as_int())),
+        Opcode::Lt => return Some(Immediate::I1(lhs.as_int() < rhs.as_int())),
+        Opcode::Lte => return Some(Immediate::I1(lhs.as_int() <= rhs.as_int())),
+        _ => return None,
+    };
+    Some(Immediate::Felt(felt))
+}
+
+/// Evaluate the unary operator `op` with an operand of type `arg_ty`, producing `result_ty`
+fn fold_unary(
+    op: Opcode,
+    overflow: Option<Overflow>,
+    arg_ty: &Type,
+    result_ty: &Type,
+    arg: Immediate,
+) -> Option<SmallVec<[Immediate; 2]>> {
+    if arg_ty == &Type::Felt {
+        return fold_felt_unary(op, result_ty, arg.as_felt()?).map(|imm| smallvec![imm]);
+    }
+
+    let bits = int_bits(arg_ty)?;
+    let v = arg.as_i128()?;
+    if overflow == Some(Overflow::Unchecked) && v < 0 {
+        return None;
+    }
+    let x = to_bits(v, bits);
+    let imm = match op {
+        Opcode::Neg => return with_overflow(-v, arg_ty, overflow),
+        Opcode::Incr => return with_overflow(v + 1, arg_ty, overflow),
+        Opcode::Pow2 if (0..bits as i128).contains(&v) => {
+            return with_overflow(1 << v, arg_ty, overflow);
+        }
+        Opcode::Ilog2 if v > 0 => Immediate::U32(127 - (v as u128).leading_zeros()),
+        Opcode::Not => Immediate::I1(!arg.as_bool()?),
+        Opcode::Bnot => to_immediate(from_bits(!x & mask(bits), arg_ty)?, arg_ty)?,
+        Opcode::Popcnt => Immediate::U32(x.count_ones()),
+        Opcode::Clz => Immediate::U32(x.leading_zeros() - (128 - bits)),
+        Opcode::Ctz => Immediate::U32(x.trailing_zeros().min(bits)),
+        Opcode::Clo => Immediate::U32((!x & mask(bits)).leading_zeros() - (128 - bits)),
+        Opcode::Cto => Immediate::U32((!x & mask(bits)).trailing_zeros().min(bits)),
+        Opcode::IsOdd => Immediate::I1(v % 2 != 0),
+        Opcode::Zext => to_immediate(x as i128, result_ty)?,
+        Opcode::Sext => {
+            let sign_extended = if (x >> (bits - 1)) & 1 == 1 {
+                x as i128 - (1i128 << bits)
+            } else {
+                x as i128
+            };
+            to_immediate(wrap(sign_extended, result_ty)?, result_ty)?
+        }
+        Opcode::Trunc => to_immediate(wrap(v, result_ty)?, result_ty)?,
+        // Casts which do not preserve the value are checked at runtime
+        Opcode::Cast => to_immediate(v, result_ty)?,
+        _ => return None,
+    };
+
+    Some(smallvec![imm])
+}
+
+/// Evaluate the unary operator `op` on a field element, producing `result_ty`
+fn fold_felt_unary(op: Opcode, result_ty: &Type, arg: Felt) -> Option<Immediate> {
+    let felt = match op {
+        Opcode::Neg => -arg,
+        Opcode::Inv if arg != Felt::ZERO => arg.inv(),
+        Opcode::Incr => arg + Felt::ONE,
+        Opcode::Pow2 if arg.as_int() < 64 => Felt::new(1 << arg.as_int()),
+        Opcode::IsOdd => return Some(Immediate::I1(arg.as_int() % 2 != 0)),
+        Opcode::Cast => return to_immediate(arg.as_int() as i128, result_ty),
+        _ => return None,
+    };
+    Some(Immediate::Felt(felt))
+}
+
+/// Evaluate the `test` instruction, i.e. whether `arg` fits in `ty`
+fn fold_test(ty: &Type, arg: Immediate) -> Option<SmallVec<[Immediate; 2]>> {
+    if int_bits(ty).is_none() && ty != &Type::Felt {
+        return None;
+    }
+    let fits = to_immediate(arg.as_i128()?, ty).is_some();
+    Some(smallvec![Immediate::I1(fits)])
+}
+
+/// Produce the results of an operation on type `ty` whose mathematical result is `value`,
+/// according to the semantics of `overflow`.
+///
+/// Returns `None` if the result cannot be determined at compile-time.
+fn with_overflow(
+    value: i128,
+    ty: &Type,
+    overflow: Option<Overflow>,
+) -> Option<SmallVec<[Immediate; 2]>> {
+    match overflow.unwrap_or(Overflow::Checked) {
+        // A checked operation which overflows will trap, so we must leave it as-is
+        Overflow::Checked => Some(smallvec![to_immediate(value, ty)?]),
+        Overflow::Unchecked if value < 0 => None,
+        Overflow::Unchecked => Some(smallvec![to_immediate(value, ty)?]),
+        Overflow::Wrapping => Some(smallvec![to_immediate(wrap(value, ty)?, ty)?]),
+        Overflow::Overflowing => {
+            let overflowed = to_immediate(value, ty).is_none();
+            let wrapped = to_immediate(wrap(value, ty)?, ty)?;
+            Some(smallvec![Immediate::I1(overflowed), wrapped])
+        }
+    }
+}
+
+/// Get the width in bits of the integral type `ty`, if it is one we can evaluate
+fn int_bits(ty: &Type) -> Option<u32> {
+    match ty {
+        Type::I1 => Some(1),
+        Type::U8 | Type::I8 => Some(8),
+        Type::U16 | Type::I16 => Some(16),
+        Type::U32 | Type::I32 => Some(32),
+        Type::U64 | Type::I64 => Some(64),
+        _ => None,
+    }
+}
+
+#[inline(always)]
+fn mask(bits: u32) -> u128 {
+    (1u128 << bits) - 1
+}
+
+/// Get the two's complement representation of `value` in `bits` bits
+#[inline]
+fn to_bits(value: i128, bits: u32) -> u128 {
+    (value as u128) & mask(bits)
+}
+
+/// Interpret the two's complement representation `bits` as a value of type `ty`
+fn from_bits(bits: u128, ty: &Type) -> Option<i128> {
+    let width = int_bits(ty)?;
+    if ty.is_signed_integer() && (bits >> (width - 1)) & 1 == 1 {
+        Some(bits as i128 - (1i128 << width))
+    } else {
+        Some(bits as i128)
+    }
+}
+
+/// Wrap `value` around the range of the integral type `ty`
+fn wrap(value: i128, ty: &Type) -> Option<i128> {
+    from_bits(to_bits(value, int_bits(ty)?), ty)
+}
+
+/// Convert `value` to an immediate of type `ty`, if it is in range for that type
+fn to_immediate(value: i128, ty: &Type) -> Option<Immediate> {
+    Some(match ty {
+        Type::I1 => match value {
+            0 => Immediate::I1(false),
+            1 => Immediate::I1(true),
+            _ => return None,
+        },
+        Type::U8 => Immediate::U8(value.try_into().ok()?),
+        Type::I8 => Immediate::I8(value.try_into().ok()?),
+        Type::U16 => Immediate::U16(value.try_into().ok()?),
+        Type::I16 => Immediate::I16(value.try_into().ok()?),
+        Type::U32 => Immediate::U32(value.try_into().ok()?),
+        Type::I32 => Immediate::I32(value.try_into().ok()?),
+        Type::U64 => Immediate::U64(value.try_into().ok()?),
+        Type::I64 => Immediate::I64(value.try_into().ok()?),
+        Type::Felt => {
+            let value = u64::try_from(value).ok().filter(|v| *v < Felt::MODULUS)?;
+            Immediate::Felt(Felt::new(value))
+        }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_hir::{
+        pass::{AnalysisManager, RewritePass},
+        testing::TestContext,
+        AbiParam, Function, FunctionBuilder, Immediate, InstBuilder, Opcode, Overflow, Signature,
+        SourceSpan, Type,
+    };
+    use pretty_assertions::{assert_eq, assert_ne};
+    use smallvec::smallvec;
+
+    use super::fold_binary;
+    use crate::SparseConditionalConstantPropagation;
+
+    /// Run constant propagation on the following IR:
+    ///
+    /// ```text,ignore
+    /// pub fn test(u32) -> u32 {
+    /// entry(n: u32):
+    ///    two = const.u32 2;
+    ///    five = add.wrapping two, 3;
+    ///    is_small = lt five, 10;
+    ///    condbr is_small, blk0(five), blk1;
+    ///
+    /// blk0(x: u32):
+    ///    product = mul.checked x, n;
+    ///    ret product;
+    ///
+    /// blk1:
+    ///    underflowed = sub.checked five, 10;
+    ///    ret underflowed;
+    /// }
+    /// ```
+    ///
+    /// We expect the arithmetic in the entry block to be folded, the conditional branch to
+    /// be resolved to `blk0`, and `blk1` to be removed, as it is unreachable. Note that the
+    /// checked subtraction in `blk1` would trap, so it must not be folded in any case, but
+    /// it is never evaluated, as `blk1` is never reachable. The uses of the block parameter
+    /// of `blk0` are rewritten to refer to the constant it is always given.
+    #[test]
+    fn sccp_simple_test() {
+        let context = TestContext::default();
+        let id = "test::sccp".parse().unwrap();
+        let mut function = Function::new(
+            id,
+            Signature::new([AbiParam::new(Type::U32)], [AbiParam::new(Type::U32)]),
+        );
+
+        {
+            let mut builder = FunctionBuilder::new(&mut function);
+            let entry = builder.current_block();
+            let n = {
+                let args = builder.block_params(entry);
+                args[0]
+            };
+
+            let a = builder.create_block(); // blk0(x: u32)
+            let x = builder.append_block_param(a, Type::U32, SourceSpan::UNKNOWN);
+            let b = builder.create_block(); // blk1
+
+            // entry
+            let two = builder.ins().u32(2, SourceSpan::UNKNOWN);
+            let five = builder.ins().add_imm_wrapping(two, Immediate::U32(3), SourceSpan::UNKNOWN);
+            let is_small = builder.ins().lt_imm(five, Immediate::U32(10), SourceSpan::UNKNOWN);
+            builder.ins().cond_br(is_small, a, &[five], b, &[], SourceSpan::UNKNOWN);
+
+            // blk0
+            builder.switch_to_block(a);
+            let product = builder.ins().mul_checked(x, n, SourceSpan::UNKNOWN);
+            builder.ins().ret(Some(product), SourceSpan::UNKNOWN);
+
+            // blk1
+            builder.switch_to_block(b);
+            let underflowed =
+                builder.ins().sub_imm_checked(five, Immediate::U32(10), SourceSpan::UNKNOWN);
+            builder.ins().ret(Some(underflowed), SourceSpan::UNKNOWN);
+        }
+
+        let original = function.to_string();
+        let mut analyses = AnalysisManager::default();
+        let mut rewrite = SparseConditionalConstantPropagation;
+        rewrite
+            .apply(&mut function, &mut analyses, &context.session)
+            .expect("constant propagation failed");
+
+        let expected = "\
+(func (export #sccp) (param u32) (result u32)
+    (block 0 (param v0 u32)
+        (let (v2 u32) (const.u32 2))
+        (let (v3 u32) (const.u32 5))
+        (let (v4 i1) (const.i1 true))
+        (br (block 1 v3)))
+
+    (block 1 (param v1 u32)
+        (let (v7 u32) (const.u32 5))
+        (let (v5 u32) (mul.checked v7 v0))
+        (ret v5))
+)";
+
+        let transformed = function.to_string();
+        assert_ne!(transformed, original);
+        assert_eq!(transformed.as_str(), expected);
+    }
+
+    /// Folding must respect the overflow semantics of the operation being folded
+    #[test]
+    fn sccp_overflow_test() {
+        let fold = |overflow| {
+            fold_binary(Opcode::Add, overflow, &Type::U8, Immediate::U8(200), Immediate::U8(100))
+        };
+
+        // A checked operation which overflows must trap at runtime
+        assert_eq!(fold(Some(Overflow::Checked)), None);
+        assert_eq!(fold(None), None);
+        // An unchecked operation is only folded if the result is in range
+        assert_eq!(fold(Some(Overflow::Unchecked)), None);
+        // Wrapping and overflowing operations wrap around
+        assert_eq!(fold(Some(Overflow::Wrapping)), Some(smallvec![Immediate::U8(44)]));
+        assert_eq!(
+            fold(Some(Overflow::Overflowing)),
+            Some(smallvec![Immediate::I1(true), Immediate::U8(44)])
+        );
+
+        // Signed operations wrap using two's complement
+        assert_eq!(
+            fold_binary(
+                Opcode::Sub,
+                Some(Overflow::Wrapping),
+                &Type::I8,
+                Immediate::I8(i8::MIN),
+                Immediate::I8(1)
+            ),
+            Some(smallvec![Immediate::I8(i8::MAX)])
+        );
+        // Division by zero is never folded
+        assert_eq!(
+            fold_binary(
+                Opcode::Div,
+                Some(Overflow::Checked),
+                &Type::U32,
+                Immediate::U32(1),
+                Immediate::U32(0)
+            ),
+            None
+        );
+    }
+}
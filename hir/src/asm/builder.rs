@@ -458,6 +458,11 @@ impl<'a> MasmOpBuilder<'a> {
         self.build(self.ip, MasmOp::Exec(id));
     }
 
+    /// Executes the named procedure in a new memory context.
+    pub fn call(mut self, id: FunctionIdent) {
+        self.build(self.ip, MasmOp::Call(id));
+    }
+
     /// Execute a procedure indirectly.
     ///
     /// Expects the hash of a function's MAST root on the stack, see `procref`
@@ -561,6 +566,14 @@ impl<'a> MasmOpBuilder<'a> {
         self.build(self.ip, MasmOp::ExpImm(exponent));
     }
 
+    /// Pops two elements from the stack, `b` and `a` respectively, and places the result of `a^b`
+    /// on the stack, where `b` is known to be representable using `bits` bits.
+    ///
+    /// Traps if `b` does not fit in `bits` bits
+    pub fn exp_bit_length(mut self, bits: u8) {
+        self.build(self.ip, MasmOp::ExpBitLength(bits));
+    }
+
     /// Pops a value off the stack, and applies logical NOT, and places the result back on the
     /// stack.
     ///
@@ -1699,7 +1712,11 @@ fn apply_op_stack_effects(
         MasmOp::AssertEqw | MasmOp::AssertEqwWithError(_) => {
             stack.dropn(8);
         }
-        MasmOp::LocAddr(_id) | MasmOp::LocStore(_id) | MasmOp::LocStorew(_id) => unreachable!(),
+        MasmOp::LocAddr(_id)
+        | MasmOp::LocLoad(_id)
+        | MasmOp::LocLoadw(_id)
+        | MasmOp::LocStore(_id)
+        | MasmOp::LocStorew(_id) => unreachable!(),
         MasmOp::MemLoad | MasmOp::MemLoadOffset => {
             let ty = stack.pop().expect("operand stack is empty");
             assert_matches!(
@@ -1783,6 +1800,16 @@ fn apply_op_stack_effects(
             stack.dropw();
             stack.padw();
         }
+        MasmOp::Hperm => {
+            // The hasher state is replaced with the permuted state
+            assert!(stack.len() > 11, "expected at least 12 elements on the stack for hperm");
+            stack.dropw();
+            stack.dropw();
+            stack.dropw();
+            stack.padw();
+            stack.padw();
+            stack.padw();
+        }
         // This function is not called from [MasmOpBuilder] when building an `if.true` instruction,
         // instead, the only time we are evaluating this is when traversing the body of a `repeat.n`
         // instruction and applying the stack effects of instructions which have already been
@@ -1821,11 +1848,11 @@ fn apply_op_stack_effects(
                 }
             }
         }
-        MasmOp::Exec(ref id) => {
+        MasmOp::Exec(ref id) | MasmOp::Call(ref id) => {
             execute_call(id, false, stack, dfg);
         }
         MasmOp::Syscall(ref id) => {
-            execute_call(id, false, stack, dfg);
+            execute_call(id, true, stack, dfg);
         }
         MasmOp::DynExec | MasmOp::DynCall => {
             assert!(
@@ -1856,13 +1883,17 @@ fn apply_op_stack_effects(
         | MasmOp::DivImm(_)
         | MasmOp::Neg
         | MasmOp::Inv
-        | MasmOp::Ilog2
         | MasmOp::Pow2
         | MasmOp::ExpImm(_) => {
             let ty = stack.peek().expect("operand stack is empty");
             assert_compatible_felt_operand!(ty, op);
         }
-        MasmOp::Exp => {
+        MasmOp::Ilog2 => {
+            let ty = stack.pop().expect("operand stack is empty");
+            assert_compatible_felt_operand!(ty, op);
+            stack.push(Type::U32);
+        }
+        MasmOp::Exp | MasmOp::ExpBitLength(_) => {
             let rty = stack.pop().expect("operand stack is empty");
             let lty = stack.pop().expect("operand stack is empty");
             assert_compatible_felt_operands!(lty, rty);
@@ -2054,7 +2085,7 @@ fn execute_call(
         assert_ne!(
             import.signature.cc,
             CallConv::Kernel,
-            "`syscall` cannot be used to call non-kernel functions"
+            "kernel functions can only be called with the `syscall` instruction"
         );
     }
     match import.signature.cc {
@@ -232,7 +232,11 @@ impl<'a> PrettyPrint for DisplayOp<'a> {
             | MasmOp::Movupw(idx)
             | MasmOp::Movdn(idx)
             | MasmOp::Movdnw(idx)) => text(format!("{op}")) + const_text(".") + display(*idx),
-            op @ (MasmOp::LocAddr(id) | MasmOp::LocStore(id) | MasmOp::LocStorew(id)) => {
+            op @ (MasmOp::LocAddr(id)
+            | MasmOp::LocLoad(id)
+            | MasmOp::LocLoadw(id)
+            | MasmOp::LocStore(id)
+            | MasmOp::LocStorew(id)) => {
                 text(format!("{op}")) + const_text(".") + display(id.as_usize())
             }
             op @ (MasmOp::MemLoadImm(addr)
@@ -305,7 +309,8 @@ impl<'a> PrettyPrint for DisplayOp<'a> {
                     + nl()
                     + const_text("end")
             }
-            op @ (MasmOp::Exec(id) | MasmOp::Syscall(id) | MasmOp::ProcRef(id)) => {
+            MasmOp::Syscall(id) => const_text("syscall") + const_text(".") + display(id.function),
+            op @ (MasmOp::Exec(id) | MasmOp::Call(id) | MasmOp::ProcRef(id)) => {
                 let FunctionIdent { module, function } = id;
                 if self.is_local_module(module) {
                     text(format!("{op}")) + const_text(".") + display(function)
@@ -322,6 +327,7 @@ impl<'a> PrettyPrint for DisplayOp<'a> {
                 text(format!("{op}")) + const_text(".") + display(*imm)
             }
             MasmOp::ExpImm(imm) => const_text("exp") + const_text(".") + display(*imm),
+            MasmOp::ExpBitLength(imm) => const_text("exp") + const_text(".u") + display(*imm),
             op @ (MasmOp::AddImm(imm)
             | MasmOp::SubImm(imm)
             | MasmOp::MulImm(imm)
@@ -369,7 +375,11 @@ impl<'a> fmt::Display for DisplayOp<'a> {
             | MasmOp::Movupw(idx)
             | MasmOp::Movdn(idx)
             | MasmOp::Movdnw(idx)) => write!(f, "{op}.{idx}"),
-            op @ (MasmOp::LocAddr(id) | MasmOp::LocStore(id) | MasmOp::LocStorew(id)) => {
+            op @ (MasmOp::LocAddr(id)
+            | MasmOp::LocLoad(id)
+            | MasmOp::LocLoadw(id)
+            | MasmOp::LocStore(id)
+            | MasmOp::LocStorew(id)) => {
                 write!(f, "{op}.{}", id.as_usize())
             }
             op @ (MasmOp::MemLoadImm(addr)
@@ -433,7 +443,8 @@ impl<'a> fmt::Display for DisplayOp<'a> {
                     DisplayIndent(self.indent),
                 )
             }
-            op @ (MasmOp::Exec(id) | MasmOp::Syscall(id) | MasmOp::ProcRef(id)) => {
+            MasmOp::Syscall(id) => write!(f, "syscall.{}", id.function.as_str()),
+            op @ (MasmOp::Exec(id) | MasmOp::Call(id) | MasmOp::ProcRef(id)) => {
                 let FunctionIdent { module, function } = id;
                 if self.is_local_module(module) {
                     write!(f, "{op}.{}", function.as_str())
@@ -445,7 +456,7 @@ impl<'a> fmt::Display for DisplayOp<'a> {
             op @ (MasmOp::AndImm(imm) | MasmOp::OrImm(imm) | MasmOp::XorImm(imm)) => {
                 write!(f, "{op}.{imm}")
             }
-            op @ MasmOp::ExpImm(imm) => write!(f, "{op}.{imm}"),
+            op @ (MasmOp::ExpImm(_) | MasmOp::ExpBitLength(_)) => write!(f, "{op}"),
             op @ (MasmOp::AddImm(imm)
             | MasmOp::SubImm(imm)
             | MasmOp::MulImm(imm)
@@ -184,6 +184,12 @@ pub enum MasmOp {
     AssertEqwWithError(u32),
     /// Places the memory address of the given local index on top of the stack
     LocAddr(LocalId),
+    /// Reads the first element of the word at the address corresponding to the given local
+    /// index, and places it on top of the stack
+    LocLoad(LocalId),
+    /// Reads the word at the address corresponding to the given local index, and places it on
+    /// top of the stack
+    LocLoadw(LocalId),
     /// Writes a value to the first element of the word at the address corresponding to the given
     /// local index
     LocStore(LocalId),
@@ -261,6 +267,12 @@ pub enum MasmOp {
     AdvPush(u8),
     /// TODO
     AdvLoadw,
+    /// Applies the Rescue Prime Optimized permutation to the hasher state formed by the top
+    /// three words of the stack, where the first two words are the rate, and the third word is
+    /// the capacity, replacing them with the permuted state
+    ///
+    /// [C, B, A] <- [C', B', A']
+    Hperm,
     /// Pops the top of the stack, and evaluates the ops in
     /// the block of code corresponding to the branch taken.
     ///
@@ -278,13 +290,21 @@ pub enum MasmOp {
     Repeat(u8, MasmBlockId),
     /// Pops `N` args off the stack, executes the procedure, results will be placed on the stack
     Exec(FunctionIdent),
+    /// Pops `N` args off the stack, executes the procedure in a new memory context, results will
+    /// be placed on the stack
+    ///
+    /// Only the top 16 elements of the operand stack are visible to the callee.
+    Call(FunctionIdent),
     /// Pops `N` args off the stack, executes the procedure in the root context, results will be
     /// placed on the stack
     Syscall(FunctionIdent),
-    /// Pops the address (MAST root hash) of a callee off the stack, and dynamically `exec` the
-    /// function
+    /// Dynamically `exec` the function whose address (MAST root hash) is on top of the stack.
+    ///
+    /// NOTE: The hash of the callee is left on the stack
     DynExec,
-    /// TODO
+    /// Dynamically `call` the function whose address (MAST root hash) is on top of the stack.
+    ///
+    /// NOTE: The hash of the callee is left on the stack
     DynCall,
     /// Pushes the address (MAST root hash) of the given function on the stack, to be used by
     /// `dynexec` or `dyncall`
@@ -315,20 +335,23 @@ pub enum MasmOp {
     Inv,
     /// Pops `a` off the stack, and places the result of incrementing it by 1 back on the stack
     Incr,
-    /// Computes the base 2 logarithm of `a`, rounded down, and places it on the advice stack.
+    /// Pops `a` off the stack, and places the base 2 logarithm of `a`, rounded down, on the stack
+    ///
+    /// NOTE: `a` must not be equal to 0
     Ilog2,
     /// Pops `a` off the stack, and places the result of `2^a` on the stack
     ///
     /// NOTE: `a` must not be > 63
     Pow2,
     /// Pops `a` and `b` off the stack, and places the result of `a^b` on the stack
-    ///
-    /// NOTE: `b` must not be > 63
     Exp,
     /// Pops `a` off the stack, and places the result of `a^<imm>` on the stack
-    ///
-    /// NOTE: `imm` must not be > 63
     ExpImm(u8),
+    /// Pops `b` and `a` off the stack, and places the result of `a^b` on the stack, where `b` is
+    /// known to be representable using `<imm>` bits
+    ///
+    /// NOTE: `imm` must not be > 64, and the operation fails if `b` does not fit in `<imm>` bits
+    ExpBitLength(u8),
     /// Pops `a` off the stack, and places the result of `1 - a` on the stack
     ///
     /// NOTE: `a` must be boolean
@@ -559,19 +582,19 @@ pub enum MasmOp {
     ///
     /// This operation is unchecked, so the result is undefined if the operands are not valid u32
     U32Popcnt,
-    /// Computes the number of leading zero bits in `a`, and places it on the advice stack
+    /// Pops `a` off the stack, and places the number of leading zero bits in `a` on the stack
     ///
     /// This operation is unchecked, so the result is undefined if the operands are not valid u32
     U32Clz,
-    /// Computes the number of trailing zero bits in `a`, and places it on the advice stack
+    /// Pops `a` off the stack, and places the number of trailing zero bits in `a` on the stack
     ///
     /// This operation is unchecked, so the result is undefined if the operands are not valid u32
     U32Ctz,
-    /// Computes the number of leading one bits in `a`, and places it on the advice stack
+    /// Pops `a` off the stack, and places the number of leading one bits in `a` on the stack
     ///
     /// This operation is unchecked, so the result is undefined if the operands are not valid u32
     U32Clo,
-    /// Computes the number of trailing one bits in `a`, and places it on the advice stack
+    /// Pops `a` off the stack, and places the number of trailing one bits in `a` on the stack
     ///
     /// This operation is unchecked, so the result is undefined if the operands are not valid u32
     U32Cto,
@@ -634,13 +657,13 @@ impl MasmOp {
             Instruction::Neg => Self::Neg,
             Instruction::Inv => Self::Inv,
             Instruction::Incr => Self::Incr,
-            //Instruction::Ilog2 => Self::ILog2,
+            Instruction::ILog2 => Self::Ilog2,
             Instruction::Pow2 => Self::Pow2,
             Instruction::Exp => Self::Exp,
             Instruction::ExpImm(imm) => {
                 Self::ExpImm(imm.as_int().try_into().expect("invalid exponent"))
             }
-            Instruction::ExpBitLength(imm) => Self::ExpImm(imm),
+            Instruction::ExpBitLength(imm) => Self::ExpBitLength(imm),
             Instruction::Not => Self::Not,
             Instruction::And => Self::And,
             Instruction::Or => Self::Or,
@@ -706,10 +729,10 @@ impl MasmOp {
             Instruction::U32Rotl => Self::U32Rotl,
             Instruction::U32RotlImm(imm) => Self::U32RotlImm(imm as u32),
             Instruction::U32Popcnt => Self::U32Popcnt,
-            //Instruction::U32Clz => Self::U32Clz,
-            //Instruction::U32Ctz => Self::U32Ctz,
-            //Instruction::U32Clo => Self::U32Clo,
-            //Instruction::U32Cto => Self::U32Cto,
+            Instruction::U32Clz => Self::U32Clz,
+            Instruction::U32Ctz => Self::U32Ctz,
+            Instruction::U32Clo => Self::U32Clo,
+            Instruction::U32Cto => Self::U32Cto,
             Instruction::U32Lt => Self::U32Lt,
             Instruction::U32Lte => Self::U32Lte,
             Instruction::U32Gt => Self::U32Gt,
@@ -823,17 +846,20 @@ impl MasmOp {
             Instruction::MemStoreImm(addr) => Self::MemStoreImm(addr),
             Instruction::MemStoreW => Self::MemStorew,
             Instruction::MemStoreWImm(addr) => Self::MemStorewImm(addr),
-            Instruction::LocLoad(_) | Instruction::LocLoadW(_) => {
-                unimplemented!("load by local id")
+            Instruction::LocLoad(id) => {
+                Self::LocLoad(LocalId::from_u8(id.try_into().expect("invalid local id")))
+            }
+            Instruction::LocLoadW(id) => {
+                Self::LocLoadw(LocalId::from_u8(id.try_into().expect("invalid local id")))
             }
             Instruction::MemStream => Self::MemStream,
             Instruction::AdvPipe => Self::AdvPipe,
             Instruction::AdvPush(byte) => Self::AdvPush(byte),
             Instruction::AdvLoadW => Self::AdvLoadw,
             Instruction::AdvInject(_) => unimplemented!("adv_inject"),
+            Instruction::HPerm => Self::Hperm,
             Instruction::Hash
             | Instruction::HMerge
-            | Instruction::HPerm
             | Instruction::MTreeGet
             | Instruction::MTreeSet
             | Instruction::MTreeMerge
@@ -852,9 +878,21 @@ impl MasmOp {
                     function: Ident::with_empty_span(Symbol::intern(name.as_ref())),
                 })
             }
-            Instruction::CallLocal(_)
-            | Instruction::CallMastRoot(_)
-            | Instruction::CallImported(_) => unimplemented!("contract calls"),
+            Instruction::CallLocal(local_index) => Self::Call(locals[local_index as usize]),
+            Instruction::CallImported(ref proc_id) => {
+                let module = imported
+                    .get_procedure_path(proc_id)
+                    .expect("reference to import that doesn't exist")
+                    .last();
+                let name = imported
+                    .get_procedure_name(proc_id)
+                    .expect("reference to import that doesn't exist");
+                Self::Call(FunctionIdent {
+                    module: Ident::with_empty_span(Symbol::intern(module)),
+                    function: Ident::with_empty_span(Symbol::intern(name.as_ref())),
+                })
+            }
+            Instruction::CallMastRoot(_) => unimplemented!("calls by mast root"),
             Instruction::SysCall(ref proc_id) => {
                 let module = imported
                     .get_procedure_path(proc_id)
@@ -1025,6 +1063,8 @@ impl MasmOp {
             Self::AssertEqw => Instruction::AssertEqw,
             Self::AssertEqwWithError(code) => Instruction::AssertEqwWithError(code),
             Self::LocAddr(id) => Instruction::Locaddr(id.as_usize() as u16),
+            Self::LocLoad(id) => Instruction::LocLoad(id.as_usize() as u16),
+            Self::LocLoadw(id) => Instruction::LocLoadW(id.as_usize() as u16),
             Self::LocStore(id) => Instruction::LocStore(id.as_usize() as u16),
             Self::LocStorew(id) => Instruction::LocStoreW(id.as_usize() as u16),
             Self::MemLoad => Instruction::MemLoad,
@@ -1043,14 +1083,20 @@ impl MasmOp {
             ),
             Self::MemStream => Instruction::MemStream,
             Self::AdvPipe => Instruction::AdvPipe,
+            Self::Hperm => Instruction::HPerm,
             Self::AdvPush(n) => Instruction::AdvPush(n),
             Self::AdvLoadw => Instruction::AdvLoadW,
             Self::If(..) | Self::While(_) | Self::Repeat(..) => {
                 panic!("control flow instructions are meant to be handled specially by the caller")
             }
-            Self::Exec(ref callee) => {
+            Self::Exec(ref callee) | Self::Call(ref callee) => {
+                let is_call = matches!(self, Self::Call(_));
                 if let Some(idx) = local_ids.get(callee).copied() {
-                    Instruction::ExecLocal(idx)
+                    if is_call {
+                        Instruction::CallLocal(idx)
+                    } else {
+                        Instruction::ExecLocal(idx)
+                    }
                 } else {
                     let aliased = if let Some(alias) = imports.alias(&callee.module) {
                         FunctionIdent {
@@ -1069,28 +1115,19 @@ impl MasmOp {
                         .get(&aliased)
                         .copied()
                         .unwrap_or_else(|| miden_assembly::ProcedureId::new(aliased.to_string()));
-                    Instruction::ExecImported(id)
+                    if is_call {
+                        Instruction::CallImported(id)
+                    } else {
+                        Instruction::ExecImported(id)
+                    }
                 }
             }
             Self::Syscall(ref callee) => {
-                let aliased = if let Some(alias) = imports.alias(&callee.module) {
-                    FunctionIdent {
-                        module: alias,
-                        function: callee.function,
-                    }
-                } else {
-                    let module_as_import =
-                        super::MasmImport::try_from(callee.module).expect("invalid module name");
-                    FunctionIdent {
-                        module: Ident::with_empty_span(module_as_import.alias),
-                        function: callee.function,
-                    }
-                };
-                let id = proc_ids
-                    .get(&aliased)
-                    .copied()
-                    .unwrap_or_else(|| miden_assembly::ProcedureId::new(aliased.to_string()));
-                Instruction::SysCall(id)
+                // Kernel procedures are not imported, they are referenced by name only, and are
+                // resolved against the kernel the program is assembled with
+                Instruction::SysCall(miden_assembly::ProcedureId::from_kernel_name(
+                    callee.function.as_str(),
+                ))
             }
             Self::DynExec => Instruction::DynExec,
             Self::DynCall => Instruction::DynCall,
@@ -1129,10 +1166,11 @@ impl MasmOp {
             Self::Neg => Instruction::Neg,
             Self::Inv => Instruction::Inv,
             Self::Incr => Instruction::Incr,
-            Self::Ilog2 => todo!("Instruction::ILog2"),
+            Self::Ilog2 => Instruction::ILog2,
             Self::Pow2 => Instruction::Pow2,
             Self::Exp => Instruction::Exp,
-            Self::ExpImm(imm) => Instruction::ExpBitLength(imm),
+            Self::ExpImm(imm) => Instruction::ExpImm(Felt::new(imm as u64)),
+            Self::ExpBitLength(imm) => Instruction::ExpBitLength(imm),
             Self::Not => Instruction::Not,
             Self::And => Instruction::And,
             Self::AndImm(imm) => {
@@ -1244,10 +1282,10 @@ impl MasmOp {
                 Instruction::U32RotrImm(imm.try_into().expect("invalid rotation"))
             }
             Self::U32Popcnt => Instruction::U32Popcnt,
-            Self::U32Clz => todo!("Instruction::U32Clz"),
-            Self::U32Ctz => todo!("Instruction::U32Ctz"),
-            Self::U32Clo => todo!("Instruction::U32Clo"),
-            Self::U32Cto => todo!("Instruction::U32Cto"),
+            Self::U32Clz => Instruction::U32Clz,
+            Self::U32Ctz => Instruction::U32Ctz,
+            Self::U32Clo => Instruction::U32Clo,
+            Self::U32Cto => Instruction::U32Cto,
             Self::U32Lt => Instruction::U32Lt,
             Self::U32Lte => Instruction::U32Lte,
             Self::U32Gt => Instruction::U32Gt,
@@ -1293,6 +1331,8 @@ impl fmt::Display for MasmOp {
             Self::AssertEqw => f.write_str("assert_eqw"),
             Self::AssertEqwWithError(code) => write!(f, "assert_eqw.err={code}"),
             Self::LocAddr(_) => f.write_str("locaddr"),
+            Self::LocLoad(_) => f.write_str("loc_load"),
+            Self::LocLoadw(_) => f.write_str("loc_loadw"),
             Self::LocStore(_) => f.write_str("loc_store"),
             Self::LocStorew(_) => f.write_str("loc_storew"),
             Self::MemLoad
@@ -1307,12 +1347,14 @@ impl fmt::Display for MasmOp {
             Self::MemStorew | Self::MemStorewImm(_) => f.write_str("mem_storew"),
             Self::MemStream => f.write_str("mem_stream"),
             Self::AdvPipe => f.write_str("adv_pipe"),
+            Self::Hperm => f.write_str("hperm"),
             Self::AdvPush(_) => f.write_str("adv_push"),
             Self::AdvLoadw => f.write_str("adv_loadw"),
             Self::If(..) => f.write_str("if.true"),
             Self::While(_) => f.write_str("while.true"),
             Self::Repeat(..) => f.write_str("repeat"),
             Self::Exec(_) => f.write_str("exec"),
+            Self::Call(_) => f.write_str("call"),
             Self::Syscall(_) => f.write_str("syscall"),
             Self::DynExec => f.write_str("dynexec"),
             Self::DynCall => f.write_str("dyncall"),
@@ -1327,7 +1369,8 @@ impl fmt::Display for MasmOp {
             Self::Ilog2 => f.write_str("ilog2"),
             Self::Pow2 => f.write_str("pow2"),
             Self::Exp => f.write_str("exp"),
-            Self::ExpImm(imm) => write!(f, "exp.u{imm}"),
+            Self::ExpImm(imm) => write!(f, "exp.{imm}"),
+            Self::ExpBitLength(imm) => write!(f, "exp.u{imm}"),
             Self::Not => f.write_str("not"),
             Self::And | Self::AndImm(_) => f.write_str("and"),
             Self::Or | Self::OrImm(_) => f.write_str("or"),
@@ -1393,3 +1436,61 @@ impl fmt::Display for MasmOp {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use miden_assembly::ast::{Instruction, ModuleImports, Node};
+
+    use super::*;
+
+    /// Convert `ix` to a [MasmOp], and back again
+    fn round_trip(ix: Instruction) -> (SmallVec<[MasmOp; 2]>, SmallVec<[Node; 2]>) {
+        let codemap = miden_diagnostics::CodeMap::new();
+        let imported = ModuleImports::new(Default::default(), Default::default());
+        let ops = MasmOp::from_masm(ix, &[], &imported);
+        let nodes = ops
+            .iter()
+            .flat_map(|op| {
+                op.into_node(
+                    &codemap,
+                    &Default::default(),
+                    &Default::default(),
+                    &Default::default(),
+                )
+            })
+            .collect();
+        (ops, nodes)
+    }
+
+    #[test]
+    fn exp_round_trip() {
+        let (ops, nodes) = round_trip(Instruction::ExpBitLength(32));
+        assert_eq!(ops.as_slice(), &[MasmOp::ExpBitLength(32)]);
+        assert_eq!(nodes.as_slice(), &[Node::Instruction(Instruction::ExpBitLength(32))]);
+        assert_eq!(ops[0].to_string(), "exp.u32");
+
+        let (ops, nodes) = round_trip(Instruction::ExpImm(Felt::new(5)));
+        assert_eq!(ops.as_slice(), &[MasmOp::ExpImm(5)]);
+        assert_eq!(nodes.as_slice(), &[Node::Instruction(Instruction::ExpImm(Felt::new(5)))]);
+        assert_eq!(ops[0].to_string(), "exp.5");
+
+        let (ops, nodes) = round_trip(Instruction::Exp);
+        assert_eq!(ops.as_slice(), &[MasmOp::Exp]);
+        assert_eq!(nodes.as_slice(), &[Node::Instruction(Instruction::Exp)]);
+    }
+
+    #[test]
+    fn bit_count_round_trip() {
+        for (ix, op) in [
+            (Instruction::ILog2, MasmOp::Ilog2),
+            (Instruction::U32Clz, MasmOp::U32Clz),
+            (Instruction::U32Ctz, MasmOp::U32Ctz),
+            (Instruction::U32Clo, MasmOp::U32Clo),
+            (Instruction::U32Cto, MasmOp::U32Cto),
+        ] {
+            let (ops, nodes) = round_trip(ix.clone());
+            assert_eq!(ops.as_slice(), &[op]);
+            assert_eq!(nodes.as_slice(), &[Node::Instruction(ix)]);
+        }
+    }
+}
@@ -12,6 +12,19 @@ pub mod attributes {
         name: symbols::Entrypoint,
         value: AttributeValue::Unit,
     };
+
+    /// This attribute indicates that the decorated function should be inlined into its
+    /// callers whenever possible, regardless of its estimated cost.
+    pub const INLINE: Attribute = Attribute {
+        name: symbols::Inline,
+        value: AttributeValue::Unit,
+    };
+
+    /// This attribute indicates that the decorated function must never be inlined
+    pub const NOINLINE: Attribute = Attribute {
+        name: symbols::Noinline,
+        value: AttributeValue::Unit,
+    };
 }
 
 /// An [AttributeSet] is a uniqued collection of attributes associated with some IR entity
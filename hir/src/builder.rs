@@ -832,6 +832,9 @@ pub trait InstBuilder<'f>: InstBuilderBase<'f> {
     /// The unit size for `count` is determined by the `src` pointer type, i.e. a pointer to u8
     /// will copy one `count` bytes, a pointer to u16 will copy `count * 2` bytes, and so on.
     ///
+    /// The source and destination regions may overlap, in which case the result is as if the
+    /// source region was first copied to a temporary buffer, i.e. the semantics of `memmove`.
+    ///
     /// NOTE: The source and destination pointer types must match, or this function will panic.
     fn memcpy(mut self, src: Value, dst: Value, count: Value, span: SourceSpan) -> Inst {
         require_integer!(self, count);
@@ -846,6 +849,26 @@ pub trait InstBuilder<'f>: InstBuilderBase<'f> {
         self.PrimOp(Opcode::MemCpy, Type::Unit, vlist, span).0
     }
 
+    /// Writes `count` copies of `value` to the memory at address `dst`.
+    ///
+    /// The unit size for `count` is determined by the `dst` pointer type, i.e. a pointer to u8
+    /// will write `count` bytes, a pointer to u16 will write `count * 2` bytes, and so on.
+    ///
+    /// NOTE: The type of `value` must match the pointee type of `dst`, or this function will
+    /// panic.
+    fn memset(mut self, dst: Value, count: Value, value: Value, span: SourceSpan) -> Inst {
+        require_integer!(self, count);
+        let pointee_ty = require_pointee!(self, dst);
+        let value_ty = self.data_flow_graph().value_type(value);
+        assert_eq!(pointee_ty, value_ty, "expected value to be a {}, got {}", pointee_ty, value_ty);
+        let mut vlist = ValueList::default();
+        {
+            let dfg = self.data_flow_graph_mut();
+            vlist.extend([dst, count, value], &mut dfg.value_lists);
+        }
+        self.PrimOp(Opcode::MemSet, Type::Unit, vlist, span).0
+    }
+
     /// This is a cast operation that permits performing arithmetic on pointer values
     /// by casting a pointer to a specified integral type.
     fn ptrtoint(self, arg: Value, ty: Type, span: SourceSpan) -> Value {
@@ -1147,6 +1170,20 @@ pub trait InstBuilder<'f>: InstBuilderBase<'f> {
         self.Call(Opcode::Call, callee, vlist, span).0
     }
 
+    fn context_call(mut self, callee: FunctionIdent, args: &[Value], span: SourceSpan) -> Inst {
+        let mut vlist = ValueList::default();
+        {
+            let dfg = self.data_flow_graph_mut();
+            assert!(
+                dfg.get_import(&callee).is_some(),
+                "must import callee ({}) before calling it",
+                &callee
+            );
+            vlist.extend(args.iter().copied(), &mut dfg.value_lists);
+        }
+        self.Call(Opcode::ContextCall, callee, vlist, span).0
+    }
+
     fn syscall(mut self, callee: FunctionIdent, args: &[Value], span: SourceSpan) -> Inst {
         let mut vlist = ValueList::default();
         {
@@ -713,6 +713,42 @@ impl DataFlowGraph {
             _ => panic!("{} must be a branch instruction", branch_inst),
         }
     }
+
+    /// Removes the argument at `index` from the arguments passed by `branch_inst` to `dest`.
+    ///
+    /// If `branch_inst` transfers control to `dest` along multiple edges, the argument is removed
+    /// from all of them.
+    /// Panics if `branch_inst` is not a branch instruction.
+    pub fn remove_branch_destination_argument(
+        &mut self,
+        branch_inst: Inst,
+        dest: Block,
+        index: usize,
+    ) {
+        match self.insts[branch_inst].data.item {
+            Instruction::Br(Br {
+                destination,
+                ref mut args,
+                ..
+            }) if destination == dest => {
+                args.remove(index, &mut self.value_lists);
+            }
+            Instruction::CondBr(CondBr {
+                then_dest: (then_dest, ref mut then_args),
+                else_dest: (else_dest, ref mut else_args),
+                ..
+            }) => {
+                if then_dest == dest {
+                    then_args.remove(index, &mut self.value_lists);
+                }
+                if else_dest == dest {
+                    else_args.remove(index, &mut self.value_lists);
+                }
+            }
+            Instruction::Br(_) | Instruction::Switch(_) => (),
+            _ => panic!("{} must be a branch instruction", branch_inst),
+        }
+    }
 }
 impl Index<Inst> for DataFlowGraph {
     type Output = Instruction;
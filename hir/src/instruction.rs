@@ -364,6 +364,9 @@ pub enum Opcode {
     Store,
     /// Copies `n` values of a given type from a source pointer to a destination pointer
     MemCpy,
+    /// Writes `n` copies of a value of a given type to consecutive elements starting at a
+    /// destination pointer
+    MemSet,
     /// Casts a pointer value to an integral type
     PtrToInt,
     /// Casts an integral type to a pointer value
@@ -423,7 +426,13 @@ pub enum Opcode {
     IsOdd,
     Min,
     Max,
+    /// Calls a function in the same memory context as the caller
     Call,
+    /// Calls a function in a new memory context, isolating it from the caller
+    ///
+    /// Only the top 16 elements of the operand stack are visible to the callee, so the arguments
+    /// and results of the callee must each fit in 16 field elements.
+    ContextCall,
     Syscall,
     Br,
     CondBr,
@@ -442,7 +451,7 @@ impl Opcode {
     }
 
     pub fn is_call(&self) -> bool {
-        matches!(self, Self::Call | Self::Syscall)
+        matches!(self, Self::Call | Self::ContextCall | Self::Syscall)
     }
 
     pub fn is_commutative(&self) -> bool {
@@ -472,8 +481,10 @@ impl Opcode {
             | Self::Store
             | Self::Alloca
             | Self::MemCpy
+            | Self::MemSet
             | Self::MemGrow
             | Self::Call
+            | Self::ContextCall
             | Self::Syscall
             | Self::Br
             | Self::CondBr
@@ -614,8 +625,10 @@ impl Opcode {
             Self::Select => 3,
             // MemCpy requires source, destination, and arity
             Self::MemCpy => 3,
+            // MemSet requires destination, arity, and value
+            Self::MemSet => 3,
             // Calls are entirely variable
-            Self::Call | Self::Syscall => 0,
+            Self::Call | Self::ContextCall | Self::Syscall => 0,
             // Unconditional branches have no fixed arguments
             Self::Br => 0,
             // Ifs have a single argument, the conditional
@@ -639,6 +652,7 @@ impl Opcode {
             | Self::AssertEq
             | Self::Store
             | Self::MemCpy
+            | Self::MemSet
             | Self::Br
             | Self::CondBr
             | Self::Switch
@@ -712,7 +726,7 @@ impl Opcode {
                 smallvec![ctrl_ty.pointee().expect("expected pointer type").clone()]
             }
             // Call results are handled separately
-            Self::Call | Self::Syscall | Self::InlineAsm => unreachable!(),
+            Self::Call | Self::ContextCall | Self::Syscall | Self::InlineAsm => unreachable!(),
         }
     }
 }
@@ -739,6 +753,7 @@ impl fmt::Display for Opcode {
             Self::Load => f.write_str("load"),
             Self::Store => f.write_str("store"),
             Self::MemCpy => f.write_str("memcpy"),
+            Self::MemSet => f.write_str("memset"),
             Self::PtrToInt => f.write_str("ptrtoint"),
             Self::IntToPtr => f.write_str("inttoptr"),
             Self::Cast => f.write_str("cast"),
@@ -749,6 +764,7 @@ impl fmt::Display for Opcode {
             Self::CondBr => f.write_str("condbr"),
             Self::Switch => f.write_str("switch"),
             Self::Call => f.write_str("call"),
+            Self::ContextCall => f.write_str("call.context"),
             Self::Syscall => f.write_str("syscall"),
             Self::Ret => f.write_str("ret"),
             Self::Test => f.write_str("test"),
@@ -804,7 +820,7 @@ impl fmt::Display for Opcode {
 /// Always check the documentation of the specific instruction involved to see if there
 /// are any specific differences in how this enum is interpreted compared to the default
 /// meaning of each variant.
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Hash)]
 pub enum Overflow {
     /// Typically, this means the operation is performed using the equivalent field element
     /// operation, rather than a dedicated operation for the given type. Because of this, the
@@ -387,13 +387,16 @@ impl Module {
 
     /// Compute the set of imports for this module, automatically aliasing modules when there
     /// are namespace conflicts
+    ///
+    /// Kernel functions are excluded, as they are invoked via `syscall` by name, rather than
+    /// being imported from the module which defines them.
     pub fn imports(&self) -> ModuleImportInfo {
         let mut imports = ModuleImportInfo::default();
         let locals = self.functions.iter().map(|f| f.id).collect::<FxHashSet<FunctionIdent>>();
 
         for function in self.functions.iter() {
             for import in function.imports() {
-                if !locals.contains(&import.id) {
+                if !locals.contains(&import.id) && !import.signature.is_kernel() {
                     imports.add(import.id);
                 }
             }
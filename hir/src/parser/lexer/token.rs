@@ -47,6 +47,7 @@ pub enum Token {
     Ret,
     Call,
     CallIndirect,
+    CallContext,
     Syscall,
     Br,
     CondBr,
@@ -54,6 +55,7 @@ pub enum Token {
     Test,
     Load,
     MemCpy,
+    MemSet,
     Asm,
     MemoryGrow,
     AddUnchecked,
@@ -204,6 +206,7 @@ impl Token {
             "ret" => Self::Ret,
             "call" => Self::Call,
             "call.indirect" => Self::CallIndirect,
+            "call.context" => Self::CallContext,
             "syscall" => Self::Syscall,
             "br" => Self::Br,
             "condbr" => Self::CondBr,
@@ -211,6 +214,7 @@ impl Token {
             "test" => Self::Test,
             "load" => Self::Load,
             "memcpy" => Self::MemCpy,
+            "memset" => Self::MemSet,
             "asm" => Self::Asm,
             "memory.grow" => Self::MemoryGrow,
             "add.unchecked" => Self::AddUnchecked,
@@ -407,6 +411,7 @@ impl fmt::Display for Token {
             Self::Ret => write!(f, "ret"),
             Self::Call => write!(f, "call"),
             Self::CallIndirect => write!(f, "call.indirect"),
+            Self::CallContext => write!(f, "call.context"),
             Self::Syscall => write!(f, "syscall"),
             Self::Br => write!(f, "br"),
             Self::CondBr => write!(f, "condbr"),
@@ -414,6 +419,7 @@ impl fmt::Display for Token {
             Self::Test => write!(f, "test"),
             Self::Load => write!(f, "load"),
             Self::MemCpy => write!(f, "memcpy"),
+            Self::MemSet => write!(f, "memset"),
             Self::Asm => write!(f, "asm"),
             Self::MemoryGrow => write!(f, "memory.grow"),
             Self::AddUnchecked => write!(f, "add.unchecked"),
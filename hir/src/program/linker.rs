@@ -154,6 +154,13 @@ pub struct Linker {
     program: Box<Program>,
     /// This is the set of modules which have yet to be linked
     pending: FxHashMap<Ident, Box<Module>>,
+    /// This is the set of modules provided by precompiled libraries, e.g. `.masl` files,
+    /// which the program is being linked against, along with the signatures of the
+    /// functions they define.
+    ///
+    /// These modules are not part of the linked program, but references to them are
+    /// resolved and validated during the link, as they are known to be provided at runtime.
+    libraries: FxHashMap<Ident, FxHashMap<Ident, Signature>>,
     /// This is the dependency graph for all functions in the program.
     ///
     /// This graph is used to obtain a topological ordering of the
@@ -194,6 +201,7 @@ impl Default for Linker {
         Self {
             program,
             pending: Default::default(),
+            libraries: Default::default(),
             callgraph: DiGraphMap::new(),
             local_callgraph: DiGraphMap::new(),
             globals: DiGraphMap::new(),
@@ -240,7 +248,7 @@ impl Linker {
         self.renamed.clear();
 
         // Raise an error if we've already got a module by this name pending
-        if self.pending.contains_key(&id) {
+        if self.pending.contains_key(&id) || self.libraries.contains_key(&id) {
             return Err(LinkerError::ModuleConflict(id));
         }
 
@@ -344,6 +352,31 @@ impl Linker {
         Ok(())
     }
 
+    /// Add a module provided by a precompiled library to the set of modules to link against.
+    ///
+    /// Unlike `add`, the module is not made part of the linked program, instead, references
+    /// to `name` will be resolved against `functions`, the signatures of the functions it
+    /// defines.
+    ///
+    /// Miden Assembly procedures do not carry type information, so the signatures of functions
+    /// loaded from a library are typically empty. When that is the case, only the linkage of
+    /// the referenced function is validated, otherwise the signature must match that of each
+    /// external declaration which references it.
+    ///
+    /// Returns a [LinkerError] if a module with the same name has already been added.
+    pub fn add_library_module<I>(&mut self, name: Ident, functions: I) -> Result<(), LinkerError>
+    where
+        I: IntoIterator<Item = (Ident, Signature)>,
+    {
+        if self.pending.contains_key(&name) || self.libraries.contains_key(&name) {
+            return Err(LinkerError::ModuleConflict(name));
+        }
+
+        self.libraries.insert(name, functions.into_iter().collect());
+
+        Ok(())
+    }
+
     /// Links all of the modules which were added, producing a [Program] if no issues are found.
     ///
     /// Returns a [LinkerError] if the link fails for any reason.
@@ -354,6 +387,7 @@ impl Linker {
     /// * Verify that all referenced modules exist, or are known to be provided at runtime
     /// * Verify that all referenced functions exist, or are known to be provided at runtime, and
     ///   that the signature known to the caller matches the actual definition.
+    /// * Verify that all references to library modules are to functions exported by that library
     /// * Verifies that the entrypoint, if set, is valid
     /// * Verify that there are no cycles in the call graph, i.e. that there is no recursion present
     /// * Verify that all references to global symbols have corresponding definitions
@@ -392,6 +426,23 @@ impl Linker {
 
             // If a referenced module is not being linked, raise an error
             if !is_linked {
+                // References to library modules are resolved against the library
+                if let Some(library) = self.libraries.get(&node.module) {
+                    let signature =
+                        library.get(&node.function).ok_or(LinkerError::MissingFunction(node))?;
+                    // All dependents are necessarily in another module, so the function
+                    // must be exported from the library
+                    if !signature.is_public() {
+                        return Err(LinkerError::LinkageMismatch(node));
+                    }
+                    // We can only verify the signature if it is known
+                    let is_typed = signature.arity() > 0 || !signature.results().is_empty();
+                    if is_typed {
+                        self.verify_dependents(node, signature, true)?;
+                    }
+                    continue;
+                }
+
                 // However we ignore standard library/intrinsic modules in this check,
                 // as they are known to be provided at runtime.
                 //
@@ -412,25 +463,7 @@ impl Linker {
             let is_externally_linkable = function.is_public();
 
             // Next, visit all of the dependent functions, and ensure their signatures match
-            for dependent_id in self.callgraph.neighbors_directed(node, Direction::Incoming) {
-                // If the dependent is in another module, but the function has internal linkage,
-                // raise an error
-                if dependent_id.module != node.module && !is_externally_linkable {
-                    return Err(LinkerError::LinkageMismatch(node));
-                }
-                // Otherwise, make sure the signatures match
-                let dependent_module = &self.pending[&dependent_id.module];
-                let dependent_function = dependent_module
-                    .function(dependent_id.function)
-                    .expect("dependency graph is outdated");
-                let external_ref =
-                    dependent_function.dfg.get_import(&node).expect("dependency graph is outdated");
-                verify_matching_signature(
-                    function.id,
-                    &function.signature,
-                    &external_ref.signature,
-                )?;
-            }
+            self.verify_dependents(function.id, &function.signature, is_externally_linkable)?;
         }
 
         // Verify global symbol references, and garbage collect unused globals
@@ -469,6 +502,38 @@ impl Linker {
         Ok(self.program)
     }
 
+    /// Visit all of the functions which depend on `callee`, and ensure that the signatures
+    /// they expect match `signature`, the actual signature of `callee`.
+    ///
+    /// If `is_externally_linkable` is false, a [LinkerError] is also raised if any of the
+    /// dependents are in another module.
+    fn verify_dependents(
+        &self,
+        callee: FunctionIdent,
+        signature: &Signature,
+        is_externally_linkable: bool,
+    ) -> Result<(), LinkerError> {
+        for dependent_id in self.callgraph.neighbors_directed(callee, Direction::Incoming) {
+            // If the dependent is in another module, but the function has internal linkage,
+            // raise an error
+            if dependent_id.module != callee.module && !is_externally_linkable {
+                return Err(LinkerError::LinkageMismatch(callee));
+            }
+            // Otherwise, make sure the signatures match
+            let dependent_module = &self.pending[&dependent_id.module];
+            let dependent_function = dependent_module
+                .function(dependent_id.function)
+                .expect("dependency graph is outdated");
+            let external_ref = dependent_function
+                .dfg
+                .get_import(&callee)
+                .expect("dependency graph is outdated");
+            verify_matching_signature(callee, signature, &external_ref.signature)?;
+        }
+
+        Ok(())
+    }
+
     /// Programs we construct may depend on one or more predefined globals/intrinsics
     /// that are provided by the compiler in order to support common functionality, such
     /// as memory management primitives. This function handles defining these prior to
@@ -124,6 +124,7 @@ impl crate::pass::AnalysisKey for Program {
 /// [Program].
 pub struct ProgramBuilder<'a> {
     modules: std::collections::BTreeMap<Ident, Box<Module>>,
+    libraries: std::collections::BTreeMap<Ident, Vec<(Ident, Signature)>>,
     entry: Option<FunctionIdent>,
     diagnostics: &'a miden_diagnostics::DiagnosticsHandler,
 }
@@ -131,6 +132,7 @@ impl<'a> ProgramBuilder<'a> {
     pub fn new(diagnostics: &'a miden_diagnostics::DiagnosticsHandler) -> Self {
         Self {
             modules: Default::default(),
+            libraries: Default::default(),
             entry: None,
             diagnostics,
         }
@@ -158,7 +160,7 @@ impl<'a> ProgramBuilder<'a> {
     /// Returns `Err` if a module with the same name already exists
     pub fn add_module(&mut self, module: Box<Module>) -> Result<(), ModuleConflictError> {
         let module_name = module.name;
-        if self.modules.contains_key(&module_name) {
+        if self.modules.contains_key(&module_name) || self.libraries.contains_key(&module_name) {
             return Err(ModuleConflictError(module_name));
         }
 
@@ -167,6 +169,29 @@ impl<'a> ProgramBuilder<'a> {
         Ok(())
     }
 
+    /// Add a module provided by a precompiled library to link the [Program] against.
+    ///
+    /// The module itself will not be part of the final [Program], but references to it
+    /// will be resolved against `functions`, see [Linker::add_library_module] for details.
+    ///
+    /// Returns `Err` if a module with the same name already exists
+    pub fn add_library_module<I>(
+        &mut self,
+        name: Ident,
+        functions: I,
+    ) -> Result<(), ModuleConflictError>
+    where
+        I: IntoIterator<Item = (Ident, Signature)>,
+    {
+        if self.modules.contains_key(&name) || self.libraries.contains_key(&name) {
+            return Err(ModuleConflictError(name));
+        }
+
+        self.libraries.insert(name, functions.into_iter().collect());
+
+        Ok(())
+    }
+
     /// Start building a [Module] with the given name.
     ///
     /// When the builder is done, the resulting [Module] will be inserted
@@ -191,6 +216,10 @@ impl<'a> ProgramBuilder<'a> {
             linker.with_entrypoint(entry)?;
         }
 
+        for (name, functions) in self.libraries.into_iter() {
+            linker.add_library_module(name, functions)?;
+        }
+
         for (_, module) in self.modules.into_iter() {
             linker.add(module)?;
         }
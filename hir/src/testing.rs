@@ -34,7 +34,7 @@ impl TestContext {
 
         let session = Session::new(
             Default::default(),
-            InputFile::from_path("test.hir").unwrap(),
+            [InputFile::from_path("test.hir").unwrap()],
             None,
             None,
             None,
@@ -142,3 +142,51 @@ fn linker_test() {
         .link()
         .expect("failed to link program");
 }
+
+/// Test that references to functions provided by precompiled libraries are resolved
+/// against the library, rather than the modules being linked
+#[test]
+fn linker_library_test() {
+    let context = TestContext::default();
+
+    let link_with_library = |functions: Vec<(Ident, Signature)>| {
+        let mut builder = ProgramBuilder::new(&context.session.diagnostics);
+        {
+            let mut mb = builder.module("test");
+            let sig = Signature::new([], [AbiParam::new(Type::Felt)]);
+            let mut fb = mb.function("main", sig).expect("unexpected symbol conflict");
+            let foo = fb
+                .import_function("mylib", "foo", Signature::new([], [AbiParam::new(Type::Felt)]))
+                .unwrap();
+            let call = fb.ins().call(foo, &[], SourceSpan::UNKNOWN);
+            let result = fb.first_result(call);
+            fb.ins().ret(Some(result), SourceSpan::UNKNOWN);
+            fb.build().expect("unexpected validation error, see diagnostics output");
+            mb.build().expect("unexpected error building test module");
+        }
+        builder
+            .add_library_module(Ident::from("mylib"), functions)
+            .expect("unexpected module conflict");
+        builder.link()
+    };
+
+    // Library procedures carry no type information, so only the linkage is checked
+    let foo = Ident::from("foo");
+    let untyped = Signature::new([], []);
+    link_with_library(vec![(foo, untyped.clone())]).expect("failed to link program");
+
+    // If the signature is known, it must match the external declaration
+    let typed = Signature::new([AbiParam::new(Type::U32)], [AbiParam::new(Type::Felt)]);
+    let err = link_with_library(vec![(foo, typed)]).unwrap_err();
+    assert!(matches!(err, LinkerError::SignatureMismatch(_)), "unexpected error: {err}");
+
+    // Procedures which are not exported from the library cannot be referenced
+    let mut internal = untyped;
+    internal.linkage = Linkage::Internal;
+    let err = link_with_library(vec![(foo, internal)]).unwrap_err();
+    assert!(matches!(err, LinkerError::LinkageMismatch(_)), "unexpected error: {err}");
+
+    // Functions not defined by the library are reported as missing
+    let err = link_with_library(vec![]).unwrap_err();
+    assert!(matches!(err, LinkerError::MissingFunction(_)), "unexpected error: {err}");
+}
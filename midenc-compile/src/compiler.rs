@@ -3,18 +3,21 @@ use std::{path::PathBuf, sync::Arc};
 use clap::{Args, ColorChoice};
 use miden_diagnostics::{term::termcolor::ColorChoice as MDColorChoice, Emitter};
 use midenc_session::{
-    InputFile, Options, OutputFile, OutputType, OutputTypeSpec, OutputTypes, ProjectType, Session,
-    TargetEnv, VerbosityFlag, Warnings,
+    InputFile, OptLevel, Options, OutputFile, OutputType, OutputTypeSpec, OutputTypes, ProjectType,
+    Session, TargetEnv, VerbosityFlag, Warnings,
 };
 
 /// Compile a program from WebAssembly or Miden IR, to Miden Assembly.
 #[derive(Debug, Args)]
 pub struct Compiler {
-    /// The input file to compile
+    /// The input file(s) to compile
+    ///
+    /// When more than one input is given, each is parsed and transformed separately,
+    /// and the resulting modules are then linked together into a single program.
     ///
     /// You may specify `-` to read from stdin, otherwise you must provide a path
     #[arg(required(true), value_name = "FILE")]
-    input: InputFile,
+    inputs: Vec<InputFile>,
     /// Specify what type and level of informational output to emit
     #[arg(
         long = "verbose",
@@ -47,7 +50,8 @@ pub struct Compiler {
     target: TargetEnv,
     /// Tells the compiler to produce an executable Miden program
     ///
-    /// When the target is `base` or `rollup`, this defaults to true
+    /// When the target is `base` or `rollup`, this defaults to true. It is an error to compile
+    /// a program without an entrypoint, use `--lib` to compile a library instead.
     #[arg(
         long = "exe",
         default_value_t = true,
@@ -57,6 +61,9 @@ pub struct Compiler {
     is_program: bool,
     /// Tells the compiler to produce a Miden library
     ///
+    /// A library is emitted as one `.masl` file per root namespace of the compiled modules,
+    /// along with a manifest of the procedures they export.
+    ///
     /// When the target is `emu`, this defaults to true
     #[arg(
         long = "lib",
@@ -66,6 +73,40 @@ pub struct Compiler {
         help_heading = "Compiler"
     )]
     is_library: bool,
+    /// Specify the level of optimization to apply to the compiled program
+    ///
+    /// The conventional shorthand for each level may also be used, i.e. `-O0`, `-O1`, `-O2`,
+    /// `-O3`, `-Os`, and `-Oz`.
+    #[arg(
+        long = "opt-level",
+        short = 'O',
+        value_enum,
+        value_name = "LEVEL",
+        next_line_help(true),
+        default_value_t = OptLevel::default(),
+        help_heading = "Compiler"
+    )]
+    optimize: OptLevel,
+    /// Add `<dir>` to the list of paths searched for libraries to link against
+    #[arg(
+        long = "search-path",
+        short = 'L',
+        value_name = "DIR",
+        help_heading = "Linker"
+    )]
+    search_paths: Vec<PathBuf>,
+    /// Link the compiled program against the Miden Assembly library `<name>`
+    ///
+    /// The library is expected to be found in a file named `<name>.masl` in one of the
+    /// library search paths. Functions referenced by the program, but not defined in any
+    /// of its inputs, will be resolved against the procedures exported by this library.
+    #[arg(
+        long = "link-library",
+        short = 'l',
+        value_name = "NAME",
+        help_heading = "Linker"
+    )]
+    link_libraries: Vec<String>,
     /// Write all intermediate compiler artifacts to `<dir>`
     ///
     /// Defaults to a directory named `target` in the current working directory
@@ -110,6 +151,12 @@ pub struct Compiler {
     /// Print the IR after running a specific pass
     #[arg(long, value_name = "PASS", help_heading = "Passes")]
     print_ir_after_pass: Option<String>,
+    /// Print the pipeline of rewrite passes that will be applied to each module
+    ///
+    /// The pipeline is determined by the optimization level, unless passes are
+    /// explicitly requested on the command line.
+    #[arg(long, default_value_t = false, help_heading = "Passes")]
+    print_pipeline: bool,
 }
 impl Compiler {
     /// Use this configuration to obtain a [Session] used for compilation
@@ -138,9 +185,13 @@ impl Compiler {
             .with_color(color)
             .with_verbosity(self.verbosity.into())
             .with_warnings(self.warn)
+            .with_optimization(self.optimize)
             .with_output_types(output_types);
+        options.search_paths = self.search_paths;
+        options.link_libraries = self.link_libraries;
         options.print_ir_after_all = self.print_ir_after_all;
         options.print_ir_after_pass = self.print_ir_after_pass;
+        options.print_pipeline = self.print_pipeline;
 
         let output_file = match self.output_file {
             Some(path) => Some(OutputFile::Real(path)),
@@ -150,7 +201,7 @@ impl Compiler {
 
         Session::new(
             self.target,
-            self.input,
+            self.inputs,
             self.output_dir,
             output_file,
             Some(tmp_dir),
@@ -160,3 +211,67 @@ impl Compiler {
         .with_project_type(project_type)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use miden_hir::pass::PassInfo;
+
+    use super::*;
+    use crate::ApplyRewritesStage;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        compiler: Compiler,
+    }
+
+    const INPUT: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../hir/src/parser/tests/input/test.hir");
+
+    fn parse(args: &[&str]) -> Session {
+        let args = ["midenc"].iter().chain(args.iter()).chain([INPUT].iter());
+        let cli = Cli::try_parse_from(args).expect("invalid arguments");
+        cli.compiler.into_session(None)
+    }
+
+    #[test]
+    fn opt_level_parsing() {
+        let cases = [
+            (&[][..], OptLevel::None),
+            (&["-O0"][..], OptLevel::None),
+            (&["-O1"][..], OptLevel::Basic),
+            (&["-O2"][..], OptLevel::Balanced),
+            (&["-O3"][..], OptLevel::Max),
+            (&["-Os"][..], OptLevel::Size),
+            (&["-Oz"][..], OptLevel::SizeMin),
+            (&["--opt-level", "balanced"][..], OptLevel::Balanced),
+            (&["--opt-level=size-min"][..], OptLevel::SizeMin),
+        ];
+        for (args, expected) in cases {
+            let session = parse(args);
+            assert_eq!(session.options.optimize, expected, "unexpected level for {args:?}");
+        }
+
+        assert!(Cli::try_parse_from(["midenc", "-O4", INPUT]).is_err());
+    }
+
+    /// The optimization level selects the optimizations which precede the rewrites required
+    /// for code generation
+    #[test]
+    fn opt_level_selects_pipeline() {
+        let codegen = ApplyRewritesStage::pipeline(&parse(&["-O0"]));
+        assert!(!codegen.is_empty());
+
+        for level in ["-O1", "-O2", "-O3", "-Os", "-Oz"] {
+            let pipeline = ApplyRewritesStage::pipeline(&parse(&[level]));
+            assert!(pipeline.len() > codegen.len(), "expected {level} to add optimizations");
+            assert!(pipeline.ends_with(&codegen));
+        }
+
+        let inline = <miden_hir_transform::InlineFunctions as PassInfo>::FLAG;
+        assert!(!ApplyRewritesStage::pipeline(&parse(&["-O1"])).contains(&inline));
+        assert!(ApplyRewritesStage::pipeline(&parse(&["-O2"])).contains(&inline));
+        assert!(!ApplyRewritesStage::pipeline(&parse(&["-Oz"])).contains(&inline));
+    }
+}
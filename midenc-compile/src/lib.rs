@@ -1,12 +1,14 @@
 mod compiler;
+mod library;
 mod stage;
 mod stages;
 
 use std::sync::Arc;
 
 use miden_codegen_masm as masm;
+use miden_diagnostics::Severity;
 use miden_hir::{pass::AnalysisManager, Symbol};
-use midenc_session::{OutputType, Session};
+use midenc_session::{OutputType, ProjectType, Session};
 
 pub use self::{compiler::Compiler, stages::Compiled};
 use self::{stage::Stage, stages::*};
@@ -48,6 +50,12 @@ pub enum CompilerError {
     /// An error occurred while emitting a MASL library
     #[error(transparent)]
     Masl(#[from] miden_assembly::LibraryError),
+    /// An error occurred while assembling the compiled output
+    #[error(transparent)]
+    Assembler(#[from] miden_assembly::AssemblyError),
+    /// An executable was requested from a program without an entrypoint
+    #[error(transparent)]
+    MissingEntrypoint(#[from] masm::MissingEntrypointError),
     /// An error occurred when reading a file
     #[error(transparent)]
     Io(#[from] std::io::Error),
@@ -122,23 +130,32 @@ pub fn register_flags(cmd: clap::Command) -> clap::Command {
 
 /// Run the compiler using the provided [Session]
 pub fn compile(session: Arc<Session>) -> CompilerResult<()> {
-    let inputs = vec![session.input.clone()];
+    let inputs = session.inputs.clone();
     let mut analyses = AnalysisManager::new();
     match compile_inputs(inputs, &mut analyses, &session) {
         Ok(Compiled::Program(ref program)) => {
-            if let Some(path) = session.emit_to(OutputType::Masl, None) {
-                use miden_assembly::utils::Serializable;
-                let masl = program.to_masl_library(session.name(), &session.codemap)?;
-                let bytes = masl.to_bytes();
-                std::fs::write(&path, bytes)?;
+            if matches!(session.project_type, ProjectType::Program) && !program.is_executable() {
+                session
+                    .diagnostics
+                    .diagnostic(Severity::Error)
+                    .with_message("unable to compile an executable program")
+                    .with_note("no entrypoint was found in the program")
+                    .with_note(
+                        "declare a function with the `entrypoint` attribute, or use `--lib` to \
+                         compile a library instead",
+                    )
+                    .emit();
+                return Err(CompilerError::Reported);
             }
+            library::emit_libraries(program, &session)?;
+            emit_cost_report(program, &session)?;
             if session.should_emit(OutputType::Masm) {
                 for module in program.modules() {
                     session.emit(module)?;
                 }
                 if program.is_executable() {
                     use miden_assembly::LibraryPath;
-                    let ast = program.to_program_ast(&session.codemap);
+                    let ast = program.to_program_ast(&session.codemap)?;
                     if let Some(path) = session
                         .emit_to(OutputType::Masm, Some(Symbol::intern(LibraryPath::EXEC_PATH)))
                     {
@@ -154,12 +171,8 @@ pub fn compile(session: Arc<Session>) -> CompilerResult<()> {
             for module in modules.into_iter() {
                 program.insert(module);
             }
-            if let Some(path) = session.emit_to(OutputType::Masl, None) {
-                use miden_assembly::utils::Serializable;
-                let masl = program.to_masl_library(session.name(), &session.codemap)?;
-                let bytes = masl.to_bytes();
-                std::fs::write(&path, bytes)?;
-            }
+            library::emit_libraries(&program, &session)?;
+            emit_cost_report(&program, &session)?;
             if session.should_emit(OutputType::Masm) {
                 for module in program.modules() {
                     session.emit(module)?;
@@ -177,9 +190,19 @@ pub fn compile(session: Arc<Session>) -> CompilerResult<()> {
     Ok(())
 }
 
+/// Write the estimated cycle cost of each function in `program`, if requested
+fn emit_cost_report(program: &masm::Program, session: &Session) -> CompilerResult<()> {
+    if session.should_emit(OutputType::Cost) {
+        let report = masm::CostReport::new(Symbol::intern(session.name().as_str()), program);
+        session.emit(&report)?;
+    }
+
+    Ok(())
+}
+
 /// Same as `compile`, but return compiled artifacts to the caller
 pub fn compile_to_memory(session: Arc<Session>) -> CompilerResult<Compiled> {
-    let inputs = vec![session.input.clone()];
+    let inputs = session.inputs.clone();
     let mut analyses = AnalysisManager::new();
     match compile_inputs(inputs, &mut analyses, &session) {
         Ok(output) => Ok(output),
@@ -191,11 +214,41 @@ pub fn compile_to_memory(session: Arc<Session>) -> CompilerResult<Compiled> {
     }
 }
 
+/// Load all of the Miden Assembly libraries which the current session links against
+///
+/// See [Session::libraries] for details on how the set of libraries is determined.
+pub fn load_libraries(session: &Session) -> CompilerResult<Vec<masm::Program>> {
+    session
+        .libraries()?
+        .into_iter()
+        .map(|path| masm::Program::from_masl(path, &session.codemap).map_err(CompilerError::from))
+        .collect()
+}
+
 fn compile_inputs(
     inputs: Vec<midenc_session::InputFile>,
     analyses: &mut AnalysisManager,
     session: &Session,
 ) -> CompilerResult<Compiled> {
+    use midenc_session::FileType;
+
+    // Libraries given as inputs are linked against, rather than compiled
+    let inputs = inputs
+        .into_iter()
+        .filter(|input| input.file_type() != FileType::Masl)
+        .collect::<Vec<_>>();
+
+    if session.options.print_pipeline {
+        let diagnostic = session
+            .diagnostics
+            .diagnostic(Severity::Note)
+            .with_message(format!("rewrite pipeline (opt-level = {})", session.options.optimize));
+        ApplyRewritesStage::pipeline(session)
+            .into_iter()
+            .fold(diagnostic, |diagnostic, name| diagnostic.with_note(name))
+            .emit();
+    }
+
     let mut stages = ParseStage
         .next(SemanticAnalysisStage)
         .next_optional(ApplyRewritesStage)
@@ -0,0 +1,278 @@
+use miden_assembly::{utils::Serializable, Assembler, AssemblyContext, Library, MaslLibrary};
+use miden_codegen_masm as masm;
+use miden_hir::{formatter::PrettyPrint, Symbol};
+use miden_stdlib::StdLibrary;
+use midenc_session::{OutputType, Session};
+use toml::{value::Table, Value};
+
+use crate::CompilerResult;
+
+/// Write the modules of `program` as a set of Miden Assembly libraries, one for each root
+/// namespace, along with a manifest describing the procedures they export.
+///
+/// Each HIR module is emitted as a module of its own, under a namespace derived from its name,
+/// so a module `foo` is emitted as the library `foo`. A nested module such as `foo::bar` is
+/// emitted as the module `foo::bar` of the library `foo`, rather than as a library of its own,
+/// as the namespace of a library must be a single identifier, and an assembler cannot load two
+/// libraries with the same namespace.
+///
+/// Each library is written to `<namespace>.masl`, and the manifest to `<name>.manifest.toml`,
+/// where `<name>` is the name of the current session, in the directory of the requested `masl`
+/// output. Nothing is written if `masl` output was not requested, or was requested on stdout.
+pub fn emit_libraries(program: &masm::Program, session: &Session) -> CompilerResult<()> {
+    let Some(path) = session.emit_to(OutputType::Masl, None) else {
+        return Ok(());
+    };
+
+    for masl in program.to_masl_libraries(&session.codemap)? {
+        let name = Symbol::intern(masl.root_ns().as_str());
+        let path = session.emit_to(OutputType::Masl, Some(name)).unwrap_or_else(|| path.clone());
+        std::fs::write(&path, masl.to_bytes())?;
+    }
+
+    let manifest = build_manifest(program, session)?;
+    let manifest = toml::to_string(&manifest).map_err(anyhow::Error::new)?;
+    std::fs::write(path.with_file_name(format!("{}.manifest.toml", session.name())), manifest)?;
+
+    Ok(())
+}
+
+/// Construct the manifest of the procedures exported by the modules of `program`.
+///
+/// The manifest contains an entry for each exported procedure, consisting of the module in which
+/// it is defined, its name, the digest of its MAST root, and its signature in Miden IR, e.g.:
+///
+/// ```toml
+/// [[procedures]]
+/// module = "foo::bar"
+/// name = "add"
+/// digest = "0x..."
+/// signature = "(param u32) (param u32) (result u32)"
+/// ```
+///
+/// Computing the digests requires assembling the program, so the standard library, and any
+/// libraries linked against by the current session, must be available. Kernel procedures are
+/// not included, as they are invoked by name via `syscall`, rather than by MAST root.
+fn build_manifest(program: &masm::Program, session: &Session) -> CompilerResult<Value> {
+    let mut assembler = Assembler::default().with_library(&StdLibrary::default())?;
+    for path in session.libraries()? {
+        let library = MaslLibrary::read_from_file(path)?;
+        assembler = assembler.with_library(&library)?;
+    }
+    let assembler = program.assemble_kernel(assembler, &session.codemap)?;
+
+    let mut procedures = vec![];
+    for module in program.modules().filter(|m| !m.is_kernel()) {
+        let ast = module.to_module_ast(&session.codemap);
+        let digests = assembler.compile_module(
+            &ast.ast,
+            Some(&ast.path),
+            &mut AssemblyContext::for_module(false),
+        )?;
+        // The digests are given in the order the exported procedures are defined in the module
+        let exports = module.functions().filter(|f| f.signature.is_public());
+        for (function, digest) in exports.zip(digests) {
+            let digest = digest.to_bytes().iter().fold(String::from("0x"), |mut hex, byte| {
+                hex.push_str(&format!("{byte:02x}"));
+                hex
+            });
+            let mut procedure = Table::new();
+            procedure.insert("module".to_string(), Value::String(module.name.to_string()));
+            procedure.insert("name".to_string(), Value::String(function.name.function.to_string()));
+            procedure.insert("digest".to_string(), Value::String(digest));
+            procedure.insert(
+                "signature".to_string(),
+                Value::String(function.signature.to_pretty_string()),
+            );
+            procedures.push(Value::Table(procedure));
+        }
+    }
+
+    let mut manifest = Table::new();
+    manifest.insert("procedures".to_string(), Value::Array(procedures));
+    Ok(Value::Table(manifest))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, sync::Arc};
+
+    use miden_diagnostics::{CaptureEmitter, Emitter};
+    use midenc_session::{InputFile, Options, OutputTypes, ProjectType};
+
+    use super::*;
+    use crate::CompilerError;
+
+    const MATH: &str = r#"
+(module #math
+    (func (export #double) (param u32) (result u32)
+        (block 0 (param v0 u32)
+            (let (v1 u32) (const.u32 2))
+            (let (v2 u32) (mul.wrapping v0 v1))
+            (ret v2))
+    )
+
+    (func (export #add) (param u32) (param u32) (result u32)
+        (block 0 (param v0 u32) (param v1 u32)
+            (let (v2 u32) (add.wrapping v0 v1))
+            (ret v2))
+    )
+
+    (func #square (param u32) (result u32)
+        (block 0 (param v0 u32)
+            (let (v1 u32) (mul.wrapping v0 v0))
+            (ret v1))
+    )
+)
+"#;
+
+    const UTIL: &str = r#"
+(module #util
+    (func (export #inc) (param u32) (result u32)
+        (block 0 (param v0 u32)
+            (let (v1 u32) (const.u32 1))
+            (let (v2 u32) (add.wrapping v0 v1))
+            (ret v2))
+    )
+)
+"#;
+
+    /// Create a session which compiles `sources`, written to disk as `<name>.hir` files in a
+    /// directory named after `test`, to which all output is also written.
+    fn session(
+        test: &str,
+        sources: &[(&str, &str)],
+        project_type: ProjectType,
+        emitter: Option<Arc<dyn Emitter>>,
+    ) -> (PathBuf, Arc<Session>) {
+        let dir = std::env::temp_dir().join(test);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        fs::create_dir_all(&dir).unwrap();
+        let inputs = sources
+            .iter()
+            .map(|(name, source)| {
+                let path = dir.join(format!("{name}.hir"));
+                fs::write(&path, source).unwrap();
+                InputFile::from_path(path).unwrap()
+            })
+            .collect();
+
+        let mut output_types = OutputTypes::default();
+        output_types.insert(OutputType::Masl, None);
+        let options = Options::new(dir.clone()).with_output_types(output_types);
+        let session = Session::new(
+            Default::default(),
+            inputs,
+            Some(dir.clone()),
+            None,
+            Some(dir.clone()),
+            options,
+            emitter,
+        )
+        .with_project_type(project_type);
+        (dir, Arc::new(session))
+    }
+
+    fn get<'a>(procedure: &'a Value, key: &str) -> &'a str {
+        procedure
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_else(|| panic!("expected procedure to have a '{key}' field: {procedure}"))
+    }
+
+    #[test]
+    fn library_manifest() {
+        let (dir, session) = session(
+            "library_manifest",
+            &[("math", MATH), ("util", UTIL)],
+            ProjectType::Library,
+            None,
+        );
+        crate::compile(session).expect("compilation failed");
+
+        let manifest = fs::read_to_string(dir.join("math.manifest.toml")).unwrap();
+        let manifest = toml::from_str::<Value>(&manifest).expect("invalid manifest");
+        let procedures = manifest
+            .get("procedures")
+            .and_then(Value::as_array)
+            .expect("expected the manifest to contain a list of procedures")
+            .iter()
+            .filter(|procedure| matches!(get(procedure, "module"), "math" | "util"))
+            .collect::<Vec<_>>();
+
+        // Only exported procedures are listed, in the order they are defined
+        let listed = procedures
+            .iter()
+            .map(|procedure| {
+                (get(procedure, "module"), get(procedure, "name"), get(procedure, "signature"))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            listed,
+            [
+                ("math", "double", "(param u32) (result u32)"),
+                ("math", "add", "(param u32) (param u32) (result u32)"),
+                ("util", "inc", "(param u32) (result u32)"),
+            ]
+        );
+
+        // Each HIR module is emitted as a library of its own, and the digests in the manifest are
+        // those of the procedures it contains
+        for namespace in ["math", "util"] {
+            let library = MaslLibrary::read_from_file(dir.join(format!("{namespace}.masl")))
+                .expect("failed to read library");
+            assert_eq!(library.root_ns().as_str(), namespace);
+            let modules = library.modules().collect::<Vec<_>>();
+            assert_eq!(modules.len(), 1);
+            let module = modules[0];
+            assert_eq!(module.path.as_str(), namespace);
+
+            let digests = Assembler::default()
+                .compile_module(
+                    &module.ast,
+                    Some(&module.path),
+                    &mut AssemblyContext::for_module(false),
+                )
+                .expect("failed to assemble library module");
+            let expected = procedures
+                .iter()
+                .filter(|procedure| get(procedure, "module") == namespace)
+                .map(|procedure| get(procedure, "digest").to_string())
+                .collect::<Vec<_>>();
+            let digests = digests
+                .iter()
+                .map(|digest| {
+                    digest.to_bytes().iter().fold(String::from("0x"), |mut hex, byte| {
+                        hex.push_str(&format!("{byte:02x}"));
+                        hex
+                    })
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(digests, expected);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn executable_without_entrypoint_is_diagnosed() {
+        let emitter = Arc::new(CaptureEmitter::default());
+        let (dir, session) = session(
+            "executable_without_entrypoint_is_diagnosed",
+            &[("math", MATH)],
+            ProjectType::Program,
+            Some(emitter.clone()),
+        );
+
+        let result = crate::compile(session);
+        assert!(matches!(result, Err(CompilerError::Reported)));
+        let captured = emitter.captured();
+        assert!(captured.contains("unable to compile an executable program"), "{captured}");
+        assert!(captured.contains("no entrypoint was found in the program"), "{captured}");
+        assert!(!dir.join("math.masl").exists(), "no library should be emitted");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -6,6 +6,22 @@ pub enum Compiled {
     Program(Box<masm::Program>),
     Modules(Vec<Box<masm::Module>>),
 }
+impl Compiled {
+    /// Convert this output into a [masm::Program], placing any unlinked modules
+    /// in a new, empty program.
+    pub fn into_program(self) -> Box<masm::Program> {
+        match self {
+            Self::Program(program) => program,
+            Self::Modules(modules) => {
+                let mut program = Box::new(masm::Program::new());
+                for module in modules.into_iter() {
+                    program.insert(module);
+                }
+                program
+            }
+        }
+    }
+}
 
 /// Perform code generation on the possibly-linked output of previous stages
 pub struct CodegenStage;
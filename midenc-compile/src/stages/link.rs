@@ -23,6 +23,14 @@ impl Stage for LinkerStage {
     ) -> CompilerResult<Self::Output> {
         if session.should_link() {
             let mut builder = hir::ProgramBuilder::new(&session.diagnostics);
+            for library in crate::load_libraries(session)? {
+                for module in library.modules() {
+                    let functions = module
+                        .functions()
+                        .map(|function| (function.name.function, function.signature.clone()));
+                    builder.add_library_module(module.name, functions)?;
+                }
+            }
             for module in input.into_iter() {
                 builder.add_module(module)?;
             }
@@ -1,8 +1,20 @@
-use miden_hir::RewritePassRegistration;
+use miden_diagnostics::Severity;
+use miden_hir::{
+    pass::{ModuleRewritePassAdapter, PassInfo},
+    RewritePassRegistration,
+};
 use miden_hir_transform as transforms;
+use midenc_session::OptLevel;
 
 use super::*;
 
+/// The rewrites which must be applied to every module prior to code generation, in order
+const CODEGEN_PIPELINE: &[&str] = &[
+    <transforms::SplitCriticalEdges as PassInfo>::FLAG,
+    <transforms::Treeify as PassInfo>::FLAG,
+    <transforms::InlineBlocks as PassInfo>::FLAG,
+];
+
 /// This stage applies all registered (and enabled) module-scoped rewrites to input HIR module(s)
 pub struct ApplyRewritesStage;
 impl Stage for ApplyRewritesStage {
@@ -19,9 +31,35 @@ impl Stage for ApplyRewritesStage {
         analyses: &mut AnalysisManager,
         session: &Session,
     ) -> CompilerResult<Self::Output> {
-        use miden_hir::pass::{ModuleRewritePassAdapter, RewriteSet};
+        use miden_hir::pass::RewriteSet;
+
+        let mut rewrites = RewriteSet::default();
+        for name in Self::pipeline(session) {
+            let Some(rewrite) = get_rewrite(name) else {
+                session
+                    .diagnostics
+                    .diagnostic(Severity::Error)
+                    .with_message(format!("invalid rewrite pipeline: no rewrite named '{name}'"))
+                    .emit();
+                return Err(CompilerError::Reported);
+            };
+            rewrites.extend([rewrite]);
+        }
 
-        // Get all registered module rewrites and apply them in the order they appear
+        rewrites.apply(&mut input, analyses, session)?;
+
+        Ok(input)
+    }
+}
+impl ApplyRewritesStage {
+    /// Get the names of the module rewrites to apply, in order, for the current session.
+    ///
+    /// If any rewrites were explicitly enabled on the command line, only those rewrites are
+    /// applied, in the order they appear. Otherwise, when conversion to Miden Assembly is
+    /// enabled, the pipeline consists of the optimizations selected by the optimization level,
+    /// followed by the rewrites required for code generation.
+    pub fn pipeline(session: &Session) -> Vec<&'static str> {
+        // Get all registered module rewrites, and select those which were enabled
         let mut registered = vec![];
         let matches = session.matches();
         for rewrite in inventory::iter::<RewritePassRegistration<hir::Module>> {
@@ -30,7 +68,7 @@ impl Stage for ApplyRewritesStage {
                 if let Some(index) = matches.index_of(flag) {
                     let is_enabled = matches.get_flag(flag);
                     if is_enabled {
-                        registered.push((index, rewrite.get()));
+                        registered.push((index, flag));
                     }
                 }
             }
@@ -41,19 +79,119 @@ impl Stage for ApplyRewritesStage {
         // then we must ensure that the basic transformation passes are applied.
         //
         // Otherwise, assume that the intent was to skip those rewrites and do not add them
-        let mut rewrites = RewriteSet::default();
         if registered.is_empty() {
+            let mut pipeline = vec![];
             if session.should_codegen() {
-                rewrites.push(ModuleRewritePassAdapter::new(transforms::SplitCriticalEdges));
-                rewrites.push(ModuleRewritePassAdapter::new(transforms::Treeify));
-                rewrites.push(ModuleRewritePassAdapter::new(transforms::InlineBlocks));
+                pipeline.extend_from_slice(optimization_pipeline(session.options.optimize));
+                pipeline.extend_from_slice(CODEGEN_PIPELINE);
             }
+            pipeline
         } else {
-            rewrites.extend(registered.into_iter().map(|(_, r)| r));
+            registered.into_iter().map(|(_, flag)| flag).collect()
         }
+    }
+}
 
-        rewrites.apply(&mut input, analyses, session)?;
+/// Get an instance of the module rewrite registered as `name`, if there is one
+fn get_rewrite(name: &str) -> Option<Box<dyn RewritePass<Entity = hir::Module>>> {
+    // The rewrites required for code generation are constructed directly, this also ensures
+    // that the rewrites registered by `miden_hir_transform` are linked into the compiler
+    match name {
+        name if name == <transforms::SplitCriticalEdges as PassInfo>::FLAG => {
+            Some(Box::new(ModuleRewritePassAdapter::new(transforms::SplitCriticalEdges)))
+        }
+        name if name == <transforms::Treeify as PassInfo>::FLAG => {
+            Some(Box::new(ModuleRewritePassAdapter::new(transforms::Treeify)))
+        }
+        name if name == <transforms::InlineBlocks as PassInfo>::FLAG => {
+            Some(Box::new(ModuleRewritePassAdapter::new(transforms::InlineBlocks)))
+        }
+        name => inventory::iter::<RewritePassRegistration<hir::Module>>
+            .into_iter()
+            .find(|rewrite| rewrite.name() == name)
+            .map(|rewrite| rewrite.get()),
+    }
+}
 
-        Ok(input)
+/// Get the names of the optimization passes to apply at `level`, in order
+///
+/// These are run prior to the rewrites required for code generation.
+///
+/// * `Basic` only applies the cheap local optimizations, i.e. promotion of locals to values,
+/// constant propagation, and dead code elimination.
+/// * `Balanced` adds function inlining and value numbering.
+/// * `Max` additionally runs a second round of the scalar optimizations, to clean up the
+/// opportunities exposed by inlining and the first round.
+/// * `Size` runs the inliner last, once the bodies of its candidates have been simplified, so
+/// that its size estimates reflect the code that would actually be duplicated. The inliner
+/// itself is bounded by the size threshold for this level.
+/// * `SizeMin` never inlines, as it can only make code larger in the general case.
+fn optimization_pipeline(level: OptLevel) -> &'static [&'static str] {
+    const INLINE: &str = <transforms::InlineFunctions as PassInfo>::FLAG;
+    const PROMOTE: &str = <transforms::PromoteLocals as PassInfo>::FLAG;
+    const SCCP: &str = <transforms::SparseConditionalConstantPropagation as PassInfo>::FLAG;
+    const GVN: &str = <transforms::GlobalValueNumbering as PassInfo>::FLAG;
+    const DCE: &str = <transforms::DeadCodeElimination as PassInfo>::FLAG;
+
+    match level {
+        OptLevel::None => &[],
+        OptLevel::Basic => &[PROMOTE, SCCP, DCE],
+        OptLevel::Balanced => &[INLINE, PROMOTE, SCCP, GVN, DCE],
+        OptLevel::Max => &[INLINE, PROMOTE, SCCP, GVN, DCE, SCCP, GVN, DCE],
+        OptLevel::Size => &[PROMOTE, SCCP, GVN, DCE, INLINE, DCE],
+        OptLevel::SizeMin => &[PROMOTE, SCCP, GVN, DCE],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each optimization level selects a distinct pipeline
+    #[test]
+    fn optimization_pipeline_is_distinct_for_each_level() {
+        let levels = [
+            OptLevel::None,
+            OptLevel::Basic,
+            OptLevel::Balanced,
+            OptLevel::Max,
+            OptLevel::Size,
+            OptLevel::SizeMin,
+        ];
+        for (i, a) in levels.iter().enumerate() {
+            for b in levels[(i + 1)..].iter() {
+                assert_ne!(
+                    optimization_pipeline(*a),
+                    optimization_pipeline(*b),
+                    "expected the pipelines for '{a}' and '{b}' to differ"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn optimization_pipeline_for_each_level() {
+        const INLINE: &str = <transforms::InlineFunctions as PassInfo>::FLAG;
+
+        assert!(optimization_pipeline(OptLevel::None).is_empty());
+        assert!(!optimization_pipeline(OptLevel::Basic).contains(&INLINE));
+        assert_eq!(optimization_pipeline(OptLevel::Balanced).first(), Some(&INLINE));
+        assert_eq!(optimization_pipeline(OptLevel::Max).first(), Some(&INLINE));
+        assert!(
+            optimization_pipeline(OptLevel::Max).len()
+                > optimization_pipeline(OptLevel::Balanced).len()
+        );
+        // The size levels only inline after simplifying, if at all
+        assert_ne!(optimization_pipeline(OptLevel::Size).first(), Some(&INLINE));
+        assert!(optimization_pipeline(OptLevel::Size).contains(&INLINE));
+        assert!(!optimization_pipeline(OptLevel::SizeMin).contains(&INLINE));
+
+        // Every pass in each pipeline must be registered
+        for level in [OptLevel::Basic, OptLevel::Balanced, OptLevel::Max, OptLevel::Size] {
+            for name in optimization_pipeline(level) {
+                assert!(get_rewrite(name).is_some(), "no rewrite named '{name}'");
+            }
+        }
+        assert!(get_rewrite("no-such-rewrite").is_none());
     }
 }
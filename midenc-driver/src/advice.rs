@@ -0,0 +1,220 @@
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context};
+use miden_core::{
+    crypto::{
+        hash::RpoDigest,
+        merkle::{MerkleStore, MerkleTree},
+    },
+    Word,
+};
+use miden_hir::{Felt, StarkField};
+use miden_processor::AdviceInputs;
+use toml::Value;
+
+/// Load the advice inputs for a program from the TOML file at `path`, adding them to `inputs`.
+///
+/// Any advice stack given in the file is placed beneath the elements already on the advice stack
+/// of `inputs`.
+///
+/// The file may contain any of the following keys, all of which are optional:
+///
+/// ```toml
+/// # The initial contents of the advice stack, the first element is the top of the stack
+/// stack = [1, 2, 3]
+///
+/// # An entry in the advice map, keyed by a word
+/// [[map]]
+/// key = [1, 0, 0, 0]
+/// values = [4, 5, 6]
+///
+/// # A Merkle tree to add to the Merkle store, given as its leaves. The number of
+/// # leaves must be a power of two.
+/// [[merkle_tree]]
+/// leaves = [[1, 0, 0, 0], [2, 0, 0, 0]]
+/// ```
+///
+/// Field elements may be given either as integers, or as strings in decimal or `0x`-prefixed
+/// hexadecimal format. The latter is required for values which cannot be represented as a
+/// TOML integer, i.e. those larger than `i64::MAX`.
+pub fn load_advice_inputs(path: &Path, inputs: AdviceInputs) -> anyhow::Result<AdviceInputs> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("unable to read advice inputs from '{}'", path.display()))?;
+    parse_advice_inputs(&source, inputs)
+        .with_context(|| format!("invalid advice inputs file '{}'", path.display()))
+}
+
+/// Parse advice inputs from a string containing TOML, see [load_advice_inputs].
+pub fn parse_advice_inputs(source: &str, mut inputs: AdviceInputs) -> anyhow::Result<AdviceInputs> {
+    let table = match source.parse::<Value>()? {
+        Value::Table(table) => table,
+        _ => bail!("expected a table at the top level"),
+    };
+
+    for (key, value) in table.iter() {
+        match key.as_str() {
+            "stack" => {
+                let stack = parse_felts(value).context("invalid advice stack")?;
+                inputs = inputs.with_stack(stack);
+            }
+            "map" => {
+                let mut entries = vec![];
+                for entry in as_array(value, "map")? {
+                    let key = entry.get("key").ok_or_else(|| anyhow!("map entry has no key"))?;
+                    let key = RpoDigest::new(parse_word(key).context("invalid map key")?);
+                    let values = match entry.get("values") {
+                        Some(values) => parse_felts(values).context("invalid map values")?,
+                        None => vec![],
+                    };
+                    entries.push((key.into(), values));
+                }
+                inputs = inputs.with_map(entries);
+            }
+            "merkle_tree" => {
+                let mut store = MerkleStore::default();
+                for tree in as_array(value, "merkle_tree")? {
+                    let leaves =
+                        tree.get("leaves").ok_or_else(|| anyhow!("merkle tree has no leaves"))?;
+                    let leaves = as_array(leaves, "leaves")?
+                        .iter()
+                        .map(parse_word)
+                        .collect::<anyhow::Result<Vec<_>>>()
+                        .context("invalid merkle tree leaf")?;
+                    let tree = MerkleTree::new(leaves)
+                        .map_err(|err| anyhow!("invalid merkle tree: {err}"))?;
+                    store.extend(tree.inner_nodes());
+                }
+                inputs = inputs.with_merkle_store(store);
+            }
+            other => bail!("unrecognized key '{other}'"),
+        }
+    }
+
+    Ok(inputs)
+}
+
+fn as_array<'a>(value: &'a Value, name: &str) -> anyhow::Result<&'a [Value]> {
+    value
+        .as_array()
+        .map(|array| array.as_slice())
+        .ok_or_else(|| anyhow!("expected '{name}' to be an array"))
+}
+
+fn parse_word(value: &Value) -> anyhow::Result<Word> {
+    let felts = parse_felts(value)?;
+    Word::try_from(felts.as_slice())
+        .map_err(|_| anyhow!("expected a word of 4 field elements, got {}", felts.len()))
+}
+
+fn parse_felts(value: &Value) -> anyhow::Result<Vec<Felt>> {
+    as_array(value, "value")?.iter().map(parse_felt).collect()
+}
+
+fn parse_felt(value: &Value) -> anyhow::Result<Felt> {
+    let n = match value {
+        Value::Integer(n) => {
+            u64::try_from(*n).map_err(|_| anyhow!("invalid field element: {n} is negative"))?
+        }
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => s.parse::<u64>(),
+        }
+        .map_err(|err| anyhow!("invalid field element '{s}': {err}"))?,
+        other => bail!("expected a field element, got '{other}'"),
+    };
+    if n >= Felt::MODULUS {
+        bail!("invalid field element: {n} is larger than the field modulus");
+    }
+    Ok(Felt::new(n))
+}
+
+#[cfg(test)]
+mod tests {
+    use miden_core::crypto::merkle::NodeIndex;
+    use miden_hir::FieldElement;
+
+    use super::*;
+
+    /// Parse `source`, returning the error message, including its causes
+    fn parse_error(source: &str) -> String {
+        let err = parse_advice_inputs(source, AdviceInputs::default())
+            .expect_err("expected advice inputs to be invalid");
+        format!("{err:#}")
+    }
+
+    #[test]
+    fn advice_inputs_parsing() {
+        let source = r#"
+stack = [1, "2", "0x3"]
+
+[[map]]
+key = [1, 0, 0, 0]
+values = [4, 5, 6]
+
+[[merkle_tree]]
+leaves = [[1, 0, 0, 0], [2, 0, 0, 0]]
+"#;
+        let inputs = AdviceInputs::default().with_stack([Felt::new(9)]);
+        let inputs = parse_advice_inputs(source, inputs).expect("invalid advice inputs");
+
+        // The stack from the file is placed beneath the existing advice stack
+        assert_eq!(inputs.stack(), &[9, 1, 2, 3].map(Felt::new));
+
+        let key: Word = [Felt::new(1), Felt::ZERO, Felt::ZERO, Felt::ZERO];
+        assert_eq!(
+            inputs.mapped_values(&RpoDigest::new(key).into()),
+            Some(&[4, 5, 6].map(Felt::new)[..])
+        );
+
+        let leaves: Vec<Word> = vec![key, [Felt::new(2), Felt::ZERO, Felt::ZERO, Felt::ZERO]];
+        let tree = MerkleTree::new(leaves).unwrap();
+        let leaf = inputs
+            .merkle_store()
+            .get_node(tree.root(), NodeIndex::new(1, 0).unwrap())
+            .expect("expected the merkle tree to be in the store");
+        assert_eq!(leaf, RpoDigest::new(key));
+    }
+
+    #[test]
+    fn advice_inputs_invalid_stack() {
+        let err = parse_error("stack = 1");
+        assert!(err.contains("expected 'value' to be an array"), "{err}");
+        let err = parse_error("stack = [1, -2]");
+        assert!(err.contains("-2 is negative"), "{err}");
+        let err = parse_error("stack = [1.5]");
+        assert!(err.contains("expected a field element"), "{err}");
+        let err = parse_error(r#"stack = ["0x"]"#);
+        assert!(err.contains("invalid field element '0x'"), "{err}");
+        let err = parse_error(r#"stack = ["18446744069414584321"]"#);
+        assert!(err.contains("larger than the field modulus"), "{err}");
+    }
+
+    #[test]
+    fn advice_inputs_invalid_map() {
+        let err = parse_error("map = 1");
+        assert!(err.contains("expected 'map' to be an array"), "{err}");
+        let err = parse_error("[[map]]\nvalues = [1]");
+        assert!(err.contains("map entry has no key"), "{err}");
+        let err = parse_error("[[map]]\nkey = [1, 0, 0]");
+        assert!(err.contains("expected a word of 4 field elements, got 3"), "{err}");
+        let err = parse_error("[[map]]\nkey = [1, 0, 0, 0]\nvalues = [-1]");
+        assert!(err.contains("invalid map values"), "{err}");
+    }
+
+    #[test]
+    fn advice_inputs_invalid_merkle_tree() {
+        let err = parse_error("[[merkle_tree]]\nroot = 1");
+        assert!(err.contains("merkle tree has no leaves"), "{err}");
+        let err = parse_error("[[merkle_tree]]\nleaves = [[1, 0, 0, 0], [2, 0]]");
+        assert!(err.contains("invalid merkle tree leaf"), "{err}");
+        let err =
+            parse_error("[[merkle_tree]]\nleaves = [[1, 0, 0, 0], [2, 0, 0, 0], [3, 0, 0, 0]]");
+        assert!(err.contains("invalid merkle tree"), "{err}");
+    }
+
+    #[test]
+    fn advice_inputs_invalid_key() {
+        let err = parse_error("stacks = [1]");
+        assert!(err.contains("unrecognized key 'stacks'"), "{err}");
+    }
+}
@@ -1,3 +1,4 @@
+mod advice;
 mod midenc;
 
 pub use self::midenc::Midenc;
@@ -14,6 +15,15 @@ pub enum DriverError {
     /// Compilation failed
     #[error(transparent)]
     Compile(#[from] midenc_compile::CompilerError),
+    /// An error occurred while executing a program with the emulator
+    #[error(transparent)]
+    Emulation(#[from] miden_codegen_masm::EmulationError),
+    /// An error occurred while assembling a program with the Miden assembler
+    #[error(transparent)]
+    Assembly(#[from] miden_assembly::AssemblyError),
+    /// An error occurred while executing or proving a program with the Miden VM
+    #[error(transparent)]
+    Execution(#[from] miden_processor::ExecutionError),
     /// An error occurred when reading a file
     #[error(transparent)]
     Io(#[from] std::io::Error),
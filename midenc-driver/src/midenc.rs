@@ -1,10 +1,14 @@
 use std::{ffi::OsString, path::PathBuf, sync::Arc};
 
 use clap::{ColorChoice, Parser, Subcommand};
-use miden_diagnostics::Emitter;
-use miden_hir::FunctionIdent;
+use miden_codegen_masm as masm;
+use miden_diagnostics::{term::termcolor::ColorChoice as MDColorChoice, Emitter};
+use miden_hir::{Felt, FunctionIdent, Stack, StarkField};
 use midenc_compile as compile;
-use midenc_session::{InputFile, TargetEnv, VerbosityFlag, Warnings};
+use midenc_session::{
+    InputFile, Options, OutputType, OutputTypes, ProjectType, Session, TargetEnv, VerbosityFlag,
+    Warnings,
+};
 
 use super::DriverError;
 
@@ -26,7 +30,7 @@ enum Commands {
     /// provides a wider array of debugging and introspection features when troubleshooting
     /// programs compiled by `midenc`.
     Exec {
-        /// Specify one or more input files to compile as part of the program to execute
+        /// Specify the input file to compile as the program to execute
         ///
         /// You may use `-` as a file name to read a file from stdin.
         #[arg(required(true), value_name = "FILE")]
@@ -80,6 +84,28 @@ enum Commands {
         /// For example, `foo::bar`
         #[arg(long, short = 'e', value_name = "NAME")]
         entrypoint: Option<FunctionIdent>,
+        /// Add `<dir>` to the list of paths searched for libraries to link against
+        #[arg(
+            long = "search-path",
+            short = 'L',
+            value_name = "DIR",
+            help_heading = "Linker"
+        )]
+        search_paths: Vec<PathBuf>,
+        /// Link the program against the Miden Assembly library `<name>`
+        #[arg(
+            long = "link-library",
+            short = 'l',
+            value_name = "NAME",
+            help_heading = "Linker"
+        )]
+        link_libraries: Vec<String>,
+        /// Profile the cycles spent in each function, printing a summary when execution finishes
+        ///
+        /// The call stacks sampled on every cycle are written to `<FILE>` in the collapsed stack
+        /// format, which can be rendered as a flamegraph with tools such as `inferno-flamegraph`.
+        #[arg(long, value_name = "FILE", help_heading = "Output")]
+        profile: Option<PathBuf>,
     },
     /// Compile and run a program with the Miden VM
     ///
@@ -87,7 +113,7 @@ enum Commands {
     ///
     /// The inputs given must constitute a valid executable program.
     Run {
-        /// Specify one or more input files to compile as part of the program to execute
+        /// Specify the input file to compile as the program to execute
         ///
         /// You may use `-` as a file name to read a file from stdin.
         #[arg(required(true), value_name = "FILE")]
@@ -144,6 +170,40 @@ enum Commands {
         /// For example, `foo::bar`
         #[arg(long, short = 'e', value_name = "NAME")]
         entrypoint: Option<FunctionIdent>,
+        /// Add `<dir>` to the list of paths searched for libraries to link against
+        #[arg(
+            long = "search-path",
+            short = 'L',
+            value_name = "DIR",
+            help_heading = "Linker"
+        )]
+        search_paths: Vec<PathBuf>,
+        /// Link the program against the Miden Assembly library `<name>`
+        #[arg(
+            long = "link-library",
+            short = 'l',
+            value_name = "NAME",
+            help_heading = "Linker"
+        )]
+        link_libraries: Vec<String>,
+        /// Path to a TOML file containing the advice inputs for the program
+        ///
+        /// The file may specify the initial advice stack, entries of the advice map, and
+        /// Merkle trees to place in the Merkle store, for example:
+        ///
+        ///     stack = [1, 2, 3]
+        ///
+        ///     [[map]]
+        ///     key = [1, 0, 0, 0]
+        ///     values = [4, 5, 6]
+        ///
+        ///     [[merkle_tree]]
+        ///     leaves = [[1, 0, 0, 0], [2, 0, 0, 0]]
+        #[arg(long, value_name = "FILE", verbatim_doc_comment)]
+        advice_inputs: Option<PathBuf>,
+        /// Generate a proof of the execution of the program, and write it to `<FILE>`
+        #[arg(long, value_name = "FILE", help_heading = "Output")]
+        proof: Option<PathBuf>,
     },
 }
 
@@ -194,12 +254,318 @@ impl Midenc {
                     Err(err) => Err(DriverError::Compile(err)),
                 }
             }
-            _ => unimplemented!(),
+            Commands::Exec {
+                input,
+                args,
+                verbosity,
+                warn,
+                color,
+                target_dir,
+                entrypoint,
+                search_paths,
+                link_libraries,
+                profile,
+            } => {
+                let args = parse_operand_stack_args(&args)?;
+                let mut session = make_session(
+                    cwd,
+                    input,
+                    TargetEnv::Emu,
+                    verbosity,
+                    warn,
+                    color,
+                    target_dir,
+                    emitter,
+                );
+                session.options.search_paths = search_paths;
+                session.options.link_libraries = link_libraries;
+                let session = Arc::new(session);
+                let mut program = compile_program(session.clone())?;
+                // Invoke the entrypoint via the program's initialization code, so that its data
+                // segments are initialized
+                if let Some(entry) = entrypoint {
+                    program.set_entrypoint(entry);
+                }
+
+                let mut emulator = masm::Emulator::default();
+                if profile.is_some() {
+                    emulator.enable_profiling();
+                }
+                emulator.load_program(program.freeze())?;
+                for library in compile::load_libraries(&session)? {
+                    emulator.load_library(Box::new(library).freeze())?;
+                }
+                {
+                    let stack = emulator.stack_mut();
+                    for arg in args.into_iter() {
+                        stack.push(arg);
+                    }
+                }
+                let result = emulator.start();
+                // The profile is written even if execution fails, as it may help explain why
+                if let Some(path) = profile {
+                    let profiler = emulator.profiler().ok_or_else(|| {
+                        DriverError::Failed(anyhow::anyhow!(
+                            "unable to write profile: profiling was not enabled"
+                        ))
+                    })?;
+                    profiler.write_collapsed_stacks(std::fs::File::create(&path)?)?;
+                    print!("{profiler}");
+                    println!("Profile written to {}", path.display());
+                }
+                let stack = result.map_err(|err| {
+                    if let Some(stacktrace) = err.stacktrace() {
+                        print_stacktrace(stacktrace);
+                    }
+                    err
+                })?;
+
+                print_operand_stack(stack.stack().iter().rev().map(|elem| elem.as_int()));
+
+                Ok(())
+            }
+            Commands::Run {
+                input,
+                args,
+                verbosity,
+                warn,
+                color,
+                target_dir,
+                target,
+                entrypoint,
+                advice_inputs,
+                proof,
+                search_paths,
+                link_libraries,
+            } => {
+                use miden_core::StackInputs;
+                use miden_processor::{
+                    AdviceInputs, DefaultHost, ExecutionOptions, MemAdviceProvider,
+                };
+
+                let args = parse_operand_stack_args(&args)?;
+                let mut session =
+                    make_session(cwd, input, target, verbosity, warn, color, target_dir, emitter);
+                session.options.search_paths = search_paths;
+                session.options.link_libraries = link_libraries;
+                let session = Arc::new(session);
+                let mut program = compile_program(session.clone())?;
+                if let Some(entry) = entrypoint {
+                    program.set_entrypoint(entry);
+                }
+                if !program.is_executable() {
+                    session
+                        .diagnostics
+                        .error("unable to run program: no entrypoint was found or specified");
+                    return Err(DriverError::Reported);
+                }
+                // The initialized data of the program is read from the top of the advice stack
+                let program_advice = AdviceInputs::default().with_stack(program.advice_stack());
+                let advice_inputs = match advice_inputs {
+                    Some(path) => advice::load_advice_inputs(&path, program_advice)?,
+                    None => program_advice,
+                };
+                let program = assemble_program(&program, &session)?;
+
+                let stack_inputs = StackInputs::new(args);
+                let host = DefaultHost::new(MemAdviceProvider::from(advice_inputs.clone()));
+                let trace = miden_processor::execute(
+                    &program,
+                    stack_inputs.clone(),
+                    host,
+                    ExecutionOptions::default(),
+                )?;
+
+                print_operand_stack(trace.stack_outputs().stack().iter().copied());
+                println!("Cycles: {}", trace.trace_len_summary().main_trace_len());
+
+                if let Some(path) = proof {
+                    let host = DefaultHost::new(MemAdviceProvider::from(advice_inputs));
+                    let (_, proof) = miden_prover::prove(
+                        &program,
+                        stack_inputs,
+                        host,
+                        miden_prover::ProvingOptions::default(),
+                    )?;
+                    std::fs::write(&path, proof.to_bytes())?;
+                    println!("Proof written to {}", path.display());
+                }
+
+                Ok(())
+            }
         }
     }
 }
 
+/// Compile the inputs of `session` in-memory, producing a [masm::Program]
+fn compile_program(session: Arc<Session>) -> Result<Box<masm::Program>, DriverError> {
+    match compile::compile_to_memory(session) {
+        Ok(compiled) => Ok(compiled.into_program()),
+        Err(compile::CompilerError::Reported) => Err(DriverError::Reported),
+        Err(err) => Err(DriverError::Compile(err)),
+    }
+}
+
+/// Assemble an executable [masm::Program] into a [miden_core::Program] using the Miden
+/// assembler, with the Miden standard library, and any libraries linked against by the
+/// current session, available.
+fn assemble_program(
+    program: &masm::Program,
+    session: &Session,
+) -> Result<miden_core::Program, DriverError> {
+    use miden_assembly::{Assembler, AssemblyContext, MaslLibrary};
+    use miden_stdlib::StdLibrary;
+
+    let mut assembler = Assembler::default().with_library(&StdLibrary::default())?;
+    for path in session.libraries().map_err(compile::CompilerError::from)? {
+        let library = MaslLibrary::read_from_file(path).map_err(compile::CompilerError::from)?;
+        assembler = assembler.with_library(&library)?;
+    }
+    let mut assembler = program.assemble_kernel(assembler, &session.codemap)?;
+    for module in program.modules().filter(|m| !m.is_kernel()) {
+        let module = module.to_module_ast(&session.codemap);
+        assembler.compile_module(
+            &module.ast,
+            Some(&module.path),
+            &mut AssemblyContext::for_module(false),
+        )?;
+    }
+    let ast = program.to_program_ast(&session.codemap).map_err(compile::CompilerError::from)?;
+    Ok(assembler.compile_ast(&ast)?)
+}
+
+/// Construct a [Session] for commands which compile their inputs in-memory in order
+/// to execute them, rather than emitting compiled artifacts to disk.
+#[allow(clippy::too_many_arguments)]
+fn make_session(
+    cwd: PathBuf,
+    input: InputFile,
+    target: TargetEnv,
+    verbosity: VerbosityFlag,
+    warn: Warnings,
+    color: ColorChoice,
+    target_dir: Option<PathBuf>,
+    emitter: Option<Arc<dyn Emitter>>,
+) -> Session {
+    let tmp_dir = target_dir.unwrap_or_else(std::env::temp_dir);
+
+    let color = match color {
+        ColorChoice::Auto => MDColorChoice::Auto,
+        ColorChoice::Always => MDColorChoice::Always,
+        ColorChoice::Never => MDColorChoice::Never,
+    };
+
+    // We must request an output type which requires code generation and linking,
+    // but no artifacts are actually written when compiling to memory
+    let mut output_types = OutputTypes::default();
+    output_types.insert(OutputType::Masl, None);
+    let options = Options::new(cwd)
+        .with_color(color)
+        .with_verbosity(verbosity.into())
+        .with_warnings(warn)
+        .with_output_types(output_types);
+
+    Session::new(target, [input], None, None, Some(tmp_dir), options, emitter)
+        .with_project_type(ProjectType::Program)
+}
+
+/// Parse the arguments given after `--` as field elements in decimal format.
+///
+/// The elements are returned in order of appearance, i.e. the last element is the one which
+/// should end up on top of the operand stack.
+fn parse_operand_stack_args(args: &[String]) -> Result<Vec<Felt>, DriverError> {
+    args.iter()
+        .map(|arg| {
+            arg.parse::<u64>()
+                .ok()
+                .filter(|value| *value < Felt::MODULUS)
+                .map(Felt::new)
+                .ok_or_else(|| {
+                    DriverError::Failed(anyhow::anyhow!(
+                        "invalid argument '{arg}': expected a field element in decimal format"
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Print the contents of the operand stack to stdout, given in order from the top of the stack.
+fn print_operand_stack<I: IntoIterator<Item = u64>>(stack: I) {
+    println!("Operand stack (top first):");
+    for (index, elem) in stack.into_iter().enumerate() {
+        println!("  [{index:>2}] {elem}");
+    }
+}
+
+fn print_stacktrace(stacktrace: &[masm::CallFrame]) {
+    eprintln!("Stack trace (most recent call first):");
+    for frame in stacktrace.iter().rev() {
+        eprintln!("  {frame}");
+    }
+}
+
 fn format_error<I: clap::CommandFactory>(err: clap::Error) -> clap::Error {
     let mut cmd = I::command();
     err.format(&mut cmd)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../hir/src/parser/tests/input/test.hir");
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn exec_parsing() {
+        let cli = Midenc::try_parse_from([
+            "midenc",
+            "exec",
+            INPUT,
+            "-e",
+            "test::main",
+            "--profile",
+            "test.folded",
+            "--",
+            "1",
+            "2",
+        ])
+        .expect("invalid arguments");
+        let Commands::Exec {
+            args,
+            entrypoint,
+            profile,
+            ..
+        } = cli.command
+        else {
+            panic!("expected the exec command");
+        };
+        assert_eq!(args, ["1", "2"]);
+        assert_eq!(entrypoint, Some("test::main".parse().unwrap()));
+        assert_eq!(profile, Some(PathBuf::from("test.folded")));
+
+        // An input file is required
+        assert!(Midenc::try_parse_from(["midenc", "exec"]).is_err());
+        // The entrypoint must be fully-qualified
+        assert!(Midenc::try_parse_from(["midenc", "exec", INPUT, "-e", "main"]).is_err());
+    }
+
+    #[test]
+    fn operand_stack_args_parsing() {
+        assert!(parse_operand_stack_args(&[]).unwrap().is_empty());
+        let args = parse_operand_stack_args(&strings(&["0", "1", "18446744069414584320"])).unwrap();
+        assert_eq!(args, [Felt::new(0), Felt::new(1), Felt::new(Felt::MODULUS - 1)]);
+
+        for arg in ["", "-1", "1.0", "0x10", "one", "18446744069414584321", "18446744073709551616"]
+        {
+            let err = parse_operand_stack_args(&strings(&["1", arg]))
+                .expect_err("expected argument to be invalid");
+            assert!(matches!(err, DriverError::Failed(_)), "unexpected error for '{arg}'");
+            assert!(err.to_string().contains(&format!("invalid argument '{arg}'")), "{err}");
+        }
+    }
+}
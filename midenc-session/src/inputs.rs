@@ -15,6 +15,9 @@ pub enum InvalidInputError {
     /// We attempted to detecth the file type from the raw bytes, but failed
     #[error("could not detect file type of input")]
     UnrecognizedFileType,
+    /// A library to link against could not be found in any of the search paths
+    #[error("unable to find library '{0}' in any of the library search paths")]
+    LibraryNotFound(String),
     /// Unable to read input file
     #[error(transparent)]
     Io(#[from] std::io::Error),
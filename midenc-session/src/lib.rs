@@ -38,8 +38,8 @@ pub enum ProjectType {
 impl ProjectType {
     pub fn default_for_target(target: TargetEnv) -> Self {
         match target {
-            // We default to compiling a program unless we find later
-            // that we do not have an entrypoint.
+            // We default to compiling a program, in which case an entrypoint
+            // is required, libraries must be requested explicitly
             TargetEnv::Base | TargetEnv::Rollup => Self::Program,
             // The emulator can run either programs or individual library functions,
             // so we compile as a library and delegate the choice of how to run it
@@ -64,8 +64,8 @@ pub struct Session {
     pub diagnostics: Arc<DiagnosticsHandler>,
     /// The location of all libraries shipped with the compiler
     pub sysroot: PathBuf,
-    /// The input being compiled
-    pub input: InputFile,
+    /// The inputs being compiled
+    pub inputs: Vec<InputFile>,
     /// The outputs to be produced by the compiler during compilation
     pub output_files: OutputFiles,
     /// Statistics gathered from the current compiler session
@@ -75,15 +75,21 @@ pub struct Session {
     arg_matches: clap::ArgMatches,
 }
 impl Session {
-    pub fn new(
+    pub fn new<I>(
         target: TargetEnv,
-        input: InputFile,
+        inputs: I,
         output_dir: Option<PathBuf>,
         output_file: Option<OutputFile>,
         tmp_dir: Option<PathBuf>,
         options: Options,
         emitter: Option<Arc<dyn Emitter>>,
-    ) -> Self {
+    ) -> Self
+    where
+        I: IntoIterator<Item = InputFile>,
+    {
+        let inputs = inputs.into_iter().collect::<Vec<_>>();
+        assert!(!inputs.is_empty(), "expected at least one input to the compiler");
+
         // TODO: Make sure we pin this down when we need to ship stuff with compiler
         let sysroot = match &options.sysroot {
             Some(sysroot) => sysroot.clone(),
@@ -100,7 +106,7 @@ impl Session {
         let output_files = match output_file {
             None => {
                 let output_dir = output_dir.unwrap_or_default();
-                let stem = options.name.clone().unwrap_or_else(|| input.filestem().to_owned());
+                let stem = options.name.clone().unwrap_or_else(|| inputs[0].filestem().to_owned());
 
                 OutputFiles::new(stem, output_dir, None, tmp_dir, options.output_types.clone())
             }
@@ -121,7 +127,7 @@ impl Session {
             codemap,
             diagnostics,
             sysroot,
-            input,
+            inputs,
             output_files,
             statistics: Default::default(),
             arg_matches: Default::default(),
@@ -171,13 +177,16 @@ impl Session {
     }
 
     /// The name of this session (used as the name of the project, output file, etc.)
+    ///
+    /// When multiple inputs are given, the name is derived from the first of them.
     pub fn name(&self) -> String {
         self.options
             .name
             .clone()
             .or_else(|| {
-                if self.input.is_real() {
-                    Some(self.input.filestem().to_string())
+                let input = &self.inputs[0];
+                if input.is_real() {
+                    Some(input.filestem().to_string())
                 } else {
                     None
                 }
@@ -229,6 +238,55 @@ impl Session {
         }
     }
 
+    /// Get the paths of all Miden Assembly libraries to link against in this session.
+    ///
+    /// This includes any `.masl` files given as inputs, followed by the libraries requested
+    /// via `Options::link_libraries`, in the order they were given.
+    pub fn libraries(&self) -> Result<Vec<PathBuf>, InvalidInputError> {
+        let mut libraries = self
+            .inputs
+            .iter()
+            .filter(|input| input.file_type() == FileType::Masl)
+            .filter_map(|input| input.as_path().map(|path| path.to_path_buf()))
+            .collect::<Vec<_>>();
+        for name in self.options.link_libraries.iter() {
+            let path = self
+                .find_library(name)
+                .ok_or_else(|| InvalidInputError::LibraryNotFound(name.clone()))?;
+            libraries.push(path);
+        }
+        Ok(libraries)
+    }
+
+    /// Search for the library named `name` using the configured library search paths.
+    ///
+    /// A library named `foo` is expected to be found in a file named `foo.masl`, in one of
+    /// the search paths. The search paths are visited in the order they were given, and the
+    /// first match is returned. If `name` is itself a path to a `.masl` file, it is returned
+    /// as-is.
+    pub fn find_library(&self, name: &str) -> Option<PathBuf> {
+        let path = Path::new(name);
+        if path.extension().is_some_and(|ext| ext == OutputType::Masl.extension()) && path.is_file()
+        {
+            return Some(path.to_path_buf());
+        }
+
+        let filename = format!("{name}.{}", OutputType::Masl.extension());
+        self.options.search_paths.iter().find_map(|dir| {
+            let dir = if dir.is_absolute() {
+                dir.clone()
+            } else {
+                self.options.current_dir.join(dir)
+            };
+            let path = dir.join(&filename);
+            if path.is_file() {
+                Some(path)
+            } else {
+                None
+            }
+        })
+    }
+
     pub fn parse_only(&self) -> bool {
         self.options.output_types.parse_only()
     }
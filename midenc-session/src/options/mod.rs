@@ -16,6 +16,10 @@ pub struct Options {
     pub output_types: OutputTypes,
     /// The paths in which to search for Miden Assembly libraries to link against
     pub search_paths: Vec<PathBuf>,
+    /// The names of the Miden Assembly libraries to link against
+    ///
+    /// Each name is resolved to a `.masl` file using `search_paths`
+    pub link_libraries: Vec<String>,
     /// The location of the libraries which are shipped with the compiler
     pub sysroot: Option<PathBuf>,
     /// Whether, and how, to color terminal output
@@ -28,6 +32,8 @@ pub struct Options {
     pub print_ir_after_all: bool,
     /// Print IR to stdout each time the named pass is applied
     pub print_ir_after_pass: Option<String>,
+    /// Print the pipeline of passes which will be applied to stdout
+    pub print_pipeline: bool,
 }
 impl Default for Options {
     fn default() -> Self {
@@ -39,15 +45,17 @@ impl Options {
     pub fn new(current_dir: PathBuf) -> Self {
         Self {
             name: None,
-            optimize: OptLevel::None,
+            optimize: OptLevel::default(),
             output_types: Default::default(),
             search_paths: vec![],
+            link_libraries: vec![],
             sysroot: None,
             color: Default::default(),
             diagnostics: Default::default(),
             current_dir,
             print_ir_after_all: false,
             print_ir_after_pass: None,
+            print_pipeline: false,
         }
     }
 
@@ -79,6 +87,11 @@ impl Options {
         self
     }
 
+    pub fn with_optimization(mut self, level: OptLevel) -> Self {
+        self.optimize = level;
+        self
+    }
+
     pub fn with_output_types(mut self, output_types: OutputTypes) -> Self {
         self.output_types = output_types;
         self
@@ -96,22 +109,54 @@ impl Options {
 }
 
 /// This enum describes the degree to which compiled programs will be optimized
-#[derive(Debug, Copy, Clone, Default, ValueEnum)]
+///
+/// Each level may also be specified using the conventional shorthand, i.e. `0`, `1`, `2`,
+/// `3`, `s` and `z`, so that `-O2` is equivalent to `-O balanced`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, ValueEnum)]
 pub enum OptLevel {
     /// No optimizations at all
+    #[default]
+    #[value(alias = "0")]
     None,
     /// Only basic optimizations are applied, e.g. constant propagation
+    #[value(alias = "1")]
     Basic,
     /// Most optimizations are applied, except when the cost is particularly high.
-    #[default]
+    #[value(alias = "2")]
     Balanced,
     /// All optimizations are applied, with all tradeoffs in favor of runtime performance
+    #[value(alias = "3")]
     Max,
     /// Most optimizations are applied, but tuned to trade runtime performance for code size
+    #[value(alias = "s")]
     Size,
     /// Only optimizations which reduce code size are applied
+    #[value(alias = "z")]
     SizeMin,
 }
+impl OptLevel {
+    /// Returns true if any optimizations should be applied at this level
+    pub fn is_optimizing(&self) -> bool {
+        !matches!(self, Self::None)
+    }
+
+    /// Returns true if this level favors smaller code size over runtime performance
+    pub fn is_size_optimizing(&self) -> bool {
+        matches!(self, Self::Size | Self::SizeMin)
+    }
+}
+impl fmt::Display for OptLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::None => f.write_str("none"),
+            Self::Basic => f.write_str("basic"),
+            Self::Balanced => f.write_str("balanced"),
+            Self::Max => f.write_str("max"),
+            Self::Size => f.write_str("size"),
+            Self::SizeMin => f.write_str("size-min"),
+        }
+    }
+}
 
 /// This enum represents the behavior of the compiler with regard to warnings
 #[derive(Debug, Copy, Clone, Default, ValueEnum)]
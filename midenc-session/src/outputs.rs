@@ -20,6 +20,9 @@ pub enum OutputType {
     /// The compiler will emit a Miden Assembly program or library
     #[default]
     Masl,
+    /// The compiler will emit a static estimate of the cycle cost of each function in Miden
+    /// Assembly
+    Cost,
 }
 impl OutputType {
     pub fn extension(&self) -> &'static str {
@@ -28,11 +31,19 @@ impl OutputType {
             Self::Hir => "hir",
             Self::Masm => "masm",
             Self::Masl => "masl",
+            Self::Cost => "cost",
         }
     }
 
     pub fn shorthand_display() -> String {
-        format!("`{}`, `{}`, `{}`, `{}`", Self::Ast, Self::Hir, Self::Masm, Self::Masl,)
+        format!(
+            "`{}`, `{}`, `{}`, `{}`, `{}`",
+            Self::Ast,
+            Self::Hir,
+            Self::Masm,
+            Self::Masl,
+            Self::Cost,
+        )
     }
 }
 impl fmt::Display for OutputType {
@@ -42,6 +53,7 @@ impl fmt::Display for OutputType {
             Self::Hir => f.write_str("hir"),
             Self::Masm => f.write_str("masm"),
             Self::Masl => f.write_str("masl"),
+            Self::Cost => f.write_str("cost"),
         }
     }
 }
@@ -54,6 +66,7 @@ impl FromStr for OutputType {
             "hir" => Ok(Self::Hir),
             "masm" => Ok(Self::Masm),
             "masl" => Ok(Self::Masl),
+            "cost" => Ok(Self::Cost),
             _ => Err(()),
         }
     }
@@ -244,11 +257,15 @@ impl OutputTypes {
     }
 
     pub fn should_codegen(&self) -> bool {
-        self.0.keys().any(|k| matches!(k, OutputType::Masm | OutputType::Masl))
+        self.0
+            .keys()
+            .any(|k| matches!(k, OutputType::Masm | OutputType::Masl | OutputType::Cost))
     }
 
     pub fn should_link(&self) -> bool {
-        self.0.keys().any(|k| matches!(k, OutputType::Masm | OutputType::Masl))
+        self.0
+            .keys()
+            .any(|k| matches!(k, OutputType::Masm | OutputType::Masl | OutputType::Cost))
     }
 }
 
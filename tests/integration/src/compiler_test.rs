@@ -550,7 +550,7 @@ impl CompilerTest {
         let program = self.ir_masm_program();
         // TODO: get code map from the self.diagnostics
         let codemap = CodeMap::new();
-        let program_ast = program.to_program_ast(&codemap);
+        let program_ast = program.to_program_ast(&codemap).expect("expected an executable program");
         for module in program.modules() {
             let core_module = module.to_module_ast(&codemap);
             let _ = assembler
@@ -716,7 +716,7 @@ fn make_diagnostics() -> DiagnosticsHandler {
 pub fn default_session() -> Session {
     let session = Session::new(
         Default::default(),
-        InputFile::from_path("test.hir").unwrap(),
+        [InputFile::from_path("test.hir").unwrap()],
         None,
         None,
         None,
@@ -0,0 +1,428 @@
+//! Differential tests of the emulator against the Miden VM
+//!
+//! Each test executes a single instruction on random inputs, in both the emulator and the VM, and
+//! checks that they agree on the resulting operand stack and memory, or that both of them fail.
+//!
+//! Some instructions cannot be compared this way: `clk`, `caller`, `procref`, `dynexec` and
+//! `dyncall` depend on cycle counts and MAST roots, which the emulator does not model, while the
+//! offset forms of `mem_load`/`mem_store` have no equivalent in the VM. Those are covered by the
+//! emulator's own tests instead.
+
+use std::sync::Arc;
+
+use miden_assembly::{Assembler, AssemblyContext};
+use miden_codegen_masm::{Emulator, Function, Module, Op, Program};
+use miden_core::StackInputs;
+use miden_diagnostics::CodeMap;
+use miden_hir::{Felt, FunctionIdent, Ident, Signature, Stack, StarkField, Symbol};
+use miden_processor::{AdviceInputs, DefaultHost, MemAdviceProvider};
+use proptest::{prelude::*, test_runner::TestCaseError};
+
+/// The number of elements of the operand stack which are compared
+const STACK_DEPTH: usize = 16;
+
+/// The number of words of memory, starting from address 0, which are compared
+const MEMORY_WORDS: usize = 8;
+
+/// The state of the machine after successfully executing a program
+#[derive(Debug, PartialEq, Eq)]
+struct Outcome {
+    /// The top [STACK_DEPTH] elements of the operand stack, top first
+    stack: Vec<u64>,
+    /// The first [MEMORY_WORDS] words of memory
+    memory: Vec<[u64; 4]>,
+}
+
+fn entrypoint() -> FunctionIdent {
+    "test::run".parse().unwrap()
+}
+
+/// Construct an executable program whose entrypoint executes `ops`
+fn build_program(ops: &[Op]) -> Arc<Program> {
+    let mut function = Function::new(entrypoint(), Signature::new([], []));
+    let body = function.body.id();
+    function.block_mut(body).extend_from_slice(ops);
+    let mut module = Module::new(Ident::with_empty_span(Symbol::intern("test")));
+    module.push_back(Box::new(function));
+    let mut program = Box::new(Program::new());
+    program.insert(Box::new(module));
+    program.set_entrypoint(entrypoint());
+    program.freeze()
+}
+
+/// Pad or truncate the operand stack to [STACK_DEPTH] elements, as the VM does
+fn top_of_stack(stack: impl IntoIterator<Item = Felt>) -> Vec<u64> {
+    stack
+        .into_iter()
+        .map(|elem| elem.as_int())
+        .chain(core::iter::repeat(0))
+        .take(STACK_DEPTH)
+        .collect()
+}
+
+/// Execute `program` in the emulator, with `inputs` on the operand stack, the first on top
+fn run_emulator(program: Arc<Program>, inputs: &[Felt]) -> Option<Outcome> {
+    let mut emulator = Emulator::default();
+    emulator.load_program(program).expect("failed to load program");
+    let stack = emulator.invoke(entrypoint(), inputs).ok()?;
+    let memory = (0..MEMORY_WORDS)
+        .map(|addr| core::array::from_fn(|index| emulator.load(addr * 16 + index * 4).as_int()))
+        .collect();
+    Some(Outcome {
+        stack: top_of_stack((0..stack.len()).map(|index| stack[index])),
+        memory,
+    })
+}
+
+/// Execute `program` in the VM, with `inputs` on the operand stack, the first on top
+fn run_vm(program: &Program, inputs: &[Felt]) -> Option<Outcome> {
+    let codemap = CodeMap::new();
+    let assembler = Assembler::default();
+    for module in program.modules() {
+        let module = module.to_module_ast(&codemap);
+        assembler
+            .compile_module(
+                &module.ast,
+                Some(&module.path),
+                &mut AssemblyContext::for_module(false),
+            )
+            .expect("failed to assemble module");
+    }
+    let program = assembler
+        .compile_ast(&program.to_program_ast(&codemap).expect("expected an executable program"))
+        .expect("failed to assemble program");
+
+    // StackInputs places the last input on top of the stack
+    let stack_inputs = StackInputs::new(inputs.iter().copied().rev().collect());
+    let host = DefaultHost::new(MemAdviceProvider::from(AdviceInputs::default()));
+    let state = miden_processor::execute_iter(&program, stack_inputs, host).last()?.ok()?;
+    let memory = (0..MEMORY_WORDS as u64)
+        .map(|addr| {
+            state
+                .memory
+                .iter()
+                .find(|(a, _)| *a == addr)
+                .map(|(_, word)| word.map(|elem| elem.as_int()))
+                .unwrap_or_default()
+        })
+        .collect();
+    Some(Outcome {
+        stack: top_of_stack(state.stack),
+        memory,
+    })
+}
+
+/// Execute `ops` in both the emulator and the VM, and check that they agree on the result
+fn run_emulator_vs_vm(ops: &[Op], inputs: &[Felt]) -> Result<(), TestCaseError> {
+    let program = build_program(ops);
+    let vm_out = run_vm(&program, inputs);
+    let emul_out = run_emulator(program, inputs);
+    prop_assert_eq!(emul_out, vm_out, "mismatch executing {:?} with inputs {:?}", ops, inputs);
+    Ok(())
+}
+
+/// Arbitrary field elements, biased towards small values so that comparisons are interesting
+fn felt() -> impl Strategy<Value = Felt> {
+    prop_oneof![
+        (0u64..4).prop_map(Felt::new),
+        any::<u32>().prop_map(|value| Felt::new(value as u64)),
+        (0..Felt::MODULUS).prop_map(Felt::new),
+    ]
+}
+
+/// Valid u32 values, biased towards the boundaries of the range
+fn u32_felt() -> impl Strategy<Value = Felt> {
+    prop_oneof![
+        (0u32..4).prop_map(|value| Felt::new(value as u64)),
+        ((u32::MAX - 3)..=u32::MAX).prop_map(|value| Felt::new(value as u64)),
+        any::<u32>().prop_map(|value| Felt::new(value as u64)),
+    ]
+}
+
+/// Mostly valid booleans, with the occasional invalid one
+fn bool_felt() -> impl Strategy<Value = Felt> {
+    prop_oneof![9 => (0u64..2).prop_map(Felt::new), 1 => Just(Felt::new(2))]
+}
+
+fn inputs<S: Strategy<Value = Felt>>(elem: S, n: usize) -> impl Strategy<Value = Vec<Felt>> {
+    prop::collection::vec(elem, n)
+}
+
+fn stack_op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        prop::sample::select(vec![
+            Op::Padw,
+            Op::Drop,
+            Op::Dropw,
+            Op::Movupw(2),
+            Op::Movdnw(2),
+            Op::Cswap,
+            Op::Cswapw,
+            Op::Cdrop,
+            Op::Cdropw,
+        ]),
+        felt().prop_map(Op::Push),
+        (felt(), felt()).prop_map(|(a, b)| Op::Push2([a, b])),
+        (felt(), felt(), felt(), felt()).prop_map(|(a, b, c, d)| Op::Pushw([a, b, c, d])),
+        any::<u32>().prop_map(Op::PushU32),
+        (0u8..12).prop_map(Op::Dup),
+        (0u8..3).prop_map(Op::Dupw),
+        (1u8..12).prop_map(Op::Swap),
+        (1u8..3).prop_map(Op::Swapw),
+        (2u8..12).prop_flat_map(|n| prop::sample::select(vec![Op::Movup(n), Op::Movdn(n)])),
+    ]
+}
+
+fn field_op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        prop::sample::select(vec![
+            Op::Add,
+            Op::Sub,
+            Op::Mul,
+            Op::Div,
+            Op::Neg,
+            Op::Inv,
+            Op::Incr,
+            Op::Ilog2,
+            Op::Eq,
+            Op::Neq,
+            Op::Gt,
+            Op::Gte,
+            Op::Lt,
+            Op::Lte,
+            Op::IsOdd,
+            Op::Eqw,
+            Op::AssertEq,
+            Op::AssertEqw,
+        ]),
+        felt().prop_flat_map(|imm| {
+            prop::sample::select(vec![
+                Op::AddImm(imm),
+                Op::SubImm(imm),
+                Op::MulImm(imm),
+                Op::EqImm(imm),
+                Op::NeqImm(imm),
+                Op::GtImm(imm),
+                Op::GteImm(imm),
+                Op::LtImm(imm),
+                Op::LteImm(imm),
+            ])
+        }),
+        felt()
+            .prop_filter("division by zero", |imm| imm.as_int() != 0)
+            .prop_map(Op::DivImm),
+        any::<u8>().prop_map(Op::ExpImm),
+        any::<u32>().prop_map(Op::AssertEqWithError),
+    ]
+}
+
+fn bool_op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        prop::sample::select(vec![Op::Not, Op::And, Op::Or, Op::Xor, Op::Assert, Op::Assertz]),
+        any::<bool>().prop_flat_map(|imm| {
+            prop::sample::select(vec![Op::AndImm(imm), Op::OrImm(imm), Op::XorImm(imm)])
+        }),
+        any::<u32>().prop_flat_map(|code| {
+            prop::sample::select(vec![Op::AssertWithError(code), Op::AssertzWithError(code)])
+        }),
+    ]
+}
+
+fn u32_op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        prop::sample::select(vec![
+            Op::U32OverflowingAdd,
+            Op::U32WrappingAdd,
+            Op::U32OverflowingAdd3,
+            Op::U32WrappingAdd3,
+            Op::U32OverflowingSub,
+            Op::U32WrappingSub,
+            Op::U32OverflowingMul,
+            Op::U32WrappingMul,
+            Op::U32OverflowingMadd,
+            Op::U32WrappingMadd,
+            Op::U32Div,
+            Op::U32Mod,
+            Op::U32DivMod,
+            Op::U32And,
+            Op::U32Or,
+            Op::U32Xor,
+            Op::U32Not,
+            Op::U32Popcnt,
+            Op::U32Clz,
+            Op::U32Ctz,
+            Op::U32Clo,
+            Op::U32Cto,
+            Op::U32Lt,
+            Op::U32Lte,
+            Op::U32Gt,
+            Op::U32Gte,
+            Op::U32Min,
+            Op::U32Max,
+        ]),
+        any::<u32>().prop_flat_map(|imm| {
+            prop::sample::select(vec![
+                Op::U32OverflowingAddImm(imm),
+                Op::U32WrappingAddImm(imm),
+                Op::U32OverflowingSubImm(imm),
+                Op::U32WrappingSubImm(imm),
+                Op::U32OverflowingMulImm(imm),
+                Op::U32WrappingMulImm(imm),
+            ])
+        }),
+        (1..=u32::MAX).prop_flat_map(|imm| {
+            prop::sample::select(vec![
+                Op::U32DivImm(imm),
+                Op::U32ModImm(imm),
+                Op::U32DivModImm(imm),
+            ])
+        }),
+        (1u32..32).prop_flat_map(|imm| {
+            prop::sample::select(vec![
+                Op::U32ShlImm(imm),
+                Op::U32ShrImm(imm),
+                Op::U32RotlImm(imm),
+                Op::U32RotrImm(imm),
+            ])
+        }),
+    ]
+}
+
+fn u32_check_op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        prop::sample::select(vec![
+            Op::U32Test,
+            Op::U32Testw,
+            Op::U32Assert,
+            Op::U32Assert2,
+            Op::U32Assertw,
+            Op::U32Cast,
+            Op::U32Split,
+        ]),
+        any::<u32>().prop_flat_map(|code| {
+            prop::sample::select(vec![
+                Op::U32AssertWithError(code),
+                Op::U32Assert2WithError(code),
+                Op::U32AssertwWithError(code),
+            ])
+        }),
+    ]
+}
+
+fn mem_op() -> impl Strategy<Value = Op> {
+    let addr = 0..(MEMORY_WORDS as u32);
+    prop_oneof![
+        prop::sample::select(vec![Op::MemLoad, Op::MemLoadw, Op::MemStore, Op::MemStorew]),
+        addr.prop_flat_map(|addr| {
+            prop::sample::select(vec![
+                Op::MemLoadImm(addr),
+                Op::MemLoadwImm(addr),
+                Op::MemStoreImm(addr),
+                Op::MemStorewImm(addr),
+            ])
+        }),
+    ]
+}
+
+/// Initialize the first [MEMORY_WORDS] words of memory with `words`
+fn init_memory(words: &[[Felt; 4]]) -> Vec<Op> {
+    words
+        .iter()
+        .enumerate()
+        .flat_map(|(addr, word)| [Op::Pushw(*word), Op::MemStorewImm(addr as u32), Op::Dropw])
+        .collect()
+}
+
+fn memory() -> impl Strategy<Value = Vec<[Felt; 4]>> {
+    prop::collection::vec(
+        (felt(), felt(), felt(), felt()).prop_map(|(a, b, c, d)| [a, b, c, d]),
+        MEMORY_WORDS,
+    )
+}
+
+proptest! {
+    #[test]
+    fn emulator_vs_vm_stack_ops(
+        op in stack_op(),
+        cond in bool_felt(),
+        mut inputs in inputs(felt(), 11),
+    ) {
+        // The conditional ops expect a boolean on top of the stack
+        inputs.insert(0, cond);
+        run_emulator_vs_vm(&[op], &inputs)?;
+    }
+
+    #[test]
+    fn emulator_vs_vm_field_ops(op in field_op(), inputs in inputs(felt(), 8)) {
+        run_emulator_vs_vm(&[op], &inputs)?;
+    }
+
+    #[test]
+    fn emulator_vs_vm_exp_ops(
+        op in prop::sample::select(vec![Op::Pow2, Op::Exp]),
+        exponent in 0u64..70,
+        base in felt(),
+    ) {
+        run_emulator_vs_vm(&[op], &[Felt::new(exponent), base])?;
+    }
+
+    #[test]
+    fn emulator_vs_vm_exp_bit_length(
+        bits in 0u8..=64,
+        exponent in prop_oneof![0u64..70, any::<u64>()],
+        base in felt(),
+    ) {
+        run_emulator_vs_vm(&[Op::ExpBitLength(bits)], &[Felt::new(exponent), base])?;
+    }
+
+    #[test]
+    fn emulator_vs_vm_bool_ops(op in bool_op(), inputs in inputs(bool_felt(), 2)) {
+        run_emulator_vs_vm(&[op], &inputs)?;
+    }
+
+    #[test]
+    fn emulator_vs_vm_u32_ops(op in u32_op(), inputs in inputs(u32_felt(), 3)) {
+        run_emulator_vs_vm(&[op], &inputs)?;
+    }
+
+    #[test]
+    fn emulator_vs_vm_u32_shifts(
+        op in prop::sample::select(vec![Op::U32Shl, Op::U32Shr, Op::U32Rotl, Op::U32Rotr]),
+        b in 0u64..32,
+        a in u32_felt(),
+    ) {
+        run_emulator_vs_vm(&[op], &[Felt::new(b), a])?;
+    }
+
+    #[test]
+    fn emulator_vs_vm_u32_checks(op in u32_check_op(), inputs in inputs(felt(), 4)) {
+        run_emulator_vs_vm(&[op], &inputs)?;
+    }
+
+    #[test]
+    fn emulator_vs_vm_mem_ops(
+        op in mem_op(),
+        addr in 0..(MEMORY_WORDS as u64),
+        memory in memory(),
+        mut inputs in inputs(felt(), 4),
+    ) {
+        // The ops which take their address from the stack expect it on top
+        inputs.insert(0, Felt::new(addr));
+        let mut ops = init_memory(&memory);
+        ops.push(op);
+        run_emulator_vs_vm(&ops, &inputs)?;
+    }
+
+    #[test]
+    fn emulator_vs_vm_mem_stream(
+        addr in 0..(MEMORY_WORDS as u64 - 1),
+        memory in memory(),
+        mut inputs in inputs(felt(), 12),
+    ) {
+        // mem_stream expects the address beneath the top three words of the stack
+        inputs.push(Felt::new(addr));
+        let mut ops = init_memory(&memory);
+        ops.push(Op::MemStream);
+        run_emulator_vs_vm(&ops, &inputs)?;
+    }
+}
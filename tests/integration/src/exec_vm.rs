@@ -1,19 +1,22 @@
 use miden_core::{Program, StackInputs};
 use miden_hir::Felt;
-use miden_processor::{DefaultHost, ExecutionOptions};
+use miden_processor::{AdviceInputs, DefaultHost, ExecutionOptions, MemAdviceProvider};
 
 use crate::felt_conversion::TestFelt;
 
 /// Execute the module using the VM with the given arguments
 /// Arguments are expected to be in the order they are passed to the entrypoint function
-pub fn execute_vm(program: &Program, args: &[Felt]) -> Vec<TestFelt> {
+/// The advice stack must contain the data the program expects, see
+/// `miden_codegen_masm::Program::advice_stack`
+pub fn execute_vm(program: &Program, advice_stack: &[Felt], args: &[Felt]) -> Vec<TestFelt> {
     // Reverse the arguments to counteract the StackInputs::new() reversing them into a stack
     let args_reversed = args.into_iter().copied().rev().collect();
     let stack_inputs = StackInputs::new(args_reversed);
+    let advice_inputs = AdviceInputs::default().with_stack(advice_stack.iter().copied());
     let trace = miden_processor::execute(
         program,
         stack_inputs,
-        DefaultHost::default(),
+        DefaultHost::new(MemAdviceProvider::from(advice_inputs)),
         ExecutionOptions::default(),
     )
     .expect("failed to execute program on VM");
@@ -13,5 +13,9 @@ pub use compiler_test::{default_session, CompilerTest};
 pub use exec_emulator::execute_emulator;
 pub use exec_vm::execute_vm;
 
+#[cfg(test)]
+mod emulator_vs_vm;
+#[cfg(test)]
+mod multiple_inputs;
 #[cfg(test)]
 mod rust_masm_tests;
@@ -0,0 +1,94 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use miden_codegen_masm::Emulator;
+use miden_core::Felt;
+use miden_hir::{FunctionIdent, Ident, Stack, StarkField, Symbol};
+use midenc_compile::Compiled;
+use midenc_session::{InputFile, Options, OutputType, OutputTypes, Session};
+
+const CALLER: &str = r#"
+(module #caller
+    (func (export #run) (param u32) (result u32)
+        (block 0 (param v0 u32)
+            (let (v1 u32) (call (#callee #double) v0))
+            (let (v2 u32) (const.u32 1))
+            (let (v3 u32) (add.wrapping v1 v2))
+            (ret v3))
+    )
+
+    (func (import #callee #double) (param u32) (result u32))
+)
+"#;
+
+const CALLEE: &str = r#"
+(module #callee
+    (func (export #double) (param u32) (result u32)
+        (block 0 (param v0 u32)
+            (let (v1 u32) (const.u32 2))
+            (let (v2 u32) (mul.wrapping v0 v1))
+            (ret v2))
+    )
+)
+"#;
+
+/// Write `sources` to disk as `<name>.hir` files, returning them as inputs to the compiler
+fn write_inputs(test: &str, sources: &[(&str, &str)]) -> (PathBuf, Vec<InputFile>) {
+    let dir = std::env::temp_dir().join(test);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    let inputs = sources
+        .iter()
+        .map(|(name, source)| {
+            let path = dir.join(format!("{name}.hir"));
+            fs::write(&path, source).unwrap();
+            InputFile::from_path(path).unwrap()
+        })
+        .collect();
+    (dir, inputs)
+}
+
+/// Compiling several inputs together links them into a single program, in which functions
+/// defined in one input can be called from another.
+#[test]
+fn compile_and_link_multiple_inputs() {
+    let (dir, inputs) =
+        write_inputs("compile_and_link_multiple_inputs", &[("caller", CALLER), ("callee", CALLEE)]);
+
+    // Request an output type which requires code generation and linking, nothing is written
+    // to disk when compiling to memory
+    let mut output_types = OutputTypes::default();
+    output_types.insert(OutputType::Masl, None);
+    let options = Options::new(dir.clone()).with_output_types(output_types);
+    let session = Arc::new(Session::new(
+        Default::default(),
+        inputs,
+        None,
+        None,
+        Some(dir.clone()),
+        options,
+        None,
+    ));
+
+    let program = match midenc_compile::compile_to_memory(session) {
+        Ok(Compiled::Program(program)) => program.freeze(),
+        Ok(Compiled::Modules(_)) => panic!("expected the inputs to be linked into a program"),
+        Err(err) => panic!("compilation failed: {err}"),
+    };
+    let caller = Ident::with_empty_span(Symbol::intern("caller"));
+    let callee = Ident::with_empty_span(Symbol::intern("callee"));
+    assert!(program.contains(caller));
+    assert!(program.contains(callee));
+
+    let mut emulator = Emulator::default();
+    emulator.load_program(program).expect("failed to load program");
+    let run = FunctionIdent {
+        module: caller,
+        function: Ident::with_empty_span(Symbol::intern("run")),
+    };
+    let stack = emulator.invoke(run, &[Felt::new(20)]).expect("execution failed");
+    assert_eq!(stack.peek().map(|felt| felt.as_int()), Some(41));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -14,13 +14,15 @@ fn fib() {
     test.expect_masm(expect_file!["../../expected/fib.masm"]);
     let ir_masm = test.ir_masm_program();
     let vm_program = &test.vm_masm_program();
+    let advice_stack = ir_masm.advice_stack();
 
     // Run the Rust and compiled MASM code against a bunch of random inputs and compare the results
     TestRunner::default()
         .run(&(1u32..30), move |a| {
             let rust_out = miden_integration_tests_rust_fib::fib(a);
             let mut args = [Felt::from(a)];
-            let vm_out: u32 = execute_vm(&vm_program, &args).first().unwrap().clone().into();
+            let vm_out: u32 =
+                execute_vm(&vm_program, &advice_stack, &args).first().unwrap().clone().into();
             prop_assert_eq!(rust_out, vm_out);
             args.reverse();
             let emul_out: u32 =
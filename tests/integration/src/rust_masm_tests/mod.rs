@@ -15,13 +15,17 @@ mod wit_sdk;
 pub fn run_masm_vs_rust<T>(
     rust_out: T,
     vm_program: &miden_core::Program,
-    _ir_masm: Arc<miden_codegen_masm::Program>,
+    ir_masm: Arc<miden_codegen_masm::Program>,
     args: &[Felt],
 ) -> Result<(), TestCaseError>
 where
     T: Clone + From<TestFelt> + std::cmp::PartialEq + std::fmt::Debug,
 {
-    let vm_out: T = execute_vm(&vm_program, &args).first().unwrap().clone().into();
+    let vm_out: T = execute_vm(&vm_program, &ir_masm.advice_stack(), &args)
+        .first()
+        .unwrap()
+        .clone()
+        .into();
     dbg!(&vm_out);
     prop_assert_eq!(rust_out.clone(), vm_out, "VM output mismatch");
     // TODO: eq for i64 and u64 fails with invalid operand stack size error
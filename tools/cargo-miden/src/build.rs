@@ -43,7 +43,7 @@ pub fn build_masm(
         .with_output_types(output_types);
     let target = TargetEnv::default();
     let session = Arc::new(
-        Session::new(target, input, Some(output_folder.to_path_buf()), None, None, options, None)
+        Session::new(target, [input], Some(output_folder.to_path_buf()), None, None, options, None)
             .with_project_type(project_type),
     );
     midenc_compile::compile(session.clone()).context("Wasm to MASM compilation failed!")?;